@@ -0,0 +1,336 @@
+//! Synchronous client, for tools without an async runtime
+
+use crate::replies::{self, DaemonInfo, Status, TriggerSummary};
+use crate::{ClientError, Options, Result};
+use autogit_shared::{Command, ConfigChange, DRY_RUN_PROTOCOL_VERSION, DaemonEvent, JournalEntry, JournalFilter, Response, SyncPreview, TRIGGER_OPTIONS_PROTOCOL_VERSION};
+use chrono::{DateTime, Utc};
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Blocking client for the daemon socket
+#[derive(Debug, Clone)]
+pub struct Client {
+    options: Options,
+}
+
+impl Client {
+    /// Client for the daemon of the current user
+    pub fn new() -> Result<Self> {
+        Ok(Self { options: Options::default_socket()? })
+    }
+
+    /// Client for the daemon listening on the given socket
+    pub fn with_socket_path(socket_path: impl Into<PathBuf>) -> Self {
+        Self { options: Options::new(socket_path.into()) }
+    }
+
+    /// Give up when sending a command or waiting for its response takes longer than this
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry connecting while the daemon is unreachable, and resume event streams after a restart
+    ///
+    /// Commands are never sent twice: only connecting is retried.
+    pub fn reconnect(mut self, attempts: u32, delay: Duration) -> Self {
+        self.options.reconnect(attempts, delay);
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.options.socket_path
+    }
+
+    /// Send any command and return the daemon's response, whether it succeeded or not
+    pub fn send(&self, command: Command) -> Result<Response> {
+        let stream = self.connect()?;
+        self.write_request(&stream, &command)?;
+        // Signal that the request is complete
+        stream.shutdown(Shutdown::Write)?;
+
+        let line = self.read_line(&mut BufReader::new(stream))?
+            .ok_or(ClientError::NoResponse)?;
+        Response::from_json(&line).map_err(|e| ClientError::Protocol(format!("{:#}", e)))
+    }
+
+    pub fn ping(&self) -> Result<()> {
+        replies::expect_ok(self.send(Command::Ping)?).map(|_| ())
+    }
+
+    /// Exchange versions with the daemon
+    pub fn hello(&self) -> Result<DaemonInfo> {
+        replies::daemon_info(self.send(Command::hello())?)
+    }
+
+    pub fn status(&self) -> Result<Status> {
+        replies::status(self.send(Command::Status)?)
+    }
+
+    /// Sync the given repositories now (all enabled ones if None)
+    pub fn trigger(&self, repos: Option<Vec<PathBuf>>, push: bool, pull: bool) -> Result<TriggerSummary> {
+        // Older daemons ignore the selection and the flags, and would sync and push everything
+        if repos.is_some() || !push || !pull {
+            replies::require_protocol(self.hello()?, "selective syncs", TRIGGER_OPTIONS_PROTOCOL_VERSION)?;
+        }
+        replies::trigger_summary(self.send(Command::Trigger { repos, push, pull, dry_run: false })?)
+    }
+
+    /// Report what syncing the given repositories would do, without changing anything
+    pub fn preview(&self, repos: Option<Vec<PathBuf>>, push: bool, pull: bool) -> Result<Vec<SyncPreview>> {
+        // Older daemons ignore the dry-run flag and would really sync
+        replies::require_protocol(self.hello()?, "dry runs", DRY_RUN_PROTOCOL_VERSION)?;
+        replies::previews(self.send(Command::Trigger { repos, push, pull, dry_run: true })?)
+    }
+
+    /// Stop automatic syncing, indefinitely or until a point in time
+    pub fn suspend(&self, until: Option<DateTime<Utc>>) -> Result<String> {
+        replies::message(self.send(Command::Suspend { until })?)
+    }
+
+    pub fn resume(&self) -> Result<String> {
+        replies::message(self.send(Command::Resume)?)
+    }
+
+    pub fn history(&self, filter: JournalFilter) -> Result<Vec<JournalEntry>> {
+        replies::history(self.send(Command::History(filter))?)
+    }
+
+    /// Add a commit made outside the daemon to its sync history
+    pub fn record_history(&self, entry: JournalEntry) -> Result<String> {
+        replies::message(self.send(Command::RecordHistory(entry))?)
+    }
+
+    /// Clear the quarantine of one repository (or of all, if no path is given)
+    pub fn unquarantine(&self, path: Option<PathBuf>) -> Result<String> {
+        replies::message(self.send(Command::Unquarantine { path })?)
+    }
+
+    /// Stop syncing one repository, indefinitely or until a point in time
+    pub fn pause(&self, path: PathBuf, until: Option<DateTime<Utc>>) -> Result<String> {
+        replies::message(self.send(Command::Pause { path, until })?)
+    }
+
+    pub fn unpause(&self, path: PathBuf) -> Result<String> {
+        replies::message(self.send(Command::Unpause { path })?)
+    }
+
+    /// Let the daemon validate, save and apply a configuration change
+    pub fn change_config(&self, change: ConfigChange) -> Result<String> {
+        replies::message(self.send(Command::from(change))?)
+    }
+
+    /// Make the daemon re-read its configuration file
+    pub fn reload(&self) -> Result<String> {
+        replies::message(self.send(Command::Reload)?)
+    }
+
+    /// Stream daemon events until the daemon closes the connection
+    pub fn subscribe(&self) -> Result<EventStream> {
+        let reader = self.open_subscription()?;
+        Ok(EventStream { client: self.clone(), reader })
+    }
+
+    fn open_subscription(&self) -> Result<BufReader<UnixStream>> {
+        // The write side stays open: closing it ends the subscription
+        let stream = self.connect()?;
+        self.write_request(&stream, &Command::Subscribe)?;
+
+        let mut reader = BufReader::new(stream);
+        let ack = self.read_line(&mut reader)?.ok_or(ClientError::NoResponse)?;
+        replies::subscription_ack(&ack)?;
+
+        // Events may be far apart
+        reader.get_ref().set_read_timeout(None)?;
+        Ok(reader)
+    }
+
+    fn connect(&self) -> Result<UnixStream> {
+        let mut attempt = 1;
+        let stream = loop {
+            match UnixStream::connect(&self.options.socket_path) {
+                Ok(stream) => break stream,
+                Err(_) if attempt < self.options.connect_attempts => {
+                    attempt += 1;
+                    std::thread::sleep(self.options.retry_delay);
+                }
+                Err(e) => return Err(self.options.not_running(e)),
+            }
+        };
+
+        stream.set_read_timeout(self.options.timeout)?;
+        stream.set_write_timeout(self.options.timeout)?;
+        Ok(stream)
+    }
+
+    fn write_request(&self, mut stream: &UnixStream, command: &Command) -> Result<()> {
+        let request = command.to_json()
+            .map_err(|e| ClientError::Protocol(format!("{:#}", e)))?;
+        stream.write_all(request.as_bytes()).map_err(|e| self.io_error(e))
+    }
+
+    /// One line from the daemon, or None if it closed the connection
+    fn read_line(&self, reader: &mut BufReader<UnixStream>) -> Result<Option<String>> {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+            Err(e) => Err(self.io_error(e)),
+        }
+    }
+
+    /// Socket timeouts surface as WouldBlock or TimedOut, depending on the platform
+    fn io_error(&self, error: std::io::Error) -> ClientError {
+        match (error.kind(), self.options.timeout) {
+            (std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut, Some(timeout)) => ClientError::Timeout(timeout),
+            _ => error.into(),
+        }
+    }
+}
+
+/// Events streamed by the daemon after `subscribe`
+///
+/// Iteration ends when the daemon closes the stream; events this client doesn't know (from newer
+/// daemons) are skipped.
+pub struct EventStream {
+    client: Client,
+    reader: BufReader<UnixStream>,
+}
+
+impl EventStream {
+    /// The next event as the raw JSON line, including events this client doesn't know
+    pub fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            let closed = match self.client.read_line(&mut self.reader) {
+                Ok(Some(line)) => return Ok(Some(line)),
+                Ok(None) => None,
+                Err(e) => Some(e),
+            };
+
+            if !self.client.options.resubscribe {
+                return closed.map_or(Ok(None), Err);
+            }
+            self.reader = self.client.open_subscription()?;
+        }
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<DaemonEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_line() {
+                Ok(Some(line)) => match DaemonEvent::from_json(&line) {
+                    Ok(event) => return Some(Ok(event)),
+                    Err(_) => continue,
+                },
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::{EventKind, JournalFilter, ResponseData};
+    use std::os::unix::net::UnixListener;
+    use tempfile::TempDir;
+
+    /// Serve one connection per reply set on a thread, returning the requests
+    fn spawn_daemon(listener: UnixListener, connections: Vec<Vec<String>>) -> std::thread::JoinHandle<Vec<String>> {
+        std::thread::spawn(move || {
+            connections.into_iter().map(|replies| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                for reply in replies {
+                    reader.get_ref().write_all(reply.as_bytes()).unwrap();
+                }
+                request
+            }).collect()
+        })
+    }
+
+    #[test]
+    fn test_typed_commands() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let history = Response::ok_with_data("history", ResponseData::History { entries: vec![] });
+        let daemon = spawn_daemon(UnixListener::bind(&path).unwrap(), vec![
+            vec![Response::ok("Suspended").to_json().unwrap()],
+            vec![history.to_json().unwrap()],
+        ]);
+
+        let client = Client::with_socket_path(&path);
+        assert_eq!(client.suspend(None).unwrap(), "Suspended");
+        let filter = JournalFilter { errors_only: true, ..Default::default() };
+        assert!(client.history(filter).unwrap().is_empty());
+
+        let requests = daemon.join().unwrap();
+        assert!(requests[0].contains("\"command\":\"suspend\""));
+        assert!(requests[1].contains("\"errors_only\":true"));
+    }
+
+    #[test]
+    fn test_not_running() {
+        let dir = TempDir::new().unwrap();
+        let client = Client::with_socket_path(dir.path().join("daemon.sock"));
+        assert!(client.ping().unwrap_err().is_not_running());
+    }
+
+    #[test]
+    fn test_timeout() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daemon.sock");
+        // Accepts connections but never answers
+        let _listener = UnixListener::bind(&path).unwrap();
+
+        let client = Client::with_socket_path(&path).timeout(Duration::from_millis(100));
+        let result = client.status();
+        assert!(matches!(result, Err(ClientError::Timeout(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn test_event_iteration() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let daemon = spawn_daemon(UnixListener::bind(&path).unwrap(), vec![vec![
+            Response::ok("Subscribed").to_json().unwrap(),
+            DaemonEvent::now(EventKind::Resumed).to_json().unwrap(),
+            "{\"timestamp\":\"2025-11-15T18:00:00Z\",\"event\":\"from_the_future\"}\n".to_owned(),
+            DaemonEvent::now(EventKind::ConfigReloaded { repositories: 2 }).to_json().unwrap(),
+        ]]);
+
+        // A short timeout only limits the acknowledgement, not the wait for events
+        let client = Client::with_socket_path(&path).timeout(Duration::from_secs(5));
+        let events: Vec<EventKind> = client.subscribe().unwrap().map(|event| event.unwrap().kind).collect();
+        assert_eq!(events, vec![EventKind::Resumed, EventKind::ConfigReloaded { repositories: 2 }]);
+        assert!(daemon.join().unwrap()[0].contains("\"command\":\"subscribe\""));
+    }
+
+    #[test]
+    fn test_event_iteration_resubscribes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let ack = Response::ok("Subscribed").to_json().unwrap();
+        let daemon = spawn_daemon(UnixListener::bind(&path).unwrap(), vec![
+            vec![ack.clone(), DaemonEvent::now(EventKind::Resumed).to_json().unwrap()],
+            vec![ack, DaemonEvent::now(EventKind::Suspended { until: None }).to_json().unwrap()],
+        ]);
+
+        let client = Client::with_socket_path(&path).reconnect(3, Duration::from_millis(10));
+        let mut events = client.subscribe().unwrap();
+        assert_eq!(events.next().unwrap().unwrap().kind, EventKind::Resumed);
+        assert_eq!(events.next().unwrap().unwrap().kind, EventKind::Suspended { until: None });
+        daemon.join().unwrap();
+
+        assert!(events.next().unwrap().unwrap_err().is_not_running());
+    }
+}
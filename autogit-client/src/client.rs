@@ -0,0 +1,415 @@
+use crate::replies::{self, DaemonInfo, Status, TriggerSummary};
+use crate::{ClientError, Options, Result};
+use autogit_shared::{Command, ConfigChange, DRY_RUN_PROTOCOL_VERSION, DaemonEvent, JournalEntry, JournalFilter, Response, SyncPreview, TRIGGER_OPTIONS_PROTOCOL_VERSION};
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixStream;
+
+/// Async client for the daemon socket
+#[derive(Debug, Clone)]
+pub struct Client {
+    options: Options,
+}
+
+impl Client {
+    /// Client for the daemon of the current user
+    pub fn new() -> Result<Self> {
+        Ok(Self { options: Options::default_socket()? })
+    }
+
+    /// Client for the daemon listening on the given socket
+    pub fn with_socket_path(socket_path: impl Into<PathBuf>) -> Self {
+        Self { options: Options::new(socket_path.into()) }
+    }
+
+    /// Give up on a command that takes longer than this (including any reconnection attempts)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry connecting while the daemon is unreachable, and resume event streams after a restart
+    ///
+    /// Commands are never sent twice: only connecting is retried.
+    pub fn reconnect(mut self, attempts: u32, delay: Duration) -> Self {
+        self.options.reconnect(attempts, delay);
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.options.socket_path
+    }
+
+    /// Send any command and return the daemon's response, whether it succeeded or not
+    pub async fn send(&self, command: Command) -> Result<Response> {
+        let request = command.to_json()
+            .map_err(|e| ClientError::Protocol(format!("{:#}", e)))?;
+
+        self.limited(async {
+            let mut stream = self.connect().await?;
+            stream.write_all(request.as_bytes()).await?;
+            // Signal that the request is complete
+            stream.shutdown().await?;
+
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).await?;
+            if line.is_empty() {
+                return Err(ClientError::NoResponse);
+            }
+
+            Response::from_json(&line).map_err(|e| ClientError::Protocol(format!("{:#}", e)))
+        }).await
+    }
+
+    pub async fn ping(&self) -> Result<()> {
+        replies::expect_ok(self.send(Command::Ping).await?).map(|_| ())
+    }
+
+    /// Exchange versions with the daemon
+    pub async fn hello(&self) -> Result<DaemonInfo> {
+        replies::daemon_info(self.send(Command::hello()).await?)
+    }
+
+    pub async fn status(&self) -> Result<Status> {
+        replies::status(self.send(Command::Status).await?)
+    }
+
+    /// Sync the given repositories now (all enabled ones if None)
+    pub async fn trigger(&self, repos: Option<Vec<PathBuf>>, push: bool, pull: bool) -> Result<TriggerSummary> {
+        // Older daemons ignore the selection and the flags, and would sync and push everything
+        if repos.is_some() || !push || !pull {
+            replies::require_protocol(self.hello().await?, "selective syncs", TRIGGER_OPTIONS_PROTOCOL_VERSION)?;
+        }
+        replies::trigger_summary(self.send(Command::Trigger { repos, push, pull, dry_run: false }).await?)
+    }
+
+    /// Report what syncing the given repositories would do, without changing anything
+    pub async fn preview(&self, repos: Option<Vec<PathBuf>>, push: bool, pull: bool) -> Result<Vec<SyncPreview>> {
+        // Older daemons ignore the dry-run flag and would really sync
+        replies::require_protocol(self.hello().await?, "dry runs", DRY_RUN_PROTOCOL_VERSION)?;
+        replies::previews(self.send(Command::Trigger { repos, push, pull, dry_run: true }).await?)
+    }
+
+    /// Stop automatic syncing, indefinitely or until a point in time
+    pub async fn suspend(&self, until: Option<DateTime<Utc>>) -> Result<String> {
+        replies::message(self.send(Command::Suspend { until }).await?)
+    }
+
+    pub async fn resume(&self) -> Result<String> {
+        replies::message(self.send(Command::Resume).await?)
+    }
+
+    pub async fn history(&self, filter: JournalFilter) -> Result<Vec<JournalEntry>> {
+        replies::history(self.send(Command::History(filter)).await?)
+    }
+
+    /// Add a commit made outside the daemon to its sync history
+    pub async fn record_history(&self, entry: JournalEntry) -> Result<String> {
+        replies::message(self.send(Command::RecordHistory(entry)).await?)
+    }
+
+    /// Clear the quarantine of one repository (or of all, if no path is given)
+    pub async fn unquarantine(&self, path: Option<PathBuf>) -> Result<String> {
+        replies::message(self.send(Command::Unquarantine { path }).await?)
+    }
+
+    /// Stop syncing one repository, indefinitely or until a point in time
+    pub async fn pause(&self, path: PathBuf, until: Option<DateTime<Utc>>) -> Result<String> {
+        replies::message(self.send(Command::Pause { path, until }).await?)
+    }
+
+    pub async fn unpause(&self, path: PathBuf) -> Result<String> {
+        replies::message(self.send(Command::Unpause { path }).await?)
+    }
+
+    /// Let the daemon validate, save and apply a configuration change
+    pub async fn change_config(&self, change: ConfigChange) -> Result<String> {
+        replies::message(self.send(Command::from(change)).await?)
+    }
+
+    /// Make the daemon re-read its configuration file
+    pub async fn reload(&self) -> Result<String> {
+        replies::message(self.send(Command::Reload).await?)
+    }
+
+    /// Stream daemon events until the daemon closes the connection
+    pub async fn subscribe(&self) -> Result<EventStream> {
+        let lines = self.open_subscription().await?;
+        Ok(EventStream { client: self.clone(), lines })
+    }
+
+    async fn open_subscription(&self) -> Result<Lines<BufReader<UnixStream>>> {
+        let request = Command::Subscribe.to_json()
+            .map_err(|e| ClientError::Protocol(format!("{:#}", e)))?;
+
+        // Only the acknowledgement is bounded by the timeout; events may be far apart
+        self.limited(async {
+            // The write side stays open: closing it ends the subscription
+            let mut stream = self.connect().await?;
+            stream.write_all(request.as_bytes()).await?;
+
+            let mut lines = BufReader::new(stream).lines();
+            let ack = lines.next_line().await?.ok_or(ClientError::NoResponse)?;
+            replies::subscription_ack(&ack)?;
+            Ok(lines)
+        }).await
+    }
+
+    async fn connect(&self) -> Result<UnixStream> {
+        let mut attempt = 1;
+        loop {
+            match UnixStream::connect(&self.options.socket_path).await {
+                Ok(stream) => return Ok(stream),
+                Err(_) if attempt < self.options.connect_attempts => {
+                    attempt += 1;
+                    tokio::time::sleep(self.options.retry_delay).await;
+                }
+                Err(e) => return Err(self.options.not_running(e)),
+            }
+        }
+    }
+
+    async fn limited<T>(&self, exchange: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.options.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, exchange).await
+                .map_err(|_| ClientError::Timeout(timeout))?,
+            None => exchange.await,
+        }
+    }
+}
+
+/// Events streamed by the daemon after `subscribe`
+pub struct EventStream {
+    client: Client,
+    lines: Lines<BufReader<UnixStream>>,
+}
+
+impl EventStream {
+    /// The next event, or None once the daemon closed the stream
+    ///
+    /// Events this client doesn't know (from newer daemons) are skipped.
+    pub async fn next(&mut self) -> Result<Option<DaemonEvent>> {
+        while let Some(line) = self.next_line().await? {
+            if let Ok(event) = DaemonEvent::from_json(&line) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The next event as the raw JSON line, including events this client doesn't know
+    pub async fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            let closed = match self.lines.next_line().await {
+                Ok(Some(line)) => return Ok(Some(line)),
+                Ok(None) => None,
+                Err(e) => Some(e),
+            };
+
+            if !self.client.options.resubscribe {
+                return closed.map_or(Ok(None), |e| Err(e.into()));
+            }
+            self.lines = self.client.open_subscription().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::{EventKind, ResponseData};
+    use tempfile::TempDir;
+    use tokio::net::UnixListener;
+
+    /// Read one request from the connection and answer with the given lines
+    async fn answer(listener: &UnixListener, replies: &[String]) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        reader.read_line(&mut request).await.unwrap();
+
+        let mut stream = reader.into_inner();
+        for reply in replies {
+            stream.write_all(reply.as_bytes()).await.unwrap();
+        }
+        request
+    }
+
+    fn socket(dir: &TempDir) -> PathBuf {
+        dir.path().join("daemon.sock")
+    }
+
+    #[tokio::test]
+    async fn test_typed_commands() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+        let client = Client::with_socket_path(socket(&dir));
+
+        let daemon = tokio::spawn(async move {
+            let status = Response::ok_with_data("status", ResponseData::Status {
+                uptime_seconds: 5,
+                check_interval_seconds: 300,
+                repositories_count: 2,
+                quarantined: vec![],
+                suspended: true,
+                suspended_until: None,
+                paused: vec![],
+                schedule: None,
+                next_check: None,
+                repositories: vec![],
+            });
+            let requests = vec![
+                answer(&listener, &[status.to_json().unwrap()]).await,
+                answer(&listener, &[Response::ok("Resumed").to_json().unwrap()]).await,
+                answer(&listener, &[Response::error("Repository not paused").to_json().unwrap()]).await,
+            ];
+            requests
+        });
+
+        let status = client.status().await.unwrap();
+        assert_eq!((status.check_interval_seconds, status.repositories_count), (300, 2));
+        assert!(status.suspended);
+
+        assert_eq!(client.resume().await.unwrap(), "Resumed");
+
+        match client.unpause(PathBuf::from("/repo")).await {
+            Err(ClientError::Daemon { message, .. }) => assert_eq!(message, "Repository not paused"),
+            other => panic!("Expected a daemon error, got {:?}", other),
+        }
+
+        let requests = daemon.await.unwrap();
+        assert!(requests[0].contains("\"command\":\"status\""));
+        assert!(requests[1].contains("\"command\":\"resume\""));
+        assert!(requests[2].contains("\"path\":\"/repo\""));
+    }
+
+    #[tokio::test]
+    async fn test_not_running() {
+        let dir = TempDir::new().unwrap();
+        let result = Client::with_socket_path(socket(&dir)).ping().await;
+        assert!(matches!(result, Err(ref e) if e.is_not_running()), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_no_response() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+        tokio::spawn(async move { answer(&listener, &[]).await });
+
+        let result = Client::with_socket_path(socket(&dir)).ping().await;
+        assert!(matches!(result, Err(ClientError::NoResponse)), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let dir = TempDir::new().unwrap();
+        // Accepts connections but never answers
+        let _listener = UnixListener::bind(socket(&dir)).unwrap();
+
+        let client = Client::with_socket_path(socket(&dir)).timeout(Duration::from_millis(100));
+        let result = client.status().await;
+        assert!(matches!(result, Err(ClientError::Timeout(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_waits_for_daemon() {
+        let dir = TempDir::new().unwrap();
+        let path = socket(&dir);
+
+        // The daemon comes up after the first attempt
+        let daemon = tokio::spawn({
+            let path = path.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let listener = UnixListener::bind(&path).unwrap();
+                answer(&listener, &[Response::ok("pong").to_json().unwrap()]).await
+            }
+        });
+
+        let client = Client::with_socket_path(&path).reconnect(20, Duration::from_millis(25));
+        client.ping().await.unwrap();
+        assert!(daemon.await.unwrap().contains("ping"));
+
+        // Without reconnection, a missing daemon fails right away
+        let dir = TempDir::new().unwrap();
+        assert!(Client::with_socket_path(socket(&dir)).ping().await.unwrap_err().is_not_running());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+
+        let daemon = tokio::spawn(async move {
+            answer(&listener, &[
+                Response::ok("Subscribed").to_json().unwrap(),
+                "{\"timestamp\":\"2025-11-15T18:00:00Z\",\"event\":\"from_the_future\"}\n".to_owned(),
+                DaemonEvent::now(EventKind::Resumed).to_json().unwrap(),
+            ]).await
+        });
+
+        let mut events = Client::with_socket_path(socket(&dir)).subscribe().await.unwrap();
+        // The unknown event is skipped
+        assert_eq!(events.next().await.unwrap().unwrap().kind, EventKind::Resumed);
+        assert!(events.next().await.unwrap().is_none());
+        assert!(daemon.await.unwrap().contains("\"command\":\"subscribe\""));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_resubscribes() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+
+        let daemon = tokio::spawn(async move {
+            let ack = Response::ok("Subscribed").to_json().unwrap();
+            // The daemon restarts after one event
+            answer(&listener, &[ack.clone(), DaemonEvent::now(EventKind::Resumed).to_json().unwrap()]).await;
+            answer(&listener, &[ack, DaemonEvent::now(EventKind::Suspended { until: None }).to_json().unwrap()]).await;
+        });
+
+        let client = Client::with_socket_path(socket(&dir)).reconnect(5, Duration::from_millis(10));
+        let mut events = client.subscribe().await.unwrap();
+        assert_eq!(events.next().await.unwrap().unwrap().kind, EventKind::Resumed);
+        assert_eq!(events.next().await.unwrap().unwrap().kind, EventKind::Suspended { until: None });
+        daemon.await.unwrap();
+
+        // Gone for good: resubscribing fails once the attempts are used up
+        assert!(events.next().await.unwrap_err().is_not_running());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_refused() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+        tokio::spawn(async move {
+            answer(&listener, &[Response::error("Too many subscribers").to_json().unwrap()]).await
+        });
+
+        let result = Client::with_socket_path(socket(&dir)).subscribe().await;
+        assert!(matches!(result, Err(ClientError::Daemon { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_selective_trigger_needs_protocol() {
+        let dir = TempDir::new().unwrap();
+        let listener = UnixListener::bind(socket(&dir)).unwrap();
+        let client = Client::with_socket_path(socket(&dir));
+
+        let daemon = tokio::spawn(async move {
+            let hello = Response::ok_with_data("hello", ResponseData::Hello {
+                protocol_version: 1,
+                daemon_version: "0.8.0".to_owned(),
+                commands: vec!["trigger".to_owned()],
+            });
+            answer(&listener, &[hello.to_json().unwrap()]).await
+        });
+
+        // Only the handshake is sent, never the trigger the old daemon would misread
+        let result = client.trigger(Some(vec![PathBuf::from("/repo")]), false, true).await;
+        assert!(matches!(result, Err(ClientError::Outdated { found: 1, required: 2, .. })), "{:?}", result);
+        assert!(daemon.await.unwrap().contains("\"command\":\"hello\""));
+    }
+}
@@ -0,0 +1,111 @@
+use autogit_shared::{ErrorKind, ResponseData};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Why talking to the daemon failed
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Failed to get socket path: {0}")]
+    SocketPath(String),
+    #[error(
+        "Failed to connect to daemon socket: {}\nIs the daemon running? Start it with: systemctl --user start autogit-daemon",
+        path.display()
+    )]
+    NotRunning {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Daemon did not respond within {} seconds", .0.as_secs_f64())]
+    Timeout(Duration),
+    #[error(
+        "Daemon closed connection without sending response\n\
+         It may be older than this client; restart it with: systemctl --user restart autogit-daemon"
+    )]
+    NoResponse,
+    #[error("Failed to communicate with daemon: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid message from daemon: {0}")]
+    Protocol(String),
+    /// The daemon answered with an error response
+    #[error("{message}")]
+    Daemon {
+        message: String,
+        /// Handshake data, attached when the daemon doesn't know the command
+        data: Option<Box<ResponseData>>,
+        /// Why the command failed, for errors that clients tell apart
+        kind: Option<ErrorKind>,
+    },
+    #[error("Daemon returned no {0} data")]
+    MissingData(&'static str),
+    /// The daemon speaks an older protocol that lacks a feature this client needs
+    #[error(
+        "The daemon (protocol {found}) is too old for {feature}, which needs protocol {required}\n\
+         Restart it with: systemctl --user restart autogit-daemon"
+    )]
+    Outdated {
+        feature: &'static str,
+        found: u32,
+        required: u32,
+    },
+}
+
+impl ClientError {
+    /// The daemon is not running (or its socket can't be reached)
+    pub fn is_not_running(&self) -> bool {
+        matches!(self, ClientError::NotRunning { .. })
+    }
+
+    /// The daemon rejected the command as unknown, i.e. it is older than this client
+    pub fn is_unknown_command(&self) -> bool {
+        matches!(self, ClientError::Daemon { data: Some(data), .. } if matches!(**data, ResponseData::Hello { .. }))
+    }
+
+    /// The daemon rejected the command because it named a repository that isn't configured
+    pub fn is_repo_not_found(&self) -> bool {
+        matches!(self, ClientError::Daemon { kind: Some(ErrorKind::RepoNotFound), .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_command() {
+        let unknown = ClientError::Daemon {
+            message: "Unknown command 'reload'".to_owned(),
+            data: Some(Box::new(ResponseData::Hello {
+                protocol_version: 1,
+                daemon_version: "0.8.0".to_owned(),
+                commands: vec![],
+            })),
+            kind: None,
+        };
+        assert!(unknown.is_unknown_command());
+        assert_eq!(unknown.to_string(), "Unknown command 'reload'");
+
+        let refused = ClientError::Daemon { message: "Repository is paused".to_owned(), data: None, kind: None };
+        assert!(!refused.is_unknown_command());
+        assert!(!refused.is_not_running());
+        assert!(!refused.is_repo_not_found());
+
+        let missing = ClientError::Daemon {
+            message: "Repository not found in configuration: /repo".to_owned(),
+            data: None,
+            kind: Some(ErrorKind::RepoNotFound),
+        };
+        assert!(missing.is_repo_not_found());
+    }
+
+    #[test]
+    fn test_not_running_message() {
+        let error = ClientError::NotRunning {
+            path: PathBuf::from("/run/user/1000/autogit/daemon.sock"),
+            source: std::io::ErrorKind::NotFound.into(),
+        };
+        assert!(error.is_not_running());
+        assert!(error.to_string().contains("/run/user/1000/autogit/daemon.sock"));
+        assert!(error.to_string().contains("Is the daemon running?"));
+    }
+}
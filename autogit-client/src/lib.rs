@@ -0,0 +1,62 @@
+//! Client for the autogit daemon socket protocol
+//!
+//! [`Client`] is async (tokio); [`blocking::Client`] offers the same methods for synchronous
+//! tools. Both open one connection per command, as the daemon expects.
+
+pub mod blocking;
+mod client;
+mod error;
+mod replies;
+
+pub use client::{Client, EventStream};
+pub use error::ClientError;
+pub use replies::{DaemonInfo, Status, TriggerSummary};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Default pause between connection attempts when reconnecting
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Connection settings shared by both clients
+#[derive(Debug, Clone)]
+struct Options {
+    socket_path: PathBuf,
+    /// Limit for one command, from connecting to reading the response (None = wait indefinitely)
+    timeout: Option<Duration>,
+    /// How often to try connecting before giving up
+    connect_attempts: u32,
+    retry_delay: Duration,
+    /// Subscribe again when the daemon closes an event stream (e.g. on restart)
+    resubscribe: bool,
+}
+
+impl Options {
+    fn new(socket_path: PathBuf) -> Self {
+        Self {
+            socket_path,
+            timeout: None,
+            connect_attempts: 1,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            resubscribe: false,
+        }
+    }
+
+    fn default_socket() -> Result<Self> {
+        let socket_path = autogit_shared::socket_path()
+            .map_err(|e| ClientError::SocketPath(format!("{:#}", e)))?;
+        Ok(Self::new(socket_path))
+    }
+
+    fn reconnect(&mut self, attempts: u32, delay: Duration) {
+        self.connect_attempts = attempts.max(1);
+        self.retry_delay = delay;
+        self.resubscribe = true;
+    }
+
+    fn not_running(&self, source: std::io::Error) -> ClientError {
+        ClientError::NotRunning { path: self.socket_path.clone(), source }
+    }
+}
@@ -0,0 +1,217 @@
+//! Typed results of daemon commands, shared by the async and blocking clients
+
+use crate::{ClientError, Result};
+use autogit_shared::{JournalEntry, PausedRepo, RepoDetail, RepoStatus, Response, ResponseData, ResponseStatus, ScheduleState, SyncPreview};
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+
+/// Versions reported by the daemon in the handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonInfo {
+    pub protocol_version: u32,
+    pub daemon_version: String,
+    /// Names of the commands the daemon understands
+    pub commands: Vec<String>,
+}
+
+/// Runtime state of the daemon
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub uptime_seconds: u64,
+    pub check_interval_seconds: u64,
+    pub repositories_count: usize,
+    /// Repositories no longer synced after repeated failures
+    pub quarantined: Vec<PathBuf>,
+    /// Automatic syncing is suspended
+    pub suspended: bool,
+    /// When a timed suspension ends
+    pub suspended_until: Option<DateTime<Utc>>,
+    /// Temporarily paused repositories
+    pub paused: Vec<PausedRepo>,
+    /// Conditions seen by the scheduling policy at the last check
+    pub schedule: Option<ScheduleState>,
+    /// When the next scheduled check runs
+    pub next_check: Option<DateTime<Utc>>,
+    /// Live state of every configured repository
+    pub repositories: Vec<RepoStatus>,
+}
+
+/// Outcome of a manual sync
+#[derive(Debug, Clone)]
+pub struct TriggerSummary {
+    pub message: String,
+    pub repos_checked: usize,
+    pub repos_committed: usize,
+    pub details: Vec<RepoDetail>,
+}
+
+/// Turn an error response into a `ClientError::Daemon`
+pub(crate) fn expect_ok(response: Response) -> Result<Response> {
+    match response.status {
+        ResponseStatus::Ok => Ok(response),
+        ResponseStatus::Error => Err(ClientError::Daemon {
+            message: response.message,
+            data: response.data.map(Box::new),
+            kind: response.error_kind,
+        }),
+    }
+}
+
+pub(crate) fn message(response: Response) -> Result<String> {
+    expect_ok(response).map(|response| response.message)
+}
+
+pub(crate) fn daemon_info(response: Response) -> Result<DaemonInfo> {
+    match expect_ok(response)?.data {
+        Some(ResponseData::Hello { protocol_version, daemon_version, commands }) => {
+            Ok(DaemonInfo { protocol_version, daemon_version, commands })
+        }
+        _ => Err(ClientError::MissingData("handshake")),
+    }
+}
+
+pub(crate) fn status(response: Response) -> Result<Status> {
+    match expect_ok(response)?.data {
+        Some(ResponseData::Status {
+            uptime_seconds,
+            check_interval_seconds,
+            repositories_count,
+            quarantined,
+            suspended,
+            suspended_until,
+            paused,
+            schedule,
+            next_check,
+            repositories,
+        }) => Ok(Status {
+            uptime_seconds,
+            check_interval_seconds,
+            repositories_count,
+            quarantined,
+            suspended,
+            suspended_until,
+            paused,
+            schedule,
+            next_check,
+            repositories,
+        }),
+        _ => Err(ClientError::MissingData("status")),
+    }
+}
+
+pub(crate) fn trigger_summary(response: Response) -> Result<TriggerSummary> {
+    let response = expect_ok(response)?;
+    match response.data {
+        Some(ResponseData::Trigger { repos_checked, repos_committed, details }) => Ok(TriggerSummary {
+            message: response.message,
+            repos_checked,
+            repos_committed,
+            details,
+        }),
+        _ => Err(ClientError::MissingData("trigger")),
+    }
+}
+
+pub(crate) fn previews(response: Response) -> Result<Vec<SyncPreview>> {
+    match expect_ok(response)?.data {
+        Some(ResponseData::Preview { previews }) => Ok(previews),
+        _ => Err(ClientError::MissingData("preview")),
+    }
+}
+
+/// Make sure the daemon is new enough for a feature before relying on it
+pub(crate) fn require_protocol(info: DaemonInfo, feature: &'static str, required: u32) -> Result<()> {
+    if info.protocol_version < required {
+        return Err(ClientError::Outdated { feature, found: info.protocol_version, required });
+    }
+    Ok(())
+}
+
+pub(crate) fn history(response: Response) -> Result<Vec<JournalEntry>> {
+    match expect_ok(response)?.data {
+        Some(ResponseData::History { entries }) => Ok(entries),
+        _ => Err(ClientError::MissingData("history")),
+    }
+}
+
+/// Check the acknowledgement of a subscription
+pub(crate) fn subscription_ack(line: &str) -> Result<()> {
+    let response = Response::from_json(line)
+        .map_err(|e| ClientError::Protocol(format!("{:#}", e)))?;
+    expect_ok(response).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::RepoNotFound;
+
+    #[test]
+    fn test_error_response() {
+        match message(Response::error("Repository not found")) {
+            Err(ClientError::Daemon { message, data: None, .. }) => assert_eq!(message, "Repository not found"),
+            other => panic!("Expected a daemon error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let response = Response::failure(&RepoNotFound(PathBuf::from("/repo")).into());
+        match message(response) {
+            Err(error) => {
+                assert!(error.is_repo_not_found());
+                assert_eq!(error.to_string(), "Repository not found in configuration: /repo");
+            }
+            other => panic!("Expected a daemon error, got {:?}", other),
+        }
+        assert!(!matches!(message(Response::error("Repository not found")), Err(error) if error.is_repo_not_found()));
+    }
+
+    #[test]
+    fn test_missing_data() {
+        assert!(matches!(status(Response::ok("status")), Err(ClientError::MissingData("status"))));
+        assert!(matches!(history(Response::ok("history")), Err(ClientError::MissingData("history"))));
+        assert!(matches!(trigger_summary(Response::ok("done")), Err(ClientError::MissingData("trigger"))));
+        assert!(matches!(previews(Response::ok("done")), Err(ClientError::MissingData("preview"))));
+    }
+
+    #[test]
+    fn test_require_protocol() {
+        let info = |protocol_version| DaemonInfo {
+            protocol_version,
+            daemon_version: "0.9.0".to_owned(),
+            commands: vec![],
+        };
+
+        assert!(require_protocol(info(2), "selective syncs", 2).is_ok());
+        match require_protocol(info(1), "selective syncs", 2) {
+            Err(e @ ClientError::Outdated { found: 1, required: 2, .. }) => {
+                assert!(e.to_string().starts_with("The daemon (protocol 1) is too old for selective syncs"));
+            }
+            other => panic!("Expected an outdated daemon error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_trigger_summary() {
+        let response = Response::ok_with_data("Checked 1 repositories, committed changes in 1", ResponseData::Trigger {
+            repos_checked: 1,
+            repos_committed: 1,
+            details: vec![],
+        });
+
+        let summary = trigger_summary(response).unwrap();
+        assert_eq!(summary.message, "Checked 1 repositories, committed changes in 1");
+        assert_eq!((summary.repos_checked, summary.repos_committed), (1, 1));
+    }
+
+    #[test]
+    fn test_subscription_ack() {
+        assert!(subscription_ack(&Response::ok("Subscribed").to_json().unwrap()).is_ok());
+        assert!(matches!(subscription_ack("not json"), Err(ClientError::Protocol(_))));
+        assert!(matches!(
+            subscription_ack(&Response::error("Too many subscribers").to_json().unwrap()),
+            Err(ClientError::Daemon { .. })
+        ));
+    }
+}
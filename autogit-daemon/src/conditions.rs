@@ -0,0 +1,289 @@
+use autogit_shared::{DaemonConfig, ScheduleState};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Charge of the system battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryStatus {
+    pub percent: u8,
+    /// Running from the battery rather than from mains power
+    pub discharging: bool,
+}
+
+/// System conditions the scheduling policy depends on
+#[derive(Debug, Clone, Default)]
+pub struct Conditions {
+    pub battery: Option<BatteryStatus>,
+    pub metered: bool,
+    pub idle: Option<Duration>,
+}
+
+/// Read the battery state from `<sysfs_root>/sys/class/power_supply`
+///
+/// Returns None on machines without a system battery. Peripheral batteries
+/// (mice, headsets) are ignored; multiple system batteries are averaged.
+pub fn read_battery(sysfs_root: &Path) -> Option<BatteryStatus> {
+    let supplies = std::fs::read_dir(sysfs_root.join("sys/class/power_supply")).ok()?;
+
+    let read = |dir: &Path, name: &str| {
+        std::fs::read_to_string(dir.join(name)).ok().map(|s| s.trim().to_owned())
+    };
+
+    let mut capacities = Vec::new();
+    let mut discharging = false;
+    let mut mains_online = None;
+
+    for supply in supplies.flatten() {
+        let dir = supply.path();
+
+        match read(&dir, "type").as_deref() {
+            Some("Battery") => {
+                if read(&dir, "scope").as_deref() == Some("Device") {
+                    continue;
+                }
+                if let Some(capacity) = read(&dir, "capacity").and_then(|c| c.parse::<u32>().ok()) {
+                    capacities.push(capacity.min(100));
+                }
+                if read(&dir, "status").as_deref() == Some("Discharging") {
+                    discharging = true;
+                }
+            }
+            Some("Mains") => {
+                let online = read(&dir, "online").as_deref() == Some("1");
+                mains_online = Some(mains_online.unwrap_or(false) || online);
+            }
+            _ => {}
+        }
+    }
+
+    if capacities.is_empty() {
+        return None;
+    }
+
+    let percent = (capacities.iter().sum::<u32>() / capacities.len() as u32) as u8;
+
+    Some(BatteryStatus {
+        percent,
+        discharging: discharging || mains_online == Some(false),
+    })
+}
+
+/// Ask NetworkManager whether the primary connection is metered (false if unknown)
+async fn metered_connection() -> bool {
+    let result: zbus::Result<u32> = async {
+        let connection = zbus::Connection::system().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+        ).await?;
+        proxy.get_property("Metered").await
+    }.await;
+
+    match result {
+        // NM_METERED_YES and NM_METERED_GUESS_YES
+        Ok(metered) => metered == 1 || metered == 3,
+        Err(e) => {
+            debug!("Could not query NetworkManager for metered connection: {}", e);
+            false
+        }
+    }
+}
+
+/// Ask logind how long the user's session has been idle (None if unknown)
+async fn idle_time() -> Option<Duration> {
+    let result: zbus::Result<(bool, u64)> = async {
+        let connection = zbus::Connection::system().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+        ).await?;
+        let idle: bool = proxy.get_property("IdleHint").await?;
+        let since: u64 = proxy.get_property("IdleSinceHint").await?;
+        Ok((idle, since))
+    }.await;
+
+    match result {
+        Ok((false, _)) => Some(Duration::ZERO),
+        Ok((true, since_usec)) => {
+            let since = UNIX_EPOCH + Duration::from_micros(since_usec);
+            Some(SystemTime::now().duration_since(since).unwrap_or_default())
+        }
+        Err(e) => {
+            debug!("Could not query logind for idle time: {}", e);
+            None
+        }
+    }
+}
+
+/// Gather the conditions the policy needs (only querying what is enabled)
+pub async fn probe(policy: &DaemonConfig, sysfs_root: &Path) -> Conditions {
+    Conditions {
+        battery: read_battery(sysfs_root),
+        metered: policy.defer_remote_on_metered && metered_connection().await,
+        idle: if policy.commit_after_idle_seconds > 0 { idle_time().await } else { None },
+    }
+}
+
+/// Decide whether pulls, pushes and commits should wait
+///
+/// An unknown idle time never defers commits, so a session without logind still syncs.
+pub fn evaluate(policy: &DaemonConfig, conditions: &Conditions) -> ScheduleState {
+    let on_battery = conditions.battery.is_some_and(|b| b.discharging);
+    let threshold = policy.defer_remote_below_battery_percent;
+
+    let remote_deferred = match conditions.battery {
+        Some(battery) if threshold > 0 && on_battery && battery.percent < threshold => {
+            Some(format!("on battery at {}% (below {}%)", battery.percent, threshold))
+        }
+        _ if policy.defer_remote_on_metered && conditions.metered => Some("metered connection".to_owned()),
+        _ => None,
+    };
+
+    let required_idle = Duration::from_secs(policy.commit_after_idle_seconds);
+    let commit_deferred = match conditions.idle {
+        Some(idle) if !required_idle.is_zero() && idle < required_idle => Some(format!(
+            "user active (idle {}s of {}s)",
+            idle.as_secs(),
+            required_idle.as_secs()
+        )),
+        _ => None,
+    };
+
+    ScheduleState {
+        battery_percent: conditions.battery.map(|b| b.percent),
+        on_battery,
+        metered: conditions.metered,
+        idle_seconds: conditions.idle.map(|idle| idle.as_secs()),
+        remote_deferred,
+        commit_deferred,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn add_supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join("sys/class/power_supply").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            std::fs::write(dir.join(file), format!("{}\n", content)).unwrap();
+        }
+    }
+
+    fn policy(battery_percent: u8, metered: bool, idle_seconds: u64) -> DaemonConfig {
+        DaemonConfig {
+            defer_remote_below_battery_percent: battery_percent,
+            defer_remote_on_metered: metered,
+            commit_after_idle_seconds: idle_seconds,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read_battery_discharging() {
+        let root = TempDir::new().unwrap();
+        add_supply(root.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        add_supply(root.path(), "BAT0", &[("type", "Battery"), ("capacity", "42"), ("status", "Discharging")]);
+
+        assert_eq!(read_battery(root.path()), Some(BatteryStatus { percent: 42, discharging: true }));
+    }
+
+    #[test]
+    fn test_read_battery_on_mains() {
+        let root = TempDir::new().unwrap();
+        add_supply(root.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        add_supply(root.path(), "BAT0", &[("type", "Battery"), ("capacity", "80"), ("status", "Charging")]);
+
+        assert_eq!(read_battery(root.path()), Some(BatteryStatus { percent: 80, discharging: false }));
+    }
+
+    #[test]
+    fn test_read_battery_multiple_and_peripherals() {
+        let root = TempDir::new().unwrap();
+        add_supply(root.path(), "BAT0", &[("type", "Battery"), ("capacity", "30"), ("status", "Not charging")]);
+        add_supply(root.path(), "BAT1", &[("type", "Battery"), ("capacity", "50"), ("status", "Discharging")]);
+        add_supply(root.path(), "hidpp_battery_0", &[("type", "Battery"), ("scope", "Device"), ("capacity", "5")]);
+
+        assert_eq!(read_battery(root.path()), Some(BatteryStatus { percent: 40, discharging: true }));
+    }
+
+    #[test]
+    fn test_read_battery_desktop() {
+        let root = TempDir::new().unwrap();
+        add_supply(root.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(read_battery(root.path()), None);
+
+        // No power_supply class at all
+        let empty = TempDir::new().unwrap();
+        assert_eq!(read_battery(empty.path()), None);
+    }
+
+    #[test]
+    fn test_evaluate_battery_threshold() {
+        let low = Conditions {
+            battery: Some(BatteryStatus { percent: 20, discharging: true }),
+            ..Default::default()
+        };
+
+        let schedule = evaluate(&policy(30, false, 0), &low);
+        assert!(schedule.on_battery);
+        assert_eq!(schedule.battery_percent, Some(20));
+        assert_eq!(schedule.remote_deferred.as_deref(), Some("on battery at 20% (below 30%)"));
+        assert!(schedule.commit_deferred.is_none());
+
+        // Disabled policy, charge above threshold or charging never defers
+        assert!(evaluate(&policy(0, false, 0), &low).remote_deferred.is_none());
+        assert!(evaluate(&policy(15, false, 0), &low).remote_deferred.is_none());
+
+        let charging = Conditions {
+            battery: Some(BatteryStatus { percent: 20, discharging: false }),
+            ..Default::default()
+        };
+        assert!(evaluate(&policy(30, false, 0), &charging).remote_deferred.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_metered() {
+        let metered = Conditions { metered: true, ..Default::default() };
+
+        let schedule = evaluate(&policy(0, true, 0), &metered);
+        assert!(schedule.metered);
+        assert_eq!(schedule.remote_deferred.as_deref(), Some("metered connection"));
+
+        assert!(evaluate(&policy(0, false, 0), &metered).remote_deferred.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_idle() {
+        let active = Conditions { idle: Some(Duration::from_secs(10)), ..Default::default() };
+        let idle = Conditions { idle: Some(Duration::from_secs(300)), ..Default::default() };
+        let unknown = Conditions::default();
+
+        let schedule = evaluate(&policy(0, false, 120), &active);
+        assert_eq!(schedule.idle_seconds, Some(10));
+        assert_eq!(schedule.commit_deferred.as_deref(), Some("user active (idle 10s of 120s)"));
+
+        assert!(evaluate(&policy(0, false, 120), &idle).commit_deferred.is_none());
+        assert!(evaluate(&policy(0, false, 120), &unknown).commit_deferred.is_none());
+        assert!(evaluate(&policy(0, false, 0), &active).commit_deferred.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe_reads_fake_sysfs() {
+        let root = TempDir::new().unwrap();
+        add_supply(root.path(), "BAT0", &[("type", "Battery"), ("capacity", "12"), ("status", "Discharging")]);
+
+        // D-Bus is only queried for enabled policies
+        let conditions = probe(&policy(25, false, 0), root.path()).await;
+        assert_eq!(conditions.battery, Some(BatteryStatus { percent: 12, discharging: true }));
+        assert!(!conditions.metered);
+        assert!(conditions.idle.is_none());
+    }
+}
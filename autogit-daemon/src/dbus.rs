@@ -0,0 +1,328 @@
+use crate::socket::Controller;
+use anyhow::{Context, Result};
+use autogit_shared::{Command, DaemonEvent, EventKind, Response, ResponseStatus};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use zbus::fdo::RequestNameFlags;
+use zbus::object_server::SignalEmitter;
+
+/// Well-known name claimed on the session bus
+pub const BUS_NAME: &str = "org.autogit.Daemon";
+
+/// Path of the daemon object
+pub const OBJECT_PATH: &str = "/org/autogit/Daemon";
+
+/// The `org.autogit.Daemon` interface, backed by the same commands as the control socket
+struct DaemonInterface {
+    controller: Controller,
+}
+
+/// Turn an error response into a D-Bus error reply
+fn into_reply(response: Response) -> zbus::fdo::Result<Response> {
+    match response.status {
+        ResponseStatus::Ok => Ok(response),
+        ResponseStatus::Error => Err(zbus::fdo::Error::Failed(response.message)),
+    }
+}
+
+#[zbus::interface(name = "org.autogit.Daemon")]
+impl DaemonInterface {
+    /// Sync the given repositories now (all enabled ones if the list is empty)
+    async fn trigger(&self, repos: Vec<String>) -> zbus::fdo::Result<String> {
+        let repos = (!repos.is_empty()).then(|| repos.into_iter().map(PathBuf::from).collect());
+        let response = self.controller.execute(Command::Trigger { repos, push: true, pull: true, dry_run: false }).await;
+        Ok(into_reply(response)?.message)
+    }
+
+    /// Stop automatic syncing for a number of seconds (0 suspends indefinitely)
+    async fn suspend(&self, seconds: u64) -> zbus::fdo::Result<String> {
+        let until = match seconds {
+            0 => None,
+            seconds => Some(deadline_after(seconds)?),
+        };
+        let response = self.controller.execute(Command::Suspend { until }).await;
+        Ok(into_reply(response)?.message)
+    }
+
+    /// Resume automatic syncing
+    async fn resume(&self) -> zbus::fdo::Result<String> {
+        let response = self.controller.execute(Command::Resume).await;
+        Ok(into_reply(response)?.message)
+    }
+
+    /// Daemon status as JSON, in the same shape as the socket protocol
+    async fn status(&self) -> zbus::fdo::Result<String> {
+        let response = into_reply(self.controller.execute(Command::Status).await)?;
+        serde_json::to_string(&response.data)
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    /// A sync cycle finished
+    #[zbus(signal)]
+    async fn sync_finished(emitter: &SignalEmitter<'_>, checked: u32, committed: u32, failed: u32) -> zbus::Result<()>;
+
+    /// Syncing or pushing a repository failed
+    #[zbus(signal)]
+    async fn error(emitter: &SignalEmitter<'_>, repo: &str, message: &str) -> zbus::Result<()>;
+}
+
+/// Claim the bus name, serve the daemon object and forward events as signals
+///
+/// The returned connection must be kept alive; dropping it releases the name.
+pub async fn publish(builder: zbus::connection::Builder<'_>, controller: Controller) -> Result<zbus::Connection> {
+    let events = controller.state.subscribe();
+    let connection = builder
+        .serve_at(OBJECT_PATH, DaemonInterface { controller })?
+        .build()
+        .await
+        .context("Failed to connect to the bus")?;
+
+    // The builder would silently queue behind another owner; fail instead
+    connection.request_name_with_flags(BUS_NAME, RequestNameFlags::DoNotQueue.into())
+        .await
+        .with_context(|| format!("Failed to claim {} on the bus", BUS_NAME))?;
+
+    tokio::spawn(forward_events(connection.clone(), events));
+    Ok(connection)
+}
+
+/// Publish the interface on the user's session bus
+pub async fn publish_session(controller: Controller) -> Result<zbus::Connection> {
+    let builder = zbus::connection::Builder::session()
+        .context("Failed to connect to the session bus")?;
+    publish(builder, controller).await
+}
+
+async fn forward_events(connection: zbus::Connection, mut events: broadcast::Receiver<DaemonEvent>) {
+    let emitter = match SignalEmitter::new(&connection, OBJECT_PATH) {
+        Ok(emitter) => emitter,
+        Err(e) => {
+            warn!("Cannot emit D-Bus signals: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                debug!("D-Bus signal forwarding skipped {} events", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let result = match event.kind {
+            EventKind::SyncFinished { repos_checked, repos_committed, repos_failed } => {
+                DaemonInterface::sync_finished(
+                    &emitter,
+                    saturating_u32(repos_checked),
+                    saturating_u32(repos_committed),
+                    saturating_u32(repos_failed),
+                ).await
+            }
+            EventKind::SyncFailed { repo, error } | EventKind::PushFailed { repo, error } => {
+                DaemonInterface::error(&emitter, &repo.to_string_lossy(), &error).await
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            warn!("Failed to emit D-Bus signal: {}", e);
+        }
+    }
+}
+
+/// The time a number of seconds from now, refusing durations past chrono's range
+fn deadline_after(seconds: u64) -> zbus::fdo::Result<DateTime<Utc>> {
+    i64::try_from(seconds).ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|duration| Utc::now().checked_add_signed(duration))
+        .ok_or_else(|| zbus::fdo::Error::InvalidArgs("Duration too long".to_owned()))
+}
+
+fn saturating_u32(count: usize) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DaemonState;
+    use crate::test_bus::TestBus;
+    use crate::test_repo::init_repo;
+    use autogit_shared::{Config, Journal, Repository};
+    use futures_util::StreamExt;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+    use tokio::sync::{RwLock, mpsc};
+
+    struct TestDaemon {
+        state: Arc<DaemonState>,
+        suspended: Arc<AtomicBool>,
+        client: zbus::Connection,
+        _service: zbus::Connection,
+        _state_dir: TempDir,
+        // Dropped last so the connections above close first
+        bus: TestBus,
+    }
+
+    async fn start_daemon(repositories: Vec<Repository>) -> Option<TestDaemon> {
+        let Some(bus) = TestBus::start() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return None;
+        };
+
+        let state_dir = TempDir::new().unwrap();
+        let state = Arc::new(DaemonState::new(Journal::new(state_dir.path().join("history.jsonl"))));
+        let suspended = Arc::new(AtomicBool::new(false));
+        let (config_tx, _) = mpsc::channel(1);
+        let controller = Controller {
+            config: Arc::new(RwLock::new(Config { repositories, ..Default::default() })),
+            start_time: Instant::now(),
+            state: state.clone(),
+            suspended: suspended.clone(),
+            config_tx,
+        };
+
+        let builder = zbus::connection::Builder::address(bus.address.as_str()).unwrap();
+        let service = publish(builder, controller).await.unwrap();
+        let client = bus.connect().await;
+
+        Some(TestDaemon { state, suspended, client, _service: service, _state_dir: state_dir, bus })
+    }
+
+    async fn proxy(connection: &zbus::Connection) -> zbus::Proxy<'static> {
+        zbus::Proxy::new(connection, BUS_NAME, OBJECT_PATH, BUS_NAME).await.unwrap()
+    }
+
+    fn create_test_repo(dir: &Path) -> Repository {
+        init_repo(dir);
+        Repository {
+            path: dir.to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Auto: {timestamp}".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+
+        let json: String = proxy(&daemon.client).await.call("Status", &()).await.unwrap();
+        let status: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(status["type"], "status");
+        assert_eq!(status["repositories_count"], 0);
+        assert_eq!(status["suspended"], false);
+    }
+
+    #[tokio::test]
+    async fn test_suspend_and_resume() {
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+        let proxy = proxy(&daemon.client).await;
+
+        let _: String = proxy.call("Suspend", &(0u64,)).await.unwrap();
+        assert!(daemon.suspended.load(Ordering::SeqCst));
+        assert!(daemon.state.suspended_until().is_none());
+
+        let _: String = proxy.call("Resume", &()).await.unwrap();
+        assert!(!daemon.suspended.load(Ordering::SeqCst));
+
+        let _: String = proxy.call("Suspend", &(3600u64,)).await.unwrap();
+        assert!(daemon.suspended.load(Ordering::SeqCst));
+        let until = daemon.state.suspended_until().unwrap();
+        assert!(until > Utc::now() + chrono::Duration::minutes(59));
+    }
+
+    #[tokio::test]
+    async fn test_suspend_too_long() {
+        assert!(deadline_after(u64::MAX).is_err());
+        assert!(deadline_after(i64::MAX as u64 / 1000 + 1).is_err());
+
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+        let result: zbus::Result<String> = proxy(&daemon.client).await
+            .call("Suspend", &(18446744073709551u64,)).await;
+        match result {
+            Err(zbus::Error::MethodError(name, Some(message), _)) => {
+                assert_eq!(name.as_str(), "org.freedesktop.DBus.Error.InvalidArgs");
+                assert_eq!(message, "Duration too long");
+            }
+            other => panic!("Expected a method error, got {:?}", other),
+        }
+        assert!(!daemon.suspended.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_emits_sync_finished() {
+        let repo_dir = TempDir::new().unwrap();
+        let repo = create_test_repo(repo_dir.path());
+        std::fs::write(repo_dir.path().join("notes.txt"), "hello").unwrap();
+        let Some(daemon) = start_daemon(vec![repo]).await else { return };
+
+        let proxy = proxy(&daemon.client).await;
+        let mut signals = proxy.receive_signal("SyncFinished").await.unwrap();
+
+        let message: String = proxy.call("Trigger", &(Vec::<String>::new(),)).await.unwrap();
+        assert!(message.contains("committed changes in 1"), "{}", message);
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), signals.next()).await.unwrap().unwrap();
+        let counts: (u32, u32, u32) = signal.body().deserialize().unwrap();
+        assert_eq!(counts, (1, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_unknown_repository() {
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+
+        let result: zbus::Result<String> = proxy(&daemon.client).await
+            .call("Trigger", &(vec!["/nonexistent/repo"],)).await;
+        match result {
+            Err(zbus::Error::MethodError(name, Some(message), _)) => {
+                assert_eq!(name.as_str(), "org.freedesktop.DBus.Error.Failed");
+                assert!(message.contains("not found in configuration"), "{}", message);
+            }
+            other => panic!("Expected a method error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failures_emit_error_signal() {
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+
+        let proxy = proxy(&daemon.client).await;
+        let mut signals = proxy.receive_signal("Error").await.unwrap();
+
+        daemon.state.emit(EventKind::SyncFailed {
+            repo: PathBuf::from("/home/user/notes"),
+            error: "disk full".to_owned(),
+        });
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), signals.next()).await.unwrap().unwrap();
+        let (repo, message): (String, String) = signal.body().deserialize().unwrap();
+        assert_eq!(repo, "/home/user/notes");
+        assert_eq!(message, "disk full");
+    }
+
+    #[tokio::test]
+    async fn test_name_already_taken() {
+        let Some(daemon) = start_daemon(Vec::new()).await else { return };
+
+        // A second daemon on the same bus cannot claim the name
+        let bus_address = daemon.bus.address.clone();
+        let (config_tx, _) = mpsc::channel(1);
+        let controller = Controller {
+            config: Arc::new(RwLock::new(Config::default())),
+            start_time: Instant::now(),
+            state: daemon.state.clone(),
+            suspended: daemon.suspended.clone(),
+            config_tx,
+        };
+        let builder = zbus::connection::Builder::address(bus_address.as_str()).unwrap();
+        assert!(publish(builder, controller).await.is_err());
+    }
+}
@@ -1,50 +1,129 @@
-use anyhow::{Context, Result, bail};
-use autogit_shared::Repository;
-use git2::{Repository as GitRepository, Signature, IndexAddOption, Status, StatusOptions};
+use anyhow::{Context, Result};
+use autogit_shared::git::{get_signature, has_origin, open_repository};
+use autogit_shared::{ChangeKind, FileChange, RemoteResult, Repository, SyncPreview};
+use git2::{Delta, DiffOptions, Oid, Repository as GitRepository, IndexAddOption, Status, StatusOptions};
 use chrono::Local;
 use notify_rust::Notification;
 use std::path::Path;
 use std::process::Command;
 use tracing::{debug, info, warn};
 
-/// Open a git repository with user-friendly error messages
-fn open_repository(path: &Path) -> Result<GitRepository> {
-    match GitRepository::open(path) {
-        Ok(repo) => Ok(repo),
-        Err(e) if e.code() == git2::ErrorCode::NotFound => {
-            bail!(
-                "Not a git repository: {}\n\
-                 The .git directory may have been deleted or the path is incorrect.\n\
-                 Use 'autogit remove {}' to remove it from configuration.",
-                path.display(),
-                path.display()
-            );
-        }
-        Err(e) => {
-            bail!("Failed to open repository {}: {}", path.display(), e);
+/// Outcome of a single sync of one repository
+#[derive(Debug, Clone, Default)]
+pub struct SyncOutcome {
+    /// The commit created during this sync, if any
+    pub commit: Option<Oid>,
+    /// Number of files included in that commit
+    pub files_changed: usize,
+    pub push: RemoteResult,
+    pub pull: RemoteResult,
+}
+
+impl SyncOutcome {
+    pub fn committed(&self) -> bool {
+        self.commit.is_some()
+    }
+}
+
+/// What a sync is allowed to do with the remote
+#[derive(Debug, Clone, Copy)]
+pub struct SyncOptions {
+    /// Pull and push (false defers both, e.g. on battery or a metered connection)
+    pub remote: bool,
+    /// Push even without a new commit, to catch up on an earlier deferred push
+    pub push_pending: bool,
+    /// Push new commits (false defers the push until a later sync)
+    pub push: bool,
+    /// Pull from the remote (false skips the pull)
+    pub pull: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            remote: true,
+            push_pending: false,
+            push: true,
+            pull: true,
         }
     }
 }
 
+/// Push, or record the push as deferred if remote operations aren't allowed
+fn push_or_defer(repo: &GitRepository, repo_path: &Path, options: SyncOptions) -> Result<RemoteResult> {
+    if options.remote && options.push {
+        push_changes(repo, repo_path)
+    } else if has_origin(repo)? {
+        debug!("Deferring push for: {}", repo_path.display());
+        Ok(RemoteResult::Deferred)
+    } else {
+        Ok(RemoteResult::Skipped)
+    }
+}
+
+/// Pull, or record the pull as deferred if remote operations aren't allowed
+fn pull_or_defer(repo: &GitRepository, repo_path: &Path, options: SyncOptions) -> Result<RemoteResult> {
+    if !options.pull {
+        Ok(RemoteResult::Skipped)
+    } else if options.remote {
+        pull_rebase(repo, repo_path)
+    } else if has_origin(repo)? {
+        debug!("Deferring pull for: {}", repo_path.display());
+        Ok(RemoteResult::Deferred)
+    } else {
+        Ok(RemoteResult::Skipped)
+    }
+}
+
 /// Push commits to remote
-/// Returns true if push was successful, false if skipped or failed
-fn push_changes(repo: &GitRepository, repo_path: &Path) -> Result<bool> {
+/// Failures are reported as `RemoteResult::Failed` rather than an error (non-fatal)
+fn push_changes(repo: &GitRepository, repo_path: &Path) -> Result<RemoteResult> {
     // Check if remote exists
-    let has_remote = match repo.find_remote("origin") {
-        Ok(_) => true,
+    match repo.find_remote("origin") {
+        Ok(_) => {}
         Err(e) if e.code() == git2::ErrorCode::NotFound => {
             debug!("No remote 'origin' configured for {}, skipping push", repo_path.display());
-            return Ok(false);
+            return Ok(RemoteResult::Skipped);
         }
         Err(e) => return Err(e.into()),
-    };
+    }
+
+    if !has_unpushed_commits(repo) {
+        debug!("Nothing to push for: {}", repo_path.display());
+        return Ok(RemoteResult::UpToDate);
+    }
+
+    // Run git push using Command
+    debug!("Pushing changes for: {}", repo_path.display());
+
+    let output = Command::new("git")
+        .arg("push")
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("Failed to execute git push for {}", repo_path.display()))?;
 
-    if !has_remote {
-        return Ok(false);
+    if output.status.success() {
+        info!("Successfully pushed changes: {}", repo_path.display());
+        Ok(RemoteResult::Done)
+    } else {
+        // Push failed - log but continue (non-fatal)
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!("Push failed for {}: {}", repo_path.display(), stderr.trim());
+
+        // Send desktop notification
+        let _ = Notification::new()
+            .summary("Git Push Failed")
+            .body(&format!("Repository: {}\n\nError:\n{}", repo_path.display(), stderr.trim()))
+            .appname(env!("CARGO_PKG_NAME"))
+            .show();
+
+        Ok(RemoteResult::Failed(stderr.trim().to_owned()))
     }
+}
 
-    // Check if there are unpushed commits by comparing local branch with its upstream
-    let has_unpushed_commits = match repo.head() {
+/// Check if there are unpushed commits by comparing the local branch with its upstream
+fn has_unpushed_commits(repo: &GitRepository) -> bool {
+    match repo.head() {
         Ok(head) => {
             if let Some(branch_name) = head.shorthand() {
                 // Try to find the upstream branch
@@ -74,56 +153,20 @@ fn push_changes(repo: &GitRepository, repo_path: &Path) -> Result<bool> {
             // If we can't determine, assume there might be changes
             true
         }
-    };
-
-    if !has_unpushed_commits {
-        debug!("Nothing to push for: {}", repo_path.display());
-        return Ok(true);
-    }
-
-    // Run git push using Command
-    debug!("Pushing changes for: {}", repo_path.display());
-
-    let output = Command::new("git")
-        .arg("push")
-        .current_dir(repo_path)
-        .output()
-        .with_context(|| format!("Failed to execute git push for {}", repo_path.display()))?;
-
-    if output.status.success() {
-        info!("Successfully pushed changes: {}", repo_path.display());
-        Ok(true)
-    } else {
-        // Push failed - log but continue (non-fatal)
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("Push failed for {}: {}", repo_path.display(), stderr.trim());
-
-        // Send desktop notification
-        let _ = Notification::new()
-            .summary("Git Push Failed")
-            .body(&format!("Repository: {}\n\nError:\n{}", repo_path.display(), stderr.trim()))
-            .appname(env!("CARGO_PKG_NAME"))
-            .show();
-
-        Ok(false)
     }
 }
 
 /// Pull and rebase from remote
-/// Returns true if pull was successful, false if skipped or failed
-fn pull_rebase(repo: &GitRepository, repo_path: &Path) -> Result<bool> {
+/// Failures are reported as `RemoteResult::Failed` rather than an error (non-fatal)
+fn pull_rebase(repo: &GitRepository, repo_path: &Path) -> Result<RemoteResult> {
     // Check if remote exists
-    let has_remote = match repo.find_remote("origin") {
-        Ok(_) => true,
+    match repo.find_remote("origin") {
+        Ok(_) => {}
         Err(e) if e.code() == git2::ErrorCode::NotFound => {
             warn!("No remote 'origin' configured for {}, skipping pull", repo_path.display());
-            return Ok(false);
+            return Ok(RemoteResult::Skipped);
         }
         Err(e) => return Err(e.into()),
-    };
-
-    if !has_remote {
-        return Ok(false);
     }
 
     // Get the current HEAD commit before pulling
@@ -146,10 +189,11 @@ fn pull_rebase(repo: &GitRepository, repo_path: &Path) -> Result<bool> {
 
         if head_before != head_after && head_after.is_some() {
             info!("Successfully pulled and rebased: {}", repo_path.display());
+            Ok(RemoteResult::Done)
         } else {
             debug!("Repository already up to date: {}", repo_path.display());
+            Ok(RemoteResult::UpToDate)
         }
-        Ok(true)
     } else {
         // Pull failed - try to abort rebase to clean up
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -183,26 +227,56 @@ fn pull_rebase(repo: &GitRepository, repo_path: &Path) -> Result<bool> {
             }
         }
 
-        // Return Ok(false) to indicate we should continue (non-fatal error)
-        Ok(false)
+        // Return Ok to indicate we should continue (non-fatal error)
+        Ok(RemoteResult::Failed(stderr.trim().to_owned()))
     }
 }
 
+/// Count commits ahead of and behind the upstream of the checked-out branch
+/// Returns None when the branch has no upstream or no commits yet; "behind" reflects the last fetch
+pub async fn ahead_behind(path: &Path) -> Result<Option<(usize, usize)>> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || ahead_behind_sync(&path))
+        .await
+        .context("Task panicked")?
+}
+
+fn ahead_behind_sync(path: &Path) -> Result<Option<(usize, usize)>> {
+    let repo = open_repository(path)?;
+    let head = match repo.head() {
+        Ok(head) if head.is_branch() => head,
+        _ => return Ok(None),
+    };
+    let (Some(local), Some(name)) = (head.target(), head.shorthand()) else {
+        return Ok(None);
+    };
+
+    let branch = repo.find_branch(name, git2::BranchType::Local)?;
+    let Some(upstream) = branch.upstream().ok().and_then(|upstream| upstream.get().target()) else {
+        return Ok(None);
+    };
+
+    let counts = repo.graph_ahead_behind(local, upstream)
+        .with_context(|| format!("Failed to compare {} with its upstream", path.display()))?;
+    Ok(Some(counts))
+}
+
 /// Initialize a repository on daemon startup
 /// Commits any pending changes and pulls from remote
-pub async fn initialize_repository(repo_config: &Repository) -> Result<()> {
-    tokio::task::spawn_blocking({
+pub async fn initialize_repository(repo_config: &Repository, options: SyncOptions) -> Result<SyncOutcome> {
+    let outcome = tokio::task::spawn_blocking({
         let repo_config = repo_config.clone();
-        move || initialize_repository_sync(&repo_config)
+        move || initialize_repository_sync(&repo_config, options)
     })
     .await
     .context("Task panicked")??;
 
-    Ok(())
+    Ok(outcome)
 }
 
-fn initialize_repository_sync(repo_config: &Repository) -> Result<()> {
+fn initialize_repository_sync(repo_config: &Repository, options: SyncOptions) -> Result<SyncOutcome> {
     let repo = open_repository(&repo_config.path)?;
+    let mut outcome = SyncOutcome::default();
 
     info!("Initializing repository: {}", repo_config.path.display());
 
@@ -214,44 +288,50 @@ fn initialize_repository_sync(repo_config: &Repository) -> Result<()> {
         stage_all_changes(&repo)?;
 
         // Check if there are actually staged changes
-        if has_staged_changes(&repo)? {
+        let files_changed = staged_change_count(&repo)?;
+        if files_changed > 0 {
             // Create a startup commit
             let commit_message = "Auto-commit on daemon startup";
-            create_commit(&repo, commit_message)?;
+            outcome.commit = Some(create_commit(&repo, commit_message)?);
+            outcome.files_changed = files_changed;
             info!("Committed pending changes in {}", repo_config.path.display());
 
             // Push the commit
-            push_changes(&repo, &repo_config.path)?;
+            outcome.push = push_or_defer(&repo, &repo_config.path, options)?;
         }
     }
 
+    if !outcome.committed() && options.remote && options.push && options.push_pending {
+        outcome.push = push_changes(&repo, &repo_config.path)?;
+    }
+
     // Try to pull and rebase
-    pull_rebase(&repo, &repo_config.path)?;
+    outcome.pull = pull_or_defer(&repo, &repo_config.path, options)?;
 
-    Ok(())
+    Ok(outcome)
 }
 
 /// Check if a repository has changes and commit them if needed
-/// Returns true if changes were committed, false otherwise
-pub async fn check_and_commit(repo_config: &Repository) -> Result<bool> {
+/// The returned outcome tells whether a commit was created and what happened with the remote
+pub async fn check_and_commit(repo_config: &Repository, options: SyncOptions) -> Result<SyncOutcome> {
     // Run blocking git operations in a blocking task
-    let committed = tokio::task::spawn_blocking({
+    let outcome = tokio::task::spawn_blocking({
         let repo_config = repo_config.clone();
-        move || check_and_commit_sync(&repo_config)
+        move || check_and_commit_sync(&repo_config, options)
     })
     .await
     .context("Task panicked")??;
 
-    Ok(committed)
+    Ok(outcome)
 }
 
-fn check_and_commit_sync(repo_config: &Repository) -> Result<bool> {
+fn check_and_commit_sync(repo_config: &Repository, options: SyncOptions) -> Result<SyncOutcome> {
     let repo = open_repository(&repo_config.path)?;
 
     // Check if there are any changes first
     let has_local_changes = has_changes(&repo)?;
 
-    let mut committed = false;
+    let mut outcome = SyncOutcome::default();
 
     // Commit local changes before pulling to avoid conflicts
     if has_local_changes {
@@ -259,22 +339,90 @@ fn check_and_commit_sync(repo_config: &Repository) -> Result<bool> {
         stage_all_changes(&repo)?;
 
         // Check again after staging (in case everything was already staged)
-        if has_staged_changes(&repo)? {
+        let files_changed = staged_change_count(&repo)?;
+        if files_changed > 0 {
             // Create commit
             let commit_message = format_commit_message(&repo_config.commit_message_template);
-            create_commit(&repo, &commit_message)?;
+            outcome.commit = Some(create_commit(&repo, &commit_message)?);
+            outcome.files_changed = files_changed;
             info!("Committed changes in {}: {}", repo_config.path.display(), commit_message);
-            committed = true;
 
             // Push the commit
-            push_changes(&repo, &repo_config.path)?;
+            outcome.push = push_or_defer(&repo, &repo_config.path, options)?;
         }
     }
 
+    // Catch up on a push that was deferred earlier
+    if !outcome.committed() && options.remote && options.push && options.push_pending {
+        outcome.push = push_changes(&repo, &repo_config.path)?;
+    }
+
     // Now pull and rebase (working directory is clean)
-    let _ = pull_rebase(&repo, &repo_config.path)?;
+    outcome.pull = pull_or_defer(&repo, &repo_config.path, options)?;
+
+    Ok(outcome)
+}
+
+/// Work out what `check_and_commit` would do, without touching the index or the remote
+pub async fn preview(repo_config: &Repository, options: SyncOptions) -> Result<SyncPreview> {
+    tokio::task::spawn_blocking({
+        let repo_config = repo_config.clone();
+        move || preview_sync(&repo_config, options)
+    })
+    .await
+    .context("Task panicked")?
+}
+
+fn preview_sync(repo_config: &Repository, options: SyncOptions) -> Result<SyncPreview> {
+    let repo = open_repository(&repo_config.path)?;
+    let files = pending_changes(&repo)?;
+    let commit_message = (!files.is_empty()).then(|| format_commit_message(&repo_config.commit_message_template));
+
+    // Same decisions as check_and_commit_sync: push a new commit, or catch up on a deferred push
+    let remote = options.remote && has_origin(&repo)?;
+    let push = remote && options.push
+        && (commit_message.is_some() || (options.push_pending && has_unpushed_commits(&repo)));
+
+    Ok(SyncPreview {
+        path: repo_config.path.clone(),
+        files,
+        commit_message,
+        pull: remote && options.pull,
+        push,
+        ..Default::default()
+    })
+}
+
+/// Files that staging all changes would put into the next commit (respects .gitignore)
+fn pending_changes(repo: &GitRepository) -> Result<Vec<FileChange>> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().context("Failed to read the HEAD tree")?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
 
-    Ok(committed)
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true);
+    opts.recurse_untracked_dirs(true);
+
+    let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
+        .context("Failed to compare the working tree with HEAD")?;
+
+    let changes = diff.deltas()
+        .filter_map(|delta| {
+            let change = match delta.status() {
+                Delta::Added | Delta::Untracked => ChangeKind::Added,
+                Delta::Modified => ChangeKind::Modified,
+                Delta::Deleted => ChangeKind::Deleted,
+                Delta::Typechange => ChangeKind::TypeChanged,
+                _ => return None,
+            };
+            let path = delta.new_file().path().or(delta.old_file().path())?;
+            Some(FileChange { path: path.to_path_buf(), change })
+        })
+        .collect();
+
+    Ok(changes)
 }
 
 /// Check if repository has any changes (staged or unstaged)
@@ -290,26 +438,29 @@ fn has_changes(repo: &GitRepository) -> Result<bool> {
 }
 
 /// Check if repository has staged changes
+#[cfg(test)]
 fn has_staged_changes(repo: &GitRepository) -> Result<bool> {
+    Ok(staged_change_count(repo)? > 0)
+}
+
+/// Count the files with staged changes
+fn staged_change_count(repo: &GitRepository) -> Result<usize> {
     let mut opts = StatusOptions::new();
 
     let statuses = repo.statuses(Some(&mut opts))
         .context("Failed to get repository status")?;
 
-    for entry in statuses.iter() {
-        let status = entry.status();
-        if status.intersects(
+    let count = statuses.iter()
+        .filter(|entry| entry.status().intersects(
             Status::INDEX_NEW
                 | Status::INDEX_MODIFIED
                 | Status::INDEX_DELETED
                 | Status::INDEX_RENAMED
                 | Status::INDEX_TYPECHANGE
-        ) {
-            return Ok(true);
-        }
-    }
+        ))
+        .count();
 
-    Ok(false)
+    Ok(count)
 }
 
 /// Stage all changes in the repository
@@ -332,7 +483,7 @@ fn stage_all_changes(repo: &GitRepository) -> Result<()> {
 }
 
 /// Create a commit with the given message
-fn create_commit(repo: &GitRepository, message: &str) -> Result<()> {
+fn create_commit(repo: &GitRepository, message: &str) -> Result<Oid> {
     // Get the signature from git config
     let signature = get_signature(repo)?;
 
@@ -356,7 +507,7 @@ fn create_commit(repo: &GitRepository, message: &str) -> Result<()> {
     // Create the commit
     let parents = parent_commit.as_ref().map(|c| vec![c]).unwrap_or_default();
 
-    repo.commit(
+    let oid = repo.commit(
         Some("HEAD"),
         &signature,
         &signature,
@@ -366,22 +517,7 @@ fn create_commit(repo: &GitRepository, message: &str) -> Result<()> {
     )
     .context("Failed to create commit")?;
 
-    Ok(())
-}
-
-/// Get git signature from repository config (respects .gitconfig)
-fn get_signature(repo: &GitRepository) -> Result<Signature<'static>> {
-    let config = repo.config()
-        .context("Failed to get repository config")?;
-
-    let name = config.get_string("user.name")
-        .context("user.name not set in git config")?;
-
-    let email = config.get_string("user.email")
-        .context("user.email not set in git config")?;
-
-    Signature::now(&name, &email)
-        .context("Failed to create signature")
+    Ok(oid)
 }
 
 /// Format commit message with placeholders replaced
@@ -397,6 +533,47 @@ fn format_commit_message(template: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_repo::init_repo;
+    use git2::Signature;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    #[tokio::test]
+    async fn test_ahead_behind() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let origin = temp_dir.path().join("origin.git");
+        git2::Repository::init_bare(&origin).unwrap();
+        git2::Repository::init(&repo_dir).unwrap();
+        git(&repo_dir, &["config", "user.name", "Test User"]);
+        git(&repo_dir, &["config", "user.email", "test@example.com"]);
+
+        // Unborn branch, then a branch without upstream
+        assert_eq!(ahead_behind(&repo_dir).await.unwrap(), None);
+        git(&repo_dir, &["commit", "--allow-empty", "-m", "first"]);
+        assert_eq!(ahead_behind(&repo_dir).await.unwrap(), None);
+
+        git(&repo_dir, &["remote", "add", "origin", origin.to_str().unwrap()]);
+        git(&repo_dir, &["push", "-u", "origin", "HEAD"]);
+        assert_eq!(ahead_behind(&repo_dir).await.unwrap(), Some((0, 0)));
+
+        git(&repo_dir, &["commit", "--allow-empty", "-m", "second"]);
+        git(&repo_dir, &["commit", "--allow-empty", "-m", "third"]);
+        assert_eq!(ahead_behind(&repo_dir).await.unwrap(), Some((2, 0)));
+
+        git(&repo_dir, &["push"]);
+        git(&repo_dir, &["reset", "--hard", "HEAD~1"]);
+        assert_eq!(ahead_behind(&repo_dir).await.unwrap(), Some((0, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_ahead_behind_missing_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(ahead_behind(&temp_dir.path().join("missing")).await.is_err());
+    }
 
     #[test]
     fn test_format_commit_message() {
@@ -616,13 +793,7 @@ mod tests {
     // Helper to create a test git repository
     fn create_test_repo() -> (tempfile::TempDir, GitRepository) {
         let temp_dir = tempfile::tempdir().unwrap();
-        let repo = GitRepository::init(temp_dir.path()).unwrap();
-
-        // Configure git user for commits
-        let mut config = repo.config().unwrap();
-        config.set_str("user.name", "Test User").unwrap();
-        config.set_str("user.email", "test@example.com").unwrap();
-
+        let repo = init_repo(temp_dir.path());
         (temp_dir, repo)
     }
 
@@ -659,22 +830,6 @@ mod tests {
         std::fs::write(repo_path.join(filename), content).unwrap();
     }
 
-    #[test]
-    fn test_open_repository_success() {
-        let (_temp, repo) = create_test_repo();
-        let result = open_repository(repo.path().parent().unwrap());
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_open_repository_not_found() {
-        let temp_dir = tempfile::tempdir().unwrap();
-        let result = open_repository(temp_dir.path());
-        assert!(result.is_err());
-        let err = result.err().unwrap();
-        assert!(err.to_string().contains("Not a git repository"));
-    }
-
     #[test]
     fn test_has_changes_empty_repo() {
         let (_temp, repo) = create_test_repo();
@@ -827,38 +982,18 @@ mod tests {
         assert_eq!(commit.parent_count(), 1);
     }
 
-    #[test]
-    fn test_get_signature_success() {
-        let (_temp, repo) = create_test_repo();
-        let signature = get_signature(&repo).unwrap();
-        assert_eq!(signature.name().unwrap(), "Test User");
-        assert_eq!(signature.email().unwrap(), "test@example.com");
-    }
-
-    #[test]
-    fn test_get_signature_uses_repo_config() {
-        let (_temp, repo) = create_test_repo();
-
-        // We configured the repo with specific values
-        let signature = get_signature(&repo).unwrap();
-
-        // Should use the test config (not global config)
-        assert_eq!(signature.name().unwrap(), "Test User");
-        assert_eq!(signature.email().unwrap(), "test@example.com");
-    }
-
     #[test]
     fn test_push_changes_no_remote() {
         let (_temp, repo) = create_test_repo();
         let result = push_changes(&repo, repo.path().parent().unwrap()).unwrap();
-        assert!(!result); // Should return false when no remote
+        assert_eq!(result, RemoteResult::Skipped); // Should be skipped when no remote
     }
 
     #[test]
     fn test_pull_rebase_no_remote() {
         let (_temp, repo) = create_test_repo();
         let result = pull_rebase(&repo, repo.path().parent().unwrap()).unwrap();
-        assert!(!result); // Should return false when no remote
+        assert_eq!(result, RemoteResult::Skipped); // Should be skipped when no remote
     }
 
     #[tokio::test]
@@ -877,8 +1012,9 @@ mod tests {
             commit_message_template: "Auto: {timestamp}".to_owned(),
         };
 
-        let committed = check_and_commit(&repo_config).await.unwrap();
-        assert!(!committed);
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(!outcome.committed());
+        assert_eq!(outcome.files_changed, 0);
     }
 
     #[tokio::test]
@@ -900,8 +1036,8 @@ mod tests {
             commit_message_template: "Auto: {timestamp}".to_owned(),
         };
 
-        let committed = check_and_commit(&repo_config).await.unwrap();
-        assert!(committed);
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(outcome.committed());
 
         // Verify the commit was created
         let repo_after = GitRepository::open(temp.path()).unwrap();
@@ -929,8 +1065,8 @@ mod tests {
             commit_message_template: "New file added".to_owned(),
         };
 
-        let committed = check_and_commit(&repo_config).await.unwrap();
-        assert!(committed);
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(outcome.committed());
 
         // Verify the new file was committed
         let repo_after = GitRepository::open(temp.path()).unwrap();
@@ -939,6 +1075,35 @@ mod tests {
         assert_eq!(commit.message().unwrap(), "New file added");
     }
 
+    #[tokio::test]
+    async fn test_check_and_commit_reports_outcome() {
+        let (temp, repo) = create_test_repo();
+        write_test_file(temp.path(), "initial.txt", "content");
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("initial.txt")).unwrap();
+        index.write().unwrap();
+        create_test_commit(&repo, "Initial commit");
+
+        write_test_file(temp.path(), "initial.txt", "changed");
+        write_test_file(temp.path(), "a.txt", "a");
+        write_test_file(temp.path(), "b.txt", "b");
+
+        let repo_config = Repository {
+            path: temp.path().to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Auto".to_owned(),
+        };
+
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert_eq!(outcome.files_changed, 3);
+        assert_eq!(outcome.push, RemoteResult::Skipped); // No remote configured
+        assert_eq!(outcome.pull, RemoteResult::Skipped);
+
+        let repo_after = GitRepository::open(temp.path()).unwrap();
+        assert_eq!(outcome.commit, repo_after.head().unwrap().target());
+    }
+
     #[tokio::test]
     async fn test_initialize_repository_clean() {
         let (temp, repo) = create_test_repo();
@@ -956,7 +1121,7 @@ mod tests {
         };
 
         // Should succeed without errors
-        let result = initialize_repository(&repo_config).await;
+        let result = initialize_repository(&repo_config, SyncOptions::default()).await;
         assert!(result.is_ok());
     }
 
@@ -979,7 +1144,7 @@ mod tests {
             commit_message_template: "Auto: {timestamp}".to_owned(),
         };
 
-        let result = initialize_repository(&repo_config).await;
+        let result = initialize_repository(&repo_config, SyncOptions::default()).await;
         assert!(result.is_ok());
 
         // Verify the change was committed
@@ -1011,8 +1176,8 @@ mod tests {
             commit_message_template: "Auto commit".to_owned(),
         };
 
-        let committed = check_and_commit(&repo_config).await.unwrap();
-        assert!(committed);
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(outcome.committed());
 
         // Verify only tracked file was committed
         let repo_after = GitRepository::open(temp.path()).unwrap();
@@ -1023,4 +1188,195 @@ mod tests {
         assert!(tree.get_name("tracked.txt").is_some());
         assert!(tree.get_name("ignored.log").is_none());
     }
+
+    /// Create a repository with one commit, pushed to a bare 'origin' next to it
+    fn create_test_repo_with_origin() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let origin = temp_dir.path().join("origin.git");
+        let work = temp_dir.path().join("work");
+
+        GitRepository::init_bare(&origin).unwrap();
+        let repo = init_repo(&work);
+        repo.remote("origin", origin.to_str().unwrap()).unwrap();
+
+        write_test_file(&work, "test.txt", "content");
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("test.txt")).unwrap();
+        index.write().unwrap();
+        create_test_commit(&repo, "Initial commit");
+
+        let output = Command::new("git")
+            .args(["push", "-u", "origin", "HEAD"])
+            .current_dir(&work)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        (temp_dir, work, origin)
+    }
+
+    fn origin_head(origin: &Path) -> Option<Oid> {
+        let repo = GitRepository::open_bare(origin).unwrap();
+        let head = repo.head().ok()?.target();
+        head
+    }
+
+    #[tokio::test]
+    async fn test_check_and_commit_defers_remote() {
+        let (_temp, work, origin) = create_test_repo_with_origin();
+        let pushed = origin_head(&origin);
+        write_test_file(&work, "test.txt", "modified");
+
+        let repo_config = Repository {
+            path: work.clone(),
+            auto_commit: true,
+            commit_message_template: "Auto commit".to_owned(),
+        };
+
+        let deferred = SyncOptions { remote: false, ..Default::default() };
+        let outcome = check_and_commit(&repo_config, deferred).await.unwrap();
+        assert!(outcome.committed());
+        assert_eq!(outcome.push, RemoteResult::Deferred);
+        assert_eq!(outcome.pull, RemoteResult::Deferred);
+        assert_eq!(origin_head(&origin), pushed); // Nothing left the machine
+
+        // Without a new commit, the deferred push is only caught up on when asked to
+        let outcome = check_and_commit(&repo_config, SyncOptions::default()).await.unwrap();
+        assert_eq!(outcome.push, RemoteResult::Skipped);
+        assert_eq!(origin_head(&origin), pushed);
+
+        let catch_up = SyncOptions { push_pending: true, ..Default::default() };
+        let outcome = check_and_commit(&repo_config, catch_up).await.unwrap();
+        assert!(!outcome.committed());
+        assert_eq!(outcome.push, RemoteResult::Done);
+        assert_eq!(origin_head(&origin), GitRepository::open(&work).unwrap().head().unwrap().target());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_remote_without_origin_is_skipped() {
+        let (temp, _repo) = create_test_repo();
+        write_test_file(temp.path(), "test.txt", "content");
+
+        let repo_config = Repository {
+            path: temp.path().to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Auto commit".to_owned(),
+        };
+
+        let deferred = SyncOptions { remote: false, ..Default::default() };
+        let outcome = check_and_commit(&repo_config, deferred).await.unwrap();
+        assert!(outcome.committed());
+        assert_eq!(outcome.push, RemoteResult::Skipped);
+        assert_eq!(outcome.pull, RemoteResult::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_check_and_commit_without_push_or_pull() {
+        let (_temp, work, origin) = create_test_repo_with_origin();
+        let pushed = origin_head(&origin);
+        write_test_file(&work, "test.txt", "modified");
+
+        let repo_config = Repository {
+            path: work.clone(),
+            auto_commit: true,
+            commit_message_template: "Auto commit".to_owned(),
+        };
+
+        let local_only = SyncOptions { push: false, pull: false, ..Default::default() };
+        let outcome = check_and_commit(&repo_config, local_only).await.unwrap();
+        assert!(outcome.committed());
+        // The push is left for a later sync, the pull is simply not done
+        assert_eq!(outcome.push, RemoteResult::Deferred);
+        assert_eq!(outcome.pull, RemoteResult::Skipped);
+        assert_eq!(origin_head(&origin), pushed);
+    }
+
+    #[tokio::test]
+    async fn test_preview_lists_pending_changes() {
+        let (temp, repo) = create_test_repo();
+        write_test_file(temp.path(), ".gitignore", "*.log\n");
+        write_test_file(temp.path(), "kept.txt", "content");
+        write_test_file(temp.path(), "removed.txt", "content");
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let head = create_test_commit(&repo, "Initial commit");
+
+        write_test_file(temp.path(), "kept.txt", "modified");
+        std::fs::remove_file(temp.path().join("removed.txt")).unwrap();
+        std::fs::create_dir(temp.path().join("notes")).unwrap();
+        write_test_file(temp.path(), "notes/new.md", "new");
+        write_test_file(temp.path(), "ignored.log", "log content");
+        let status_before = Command::new("git").args(["status", "--porcelain"]).current_dir(temp.path()).output().unwrap();
+
+        let repo_config = Repository {
+            path: temp.path().to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Notes from {date}".to_owned(),
+        };
+
+        let preview = preview(&repo_config, SyncOptions::default()).await.unwrap();
+        let mut files: Vec<_> = preview.files.iter().map(|f| (f.path.to_str().unwrap(), f.change)).collect();
+        files.sort_by_key(|(path, _)| *path);
+        assert_eq!(files, vec![
+            ("kept.txt", ChangeKind::Modified),
+            ("notes/new.md", ChangeKind::Added),
+            ("removed.txt", ChangeKind::Deleted),
+        ]);
+        assert_eq!(preview.commit_message, Some(format_commit_message("Notes from {date}")));
+        // Without an origin there is nothing to pull from or push to
+        assert!(!preview.pull && !preview.push);
+
+        // Nothing was staged or committed
+        let status_after = Command::new("git").args(["status", "--porcelain"]).current_dir(temp.path()).output().unwrap();
+        assert_eq!(status_before.stdout, status_after.stdout);
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+    }
+
+    #[tokio::test]
+    async fn test_preview_clean_repository() {
+        let (_temp, work, _origin) = create_test_repo_with_origin();
+        let repo_config = Repository {
+            path: work,
+            auto_commit: true,
+            commit_message_template: "Auto commit".to_owned(),
+        };
+
+        let clean = preview(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(clean.files.is_empty());
+        assert_eq!(clean.commit_message, None);
+        assert!(clean.pull);
+        assert!(!clean.push);
+
+        // A deferred push is only caught up on when there is something to push
+        let catch_up = SyncOptions { push_pending: true, ..Default::default() };
+        assert!(!preview(&repo_config, catch_up).await.unwrap().push);
+    }
+
+    #[tokio::test]
+    async fn test_preview_remote_decisions() {
+        let (_temp, work, origin) = create_test_repo_with_origin();
+        let pushed = origin_head(&origin);
+        write_test_file(&work, "test.txt", "modified");
+
+        let repo_config = Repository {
+            path: work.clone(),
+            auto_commit: true,
+            commit_message_template: "Auto commit".to_owned(),
+        };
+
+        let full = preview(&repo_config, SyncOptions::default()).await.unwrap();
+        assert!(full.pull && full.push);
+
+        let local_only = SyncOptions { push: false, pull: false, ..Default::default() };
+        let local = preview(&repo_config, local_only).await.unwrap();
+        assert!(!local.pull && !local.push);
+
+        let deferred = SyncOptions { remote: false, ..Default::default() };
+        let deferred = preview(&repo_config, deferred).await.unwrap();
+        assert!(!deferred.pull && !deferred.push);
+
+        assert_eq!(origin_head(&origin), pushed);
+        assert_eq!(full.files.len(), 1);
+    }
 }
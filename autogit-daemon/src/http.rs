@@ -0,0 +1,491 @@
+use anyhow::{Context, Result, bail};
+use autogit_shared::{Command, CommandError, JournalFilter, Response, ResponseStatus};
+use axum::Router;
+use axum::extract::{Path as UrlPath, Query, Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::routing::{get, post};
+use futures_util::Stream;
+use std::convert::Infallible;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::socket::{self, Controller};
+
+/// Bind the API to the loopback interface only
+pub async fn bind(port: u16) -> Result<TcpListener> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpListener::bind(addr).await
+        .with_context(|| format!("Failed to bind HTTP API to {}", addr))
+}
+
+/// Serve the API until the listener fails, to clients presenting `token`
+pub async fn serve(listener: TcpListener, api: Controller, token: String) -> Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        info!("HTTP API listening on http://{}", addr);
+    }
+
+    axum::serve(listener, router(api, Arc::from(token))).await
+        .context("HTTP API server failed")
+}
+
+fn router(api: Controller, token: Arc<str>) -> Router {
+    Router::new()
+        .route("/api/command", post(command))
+        .route("/api/status", get(status))
+        .route("/api/history", get(history))
+        .route("/api/events", get(events))
+        .route("/api/{command}", post(named_command))
+        .layer(middleware::from_fn_with_state(token, require_token))
+        .with_state(api)
+}
+
+/// Read the bearer token, generating a random one on first use
+///
+/// An existing file must belong to the daemon's user and be unreadable to anyone else, since the
+/// token grants full control over the daemon.
+pub fn load_or_create_token(path: &Path) -> Result<String> {
+    match std::fs::File::open(path) {
+        Ok(mut file) => {
+            let metadata = file.metadata()
+                .with_context(|| format!("Failed to inspect HTTP API token file: {}", path.display()))?;
+            if metadata.uid() != socket::effective_uid() {
+                bail!(
+                    "HTTP API token file {} is owned by uid {}, not by the current user",
+                    path.display(),
+                    metadata.uid()
+                );
+            }
+            if metadata.mode() & 0o077 != 0 {
+                bail!(
+                    "HTTP API token file {} is accessible to other users (mode {:o}); restrict it with: chmod 600 {}",
+                    path.display(),
+                    metadata.mode() & 0o777,
+                    path.display()
+                );
+            }
+
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)
+                .with_context(|| format!("Failed to read HTTP API token file: {}", path.display()))?;
+
+            let token = contents.trim();
+            if token.is_empty() {
+                bail!("HTTP API token file is empty: {}", path.display());
+            }
+            Ok(token.to_owned())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let token = generate_token()?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)
+                .with_context(|| format!("Failed to create HTTP API token file: {}", path.display()))?;
+            writeln!(file, "{}", token)
+                .with_context(|| format!("Failed to write HTTP API token file: {}", path.display()))?;
+
+            info!("Created HTTP API token file: {}", path.display());
+            Ok(token)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to read HTTP API token file: {}", path.display())),
+    }
+}
+
+/// 32 random bytes from the kernel, hex encoded
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .context("Failed to generate HTTP API token")?;
+
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Reject requests without the right `Authorization: Bearer` header
+async fn require_token(State(token): State<Arc<str>>, request: Request, next: Next) -> HttpResponse {
+    let presented = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if presented.is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes())) {
+        return next.run(request).await;
+    }
+
+    warn!("Rejected HTTP API request without a valid token: {} {}", request.method(), request.uri().path());
+    let mut response = reply(Response::error("Missing or invalid bearer token"));
+    *response.status_mut() = StatusCode::UNAUTHORIZED;
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Compare tokens without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// `POST /api/command` with any socket protocol command as the body
+async fn command(State(api): State<Controller>, body: String) -> HttpResponse {
+    match Command::parse(&body) {
+        Ok(command) => execute(&api, command).await,
+        Err(e) => rejected(&e),
+    }
+}
+
+/// `POST /api/<name>` with the command's fields (if any) as the body, e.g. `/api/trigger`
+async fn named_command(State(api): State<Controller>, UrlPath(name): UrlPath<String>, body: String) -> HttpResponse {
+    match command_from_body(&name, &body) {
+        Ok(command) => execute(&api, command).await,
+        Err(e) => rejected(&e),
+    }
+}
+
+async fn status(State(api): State<Controller>) -> HttpResponse {
+    execute(&api, Command::Status).await
+}
+
+/// `GET /api/history?repo=…&since=…&errors_only=true&limit=…`
+async fn history(State(api): State<Controller>, Query(filter): Query<JournalFilter>) -> HttpResponse {
+    execute(&api, Command::History(filter)).await
+}
+
+/// `GET /api/events`: daemon events as Server-Sent Events, named after their type
+async fn events(State(api): State<Controller>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = api.state.subscribe();
+
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    // Same JSON as on the socket, with the event type as the SSE event name
+                    let name = serde_json::to_value(&event.kind).ok()
+                        .and_then(|value| value.get("event")?.as_str().map(str::to_owned))
+                        .unwrap_or_else(|| "event".to_owned());
+                    let data = event.to_json().unwrap_or_default();
+                    let sse = Event::default().event(name).data(data.trim_end());
+                    return Some((Ok(sse), receiver));
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("HTTP event subscriber fell behind, dropped {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Build a command from its name and an optional JSON object of fields
+fn command_from_body(name: &str, body: &str) -> Result<Command, CommandError> {
+    let mut fields = if body.trim().is_empty() {
+        serde_json::Map::new()
+    } else {
+        match serde_json::from_str(body) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            Ok(_) => return Err(CommandError::Malformed("request body must be a JSON object".to_owned())),
+            Err(e) => return Err(CommandError::Malformed(e.to_string())),
+        }
+    };
+
+    fields.insert("command".to_owned(), serde_json::Value::String(name.to_owned()));
+    Command::parse(&serde_json::Value::Object(fields).to_string())
+}
+
+async fn execute(api: &Controller, command: Command) -> HttpResponse {
+    if let Command::Subscribe = command {
+        return reply(Response::error("Subscribe with GET /api/events instead"));
+    }
+
+    info!("Received HTTP API command: {:?}", command);
+    reply(api.execute(command).await)
+}
+
+fn rejected(error: &CommandError) -> HttpResponse {
+    warn!("Rejected HTTP API command: {}", error);
+    let mut response = reply(socket::rejected_command_response(error));
+    if let CommandError::Unknown(_) = error {
+        *response.status_mut() = StatusCode::NOT_FOUND;
+    }
+    response
+}
+
+/// The protocol response as JSON, with 400 for errors
+fn reply(response: Response) -> HttpResponse {
+    let status = match response.status {
+        ResponseStatus::Ok => StatusCode::OK,
+        ResponseStatus::Error => StatusCode::BAD_REQUEST,
+    };
+    (status, axum::Json(response)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DaemonState;
+    use autogit_shared::{Config, EventKind, Journal, ResponseData};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpStream;
+    use tokio::sync::{RwLock, mpsc};
+
+    const TOKEN: &str = "secret-token";
+
+    struct TestApi {
+        addr: SocketAddr,
+        state: Arc<DaemonState>,
+        suspended: Arc<AtomicBool>,
+        _state_dir: TempDir,
+    }
+
+    async fn start_api() -> TestApi {
+        let state_dir = TempDir::new().unwrap();
+        let state = Arc::new(DaemonState::new(Journal::new(state_dir.path().join("history.jsonl"))));
+        let suspended = Arc::new(AtomicBool::new(false));
+        let (config_tx, _) = mpsc::channel(1);
+
+        let api = Controller {
+            config: Arc::new(RwLock::new(Config::default())),
+            start_time: Instant::now(),
+            state: state.clone(),
+            suspended: suspended.clone(),
+            config_tx,
+        };
+
+        let listener = bind(0).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, api, TOKEN.to_owned()));
+
+        TestApi { addr, state, suspended, _state_dir: state_dir }
+    }
+
+    /// Send one request and return the status code and body
+    async fn request(addr: SocketAddr, method: &str, path: &str, token: Option<&str>, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let auth = token.map(|t| format!("Authorization: Bearer {}\r\n", t)).unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method, path, auth, body.len(), body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).await.unwrap();
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_owned())
+    }
+
+    #[tokio::test]
+    async fn test_binds_loopback_only() {
+        let listener = bind(0).await.unwrap();
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let api = start_api().await;
+
+        let (status, body) = request(api.addr, "GET", "/api/status", None, "").await;
+        assert_eq!(status, 401);
+        assert!(body.contains("bearer token"));
+
+        let (status, _) = request(api.addr, "GET", "/api/status", Some("wrong"), "").await;
+        assert_eq!(status, 401);
+
+        let (status, body) = request(api.addr, "GET", "/api/status", Some(TOKEN), "").await;
+        assert_eq!(status, 200);
+        let response: Response = serde_json::from_str(&body).unwrap();
+        assert!(matches!(response.data, Some(ResponseData::Status { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_suspend_and_resume() {
+        let api = start_api().await;
+
+        let (status, _) = request(api.addr, "POST", "/api/suspend", Some(TOKEN), "").await;
+        assert_eq!(status, 200);
+        assert!(api.suspended.load(Ordering::Relaxed));
+
+        let (status, _) = request(api.addr, "POST", "/api/resume", Some(TOKEN), "").await;
+        assert_eq!(status, 200);
+        assert!(!api.suspended.load(Ordering::Relaxed));
+
+        // A timed suspension with the same fields as the socket command
+        let body = r#"{"until":"2099-01-01T00:00:00Z"}"#;
+        let (status, _) = request(api.addr, "POST", "/api/suspend", Some(TOKEN), body).await;
+        assert_eq!(status, 200);
+        assert!(api.state.suspended_until().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_and_generic_command() {
+        let api = start_api().await;
+
+        let (status, body) = request(api.addr, "POST", "/api/trigger", Some(TOKEN), "").await;
+        assert_eq!(status, 200, "{}", body);
+
+        let body = r#"{"repos":["/not/configured"]}"#;
+        let (status, body) = request(api.addr, "POST", "/api/trigger", Some(TOKEN), body).await;
+        assert_eq!(status, 400);
+        assert!(body.contains("not found in configuration"));
+
+        let (status, body) = request(api.addr, "POST", "/api/command", Some(TOKEN), r#"{"command":"ping"}"#).await;
+        assert_eq!(status, 200);
+        assert!(body.contains("pong"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unknown_and_malformed_commands() {
+        let api = start_api().await;
+
+        let (status, body) = request(api.addr, "POST", "/api/frobnicate", Some(TOKEN), "").await;
+        assert_eq!(status, 404);
+        // Carries the handshake so clients learn what this daemon supports
+        assert!(body.contains("\"commands\""));
+
+        let (status, _) = request(api.addr, "POST", "/api/suspend", Some(TOKEN), "[1, 2]").await;
+        assert_eq!(status, 400);
+
+        let (status, _) = request(api.addr, "POST", "/api/command", Some(TOKEN), "not json").await;
+        assert_eq!(status, 400);
+
+        let (status, _) = request(api.addr, "POST", "/api/subscribe", Some(TOKEN), "").await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_history_query() {
+        let api = start_api().await;
+        for repo in ["/a", "/b"] {
+            api.state.journal().append(&autogit_shared::JournalEntry {
+                timestamp: chrono::Utc::now(),
+                repo: PathBuf::from(repo),
+                commit: Some("abc".to_owned()),
+                files_changed: 1,
+                push: Default::default(),
+                pull: Default::default(),
+                error: None,
+            }).unwrap();
+        }
+
+        let (status, body) = request(api.addr, "GET", "/api/history?repo=/b&limit=5", Some(TOKEN), "").await;
+        assert_eq!(status, 200);
+        let response: Response = serde_json::from_str(&body).unwrap();
+        match response.data {
+            Some(ResponseData::History { entries }) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].repo, PathBuf::from("/b"));
+            }
+            other => panic!("Expected History data, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_stream() {
+        let api = start_api().await;
+
+        let mut stream = TcpStream::connect(api.addr).await.unwrap();
+        let request = format!(
+            "GET /api/events HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\n\r\n",
+            TOKEN
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert!(line.starts_with("HTTP/1.1 200"));
+
+        // The subscription exists once the headers are sent
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            if line.to_ascii_lowercase().starts_with("content-type") {
+                assert!(line.contains("text/event-stream"));
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        api.state.emit(EventKind::Resumed);
+
+        let mut received = String::new();
+        while !received.contains("data:") {
+            line.clear();
+            tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+                .await
+                .expect("Timed out waiting for event")
+                .unwrap();
+            received.push_str(&line);
+        }
+        assert!(received.contains("event: resumed"));
+        assert!(received.contains(r#""event":"resumed""#));
+    }
+
+    #[test]
+    fn test_load_or_create_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("autogit").join("http-token");
+
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        // Reused on the next start
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+
+        std::fs::write(&path, "  my-token\n").unwrap();
+        assert_eq!(load_or_create_token(&path).unwrap(), "my-token");
+
+        std::fs::write(&path, "\n").unwrap();
+        assert!(load_or_create_token(&path).is_err());
+
+        // Refused once other users can read it
+        std::fs::write(&path, "my-token\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let error = load_or_create_token(&path).unwrap_err().to_string();
+        assert!(error.contains("accessible to other users (mode 644)"), "{}", error);
+    }
+
+    #[test]
+    fn test_command_from_body() {
+        assert!(matches!(command_from_body("resume", ""), Ok(Command::Resume)));
+        assert!(matches!(
+            command_from_body("trigger", r#"{"push":false}"#),
+            Ok(Command::Trigger { push: false, pull: true, repos: None, dry_run: false })
+        ));
+        assert!(matches!(command_from_body("nope", ""), Err(CommandError::Unknown(_))));
+        assert!(matches!(command_from_body("pause", "{}"), Err(CommandError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use autogit_shared::{Command, Response, ResponseStatus};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Exit code when another daemon is already running (systemd is told not to restart on it)
+pub const EXIT_ALREADY_RUNNING: i32 = 3;
+
+/// How long to wait for a running daemon to answer a ping
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Exclusive lock on the pid file, held for the lifetime of the daemon
+///
+/// The kernel releases the flock when the process exits, so a crashed daemon never leaves a
+/// stale lock behind.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Why the daemon may not start
+#[derive(Debug, thiserror::Error)]
+pub enum InstanceError {
+    #[error("autogit-daemon is already running{}", describe_pid(*.pid))]
+    AlreadyRunning { pid: Option<u32> },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn describe_pid(pid: Option<u32>) -> String {
+    pid.map(|pid| format!(" (pid {})", pid)).unwrap_or_default()
+}
+
+impl InstanceLock {
+    /// Lock the pid file and record our pid, or report the daemon holding it
+    pub fn acquire(pid_path: &Path) -> Result<Self, InstanceError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(pid_path)
+            .with_context(|| format!("Failed to open pid file: {}", pid_path.display()))?;
+
+        // SAFETY: the descriptor stays valid for the lifetime of `file`
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::WouldBlock {
+                let mut contents = String::new();
+                let _ = file.read_to_string(&mut contents);
+                return Err(InstanceError::AlreadyRunning { pid: contents.trim().parse().ok() });
+            }
+            return Err(anyhow::Error::new(error)
+                .context(format!("Failed to lock pid file: {}", pid_path.display()))
+                .into());
+        }
+
+        file.set_len(0)
+            .and_then(|_| file.rewind())
+            .and_then(|_| writeln!(file, "{}", std::process::id()))
+            .with_context(|| format!("Failed to write pid file: {}", pid_path.display()))?;
+
+        Ok(Self { _file: file })
+    }
+
+    /// Lock the pid file, also refusing when a daemon answers on any of `sockets`
+    ///
+    /// Daemons from before the pid file only show themselves by answering a ping, on the current
+    /// socket or, before the runtime directory, on the one under the config directory.
+    pub fn claim(pid_path: &Path, sockets: &[PathBuf]) -> Result<Self, InstanceError> {
+        let lock = Self::acquire(pid_path)?;
+        if sockets.iter().any(|socket| daemon_responds(socket)) {
+            return Err(InstanceError::AlreadyRunning { pid: None });
+        }
+        Ok(lock)
+    }
+}
+
+/// Whether a daemon answers a ping on the socket (catches daemons that predate the pid file)
+pub fn daemon_responds(socket_path: &Path) -> bool {
+    let ping = || -> Result<bool> {
+        let mut stream = UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(PING_TIMEOUT))?;
+        stream.set_write_timeout(Some(PING_TIMEOUT))?;
+        stream.write_all(Command::Ping.to_json()?.as_bytes())?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        Ok(Response::from_json(&line)?.status == ResponseStatus::Ok)
+    };
+
+    ping().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_lock_is_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let pid_path = temp_dir.path().join("daemon.pid");
+
+        let lock = InstanceLock::acquire(&pid_path).unwrap();
+        let recorded = std::fs::read_to_string(&pid_path).unwrap();
+        assert_eq!(recorded.trim(), std::process::id().to_string());
+
+        match InstanceLock::acquire(&pid_path) {
+            Err(e @ InstanceError::AlreadyRunning { pid: Some(pid) }) => {
+                assert_eq!(pid, std::process::id());
+                assert!(e.to_string().contains(&format!("already running (pid {})", pid)));
+            }
+            other => panic!("Expected AlreadyRunning, got {:?}", other),
+        }
+
+        // Released when the daemon exits
+        drop(lock);
+        assert!(InstanceLock::acquire(&pid_path).is_ok());
+    }
+
+    #[test]
+    fn test_stale_pid_file_is_taken_over() {
+        let temp_dir = TempDir::new().unwrap();
+        let pid_path = temp_dir.path().join("daemon.pid");
+        std::fs::write(&pid_path, "999999999\nleftover\n").unwrap();
+
+        let _lock = InstanceLock::acquire(&pid_path).unwrap();
+        let recorded = std::fs::read_to_string(&pid_path).unwrap();
+        assert_eq!(recorded, format!("{}\n", std::process::id()));
+    }
+
+    #[test]
+    fn test_acquire_in_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let result = InstanceLock::acquire(&temp_dir.path().join("missing").join("daemon.pid"));
+        assert!(matches!(result, Err(InstanceError::Other(_))));
+    }
+
+    /// Answer one ping on `socket_path` from a background thread
+    fn answer_ping(socket_path: &Path) -> std::thread::JoinHandle<()> {
+        let listener = UnixListener::bind(socket_path).unwrap();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            assert!(line.contains("ping"));
+            (&stream).write_all(Response::ok("pong").to_json().unwrap().as_bytes()).unwrap();
+        })
+    }
+
+    #[test]
+    fn test_claim_refuses_daemon_on_legacy_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        let pid_path = temp_dir.path().join("daemon.pid");
+        let sockets = [temp_dir.path().join("daemon.sock"), temp_dir.path().join("legacy.sock")];
+
+        // Nobody answers on either socket
+        drop(InstanceLock::claim(&pid_path, &sockets).unwrap());
+
+        // An old daemon still listening where sockets used to live
+        let server = answer_ping(&sockets[1]);
+        let result = InstanceLock::claim(&pid_path, &sockets);
+        assert!(matches!(result, Err(InstanceError::AlreadyRunning { pid: None })), "{:?}", result);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_daemon_responds() {
+        let temp_dir = TempDir::new().unwrap();
+        let socket_path = temp_dir.path().join("daemon.sock");
+
+        // Nothing there
+        assert!(!daemon_responds(&socket_path));
+
+        let server = answer_ping(&socket_path);
+        assert!(daemon_responds(&socket_path));
+        server.join().unwrap();
+
+        // A socket file nobody listens on
+        assert!(!daemon_responds(&socket_path));
+    }
+}
@@ -1,16 +1,30 @@
+mod conditions;
+mod dbus;
 mod git;
+mod http;
+mod instance;
+mod sleep;
 mod socket;
+mod state;
+mod sync;
+#[cfg(test)]
+mod test_bus;
+#[cfg(test)]
+mod test_repo;
 mod tray;
 
 use anyhow::{Context, Result};
-use autogit_shared::Config;
+use autogit_shared::{Config, ConfigChange, DaemonConfig, EventKind, Journal, Repository, Response, WatchRoot, legacy_socket_path, pid_path, socket_path};
+use instance::InstanceError;
+use state::DaemonState;
+use sync::SyncTrigger;
 use notify::{Watcher, RecursiveMode, Event};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::{RwLock, mpsc};
 use tokio::signal::unix::{signal, SignalKind};
-use tracing::{info, error, warn};
+use tracing::{debug, info, error, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -24,45 +38,44 @@ async fn main() -> Result<()> {
 
     info!("Starting autogit-daemon v{}", env!("CARGO_PKG_VERSION"));
 
+    // Refuse to run next to another daemon, before touching any repository
+    let _instance = claim_instance()
+        .context("Failed to check for a running daemon")?;
+
     // Load configuration
     let config_path = Config::default_config_path()
         .context("Failed to get default config path")?;
 
     info!("Loading configuration from: {}", config_path.display());
 
-    let config = Config::load_or_create_default()
+    let mut config = Config::load_or_create_default()
         .context("Failed to load configuration")?;
+    config.discover();
+
+    info!(
+        "Loaded configuration with {} repositories ({} found under watch roots)",
+        config.repositories.len() + config.discovered.len(),
+        config.discovered.len()
+    );
+
+    // Sync history journal
+    let journal_path = Journal::default_path()
+        .context("Failed to get sync history path")?;
+    info!("Recording sync history to: {}", journal_path.display());
+    let state = Arc::new(DaemonState::new(Journal::new(journal_path)));
 
-    info!("Loaded configuration with {} repositories", config.repositories.len());
+    // Evaluate the battery, network and idle policy before touching any remote
+    update_schedule(&config.daemon, &state).await;
 
     // Initialize all repositories (commit pending changes and pull)
     info!("Initializing repositories...");
     let mut initial_repo_details = Vec::new();
-    for repo in &config.repositories {
+    for repo in config.all_repositories() {
         if !repo.auto_commit {
             continue;
         }
 
-        match git::initialize_repository(repo).await {
-            Ok(()) => {
-                info!("Initialized repository: {}", repo.path.display());
-                initial_repo_details.push(autogit_shared::protocol::RepoDetail {
-                    path: repo.path.clone(),
-                    committed: false,  // Initialization doesn't track if commit happened
-                    files_changed: None,
-                    error: None,
-                });
-            }
-            Err(e) => {
-                error!("Error initializing repository {}: {:#}", repo.path.display(), e);
-                initial_repo_details.push(autogit_shared::protocol::RepoDetail {
-                    path: repo.path.clone(),
-                    committed: false,
-                    files_changed: None,
-                    error: Some(format!("{:#}", e)),
-                });
-            }
-        }
+        initial_repo_details.push(sync::initialize_repository(repo, &config.daemon, &state).await);
     }
     info!("Repository initialization complete");
 
@@ -73,11 +86,15 @@ async fn main() -> Result<()> {
     let (reload_tx, reload_rx) = mpsc::channel(10);
     let config_path_clone = config_path.clone();
 
+    let watched_path = config_path.clone();
     let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         match res {
             Ok(event) => {
-                // Only trigger reload on modify events
-                if event.kind.is_modify() {
+                // Saves replace the file by renaming over it, so also react to creation
+                let config_changed = (event.kind.is_modify() || event.kind.is_create())
+                    && event.paths.contains(&watched_path);
+
+                if config_changed {
                     info!("Config file changed, triggering reload");
                     let _ = reload_tx.blocking_send(());
                 }
@@ -89,9 +106,11 @@ async fn main() -> Result<()> {
     })
     .context("Failed to create config file watcher")?;
 
-    // Watch the config file
-    watcher.watch(&config_path, RecursiveMode::NonRecursive)
-        .with_context(|| format!("Failed to watch config file: {}", config_path.display()))?;
+    // Watch the directory, since an atomic save leaves a watch on the old file dangling
+    let config_dir = config_path.parent()
+        .context("Config file has no parent directory")?;
+    watcher.watch(config_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch config directory: {}", config_dir.display()))?;
 
     info!("Watching config file for changes: {}", config_path.display());
 
@@ -107,7 +126,7 @@ async fn main() -> Result<()> {
     let enable_tray = config.read().await.daemon.enable_tray;
     let initial_tray = if enable_tray {
         let cfg = config.read().await;
-        let repo_count = cfg.repositories.len();
+        let repo_count = cfg.all_repositories().count();
         let check_interval = cfg.daemon.check_interval_seconds;
         drop(cfg);
 
@@ -139,6 +158,26 @@ async fn main() -> Result<()> {
 
     let tray_handle = Arc::new(RwLock::new(initial_tray));
 
+    // Hold back system sleep until the last changes are committed and pushed; the watcher runs
+    // even while the option is off, so that turning it on needs no restart
+    {
+        let (config, state, suspended) = (Arc::clone(&config), Arc::clone(&state), Arc::clone(&suspended));
+        tokio::spawn(async move {
+            let result = async {
+                let connection = zbus::Connection::system().await?;
+                sleep::watch_sleep(&connection, Arc::clone(&config), state, suspended).await
+            }.await;
+
+            if let Err(e) = result {
+                if config.read().await.daemon.sync_before_sleep {
+                    warn!("Sync before sleep unavailable: {:#}", e);
+                } else {
+                    debug!("Sleep watcher unavailable: {:#}", e);
+                }
+            }
+        });
+    }
+
     // Set up signal handling for graceful shutdown
     let sigterm = signal(SignalKind::terminate())
         .context("Failed to create SIGTERM handler")?;
@@ -157,6 +196,7 @@ async fn main() -> Result<()> {
         sigint,
         socket_listener,
         start_time,
+        state,
         tray_handle,
         tray_action_rx,
         suspended,
@@ -174,6 +214,29 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Take the single-instance lock, exiting with `EXIT_ALREADY_RUNNING` if another daemon holds it
+fn claim_instance() -> Result<instance::InstanceLock> {
+    let socket_path = socket_path()?;
+    if let Some(dir) = socket_path.parent() {
+        socket::create_private_dir(dir)?;
+    }
+
+    let mut sockets = vec![socket_path.clone()];
+    sockets.extend(legacy_socket_path().ok().filter(|legacy| *legacy != socket_path));
+
+    let result = instance::InstanceLock::claim(&pid_path(&socket_path)?, &sockets);
+
+    match result {
+        Ok(lock) => Ok(lock),
+        Err(e @ InstanceError::AlreadyRunning { .. }) => {
+            error!("{}; stop it first with: systemctl --user stop autogit-daemon", e);
+            std::process::exit(instance::EXIT_ALREADY_RUNNING);
+        }
+        Err(InstanceError::Other(e)) => Err(e),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_daemon(
     config: Arc<RwLock<Config>>,
     config_path: PathBuf,
@@ -182,6 +245,7 @@ pub(crate) async fn run_daemon(
     mut sigint: tokio::signal::unix::Signal,
     socket_listener: tokio::net::UnixListener,
     start_time: Instant,
+    state: Arc<DaemonState>,
     tray_handle: Arc<RwLock<Option<ksni::Handle<tray::AutogitTray>>>>,
     mut tray_action_rx: mpsc::Receiver<tray::TrayAction>,
     suspended: Arc<std::sync::atomic::AtomicBool>,
@@ -197,13 +261,47 @@ pub(crate) async fn run_daemon(
 
     // Skip the first immediate tick since we already initialized repositories
     interval.tick().await;
+    schedule_next_check(&state, &interval);
+
+    // Socket clients hand configuration changes to this loop, which applies them one at a time
+    let (config_tx, mut config_rx) = mpsc::channel::<socket::ConfigRequest>(10);
+
+    // The HTTP API and D-Bus interface execute the same commands as the socket
+    let controller = socket::Controller {
+        config: Arc::clone(&config),
+        start_time,
+        state: Arc::clone(&state),
+        suspended: Arc::clone(&suspended),
+        config_tx: config_tx.clone(),
+    };
+    let daemon_config = config.read().await.daemon.clone();
+
+    if daemon_config.http_api_port != 0 {
+        if let Err(e) = start_http_api(&daemon_config, controller.clone()).await {
+            warn!("HTTP API unavailable: {:#}", e);
+        }
+    }
+
+    // Kept alive until the daemon exits, releasing the bus name
+    let _dbus = if daemon_config.dbus_interface {
+        match dbus::publish_session(controller).await {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                warn!("D-Bus interface unavailable: {:#}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     loop {
         tokio::select! {
             biased;
 
             _ = sigterm.recv() => {
-                info!("Received SIGTERM, shutting down gracefully");
+                info!("Received SIGTERM, running final sync before shutting down");
+                sync::final_sync(&*config.read().await, &state, &suspended).await;
                 break;
             }
 
@@ -215,13 +313,17 @@ pub(crate) async fn run_daemon(
             // Handle incoming socket connections
             Ok((stream, _addr)) = socket_listener.accept() => {
                 let config_clone = Arc::clone(&config);
+                let state_clone = Arc::clone(&state);
                 let suspended_clone = Arc::clone(&suspended);
+                let config_tx = config_tx.clone();
                 tokio::spawn(async move {
-                    socket::handle_connection(stream, config_clone, start_time, suspended_clone).await;
+                    socket::handle_connection(stream, config_clone, start_time, state_clone, suspended_clone, config_tx).await;
                 });
             }
 
             _ = interval.tick() => {
+                schedule_next_check(&state, &interval);
+
                 // Attempt to retry tray initialization if it failed initially
                 let enable_tray = config.read().await.daemon.enable_tray;
                 let current_retries = tray_retry_count.load(std::sync::atomic::Ordering::Relaxed);
@@ -231,7 +333,7 @@ pub(crate) async fn run_daemon(
                     info!("Retrying system tray initialization (attempt {}/3)", new_count);
 
                     let cfg = config.read().await;
-                    let repo_count = cfg.repositories.len();
+                    let repo_count = cfg.all_repositories().count();
                     let check_interval = cfg.daemon.check_interval_seconds;
                     drop(cfg);
 
@@ -256,66 +358,57 @@ pub(crate) async fn run_daemon(
                     }
                 }
 
+                // Resume automatically once a timed suspension runs out
+                if state.expire_suspension(&suspended) {
+                    info!("Timed suspension ended, daemon resumed");
+                }
+
+                // Refresh remaining suspension and pause times in the tray
+                if let Some(tray) = tray_handle.read().await.as_ref() {
+                    let (suspended_until, paused) = (state.suspended_until(), state.paused_repos());
+                    tray.update(move |t| {
+                        t.set_pause_info(suspended_until, paused);
+                    }).await;
+                }
+
                 // Skip if daemon is suspended
                 if suspended.load(std::sync::atomic::Ordering::Relaxed) {
                     continue;
                 }
 
+                // Pick up repositories created or removed under the watch roots since the last check
+                refresh_discovered(&config, &state, &tray_handle).await;
+
                 // Normal check cycle
-                if let Some(ref tray) = tray_handle.read().await.as_ref() {
+                if let Some(tray) = tray_handle.read().await.as_ref() {
                     tray.update(|t| {
-                        let _ = t.set_status(tray::TrayStatus::Syncing);
+                        t.set_status(tray::TrayStatus::Syncing);
                     }).await;
                 }
 
                 let cfg = config.read().await;
-                let mut any_errors = false;
-                let mut repo_details = Vec::new();
+
+                update_schedule(&cfg.daemon, &state).await;
 
                 // Process each repository
-                for repo in &cfg.repositories {
-                    if !repo.auto_commit {
-                        continue;
-                    }
+                let repo_details = sync::sync_all(&cfg, &state, SyncTrigger::Scheduled).await;
 
-                    match git::check_and_commit(repo).await {
-                        Ok(committed) => {
-                            if committed {
-                                info!("Committed changes in: {}", repo.path.display());
-                            }
-                            repo_details.push(autogit_shared::protocol::RepoDetail {
-                                path: repo.path.clone(),
-                                committed,
-                                files_changed: None,
-                                error: None,
-                            });
-                        }
-                        Err(e) => {
-                            error!("Error processing repository {}: {:#}", repo.path.display(), e);
-                            any_errors = true;
-                            repo_details.push(autogit_shared::protocol::RepoDetail {
-                                path: repo.path.clone(),
-                                committed: false,
-                                files_changed: None,
-                                error: Some(format!("{:#}", e)),
-                            });
-                        }
-                    }
-                }
+                let failing = repo_details.iter().filter(|d| d.error.is_some()).count();
 
                 // Update tray status and repository details
-                if let Some(ref tray) = tray_handle.read().await.as_ref() {
+                if let Some(tray) = tray_handle.read().await.as_ref() {
                     tray.update(|t| {
-                        let _ = t.update_repo_details(repo_details.clone());
+                        t.update_repo_details(repo_details.clone());
                     }).await;
 
-                    if any_errors {
-                        tray.update(|t| {
-                            let _ = t.increment_errors();
+                    if failing > 0 {
+                        tray.update(move |t| {
+                            t.set_error_count(failing);
                         }).await;
                     } else {
                         tray.update(|t| {
-                            let _ = t.set_last_sync();
+                            t.set_error_count(0);
+                            t.set_last_sync();
                         }).await;
                     }
                 }
@@ -326,106 +419,58 @@ pub(crate) async fn run_daemon(
                 info!("Reloading configuration from: {}", config_path.display());
 
                 match Config::load(&config_path) {
-                    Ok(new_config) => {
-                        let (old_interval, old_enable_tray) = {
-                            let cfg = config.read().await;
-                            (cfg.daemon.check_interval_seconds, cfg.daemon.enable_tray)
-                        };
+                    Ok(mut new_config) => {
+                        new_config.discover();
 
-                        let new_interval = new_config.daemon.check_interval_seconds;
-                        let new_enable_tray = new_config.daemon.enable_tray;
-
-                        // Find new repositories (those not in old config)
-                        let new_repos: Vec<_> = {
-                            let old_config = config.read().await;
-                            new_config.repositories.iter()
-                                .filter(|new_repo| {
-                                    !old_config.repositories.iter()
-                                        .any(|old_repo| old_repo.path == new_repo.path)
-                                })
-                                .cloned()
-                                .collect()
-                        };
+                        // Our own saves of client changes come back through the watcher
+                        if new_config == *config.read().await {
+                            info!("Configuration unchanged");
+                            continue;
+                        }
 
-                        // Initialize new repositories
-                        for repo in &new_repos {
-                            if !repo.auto_commit {
-                                continue;
-                            }
+                        let new_interval = apply_config(
+                            new_config, &config, &state, &tray_handle, &tray_action_tx, &suspended, &tray_retry_count,
+                        ).await;
 
-                            info!("Initializing newly added repository: {}", repo.path.display());
-                            match git::initialize_repository(repo).await {
-                                Ok(()) => {
-                                    info!("Initialized repository: {}", repo.path.display());
-                                }
-                                Err(e) => {
-                                    error!("Error initializing repository {}: {:#}", repo.path.display(), e);
-                                }
-                            }
+                        if let Some(seconds) = new_interval {
+                            interval = tokio::time::interval(std::time::Duration::from_secs(seconds));
                         }
+                    }
+                    Err(e) => {
+                        error!("Failed to reload configuration: {:#}", e);
+                        warn!("Keeping previous configuration");
+                    }
+                }
+            }
 
-                        // Update config
-                        *config.write().await = new_config;
-
-                        let new_repo_count = config.read().await.repositories.len();
-                        info!("Configuration reloaded successfully with {} repositories", new_repo_count);
+            // Configuration changes and reloads requested over the socket
+            Some(request) = config_rx.recv() => {
+                let discovered = config.read().await.discovered.clone();
+                let response = match change_config_file(&config_path, request.change.as_ref(), discovered) {
+                    Ok(new_config) => {
+                        let message = match &request.change {
+                            Some(change) => change.summary(),
+                            None => format!("Configuration reloaded with {} repositories", new_config.all_repositories().count()),
+                        };
+                        info!("{}", message);
 
-                        // Update tray with new repository count and interval
-                        if let Some(ref tray) = tray_handle.read().await.as_ref() {
-                            tray.update(|t| {
-                                let _ = t.set_repo_count(new_repo_count);
-                            }).await;
+                        let new_interval = apply_config(
+                            new_config, &config, &state, &tray_handle, &tray_action_tx, &suspended, &tray_retry_count,
+                        ).await;
 
-                            // Update check interval in tray if changed
-                            if old_interval != new_interval {
-                                tray.update(|t| {
-                                    let _ = t.set_check_interval(new_interval);
-                                }).await;
-                            }
+                        if let Some(seconds) = new_interval {
+                            interval = tokio::time::interval(std::time::Duration::from_secs(seconds));
                         }
 
-                        // Update interval if it changed
-                        if old_interval != new_interval {
-                            info!("Check interval changed from {}s to {}s, updating timer",
-                                  old_interval, new_interval);
-                            interval = tokio::time::interval(std::time::Duration::from_secs(new_interval));
-                        }
-
-                        // Handle tray enable/disable changes
-                        if old_enable_tray != new_enable_tray {
-                            if new_enable_tray {
-                                // Tray was disabled, now enable it
-                                info!("System tray enabled in configuration, spawning tray icon");
-                                let repo_count = new_repo_count;
-                                let tray = tray::AutogitTray::new(repo_count, tray_action_tx.clone(), suspended.clone());
-                                tray.set_check_interval(new_interval);
-                                // Note: repo_details will be populated on next sync cycle
-                                match tray.spawn_tray().await {
-                                    Ok(handle) => {
-                                        info!("System tray icon spawned successfully");
-                                        *tray_handle.write().await = Some(handle);
-                                        tray_retry_count.store(3, std::sync::atomic::Ordering::Relaxed);
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to spawn system tray icon: {:#}", e);
-                                        warn!("Will retry up to 3 times on next interval ticks");
-                                        tray_retry_count.store(0, std::sync::atomic::Ordering::Relaxed);
-                                    }
-                                }
-                            } else {
-                                // Tray was enabled, now disable it
-                                info!("System tray disabled in configuration, removing tray icon");
-                                *tray_handle.write().await = None;
-                                tray_retry_count.store(3, std::sync::atomic::Ordering::Relaxed);
-                                info!("System tray icon removed");
-                            }
-                        }
+                        Response::ok(message)
                     }
                     Err(e) => {
-                        error!("Failed to reload configuration: {:#}", e);
-                        warn!("Keeping previous configuration");
+                        warn!("Rejected configuration change: {:#}", e);
+                        Response::failure(&e)
                     }
-                }
+                };
+
+                let _ = request.reply.send(response);
             }
 
             // Handle tray icon actions
@@ -439,59 +484,56 @@ pub(crate) async fn run_daemon(
                         }
 
                         info!("Manual sync triggered from tray icon");
-                        if let Some(ref tray) = tray_handle.read().await.as_ref() {
+                        if let Some(tray) = tray_handle.read().await.as_ref() {
                             tray.update(|t| {
-                                let _ = t.set_status(tray::TrayStatus::Syncing);
+                                t.set_status(tray::TrayStatus::Syncing);
                             }).await;
                         }
 
-                        let cfg = config.read().await;
-                        let mut any_errors = false;
+                        let repo_details = sync::sync_all(&*config.read().await, &state, SyncTrigger::Manual).await;
 
-                        for repo in &cfg.repositories {
-                            if !repo.auto_commit {
-                                continue;
-                            }
+                        let failing = repo_details.iter().filter(|d| d.error.is_some()).count();
 
-                            match git::check_and_commit(repo).await {
-                                Ok(committed) => {
-                                    if committed {
-                                        info!("Committed changes in: {}", repo.path.display());
-                                    }
+                        if let Some(tray) = tray_handle.read().await.as_ref() {
+                            tray.update(move |t| {
+                                t.update_repo_details(repo_details);
+                                t.set_error_count(failing);
+                                if failing == 0 {
+                                    t.set_last_sync();
                                 }
-                                Err(e) => {
-                                    error!("Error processing repository {}: {:#}", repo.path.display(), e);
-                                    any_errors = true;
-                                }
-                            }
+                            }).await;
                         }
+                    }
 
-                        if let Some(ref tray) = tray_handle.read().await.as_ref() {
-                            if any_errors {
-                                tray.update(|t| {
-                                    let _ = t.increment_errors();
-                                }).await;
-                            } else {
-                                tray.update(|t| {
-                                    let _ = t.set_last_sync();
-                                }).await;
-                            }
+                    tray::TrayAction::ClearQuarantine => {
+                        let released = state.clear_quarantine(None);
+                        info!("Cleared quarantine for {} repositories from tray icon", released.len());
+
+                        // Released repositories are retried on the next tick
+                        if let Some(tray) = tray_handle.read().await.as_ref() {
+                            tray.update(|t| {
+                                t.clear_quarantine_flags();
+                            }).await;
                         }
                     }
 
                     tray::TrayAction::ToggleSuspend => {
                         let new_state = !suspended.load(std::sync::atomic::Ordering::Relaxed);
-                        suspended.store(new_state, std::sync::atomic::Ordering::Relaxed);
 
                         if new_state {
+                            state.suspend(&suspended, None);
                             info!("Daemon suspended");
                         } else {
+                            state.resume(&suspended);
                             info!("Daemon resumed");
                         }
 
                         // Update tray to reflect new state
-                        if let Some(ref tray) = tray_handle.read().await.as_ref() {
-                            tray.update(|_t| {}).await;
+                        if let Some(tray) = tray_handle.read().await.as_ref() {
+                            let paused = state.paused_repos();
+                            tray.update(move |t| {
+                                t.set_pause_info(None, paused);
+                            }).await;
                         }
                     }
 
@@ -507,10 +549,252 @@ pub(crate) async fn run_daemon(
     Ok(())
 }
 
+/// Read the config file and apply a client's change to it, saving the result
+///
+/// Starting from the file (not the running config) keeps edits made in an editor
+/// since the last reload, and refuses to overwrite a file that doesn't parse. The change
+/// sees the repositories the running config discovered.
+fn change_config_file(path: &Path, change: Option<&ConfigChange>, discovered: Vec<Repository>) -> Result<Config> {
+    let mut config = Config::load(path)?;
+    config.discovered = discovered;
+
+    if let Some(change) = change {
+        config.apply(change)?;
+        config.save(path)?;
+    }
+
+    config.discover();
+    Ok(config)
+}
+
+/// Bind the HTTP API and serve it in the background
+async fn start_http_api(daemon: &DaemonConfig, controller: socket::Controller) -> Result<()> {
+    let token = http::load_or_create_token(&daemon.http_api_token_path()?)?;
+    let listener = http::bind(daemon.http_api_port).await?;
+
+    tokio::spawn(async move {
+        if let Err(e) = http::serve(listener, controller, token).await {
+            error!("{:#}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Publish when the timer fires next, for status requests
+fn schedule_next_check(state: &DaemonState, interval: &tokio::time::Interval) {
+    let period = chrono::Duration::from_std(interval.period()).unwrap_or_default();
+    state.set_next_check(chrono::Utc::now() + period);
+}
+
+/// Switch to a new configuration: initialize added repositories, forget removed ones
+/// and update the tray. Returns the new check interval if it changed.
+async fn apply_config(
+    new_config: Config,
+    config: &RwLock<Config>,
+    state: &DaemonState,
+    tray_handle: &RwLock<Option<ksni::Handle<tray::AutogitTray>>>,
+    tray_action_tx: &mpsc::Sender<tray::TrayAction>,
+    suspended: &Arc<std::sync::atomic::AtomicBool>,
+    tray_retry_count: &std::sync::atomic::AtomicU8,
+) -> Option<u64> {
+    let (old_interval, old_enable_tray) = {
+        let cfg = config.read().await;
+
+        // The HTTP API and D-Bus interface are set up once at startup
+        if (cfg.daemon.http_api_port, &cfg.daemon.http_api_token_file)
+            != (new_config.daemon.http_api_port, &new_config.daemon.http_api_token_file)
+        {
+            warn!("HTTP API settings changed; restart the daemon to apply them");
+        }
+        if cfg.daemon.dbus_interface != new_config.daemon.dbus_interface {
+            warn!("D-Bus interface setting changed; restart the daemon to apply it");
+        }
+
+        (cfg.daemon.check_interval_seconds, cfg.daemon.enable_tray)
+    };
+
+    let new_interval = new_config.daemon.check_interval_seconds;
+    let new_enable_tray = new_config.daemon.enable_tray;
+
+    // Find new repositories (those not in old config)
+    let new_repos: Vec<_> = {
+        let old_config = config.read().await;
+        new_config.all_repositories()
+            .filter(|new_repo| {
+                !old_config.all_repositories()
+                    .any(|old_repo| old_repo.path == new_repo.path)
+            })
+            .cloned()
+            .collect()
+    };
+
+    // Initialize new repositories
+    for repo in &new_repos {
+        if !repo.auto_commit {
+            continue;
+        }
+
+        info!("Initializing newly added repository: {}", repo.path.display());
+        sync::initialize_repository(repo, &new_config.daemon, state).await;
+    }
+
+    // Forget failures of repositories that were removed
+    let configured: Vec<_> = new_config.all_repositories()
+        .map(|repo| repo.path.clone())
+        .collect();
+    state.retain_repos(&configured);
+
+    // Update config
+    *config.write().await = new_config;
+
+    let new_repo_count = config.read().await.all_repositories().count();
+    info!("Configuration reloaded successfully with {} repositories", new_repo_count);
+    state.emit(EventKind::ConfigReloaded { repositories: new_repo_count });
+
+    // Update tray with new repository count and interval
+    if let Some(tray) = tray_handle.read().await.as_ref() {
+        tray.update(|t| {
+            t.set_repo_count(new_repo_count);
+        }).await;
+
+        // Update check interval in tray if changed
+        if old_interval != new_interval {
+            tray.update(|t| {
+                t.set_check_interval(new_interval);
+            }).await;
+        }
+    }
+
+    // Handle tray enable/disable changes
+    if old_enable_tray != new_enable_tray {
+        if new_enable_tray {
+            // Tray was disabled, now enable it
+            info!("System tray enabled in configuration, spawning tray icon");
+            let repo_count = new_repo_count;
+            let tray = tray::AutogitTray::new(repo_count, tray_action_tx.clone(), suspended.clone());
+            tray.set_check_interval(new_interval);
+            // Note: repo_details will be populated on next sync cycle
+            match tray.spawn_tray().await {
+                Ok(handle) => {
+                    info!("System tray icon spawned successfully");
+                    *tray_handle.write().await = Some(handle);
+                    tray_retry_count.store(3, std::sync::atomic::Ordering::Relaxed);
+                }
+                Err(e) => {
+                    warn!("Failed to spawn system tray icon: {:#}", e);
+                    warn!("Will retry up to 3 times on next interval ticks");
+                    tray_retry_count.store(0, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        } else {
+            // Tray was enabled, now disable it
+            info!("System tray disabled in configuration, removing tray icon");
+            *tray_handle.write().await = None;
+            tray_retry_count.store(3, std::sync::atomic::Ordering::Relaxed);
+            info!("System tray icon removed");
+        }
+    }
+
+    // The caller replaces its timer if the interval changed
+    if old_interval != new_interval {
+        info!("Check interval changed from {}s to {}s, updating timer", old_interval, new_interval);
+        Some(new_interval)
+    } else {
+        None
+    }
+}
+
+/// Search the watch roots again and follow repositories created or removed under them
+///
+/// The search runs on a blocking thread without the config lock, so requests aren't held up
+/// by a slow filesystem.
+async fn refresh_discovered(
+    config: &RwLock<Config>,
+    state: &DaemonState,
+    tray_handle: &RwLock<Option<ksni::Handle<tray::AutogitTray>>>,
+) {
+    let watch_roots = config.read().await.watch_roots.clone();
+    if watch_roots.is_empty() {
+        return;
+    }
+
+    let found = match tokio::task::spawn_blocking({
+        let watch_roots = watch_roots.clone();
+        move || WatchRoot::discover_all(&watch_roots)
+    }).await {
+        Ok(found) => found,
+        Err(e) => {
+            warn!("Failed to search the watch roots: {}", e);
+            return;
+        }
+    };
+
+    let (new_repos, daemon, configured) = {
+        let mut cfg = config.write().await;
+
+        // A reload replaced the roots meanwhile; it discovered the repositories itself
+        if cfg.watch_roots != watch_roots {
+            return;
+        }
+
+        let old: Vec<_> = cfg.discovered.iter().map(|repo| repo.path.clone()).collect();
+        if !cfg.set_discovered(found) {
+            return;
+        }
+
+        let new_repos: Vec<_> = cfg.discovered.iter()
+            .filter(|repo| !old.contains(&repo.path))
+            .cloned()
+            .collect();
+        let configured: Vec<_> = cfg.all_repositories().map(|repo| repo.path.clone()).collect();
+        (new_repos, cfg.daemon.clone(), configured)
+    };
+
+    info!("Repositories under the watch roots changed, now following {}", configured.len());
+
+    for repo in new_repos.iter().filter(|repo| repo.auto_commit) {
+        info!("Initializing newly discovered repository: {}", repo.path.display());
+        sync::initialize_repository(repo, &daemon, state).await;
+    }
+
+    // Forget failures of repositories that disappeared
+    state.retain_repos(&configured);
+
+    if let Some(tray) = tray_handle.read().await.as_ref() {
+        let repo_count = configured.len();
+        tray.update(move |t| {
+            t.set_repo_count(repo_count);
+        }).await;
+    }
+}
+
+/// Re-evaluate the scheduling policy, logging when deferral starts or ends
+async fn update_schedule(policy: &DaemonConfig, state: &DaemonState) {
+    let conditions = conditions::probe(policy, Path::new("/")).await;
+    let schedule = conditions::evaluate(policy, &conditions);
+    let previous = state.schedule().unwrap_or_default();
+
+    if schedule.remote_deferred != previous.remote_deferred {
+        match &schedule.remote_deferred {
+            Some(reason) => info!("Deferring pulls and pushes: {}", reason),
+            None => info!("Remote operations resumed"),
+        }
+    }
+    if schedule.commit_deferred != previous.commit_deferred {
+        match &schedule.commit_deferred {
+            Some(reason) => info!("Deferring commits: {}", reason),
+            None => info!("Commits resumed"),
+        }
+    }
+
+    state.set_schedule(schedule);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use autogit_shared::{Config, DaemonConfig, Repository};
+    use autogit_shared::{Config, DaemonConfig, Journal, Repository};
     use std::env;
     use tempfile::TempDir;
     use tokio::net::UnixListener;
@@ -522,11 +806,19 @@ mod tests {
             daemon: DaemonConfig {
                 check_interval_seconds: 1, // Short interval for testing
                 enable_tray: false, // Disable tray in tests
+                dbus_interface: false, // Stay off the user's session bus
+                ..Default::default()
             },
             repositories: vec![],
+            ..Default::default()
         }
     }
 
+    // Helper to create daemon state with a journal inside the test directory
+    fn create_test_state(temp_dir: &TempDir) -> Arc<DaemonState> {
+        Arc::new(DaemonState::new(Journal::new(temp_dir.path().join("history.jsonl"))))
+    }
+
     // Helper to create a test socket listener
     async fn create_test_socket() -> UnixListener {
         let temp_dir = TempDir::new().unwrap();
@@ -541,6 +833,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create initial config
         let config_path = Config::default_config_path().unwrap();
@@ -557,6 +850,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -578,6 +872,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -626,6 +921,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create initial config with no repos
         let config_path = Config::default_config_path().unwrap();
@@ -640,6 +936,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -661,6 +958,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -713,6 +1011,83 @@ mod tests {
         let _ = std::fs::remove_file(&config_path);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_watch_root_picks_up_new_repositories() {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        let notes = temp_dir.path().canonicalize().unwrap().join("notes");
+        std::fs::create_dir_all(notes.join("work/.git")).unwrap();
+
+        // Disabled by the root's settings, so the ticks don't try to sync the fake repositories
+        let config_path = Config::default_config_path().unwrap();
+        let mut config = create_test_config();
+        config.watch_roots.push(autogit_shared::WatchRoot {
+            path: notes.clone(),
+            depth: 1,
+            auto_commit: false,
+            commit_message_template: "Notes: {timestamp}".to_owned(),
+        });
+        config.save(&config_path).unwrap();
+        config.discover();
+        assert_eq!(config.discovered.len(), 1);
+
+        let config = Arc::new(RwLock::new(config));
+
+        let (_reload_tx, reload_rx) = mpsc::channel(10);
+        let sigterm = signal(SignalKind::terminate()).unwrap();
+        let sigint = signal(SignalKind::interrupt()).unwrap();
+        let socket_listener = create_test_socket().await;
+        let state = create_test_state(&temp_dir);
+        let mut events = state.subscribe();
+
+        let tray_handle = Arc::new(RwLock::new(None));
+        let (_tray_action_tx, tray_action_rx) = mpsc::channel(10);
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (tray_action_tx, _tray_action_rx2) = mpsc::channel(10);
+        let tray_retry_count = Arc::new(std::sync::atomic::AtomicU8::new(3));
+
+        let daemon_handle = tokio::spawn(run_daemon(
+            Arc::clone(&config),
+            config_path.clone(),
+            reload_rx,
+            sigterm,
+            sigint,
+            socket_listener,
+            Instant::now(),
+            Arc::clone(&state),
+            tray_handle,
+            tray_action_rx,
+            suspended,
+            tray_action_tx,
+            tray_retry_count,
+        ));
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        std::fs::create_dir_all(notes.join("journal/.git")).unwrap();
+
+        // Found on the next check, with the root's settings
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        {
+            let cfg = config.read().await;
+            let paths: Vec<_> = cfg.discovered.iter().map(|repo| repo.path.clone()).collect();
+            assert_eq!(paths, [notes.join("journal"), notes.join("work")]);
+            assert_eq!(cfg.discovered[0].commit_message_template, "Notes: {timestamp}");
+            assert!(cfg.repositories.is_empty());
+        }
+
+        // Only the discovered set changed, so the configuration wasn't reloaded
+        while let Ok(event) = events.try_recv() {
+            assert!(!matches!(event.kind, EventKind::ConfigReloaded { .. }), "{:?}", event);
+        }
+
+        daemon_handle.abort();
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_config_reload_handles_invalid_config() {
@@ -720,6 +1095,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create initial config
         let config_path = Config::default_config_path().unwrap();
@@ -735,6 +1111,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -756,6 +1133,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -796,6 +1174,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create initial config with one repo
         let config_path = Config::default_config_path().unwrap();
@@ -815,6 +1194,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -836,6 +1216,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -889,6 +1270,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create config with very short interval
         let config_path = Config::default_config_path().unwrap();
@@ -904,6 +1286,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -925,6 +1308,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -953,6 +1337,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create initial config
         let config_path = Config::default_config_path().unwrap();
@@ -967,6 +1352,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -988,6 +1374,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1019,7 +1406,7 @@ mod tests {
         {
             let cfg = config.read().await;
             assert_eq!(cfg.repositories.len(), 1);
-            assert_eq!(cfg.repositories[0].auto_commit, false);
+            assert!(!cfg.repositories[0].auto_commit);
         }
 
         // Shutdown daemon
@@ -1036,6 +1423,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let config = create_test_config();
@@ -1048,6 +1436,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1067,6 +1456,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1097,6 +1487,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let config = create_test_config();
@@ -1109,6 +1500,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1129,6 +1521,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1161,6 +1554,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let config = create_test_config();
@@ -1173,6 +1567,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1192,6 +1587,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1221,6 +1617,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let config = create_test_config();
@@ -1233,6 +1630,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1252,6 +1650,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1281,6 +1680,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1294,6 +1694,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1313,6 +1714,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1331,6 +1733,70 @@ mod tests {
         let _ = std::fs::remove_file(&config_path);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_timed_suspension_resumes_on_tick() {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        let config_path = Config::default_config_path().unwrap();
+        let config = create_test_config();
+        config.save(&config_path).unwrap();
+
+        let config = Arc::new(RwLock::new(config));
+
+        let (_reload_tx, reload_rx) = mpsc::channel(10);
+        let sigterm = signal(SignalKind::terminate()).unwrap();
+        let sigint = signal(SignalKind::interrupt()).unwrap();
+        let socket_listener = create_test_socket().await;
+        let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
+
+        // Suspended until shortly after startup
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        state.suspend(&suspended, Some(chrono::Utc::now() + chrono::Duration::milliseconds(200)));
+        assert!(suspended.load(std::sync::atomic::Ordering::Relaxed));
+
+        let config_clone = Arc::clone(&config);
+        let config_path_clone = config_path.clone();
+        let state_clone = Arc::clone(&state);
+        let suspended_clone = Arc::clone(&suspended);
+
+        let tray_handle = Arc::new(RwLock::new(None));
+        let (tray_action_tx, tray_action_rx) = mpsc::channel(10);
+        let tray_retry_count = Arc::new(std::sync::atomic::AtomicU8::new(3));
+
+        let daemon_handle = tokio::spawn(async move {
+            let _ = run_daemon(
+                config_clone,
+                config_path_clone,
+                reload_rx,
+                sigterm,
+                sigint,
+                socket_listener,
+                start_time,
+                state_clone,
+                tray_handle,
+                tray_action_rx,
+                suspended_clone,
+                tray_action_tx,
+                tray_retry_count,
+            ).await;
+        });
+
+        // The tick after the deadline resumes the daemon
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+
+        assert!(!suspended.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(state.suspended_until().is_none());
+
+        daemon_handle.abort();
+        let _ = std::fs::remove_file(&config_path);
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_tray_enable_disable_via_config_reload() {
@@ -1338,6 +1804,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1351,6 +1818,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1370,6 +1838,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1407,6 +1876,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1421,6 +1891,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1443,6 +1914,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1477,6 +1949,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1491,6 +1964,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1511,6 +1985,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1543,6 +2018,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1557,6 +2033,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1576,6 +2053,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1601,6 +2079,7 @@ mod tests {
 
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let config_path = Config::default_config_path().unwrap();
         let mut config = create_test_config();
@@ -1615,6 +2094,7 @@ mod tests {
         let sigint = signal(SignalKind::interrupt()).unwrap();
         let socket_listener = create_test_socket().await;
         let start_time = Instant::now();
+        let state = create_test_state(&temp_dir);
 
         let config_clone = Arc::clone(&config);
         let config_path_clone = config_path.clone();
@@ -1634,6 +2114,7 @@ mod tests {
                 sigint,
                 socket_listener,
                 start_time,
+                state,
                 tray_handle,
                 tray_action_rx,
                 suspended,
@@ -1651,4 +2132,113 @@ mod tests {
         daemon_handle.abort();
         let _ = std::fs::remove_file(&config_path);
     }
+
+    /// Send one command to a daemon socket and read the response
+    async fn send_command(socket_path: &Path, command: autogit_shared::Command) -> Response {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let mut stream = tokio::net::UnixStream::connect(socket_path).await.unwrap();
+        stream.write_all(command.to_json().unwrap().as_bytes()).await.unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).await.unwrap();
+        Response::from_json(&line).unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_config_changes_over_socket() {
+        use autogit_shared::{Command, RepoOption, ResponseStatus};
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        let config_path = Config::default_config_path().unwrap();
+        let mut config = create_test_config();
+        config.daemon.check_interval_seconds = 60;
+        config.save(&config_path).unwrap();
+
+        let config = Arc::new(RwLock::new(config));
+
+        let (reload_tx, reload_rx) = mpsc::channel(10);
+        let sigterm = signal(SignalKind::terminate()).unwrap();
+        let sigint = signal(SignalKind::interrupt()).unwrap();
+        let socket_path = temp_dir.path().join("daemon.sock");
+        let socket_listener = UnixListener::bind(&socket_path).unwrap();
+        let state = create_test_state(&temp_dir);
+        let state_clone = Arc::clone(&state);
+
+        let config_clone = Arc::clone(&config);
+        let config_path_clone = config_path.clone();
+
+        let tray_handle = Arc::new(RwLock::new(None));
+        let (_tray_action_tx, tray_action_rx) = mpsc::channel(10);
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (tray_action_tx, _tray_action_rx2) = mpsc::channel(10);
+        let tray_retry_count = Arc::new(std::sync::atomic::AtomicU8::new(3));
+
+        let daemon_handle = tokio::spawn(async move {
+            let _ = run_daemon(
+                config_clone,
+                config_path_clone,
+                reload_rx,
+                sigterm,
+                sigint,
+                socket_listener,
+                Instant::now(),
+                state_clone,
+                tray_handle,
+                tray_action_rx,
+                suspended,
+                tray_action_tx,
+                tray_retry_count,
+            ).await;
+        });
+
+        // A change is saved to the file and applied before the response arrives
+        let response = send_command(&socket_path, Command::SetInterval { seconds: 120 }).await;
+        assert_eq!(response.status, ResponseStatus::Ok, "{}", response.message);
+        assert_eq!(config.read().await.daemon.check_interval_seconds, 120);
+        assert_eq!(Config::load(&config_path).unwrap().daemon.check_interval_seconds, 120);
+
+        let repo_dir = TempDir::new().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let add = Command::AddRepo { path: repo_dir.path().to_path_buf(), commit_message_template: None };
+        let response = send_command(&socket_path, add.clone()).await;
+        assert_eq!(response.status, ResponseStatus::Ok, "{}", response.message);
+        assert_eq!(config.read().await.repositories.len(), 1);
+
+        let disable = Command::SetRepoOption { path: repo_dir.path().to_path_buf(), option: RepoOption::AutoCommit(false) };
+        let response = send_command(&socket_path, disable).await;
+        assert_eq!(response.status, ResponseStatus::Ok, "{}", response.message);
+        assert!(!Config::load(&config_path).unwrap().repositories[0].auto_commit);
+
+        // Invalid changes are refused and leave the file alone
+        let before = std::fs::read_to_string(&config_path).unwrap();
+        let response = send_command(&socket_path, add).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.contains("already configured"));
+        let response = send_command(&socket_path, Command::SetInterval { seconds: 0 }).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), before);
+
+        // Reload picks up edits made behind the daemon's back
+        let mut edited = Config::load(&config_path).unwrap();
+        edited.repositories.clear();
+        edited.save(&config_path).unwrap();
+        let response = send_command(&socket_path, Command::Reload).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(response.message, "Configuration reloaded with 0 repositories");
+        assert!(config.read().await.repositories.is_empty());
+
+        // The watcher noticing the daemon's own save doesn't reload again
+        let mut events = state.subscribe();
+        reload_tx.send(()).await.unwrap();
+        send_command(&socket_path, Command::Reload).await;
+        assert!(matches!(events.recv().await.unwrap().kind, EventKind::ConfigReloaded { .. }));
+        assert!(events.try_recv().is_err());
+
+        daemon_handle.abort();
+    }
 }
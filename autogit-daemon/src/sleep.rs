@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use autogit_shared::{Config, EventKind};
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use tokio::sync::RwLock;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info};
+use zbus::zvariant::OwnedFd;
+
+use crate::state::DaemonState;
+use crate::sync;
+
+/// Take a logind delay lock; sleep waits until the returned fd is closed
+async fn inhibit(manager: &zbus::Proxy<'_>) -> Result<OwnedFd> {
+    manager
+        .call("Inhibit", &("sleep", "autogit", "Committing and pushing repositories", "delay"))
+        .await
+        .context("Failed to take sleep inhibitor lock")
+}
+
+/// Take the inhibitor lock if syncing before sleep is enabled, or release it if it was turned off
+async fn follow_config(manager: &zbus::Proxy<'_>, config: &RwLock<Config>, lock: Option<OwnedFd>) -> Result<Option<OwnedFd>> {
+    match (config.read().await.daemon.sync_before_sleep, lock) {
+        (true, None) => {
+            let lock = inhibit(manager).await?;
+            info!("Holding sleep inhibitor lock to sync before sleep");
+            Ok(Some(lock))
+        }
+        (false, Some(_)) => {
+            info!("Sync before sleep disabled, released sleep inhibitor lock");
+            Ok(None)
+        }
+        (_, lock) => Ok(lock),
+    }
+}
+
+/// Run a final sync whenever logind announces that the system is about to sleep
+///
+/// While `sync_before_sleep` is enabled, an inhibitor lock holds sleep back; it is released once
+/// the sync is done (or logind's delay runs out) and taken again after resume. Configuration
+/// reloads take or release it as the option changes. Only returns if the connection to logind is lost.
+pub async fn watch_sleep(
+    connection: &zbus::Connection,
+    config: Arc<RwLock<Config>>,
+    state: Arc<DaemonState>,
+    suspended: Arc<AtomicBool>,
+) -> Result<()> {
+    let manager = zbus::Proxy::new(
+        connection,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    ).await?;
+
+    let mut signals = manager.receive_signal("PrepareForSleep").await
+        .context("Failed to subscribe to PrepareForSleep")?;
+    let mut events = state.subscribe();
+    let mut lock = follow_config(&manager, &config, None).await?;
+    let mut sleeping = false;
+
+    loop {
+        tokio::select! {
+            message = signals.next() => {
+                let Some(message) = message else {
+                    break;
+                };
+                let going_to_sleep: bool = message.body().deserialize()
+                    .context("Malformed PrepareForSleep signal")?;
+
+                if going_to_sleep {
+                    sleeping = true;
+                    if lock.take().is_some() {
+                        info!("System is about to sleep, running final sync");
+                        sync::final_sync(&*config.read().await, &state, &suspended).await;
+                        debug!("Released sleep inhibitor lock");
+                    }
+                } else {
+                    info!("System resumed");
+                    sleeping = false;
+                    lock = follow_config(&manager, &config, lock).await?;
+                }
+            }
+            event = events.recv() => {
+                let reloaded = match event {
+                    Ok(event) => matches!(event.kind, EventKind::ConfigReloaded { .. }),
+                    // A missed event may have been a reload as well
+                    Err(RecvError::Lagged(_)) => true,
+                    Err(RecvError::Closed) => break,
+                };
+                if reloaded && !sleeping {
+                    lock = follow_config(&manager, &config, lock).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::TestBus;
+    use crate::test_repo::init_repo;
+    use autogit_shared::{Journal, JournalFilter, Repository};
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// Stand-in for logind that hands out pipe ends as inhibitor locks
+    struct FakeLogind {
+        inhibits: Arc<AtomicUsize>,
+        locks: Arc<std::sync::Mutex<Vec<std::io::PipeWriter>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl FakeLogind {
+        fn inhibit(&self, what: &str, _who: &str, _why: &str, mode: &str) -> OwnedFd {
+            assert_eq!((what, mode), ("sleep", "delay"));
+            self.inhibits.fetch_add(1, Ordering::SeqCst);
+
+            let (reader, writer) = std::io::pipe().unwrap();
+            self.locks.lock().unwrap().push(writer);
+            std::os::fd::OwnedFd::from(reader).into()
+        }
+    }
+
+    /// Whether the client still holds the lock (writing fails once the read end is closed)
+    fn lock_held(locks: &std::sync::Mutex<Vec<std::io::PipeWriter>>) -> bool {
+        locks.lock().unwrap().last_mut().unwrap().write_all(b"x").is_ok()
+    }
+
+    async fn emit_prepare_for_sleep(connection: &zbus::Connection, start: bool) {
+        connection.emit_signal(
+            None::<zbus::names::BusName<'_>>,
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+            "PrepareForSleep",
+            &(start,),
+        ).await.unwrap();
+    }
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        for _ in 0..250 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Condition not reached in time");
+    }
+
+    #[tokio::test]
+    async fn test_sync_before_sleep() {
+        let Some(bus) = TestBus::start() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let inhibits = Arc::new(AtomicUsize::new(0));
+        let locks = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logind = zbus::connection::Builder::address(bus.address.as_str()).unwrap()
+            .name("org.freedesktop.login1").unwrap()
+            .serve_at("/org/freedesktop/login1", FakeLogind { inhibits: inhibits.clone(), locks: locks.clone() }).unwrap()
+            .build().await.unwrap();
+
+        let repo_dir = TempDir::new().unwrap();
+        init_repo(repo_dir.path());
+        let state_dir = TempDir::new().unwrap();
+        let state = Arc::new(DaemonState::new(Journal::new(state_dir.path().join("history.jsonl"))));
+        let config = Arc::new(RwLock::new(Config {
+            repositories: vec![Repository {
+                path: repo_dir.path().to_path_buf(),
+                auto_commit: true,
+                commit_message_template: "Auto: {timestamp}".to_owned(),
+            }],
+            ..Default::default()
+        }));
+
+        let client = bus.connect().await;
+        let watcher = tokio::spawn({
+            let (config, state) = (config.clone(), state.clone());
+            async move {
+                watch_sleep(&client, config, state, Arc::new(AtomicBool::new(false))).await
+            }
+        });
+
+        // Turning the option on takes effect without a restart
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(inhibits.load(Ordering::SeqCst), 0);
+        config.write().await.daemon.sync_before_sleep = true;
+        state.emit(EventKind::ConfigReloaded { repositories: 1 });
+        wait_for(|| inhibits.load(Ordering::SeqCst) == 1).await;
+        assert!(lock_held(&locks));
+
+        // Unsaved work is committed and the lock released before sleeping
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+        emit_prepare_for_sleep(&logind, true).await;
+        wait_for(|| !lock_held(&locks)).await;
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].commit.is_some());
+
+        // The lock is taken again after resume
+        emit_prepare_for_sleep(&logind, false).await;
+        wait_for(|| inhibits.load(Ordering::SeqCst) == 2).await;
+        assert!(lock_held(&locks));
+
+        // Turning it off releases the lock
+        config.write().await.daemon.sync_before_sleep = false;
+        state.emit(EventKind::ConfigReloaded { repositories: 1 });
+        wait_for(|| !lock_held(&locks)).await;
+
+        watcher.abort();
+    }
+}
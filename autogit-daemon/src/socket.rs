@@ -1,40 +1,106 @@
-use anyhow::{Context, Result};
-use autogit_shared::{Command, Response, ResponseData, RepoDetail, socket_path};
+use anyhow::{Context, Result, bail};
+use autogit_shared::{Command, CommandError, ConfigChange, EventKind, JournalEntry, JournalFilter, PROTOCOL_VERSION, RepoNotFound, RepoStatus, Response, ResponseData, socket_path};
+use chrono::{DateTime, Local, Utc};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::RwLock;
-use tracing::{info, error, warn};
+use tokio::sync::{RwLock, broadcast, mpsc, oneshot};
+use tracing::{debug, info, error, warn};
 
 use crate::Config;
+use crate::state::DaemonState;
+use crate::sync::{SyncTrigger, Transfer};
 
 /// Start the Unix socket listener
+///
+/// The socket is only accessible to the current user (mode 0600 in a 0700 directory), and a
+/// socket that another daemon is still listening on is left alone.
 pub fn create_listener() -> Result<UnixListener> {
     let socket_path = socket_path()
         .context("Failed to get socket path")?;
 
+    if let Some(parent) = socket_path.parent() {
+        create_private_dir(parent)?;
+    }
+
     // Remove old socket file if it exists (from previous unclean shutdown)
-    if socket_path.exists() {
+    if socket_path.symlink_metadata().is_ok() {
+        if std::os::unix::net::UnixStream::connect(&socket_path).is_ok() {
+            bail!(
+                "Another autogit-daemon is already listening on {}",
+                socket_path.display()
+            );
+        }
+
         warn!("Removing stale socket file: {}", socket_path.display());
         std::fs::remove_file(&socket_path)
             .with_context(|| format!("Failed to remove stale socket: {}", socket_path.display()))?;
     }
 
-    // Ensure parent directory exists
-    if let Some(parent) = socket_path.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create socket directory: {}", parent.display()))?;
-    }
-
     let listener = UnixListener::bind(&socket_path)
         .with_context(|| format!("Failed to bind Unix socket: {}", socket_path.display()))?;
 
+    std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict socket permissions: {}", socket_path.display()))?;
+
     info!("Listening on Unix socket: {}", socket_path.display());
 
     Ok(listener)
 }
 
+/// Create the socket directory, or tighten an existing one, so only the current user can enter it
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Failed to create socket directory: {}", dir.display()))?;
+
+    let metadata = std::fs::metadata(dir)
+        .with_context(|| format!("Failed to inspect socket directory: {}", dir.display()))?;
+
+    if metadata.uid() != effective_uid() {
+        bail!(
+            "Socket directory {} is owned by uid {}, not by the current user",
+            dir.display(),
+            metadata.uid()
+        );
+    }
+
+    if metadata.mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .with_context(|| format!("Failed to restrict socket directory: {}", dir.display()))?;
+    }
+
+    Ok(())
+}
+
+/// User ID the daemon runs as
+pub fn effective_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+/// Refuse connections from processes of other users (checked with SO_PEERCRED)
+fn check_peer(stream: &UnixStream, allowed_uid: u32) -> Result<()> {
+    let peer = stream.peer_cred()
+        .context("Failed to read peer credentials")?;
+
+    if peer.uid() != allowed_uid {
+        bail!(
+            "Permission denied: connection from uid {} (pid {}) refused, the daemon belongs to uid {}",
+            peer.uid(),
+            peer.pid().map_or_else(|| "unknown".to_owned(), |pid| pid.to_string()),
+            allowed_uid
+        );
+    }
+
+    Ok(())
+}
+
 /// Clean up the socket file on shutdown
 pub fn cleanup_socket() {
     if let Ok(path) = socket_path() {
@@ -48,14 +114,22 @@ pub fn cleanup_socket() {
     }
 }
 
+/// A configuration change (or a reload, if `change` is None) for the main loop to apply
+pub struct ConfigRequest {
+    pub change: Option<ConfigChange>,
+    pub reply: oneshot::Sender<Response>,
+}
+
 /// Handle an incoming connection
 pub async fn handle_connection(
     stream: UnixStream,
     config: Arc<RwLock<Config>>,
     start_time: Instant,
+    state: Arc<DaemonState>,
     suspended: Arc<std::sync::atomic::AtomicBool>,
+    config_tx: mpsc::Sender<ConfigRequest>,
 ) {
-    if let Err(e) = handle_connection_impl(stream, config, start_time, suspended).await {
+    if let Err(e) = handle_connection_impl(stream, config, start_time, state, suspended, config_tx).await {
         error!("Error handling socket connection: {:#}", e);
     }
 }
@@ -64,8 +138,15 @@ async fn handle_connection_impl(
     stream: UnixStream,
     config: Arc<RwLock<Config>>,
     start_time: Instant,
+    state: Arc<DaemonState>,
     suspended: Arc<std::sync::atomic::AtomicBool>,
+    config_tx: mpsc::Sender<ConfigRequest>,
 ) -> Result<()> {
+    if let Err(e) = check_peer(&stream, effective_uid()) {
+        warn!("{:#}", e);
+        return write_response(BufReader::new(stream), Response::error(format!("{:#}", e))).await;
+    }
+
     let mut reader = BufReader::new(stream);
     let mut line = String::new();
 
@@ -78,32 +159,119 @@ async fn handle_connection_impl(
         return Ok(());
     }
 
-    // Parse command
-    let command = Command::from_json(&line)
-        .context("Failed to parse command")?;
+    // Parse command; clients get an error response rather than a dropped connection
+    let command = match Command::parse(&line) {
+        Ok(command) => command,
+        Err(e) => {
+            warn!("Rejected socket command: {}", e);
+            return write_response(reader, rejected_command_response(&e)).await;
+        }
+    };
 
     info!("Received socket command: {:?}", command);
 
-    // Execute command and get response
-    let response = match command {
+    if let Command::Subscribe = command {
+        return stream_events(reader.into_inner(), &state).await;
+    }
+
+    let response = execute_command(command, config, start_time, &state, suspended, &config_tx).await;
+    write_response(reader, response).await
+}
+
+/// Everything needed to execute commands outside a socket connection (HTTP API, D-Bus)
+#[derive(Clone)]
+pub struct Controller {
+    pub config: Arc<RwLock<Config>>,
+    pub start_time: Instant,
+    pub state: Arc<DaemonState>,
+    pub suspended: Arc<std::sync::atomic::AtomicBool>,
+    pub config_tx: mpsc::Sender<ConfigRequest>,
+}
+
+impl Controller {
+    pub async fn execute(&self, command: Command) -> Response {
+        execute_command(
+            command,
+            self.config.clone(),
+            self.start_time,
+            &self.state,
+            self.suspended.clone(),
+            &self.config_tx,
+        ).await
+    }
+}
+
+/// Execute a command and build its response; shared by all control transports
+///
+/// Subscriptions need a streaming connection and are handled by each transport itself.
+pub async fn execute_command(
+    command: Command,
+    config: Arc<RwLock<Config>>,
+    start_time: Instant,
+    state: &DaemonState,
+    suspended: Arc<std::sync::atomic::AtomicBool>,
+    config_tx: &mpsc::Sender<ConfigRequest>,
+) -> Response {
+    match command {
+        Command::Hello { protocol_version, client_version } => {
+            handle_hello_command(protocol_version, client_version.as_deref())
+        }
         Command::Ping => {
             Response::ok("pong")
         }
         Command::Status => {
-            handle_status_command(config, start_time, suspended.clone()).await
+            handle_status_command(config, start_time, state, suspended).await
         }
-        Command::Trigger => {
-            handle_trigger_command(config).await
+        Command::Trigger { repos, push, pull, dry_run } => {
+            handle_trigger_command(config, state, repos, Transfer { push, pull }, dry_run).await
         }
-        Command::Suspend => {
-            handle_suspend_command(suspended.clone()).await
+        Command::Suspend { until } => {
+            handle_suspend_command(state, suspended, until).await
         }
         Command::Resume => {
-            handle_resume_command(suspended.clone()).await
+            handle_resume_command(state, suspended).await
         }
-    };
+        Command::History(filter) => {
+            handle_history_command(state, filter).await
+        }
+        Command::RecordHistory(entry) => {
+            handle_record_history_command(config, state, entry).await
+        }
+        Command::Unquarantine { path } => {
+            handle_unquarantine_command(state, path)
+        }
+        Command::Pause { path, until } => {
+            handle_pause_command(config, state, path, until).await
+        }
+        Command::Unpause { path } => {
+            handle_unpause_command(state, path)
+        }
+        Command::Subscribe => {
+            Response::error("Subscriptions need a streaming connection")
+        }
+        Command::AddRepo { path, commit_message_template } => {
+            request_config_change(config_tx, Some(ConfigChange::AddRepo { path, commit_message_template })).await
+        }
+        Command::RemoveRepo { path } => {
+            request_config_change(config_tx, Some(ConfigChange::RemoveRepo { path })).await
+        }
+        Command::SetRepoOption { path, option } => {
+            request_config_change(config_tx, Some(ConfigChange::SetRepoOption { path, option })).await
+        }
+        Command::SetInterval { seconds } => {
+            request_config_change(config_tx, Some(ConfigChange::SetInterval { seconds })).await
+        }
+        Command::AddWatchRoot { root } => {
+            request_config_change(config_tx, Some(ConfigChange::AddWatchRoot { root })).await
+        }
+        Command::Reload => {
+            request_config_change(config_tx, None).await
+        }
+    }
+}
 
-    // Send response
+/// Send the response line back to the client
+async fn write_response(reader: BufReader<UnixStream>, response: Response) -> Result<()> {
     let response_json = response.to_json()
         .context("Failed to serialize response")?;
 
@@ -118,9 +286,97 @@ async fn handle_connection_impl(
     Ok(())
 }
 
+/// Acknowledge a subscription, then forward events until the client closes the connection
+async fn stream_events(stream: UnixStream, state: &DaemonState) -> Result<()> {
+    // Subscribe before acknowledging so the client can't miss anything after the ack
+    let mut events = state.subscribe();
+    let (read_half, mut write_half) = stream.into_split();
+
+    let ack = Response::ok("Subscribed to daemon events").to_json()?;
+    write_half.write_all(ack.as_bytes()).await
+        .context("Failed to acknowledge subscription")?;
+
+    let mut reader = BufReader::new(read_half);
+    let mut ignored = String::new();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Event subscriber fell behind, dropped {} events", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+
+                if write_half.write_all(event.to_json()?.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+
+            // Subscribers don't send anything else; EOF means they went away
+            read = reader.read_line(&mut ignored) => {
+                match read {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => ignored.clear(),
+                }
+            }
+        }
+    }
+
+    debug!("Event subscriber disconnected");
+    Ok(())
+}
+
+/// Versions and commands of this daemon, as reported in the handshake
+fn hello_data() -> ResponseData {
+    ResponseData::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        daemon_version: env!("CARGO_PKG_VERSION").to_owned(),
+        commands: Command::NAMES.iter().map(|name| (*name).to_owned()).collect(),
+    }
+}
+
+fn handle_hello_command(protocol_version: u32, client_version: Option<&str>) -> Response {
+    info!(
+        "Client hello: protocol {}, version {}",
+        protocol_version,
+        client_version.unwrap_or("unknown")
+    );
+
+    let message = if protocol_version > PROTOCOL_VERSION {
+        format!(
+            "Daemon speaks protocol {} (client {}); restart the daemon to use newer commands",
+            PROTOCOL_VERSION, protocol_version
+        )
+    } else {
+        format!("autogit-daemon {}", env!("CARGO_PKG_VERSION"))
+    };
+
+    Response::ok_with_data(message, hello_data())
+}
+
+/// Error for a command that could not be parsed, carrying the handshake data for unknown ones
+pub fn rejected_command_response(error: &CommandError) -> Response {
+    match error {
+        CommandError::Unknown(_) => Response {
+            data: Some(hello_data()),
+            ..Response::error(format!(
+                "{} (autogit-daemon {} may be older than the client; restart it after upgrading)",
+                error,
+                env!("CARGO_PKG_VERSION")
+            ))
+        },
+        CommandError::Malformed(_) => Response::error(error.to_string()),
+    }
+}
+
 async fn handle_status_command(
     config: Arc<RwLock<Config>>,
     start_time: Instant,
+    state: &DaemonState,
     suspended: Arc<std::sync::atomic::AtomicBool>,
 ) -> Response {
     let cfg = config.read().await;
@@ -133,59 +389,91 @@ async fn handle_status_command(
         "Daemon status"
     };
 
+    let check_interval_seconds = cfg.daemon.check_interval_seconds;
+    let configured: Vec<_> = cfg.all_repositories().cloned().collect();
+    drop(cfg);
+
+    let mut repositories = Vec::with_capacity(configured.len());
+    for repo in configured {
+        // A repository that can't be opened shows its sync error instead of upstream counts
+        let counts = crate::git::ahead_behind(&repo.path).await.ok().flatten();
+        let health = state.health(&repo.path);
+        repositories.push(RepoStatus {
+            enabled: repo.auto_commit,
+            paused: state.is_paused(&repo.path),
+            quarantined: health.quarantined,
+            last_sync: state.last_sync(&repo.path),
+            ahead: counts.map(|(ahead, _)| ahead),
+            behind: counts.map(|(_, behind)| behind),
+            push_pending: state.is_push_pending(&repo.path),
+            error: health.last_error,
+            path: repo.path,
+        });
+    }
+
     Response::ok_with_data(
         message,
         ResponseData::Status {
             uptime_seconds: uptime,
-            check_interval_seconds: cfg.daemon.check_interval_seconds,
-            repositories_count: cfg.repositories.len(),
+            check_interval_seconds,
+            repositories_count: repositories.len(),
+            quarantined: state.quarantined_repos(),
+            suspended: is_suspended,
+            suspended_until: state.suspended_until().filter(|_| is_suspended),
+            paused: state.paused_repos(),
+            schedule: state.schedule(),
+            next_check: state.next_check(),
+            repositories,
         },
     )
 }
 
-async fn handle_trigger_command(config: Arc<RwLock<Config>>) -> Response {
+async fn handle_trigger_command(
+    config: Arc<RwLock<Config>>,
+    state: &DaemonState,
+    repos: Option<Vec<PathBuf>>,
+    transfer: Transfer,
+    dry_run: bool,
+) -> Response {
     let cfg = config.read().await;
 
-    let mut repos_checked = 0;
-    let mut repos_committed = 0;
-    let mut details = Vec::new();
-
-    // Process each repository
-    for repo in &cfg.repositories {
-        if !repo.auto_commit {
-            continue;
+    // Without a selection every enabled repository is synced; named ones must be configured and enabled
+    let selected: Vec<_> = match repos {
+        None => cfg.all_repositories().filter(|repo| repo.auto_commit).collect(),
+        Some(paths) => {
+            let mut selected = Vec::with_capacity(paths.len());
+            for path in paths {
+                match cfg.all_repositories().find(|repo| repo.path == path) {
+                    None => {
+                        return Response::failure(&RepoNotFound(path).into());
+                    }
+                    Some(repo) if !repo.auto_commit => {
+                        return Response::error(format!(
+                            "Repository is disabled: {} (run 'autogit enable' first)",
+                            path.display()
+                        ));
+                    }
+                    Some(repo) => selected.push(repo),
+                }
+            }
+            selected
         }
+    };
 
-        repos_checked += 1;
+    if dry_run {
+        let previews = crate::sync::preview_repos(&selected, state, transfer).await;
+        let would_commit = previews.iter().filter(|p| p.commit_message.is_some()).count();
 
-        match crate::git::check_and_commit(repo).await {
-            Ok(committed) => {
-                if committed {
-                    repos_committed += 1;
-                }
+        return Response::ok_with_data(
+            format!("Would commit changes in {} of {} repositories", would_commit, previews.len()),
+            ResponseData::Preview { previews },
+        );
+    }
 
-                details.push(RepoDetail {
-                    path: repo.path.clone(),
-                    committed,
-                    files_changed: if committed { Some(0) } else { None }, // TODO: track actual file count
-                    error: None,
-                });
+    let details = crate::sync::sync_repos(&selected, &cfg.daemon, state, SyncTrigger::Manual, transfer).await;
 
-                if committed {
-                    info!("Committed changes in: {}", repo.path.display());
-                }
-            }
-            Err(e) => {
-                error!("Error processing repository {}: {:#}", repo.path.display(), e);
-                details.push(RepoDetail {
-                    path: repo.path.clone(),
-                    committed: false,
-                    files_changed: None,
-                    error: Some(format!("{:#}", e)),
-                });
-            }
-        }
-    }
+    let repos_checked = details.len();
+    let repos_committed = details.iter().filter(|d| d.committed).count();
 
     info!("Manual trigger complete: checked {}, committed {}", repos_checked, repos_committed);
 
@@ -199,19 +487,121 @@ async fn handle_trigger_command(config: Arc<RwLock<Config>>) -> Response {
     )
 }
 
-async fn handle_suspend_command(suspended: Arc<std::sync::atomic::AtomicBool>) -> Response {
-    let was_suspended = suspended.swap(true, std::sync::atomic::Ordering::Relaxed);
+/// Hand a configuration change to the main loop and wait until it is saved and applied
+async fn request_config_change(config_tx: &mpsc::Sender<ConfigRequest>, change: Option<ConfigChange>) -> Response {
+    let (reply, response) = oneshot::channel();
+
+    if config_tx.send(ConfigRequest { change, reply }).await.is_err() {
+        return Response::error("Daemon is shutting down");
+    }
+
+    response.await.unwrap_or_else(|_| Response::error("Daemon is shutting down"))
+}
+
+async fn handle_history_command(state: &DaemonState, filter: JournalFilter) -> Response {
+    match state.journal().query(&filter) {
+        Ok(entries) => Response::ok_with_data(
+            format!("{} history entries", entries.len()),
+            ResponseData::History { entries },
+        ),
+        Err(e) => {
+            error!("Failed to read sync history: {:#}", e);
+            Response::error(format!("Failed to read sync history: {:#}", e))
+        }
+    }
+}
+
+fn handle_unquarantine_command(state: &DaemonState, path: Option<PathBuf>) -> Response {
+    let released = state.clear_quarantine(path.as_deref());
+
+    match (path, released.len()) {
+        (Some(path), 0) => Response::error(format!("Repository is not quarantined: {}", path.display())),
+        (_, count) => {
+            for repo in &released {
+                info!("Cleared quarantine for {}", repo.display());
+            }
+            Response::ok(format!("Released {} quarantined repositories", count))
+        }
+    }
+}
+
+/// Append a client's commit to the journal, so it shows up in the history and can be undone
+async fn handle_record_history_command(config: Arc<RwLock<Config>>, state: &DaemonState, entry: JournalEntry) -> Response {
+    if !config.read().await.all_repositories().any(|repo| repo.path == entry.repo) {
+        return Response::failure(&RepoNotFound(entry.repo).into());
+    }
+
+    if let Err(e) = state.journal().append(&entry) {
+        error!("Failed to write sync history: {:#}", e);
+        return Response::error(format!("Failed to write sync history: {:#}", e));
+    }
+
+    if let Some(commit) = &entry.commit {
+        state.emit(EventKind::CommitCreated {
+            repo: entry.repo.clone(),
+            commit: commit.clone(),
+            files_changed: entry.files_changed,
+        });
+    }
+
+    Response::ok(format!("Recorded history entry for {}", entry.repo.display()))
+}
+
+async fn handle_pause_command(
+    config: Arc<RwLock<Config>>,
+    state: &DaemonState,
+    path: PathBuf,
+    until: Option<DateTime<Utc>>,
+) -> Response {
+    if !config.read().await.all_repositories().any(|repo| repo.path == path) {
+        return Response::failure(&RepoNotFound(path).into());
+    }
+
+    state.pause(&path, until);
+
+    match until {
+        Some(until) => {
+            info!("Paused {} until {}", path.display(), until);
+            Response::ok(format!("Paused {} until {}", path.display(), format_local_time(until)))
+        }
+        None => {
+            info!("Paused {}", path.display());
+            Response::ok(format!("Paused {}", path.display()))
+        }
+    }
+}
 
-    if was_suspended {
-        Response::ok("Daemon was already suspended")
+fn handle_unpause_command(state: &DaemonState, path: PathBuf) -> Response {
+    if state.unpause(&path) {
+        info!("Unpaused {}", path.display());
+        Response::ok(format!("Resumed syncing {}", path.display()))
     } else {
-        info!("Daemon suspended via socket command");
-        Response::ok("Daemon suspended")
+        Response::error(format!("Repository is not paused: {}", path.display()))
     }
 }
 
-async fn handle_resume_command(suspended: Arc<std::sync::atomic::AtomicBool>) -> Response {
-    let was_suspended = suspended.swap(false, std::sync::atomic::Ordering::Relaxed);
+async fn handle_suspend_command(
+    state: &DaemonState,
+    suspended: Arc<std::sync::atomic::AtomicBool>,
+    until: Option<DateTime<Utc>>,
+) -> Response {
+    let was_suspended = state.suspend(&suspended, until);
+
+    match (was_suspended, until) {
+        (_, Some(until)) => {
+            info!("Daemon suspended via socket command until {}", until);
+            Response::ok(format!("Daemon suspended until {}", format_local_time(until)))
+        }
+        (true, None) => Response::ok("Daemon was already suspended"),
+        (false, None) => {
+            info!("Daemon suspended via socket command");
+            Response::ok("Daemon suspended")
+        }
+    }
+}
+
+async fn handle_resume_command(state: &DaemonState, suspended: Arc<std::sync::atomic::AtomicBool>) -> Response {
+    let was_suspended = state.resume(&suspended);
 
     if !was_suspended {
         Response::ok("Daemon was already running")
@@ -221,11 +611,16 @@ async fn handle_resume_command(suspended: Arc<std::sync::atomic::AtomicBool>) ->
     }
 }
 
+/// Format a deadline in local time for response messages
+fn format_local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use autogit_shared::{Config, DaemonConfig, Repository, ResponseStatus};
-    use std::path::PathBuf;
+    use autogit_shared::{Config, DaemonConfig, DaemonEvent, ErrorKind, EventKind, Journal, JournalEntry, RemoteResult, Repository, ResponseStatus, ScheduleState};
+    use std::path::Path;
     use std::time::Duration;
     use tempfile::TempDir;
     use std::env;
@@ -236,16 +631,25 @@ mod tests {
             daemon: DaemonConfig {
                 check_interval_seconds: 300,
                 enable_tray: false,
+                ..Default::default()
             },
             repositories: vec![],
+            ..Default::default()
         }
     }
 
+    fn create_test_state() -> (TempDir, DaemonState) {
+        let temp_dir = TempDir::new().unwrap();
+        let state = DaemonState::new(Journal::new(temp_dir.path().join("history.jsonl")));
+        (temp_dir, state)
+    }
+
     fn create_test_config_with_repos() -> Config {
         Config {
             daemon: DaemonConfig {
                 check_interval_seconds: 120,
                 enable_tray: false,
+                ..Default::default()
             },
             repositories: vec![
                 Repository {
@@ -264,6 +668,7 @@ mod tests {
                     commit_message_template: "Checkpoint".to_owned(),
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -272,13 +677,14 @@ mod tests {
         let config = Arc::new(RwLock::new(create_test_config()));
         let start_time = Instant::now();
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
 
-        let response = handle_status_command(config, start_time, suspended).await;
+        let response = handle_status_command(config, start_time, &state, suspended).await;
 
         assert_eq!(response.status, ResponseStatus::Ok);
         assert_eq!(response.message, "Daemon status");
 
-        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count }) = response.data {
+        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count, .. }) = response.data {
             assert_eq!(uptime_seconds, 0); // Just started
             assert_eq!(check_interval_seconds, 300);
             assert_eq!(repositories_count, 0);
@@ -292,14 +698,15 @@ mod tests {
         let config = Arc::new(RwLock::new(create_test_config_with_repos()));
         let start_time = Instant::now();
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
 
         tokio::time::sleep(Duration::from_millis(10)).await;
 
-        let response = handle_status_command(config, start_time, suspended).await;
+        let response = handle_status_command(config, start_time, &state, suspended).await;
 
         assert_eq!(response.status, ResponseStatus::Ok);
 
-        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count }) = response.data {
+        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count, .. }) = response.data {
             // uptime_seconds is u64, so it's always >= 0, just verify it exists
             let _ = uptime_seconds;
             assert_eq!(check_interval_seconds, 120);
@@ -314,8 +721,9 @@ mod tests {
         let config = Arc::new(RwLock::new(create_test_config()));
         let start_time = Instant::now() - Duration::from_secs(5);
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
 
-        let response = handle_status_command(config, start_time, suspended).await;
+        let response = handle_status_command(config, start_time, &state, suspended).await;
 
         if let Some(ResponseData::Status { uptime_seconds, .. }) = response.data {
             assert!(uptime_seconds >= 5);
@@ -327,9 +735,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_handle_trigger_command_empty_config() {
+        let (_state_dir, state) = create_test_state();
         let config = Arc::new(RwLock::new(create_test_config()));
 
-        let response = handle_trigger_command(config).await;
+        let response = handle_trigger_command(config, &state, None, Transfer::default(), false).await;
 
         assert_eq!(response.status, ResponseStatus::Ok);
         assert!(response.message.contains("0 repositories"));
@@ -345,11 +754,12 @@ mod tests {
 
     #[tokio::test]
     async fn test_handle_trigger_command_skips_disabled_repos() {
+        let (_state_dir, state) = create_test_state();
         let config = Arc::new(RwLock::new(create_test_config_with_repos()));
 
         // This will fail to actually commit (repos don't exist), but we're testing
         // that it only processes enabled repos
-        let response = handle_trigger_command(config).await;
+        let response = handle_trigger_command(config, &state, None, Transfer::default(), false).await;
 
         assert_eq!(response.status, ResponseStatus::Ok);
 
@@ -369,9 +779,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_handle_trigger_command_response_format() {
+        let (_state_dir, state) = create_test_state();
         let config = Arc::new(RwLock::new(create_test_config_with_repos()));
 
-        let response = handle_trigger_command(config).await;
+        let response = handle_trigger_command(config, &state, None, Transfer::default(), false).await;
 
         assert_eq!(response.status, ResponseStatus::Ok);
         assert!(response.message.contains("Checked"));
@@ -379,12 +790,73 @@ mod tests {
         assert!(response.data.is_some());
     }
 
+    #[tokio::test]
+    async fn test_handle_trigger_command_selected_repos() {
+        let (_state_dir, state) = create_test_state();
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+
+        let repos = Some(vec![PathBuf::from("/test/repo3")]);
+        let response = handle_trigger_command(config, &state, repos, Transfer::default(), false).await;
+
+        assert_eq!(response.status, ResponseStatus::Ok);
+        if let Some(ResponseData::Trigger { repos_checked, details, .. }) = response.data {
+            assert_eq!(repos_checked, 1);
+            assert_eq!(details.len(), 1);
+            assert_eq!(details[0].path, PathBuf::from("/test/repo3"));
+        } else {
+            panic!("Expected Trigger response data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_trigger_command_rejects_unknown_and_disabled() {
+        let (_state_dir, state) = create_test_state();
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+
+        let unknown = Some(vec![PathBuf::from("/test/repo1"), PathBuf::from("/elsewhere")]);
+        let response = handle_trigger_command(config.clone(), &state, unknown, Transfer::default(), false).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.contains("/elsewhere"));
+
+        let disabled = Some(vec![PathBuf::from("/test/repo2")]);
+        let response = handle_trigger_command(config, &state, disabled, Transfer::default(), false).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.contains("disabled"));
+
+        // Nothing was synced for a rejected request
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_trigger_command_dry_run() {
+        let (_state_dir, state) = create_test_state();
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+
+        let repos = Some(vec![PathBuf::from("/test/repo3")]);
+        let response = handle_trigger_command(config, &state, repos, Transfer::default(), true).await;
+
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(response.message, "Would commit changes in 0 of 1 repositories");
+        match response.data {
+            Some(ResponseData::Preview { previews }) => {
+                assert_eq!(previews.len(), 1);
+                assert_eq!(previews[0].path, PathBuf::from("/test/repo3"));
+                assert!(previews[0].error.is_some());
+            }
+            other => panic!("Expected Preview response data, got {:?}", other),
+        }
+
+        // A dry run leaves no trace in the history
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+    }
+
     #[test]
     #[serial]
     fn test_cleanup_socket_no_socket() {
         // Setup temp config dir
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Socket doesn't exist, should not error
         cleanup_socket();
@@ -400,6 +872,7 @@ mod tests {
         // Setup temp config dir
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         // Create the socket directory and a fake socket file
         let socket_path = socket_path().unwrap();
@@ -421,6 +894,7 @@ mod tests {
         // Setup temp config dir
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let socket_path = socket_path().unwrap();
         assert!(!socket_path.exists());
@@ -444,6 +918,7 @@ mod tests {
         // Setup temp config dir
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
 
         let socket_path = socket_path().unwrap();
         std::fs::create_dir_all(socket_path.parent().unwrap()).unwrap();
@@ -464,18 +939,83 @@ mod tests {
         cleanup_socket();
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_create_listener_is_private() {
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        // An existing directory with loose permissions is tightened
+        let socket_path = socket_path().unwrap();
+        let socket_dir = socket_path.parent().unwrap();
+        std::fs::create_dir_all(socket_dir).unwrap();
+        std::fs::set_permissions(socket_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let listener = create_listener().unwrap();
+
+        assert_eq!(socket_path, temp_dir.path().join("autogit").join("daemon.sock"));
+        assert_eq!(std::fs::metadata(socket_dir).unwrap().mode() & 0o777, 0o700);
+        assert_eq!(std::fs::metadata(&socket_path).unwrap().mode() & 0o777, 0o600);
+
+        drop(listener);
+        cleanup_socket();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_create_listener_keeps_live_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        let _running = create_listener().unwrap();
+
+        // A second daemon must not steal the socket of the running one
+        let err = create_listener().unwrap_err();
+        assert!(err.to_string().contains("already listening"));
+        assert!(UnixStream::connect(socket_path().unwrap()).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_create_listener_replaces_dead_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", temp_dir.path());
+
+        // A socket left behind by a crashed daemon accepts no connections
+        let socket_path = socket_path().unwrap();
+        std::fs::create_dir_all(socket_path.parent().unwrap()).unwrap();
+        drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+
+        let _listener = create_listener().unwrap();
+        assert!(UnixStream::connect(&socket_path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_peer() {
+        let (client, server) = UnixStream::pair().unwrap();
+        assert!(check_peer(&server, effective_uid()).is_ok());
+
+        let err = check_peer(&server, effective_uid() + 1).unwrap_err();
+        assert!(err.to_string().contains("Permission denied"));
+        drop(client);
+    }
+
     #[tokio::test]
     async fn test_handle_trigger_different_repo_counts() {
+        let (_state_dir, state) = create_test_state();
         // Test with 0 repos
         let config0 = Arc::new(RwLock::new(create_test_config()));
-        let response0 = handle_trigger_command(config0).await;
+        let response0 = handle_trigger_command(config0, &state, None, Transfer::default(), false).await;
         if let Some(ResponseData::Trigger { repos_checked, .. }) = response0.data {
             assert_eq!(repos_checked, 0);
         }
 
         // Test with 2 enabled repos (out of 3 total)
         let config2 = Arc::new(RwLock::new(create_test_config_with_repos()));
-        let response2 = handle_trigger_command(config2).await;
+        let response2 = handle_trigger_command(config2, &state, None, Transfer::default(), false).await;
         if let Some(ResponseData::Trigger { repos_checked, .. }) = response2.data {
             assert_eq!(repos_checked, 2);
         }
@@ -484,7 +1024,7 @@ mod tests {
         let mut config_all = create_test_config_with_repos();
         config_all.repositories[1].auto_commit = true; // Enable repo2
         let config_all = Arc::new(RwLock::new(config_all));
-        let response_all = handle_trigger_command(config_all).await;
+        let response_all = handle_trigger_command(config_all, &state, None, Transfer::default(), false).await;
         if let Some(ResponseData::Trigger { repos_checked, .. }) = response_all.data {
             assert_eq!(repos_checked, 3);
         }
@@ -500,8 +1040,9 @@ mod tests {
             let config = Arc::new(RwLock::new(config));
             let start_time = Instant::now();
             let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let (_state_dir, state) = create_test_state();
 
-            let response = handle_status_command(config, start_time, suspended).await;
+            let response = handle_status_command(config, start_time, &state, suspended).await;
 
             if let Some(ResponseData::Status { check_interval_seconds, .. }) = response.data {
                 assert_eq!(check_interval_seconds, interval);
@@ -513,14 +1054,15 @@ mod tests {
 
     #[tokio::test]
     async fn test_response_messages() {
+        let (_state_dir, state) = create_test_state();
         // Test status message
         let config = Arc::new(RwLock::new(create_test_config()));
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
-        let response = handle_status_command(config.clone(), Instant::now(), suspended).await;
+        let response = handle_status_command(config.clone(), Instant::now(), &state, suspended).await;
         assert_eq!(response.message, "Daemon status");
 
         // Test trigger message format
-        let response = handle_trigger_command(config).await;
+        let response = handle_trigger_command(config, &state, None, Transfer::default(), false).await;
         assert!(response.message.starts_with("Checked"));
         assert!(response.message.contains("repositories"));
         assert!(response.message.contains("committed changes in"));
@@ -529,12 +1071,13 @@ mod tests {
     #[tokio::test]
     async fn test_handle_suspend_command() {
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
 
         // Initially not suspended
         assert!(!suspended.load(std::sync::atomic::Ordering::Relaxed));
 
         // Suspend the daemon
-        let response = handle_suspend_command(suspended.clone()).await;
+        let response = handle_suspend_command(&state, suspended.clone(), None).await;
         assert_eq!(response.status, ResponseStatus::Ok);
         assert_eq!(response.message, "Daemon suspended");
 
@@ -545,12 +1088,13 @@ mod tests {
     #[tokio::test]
     async fn test_handle_resume_command() {
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let (_state_dir, state) = create_test_state();
 
         // Initially suspended
         assert!(suspended.load(std::sync::atomic::Ordering::Relaxed));
 
         // Resume the daemon
-        let response = handle_resume_command(suspended.clone()).await;
+        let response = handle_resume_command(&state, suspended.clone()).await;
         assert_eq!(response.status, ResponseStatus::Ok);
         assert_eq!(response.message, "Daemon resumed");
 
@@ -561,12 +1105,13 @@ mod tests {
     #[tokio::test]
     async fn test_suspend_already_suspended() {
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let (_state_dir, state) = create_test_state();
 
         // Already suspended
         assert!(suspended.load(std::sync::atomic::Ordering::Relaxed));
 
         // Suspend again (should be idempotent)
-        let response = handle_suspend_command(suspended.clone()).await;
+        let response = handle_suspend_command(&state, suspended.clone(), None).await;
         assert_eq!(response.status, ResponseStatus::Ok);
 
         // Still suspended
@@ -576,12 +1121,13 @@ mod tests {
     #[tokio::test]
     async fn test_resume_already_running() {
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
 
         // Already running (not suspended)
         assert!(!suspended.load(std::sync::atomic::Ordering::Relaxed));
 
         // Resume again (should be idempotent)
-        let response = handle_resume_command(suspended.clone()).await;
+        let response = handle_resume_command(&state, suspended.clone()).await;
         assert_eq!(response.status, ResponseStatus::Ok);
 
         // Still running
@@ -592,17 +1138,425 @@ mod tests {
     async fn test_status_command_when_suspended() {
         let config = Arc::new(RwLock::new(create_test_config()));
         let suspended = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let (_state_dir, state) = create_test_state();
 
-        let response = handle_status_command(config, Instant::now(), suspended).await;
+        let response = handle_status_command(config, Instant::now(), &state, suspended).await;
         assert_eq!(response.status, ResponseStatus::Ok);
         assert_eq!(response.message, "Daemon status (suspended)");
 
         // Check that response includes status data
-        if let Some(ResponseData::Status { uptime_seconds: _, check_interval_seconds, repositories_count }) = response.data {
+        if let Some(ResponseData::Status { check_interval_seconds, repositories_count, suspended, .. }) = response.data {
+            assert!(suspended);
             assert_eq!(check_interval_seconds, 300); // From create_test_config()
             assert_eq!(repositories_count, 0); // No repos in test config
         } else {
             panic!("Expected Status response data");
         }
     }
+
+    #[tokio::test]
+    async fn test_status_lists_quarantined_repos() {
+        let config = Arc::new(RwLock::new(create_test_config()));
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
+        let policy = DaemonConfig { quarantine_after_failures: 1, ..Default::default() };
+
+        state.record_failure(&PathBuf::from("/test/gone"), "missing".to_owned(), &policy);
+
+        let response = handle_status_command(config, Instant::now(), &state, suspended).await;
+        if let Some(ResponseData::Status { quarantined, .. }) = response.data {
+            assert_eq!(quarantined, vec![PathBuf::from("/test/gone")]);
+        } else {
+            panic!("Expected Status response data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_schedule() {
+        let config = Arc::new(RwLock::new(create_test_config()));
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
+
+        let schedule = ScheduleState {
+            battery_percent: Some(15),
+            on_battery: true,
+            remote_deferred: Some("on battery at 15% (below 20%)".to_owned()),
+            ..Default::default()
+        };
+        state.set_schedule(schedule.clone());
+
+        let response = handle_status_command(config, Instant::now(), &state, suspended).await;
+        if let Some(ResponseData::Status { schedule: reported, .. }) = response.data {
+            assert_eq!(reported, Some(schedule));
+        } else {
+            panic!("Expected Status response data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_repository_state() {
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
+        let policy = DaemonConfig::default();
+        let next_check = Utc::now() + chrono::Duration::minutes(2);
+
+        let synced = JournalEntry {
+            timestamp: Utc::now(),
+            repo: PathBuf::from("/test/repo1"),
+            commit: Some("abc123".to_owned()),
+            files_changed: 2,
+            push: RemoteResult::Deferred,
+            pull: RemoteResult::Done,
+            error: None,
+        };
+        state.set_last_sync(synced.clone());
+        state.set_push_pending(&synced.repo, true);
+        state.record_failure(Path::new("/test/repo3"), "disk full".to_owned(), &policy);
+        state.pause(Path::new("/test/repo3"), None);
+        state.set_next_check(next_check);
+
+        let response = handle_status_command(config, Instant::now(), &state, suspended).await;
+        let Some(ResponseData::Status { repositories, next_check: reported, .. }) = response.data else {
+            panic!("Expected Status response data");
+        };
+        assert_eq!(reported, Some(next_check));
+        assert_eq!(repositories.len(), 3);
+
+        let repo1 = &repositories[0];
+        assert!(repo1.enabled && repo1.push_pending && !repo1.paused);
+        assert_eq!(repo1.last_sync.as_ref().and_then(|e| e.commit.as_deref()), Some("abc123"));
+        // The paths don't exist, so there is nothing to compare with an upstream
+        assert_eq!((repo1.ahead, repo1.behind), (None, None));
+
+        assert!(!repositories[1].enabled);
+        assert!(repositories[1].last_sync.is_none());
+
+        let repo3 = &repositories[2];
+        assert!(repo3.paused);
+        assert_eq!(repo3.error.as_deref(), Some("disk full"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_unquarantine_command() {
+        let (_state_dir, state) = create_test_state();
+        let policy = DaemonConfig { quarantine_after_failures: 1, ..Default::default() };
+
+        state.record_failure(&PathBuf::from("/test/a"), "missing".to_owned(), &policy);
+        state.record_failure(&PathBuf::from("/test/b"), "missing".to_owned(), &policy);
+
+        let response = handle_unquarantine_command(&state, Some(PathBuf::from("/test/a")));
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(state.quarantined_repos(), vec![PathBuf::from("/test/b")]);
+
+        // Releasing a repository that isn't quarantined is an error
+        let response = handle_unquarantine_command(&state, Some(PathBuf::from("/test/a")));
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.contains("not quarantined"));
+
+        let response = handle_unquarantine_command(&state, None);
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(response.message.contains("Released 1"));
+        assert!(state.quarantined_repos().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timed_suspend_reported_in_status() {
+        let config = Arc::new(RwLock::new(create_test_config()));
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
+        let until = chrono::Utc::now() + chrono::Duration::hours(2);
+
+        let response = handle_suspend_command(&state, suspended.clone(), Some(until)).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(response.message.starts_with("Daemon suspended until"));
+        assert!(suspended.load(std::sync::atomic::Ordering::Relaxed));
+
+        let response = handle_status_command(config.clone(), Instant::now(), &state, suspended.clone()).await;
+        if let Some(ResponseData::Status { suspended_until, .. }) = response.data {
+            assert_eq!(suspended_until, Some(until));
+        } else {
+            panic!("Expected Status response data");
+        }
+
+        // Resuming cancels the timed suspension
+        handle_resume_command(&state, suspended.clone()).await;
+        let response = handle_status_command(config, Instant::now(), &state, suspended).await;
+        if let Some(ResponseData::Status { suspended_until, .. }) = response.data {
+            assert!(suspended_until.is_none());
+        } else {
+            panic!("Expected Status response data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_pause_and_unpause_commands() {
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (_state_dir, state) = create_test_state();
+        let repo = PathBuf::from("/test/repo1");
+
+        let response = handle_pause_command(config.clone(), &state, repo.clone(), None).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(state.is_paused(&repo));
+
+        let response = handle_status_command(config.clone(), Instant::now(), &state, suspended).await;
+        if let Some(ResponseData::Status { paused, .. }) = response.data {
+            assert_eq!(paused.len(), 1);
+            assert_eq!(paused[0].path, repo);
+            assert!(paused[0].until.is_none());
+        } else {
+            panic!("Expected Status response data");
+        }
+
+        let response = handle_unpause_command(&state, repo.clone());
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(!state.is_paused(&repo));
+
+        let response = handle_unpause_command(&state, repo);
+        assert_eq!(response.status, ResponseStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn test_handle_pause_unknown_repository() {
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+        let (_state_dir, state) = create_test_state();
+
+        let response = handle_pause_command(config, &state, PathBuf::from("/test/unknown"), None).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.contains("not found"));
+        assert_eq!(response.error_kind, Some(ErrorKind::RepoNotFound));
+        assert!(state.paused_repos().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_history_command() {
+        let (_state_dir, state) = create_test_state();
+
+        // Empty journal
+        let response = handle_history_command(&state, JournalFilter::default()).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        if let Some(ResponseData::History { entries }) = response.data {
+            assert!(entries.is_empty());
+        } else {
+            panic!("Expected History response data");
+        }
+
+        for (repo, error) in [("/test/repo1", None), ("/test/repo2", Some("boom"))] {
+            state.journal().append(&JournalEntry {
+                timestamp: chrono::Utc::now(),
+                repo: PathBuf::from(repo),
+                commit: None,
+                files_changed: 0,
+                push: RemoteResult::Skipped,
+                pull: RemoteResult::Skipped,
+                error: error.map(str::to_owned),
+            }).unwrap();
+        }
+
+        let filter = JournalFilter { errors_only: true, ..Default::default() };
+        let response = handle_history_command(&state, filter).await;
+        if let Some(ResponseData::History { entries }) = response.data {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].repo, PathBuf::from("/test/repo2"));
+        } else {
+            panic!("Expected History response data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_record_history_command() {
+        let (_state_dir, state) = create_test_state();
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+        let mut events = state.subscribe();
+        let entry = |repo: &str| JournalEntry {
+            timestamp: chrono::Utc::now(),
+            repo: PathBuf::from(repo),
+            commit: Some("abc123".to_owned()),
+            files_changed: 1,
+            push: RemoteResult::Skipped,
+            pull: RemoteResult::Skipped,
+            error: None,
+        };
+
+        let response = handle_record_history_command(Arc::clone(&config), &state, entry("/test/unknown")).await;
+        assert_eq!(response.error_kind, Some(ErrorKind::RepoNotFound));
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+
+        let response = handle_record_history_command(config, &state, entry("/test/repo1")).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].commit.as_deref(), Some("abc123"));
+        assert!(matches!(events.try_recv().unwrap().kind, EventKind::CommitCreated { files_changed: 1, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_handle_trigger_command_records_history() {
+        let (_state_dir, state) = create_test_state();
+        let config = Arc::new(RwLock::new(create_test_config_with_repos()));
+
+        // Both enabled repos don't exist, so both failures end up in the journal
+        handle_trigger_command(config, &state, None, Transfer::default(), false).await;
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.is_error()));
+    }
+
+    /// Send one raw line through handle_connection_impl and read the reply
+    async fn exchange(line: &str) -> Response {
+        // No main loop behind this connection
+        let (config_tx, _) = mpsc::channel(1);
+        exchange_with(line, config_tx).await
+    }
+
+    /// Like `exchange`, with configuration changes going to the given channel
+    async fn exchange_with(line: &str, config_tx: mpsc::Sender<ConfigRequest>) -> Response {
+        let (client, server) = UnixStream::pair().unwrap();
+        let (_state_dir, state) = create_test_state();
+
+        let (result, response) = tokio::join!(
+            handle_connection_impl(
+                server,
+                Arc::new(RwLock::new(create_test_config())),
+                Instant::now(),
+                Arc::new(state),
+                Arc::new(std::sync::atomic::AtomicBool::new(false)),
+                config_tx,
+            ),
+            async {
+                let mut client = client;
+                client.write_all(line.as_bytes()).await.unwrap();
+                let mut reply = String::new();
+                BufReader::new(client).read_line(&mut reply).await.unwrap();
+                reply
+            }
+        );
+
+        result.unwrap();
+        Response::from_json(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hello_handshake() {
+        let response = exchange(&Command::hello().to_json().unwrap()).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+
+        match response.data {
+            Some(ResponseData::Hello { protocol_version, daemon_version, commands }) => {
+                assert_eq!(protocol_version, PROTOCOL_VERSION);
+                assert_eq!(daemon_version, env!("CARGO_PKG_VERSION"));
+                assert!(commands.iter().any(|c| c == "hello"));
+                assert!(commands.iter().any(|c| c == "pause"));
+            }
+            _ => panic!("Expected Hello response data"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hello_from_newer_client() {
+        let line = format!("{{\"command\":\"hello\",\"protocol_version\":{}}}\n", PROTOCOL_VERSION + 1);
+        let response = exchange(&line).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(response.message.contains("restart the daemon"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command_gets_error_response() {
+        let response = exchange("{\"command\":\"teleport\"}\n").await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.starts_with("Unknown command 'teleport'"));
+        assert!(matches!(response.data, Some(ResponseData::Hello { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_command_gets_error_response() {
+        let response = exchange("not json\n").await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert!(response.message.starts_with("Malformed command"));
+        assert!(response.data.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_command_without_handshake() {
+        let response = exchange("{\"command\":\"ping\"}\n").await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(response.message, "pong");
+    }
+
+    #[tokio::test]
+    async fn test_config_commands_forwarded_to_main_loop() {
+        let (config_tx, mut config_rx) = mpsc::channel(1);
+
+        // Stand-in for the main loop that rejects everything but a reload
+        let main_loop = tokio::spawn(async move {
+            let mut changes = Vec::new();
+            while let Some(request) = config_rx.recv().await {
+                let ConfigRequest { change, reply } = request;
+                let response = match &change {
+                    None => Response::ok("Configuration reloaded with 0 repositories"),
+                    Some(change) => Response::error(format!("Refused: {}", change.summary())),
+                };
+                changes.push(change);
+                reply.send(response).unwrap();
+            }
+            changes
+        });
+
+        let line = Command::SetInterval { seconds: 60 }.to_json().unwrap();
+        let response = exchange_with(&line, config_tx.clone()).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert_eq!(response.message, "Refused: Check interval set to 60 seconds");
+
+        let response = exchange_with(&Command::Reload.to_json().unwrap(), config_tx).await;
+        assert_eq!(response.status, ResponseStatus::Ok);
+
+        assert_eq!(main_loop.await.unwrap(), vec![Some(ConfigChange::SetInterval { seconds: 60 }), None]);
+    }
+
+    #[tokio::test]
+    async fn test_config_command_while_shutting_down() {
+        let response = exchange(&Command::Reload.to_json().unwrap()).await;
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert_eq!(response.message, "Daemon is shutting down");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_streams_events() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let (_state_dir, state) = create_test_state();
+        let state = Arc::new(state);
+        let suspended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let connection = tokio::spawn(handle_connection_impl(
+            server,
+            Arc::new(RwLock::new(create_test_config())),
+            Instant::now(),
+            Arc::clone(&state),
+            Arc::clone(&suspended),
+            mpsc::channel(1).0,
+        ));
+
+        let (read_half, mut write_half) = client.into_split();
+        let mut lines = BufReader::new(read_half).lines();
+        write_half.write_all(Command::Subscribe.to_json().unwrap().as_bytes()).await.unwrap();
+
+        let ack = Response::from_json(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(ack.status, ResponseStatus::Ok);
+
+        state.suspend(&suspended, None);
+        state.resume(&suspended);
+        state.emit(EventKind::ConfigReloaded { repositories: 4 });
+
+        for expected in [EventKind::Suspended { until: None }, EventKind::Resumed, EventKind::ConfigReloaded { repositories: 4 }] {
+            let line = lines.next_line().await.unwrap().unwrap();
+            assert_eq!(DaemonEvent::from_json(&line).unwrap().kind, expected);
+        }
+
+        // Closing the connection ends the subscription
+        drop(write_half);
+        drop(lines);
+        tokio::time::timeout(Duration::from_secs(5), connection).await.unwrap().unwrap().unwrap();
+    }
 }
@@ -0,0 +1,571 @@
+use autogit_shared::{DaemonConfig, DaemonEvent, EventKind, Journal, JournalEntry, PausedRepo, ScheduleState};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{MutexGuard, broadcast};
+
+/// Events buffered per subscriber before it starts missing some
+const EVENT_BUFFER: usize = 256;
+
+/// Runtime state shared between the main loop, socket handlers and the tray
+pub struct DaemonState {
+    journal: Journal,
+    health: Mutex<HashMap<PathBuf, RepoHealth>>,
+    suspended_until: Mutex<Option<DateTime<Utc>>>,
+    /// Paused repositories and when their pause ends (None = until unpaused)
+    paused: Mutex<HashMap<PathBuf, Option<DateTime<Utc>>>>,
+    /// Latest evaluation of the battery, network and idle policy
+    schedule: Mutex<Option<ScheduleState>>,
+    /// Repositories with a commit whose push was deferred
+    pending_push: Mutex<HashSet<PathBuf>>,
+    /// Most recent sync outcome per repository, including ones with nothing to do
+    last_sync: Mutex<HashMap<PathBuf, JournalEntry>>,
+    /// When the main loop runs its next scheduled check
+    next_check: Mutex<Option<DateTime<Utc>>>,
+    /// Live events for socket subscribers
+    events: broadcast::Sender<DaemonEvent>,
+    /// Held while a sync runs, so two never stage, commit and push at the same time
+    syncing: tokio::sync::Mutex<()>,
+}
+
+/// Failure tracking for a single repository
+#[derive(Debug, Clone, Default)]
+pub struct RepoHealth {
+    pub consecutive_failures: u32,
+    /// Scheduled ticks left to skip before the next attempt
+    pub skip_ticks: u64,
+    pub quarantined: bool,
+    pub last_error: Option<String>,
+}
+
+/// Whether a repository should be synced right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncGate {
+    Ready,
+    BackingOff,
+    Quarantined,
+}
+
+/// What happened after recording a failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureAction {
+    /// Retry after skipping this many scheduled ticks
+    Backoff(u64),
+    /// The failure limit was just reached
+    Quarantined,
+}
+
+impl DaemonState {
+    pub fn new(journal: Journal) -> Self {
+        Self {
+            journal,
+            health: Mutex::new(HashMap::new()),
+            suspended_until: Mutex::new(None),
+            paused: Mutex::new(HashMap::new()),
+            schedule: Mutex::new(None),
+            pending_push: Mutex::new(HashSet::new()),
+            last_sync: Mutex::new(HashMap::new()),
+            next_check: Mutex::new(None),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            syncing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Sync history journal
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Current failure tracking for a repository (default if it never failed)
+    pub fn health(&self, path: &Path) -> RepoHealth {
+        self.health.lock().unwrap().get(path).cloned().unwrap_or_default()
+    }
+
+    /// Decide whether a scheduled sync should run, consuming one backoff tick if not
+    pub fn scheduled_gate(&self, path: &Path) -> SyncGate {
+        let mut health = self.health.lock().unwrap();
+        match health.get_mut(path) {
+            Some(h) if h.quarantined => SyncGate::Quarantined,
+            Some(h) if h.skip_ticks > 0 => {
+                h.skip_ticks -= 1;
+                SyncGate::BackingOff
+            }
+            _ => SyncGate::Ready,
+        }
+    }
+
+    /// Whether a repository is quarantined (manual syncs skip those as well)
+    pub fn is_quarantined(&self, path: &Path) -> bool {
+        self.health.lock().unwrap().get(path).is_some_and(|h| h.quarantined)
+    }
+
+    /// Reset failure tracking after a successful sync
+    pub fn record_success(&self, path: &Path) {
+        self.health.lock().unwrap().remove(path);
+    }
+
+    /// Count a failed sync and compute the exponential backoff
+    ///
+    /// After n consecutive failures the next 2^(n-1) - 1 ticks are skipped, capped at
+    /// `max_backoff_seconds`; reaching `quarantine_after_failures` quarantines the repository.
+    pub fn record_failure(&self, path: &Path, error: String, policy: &DaemonConfig) -> FailureAction {
+        let mut health = self.health.lock().unwrap();
+        let h = health.entry(path.to_path_buf()).or_default();
+
+        h.consecutive_failures += 1;
+        h.last_error = Some(error);
+
+        if policy.quarantine_after_failures > 0 && h.consecutive_failures >= policy.quarantine_after_failures {
+            h.quarantined = true;
+            h.skip_ticks = 0;
+            return FailureAction::Quarantined;
+        }
+
+        let max_ticks = policy.max_backoff_seconds / policy.check_interval_seconds.max(1);
+        let exponent = (h.consecutive_failures - 1).min(63);
+        h.skip_ticks = (1u64 << exponent).saturating_sub(1).min(max_ticks);
+
+        FailureAction::Backoff(h.skip_ticks)
+    }
+
+    /// Release one quarantined repository, or all of them; returns the released paths
+    pub fn clear_quarantine(&self, path: Option<&Path>) -> Vec<PathBuf> {
+        let mut health = self.health.lock().unwrap();
+        let released: Vec<PathBuf> = health.iter()
+            .filter(|(p, h)| h.quarantined && path.is_none_or(|wanted| wanted == p.as_path()))
+            .map(|(p, _)| p.clone())
+            .collect();
+
+        for p in &released {
+            health.remove(p);
+        }
+
+        released
+    }
+
+    /// All currently quarantined repositories
+    pub fn quarantined_repos(&self) -> Vec<PathBuf> {
+        let health = self.health.lock().unwrap();
+        let mut repos: Vec<PathBuf> = health.iter()
+            .filter(|(_, h)| h.quarantined)
+            .map(|(p, _)| p.clone())
+            .collect();
+        repos.sort();
+        repos
+    }
+
+    /// Drop tracking and pauses for repositories that are no longer configured
+    pub fn retain_repos(&self, configured: &[PathBuf]) {
+        self.health.lock().unwrap().retain(|p, _| configured.contains(p));
+        self.paused.lock().unwrap().retain(|p, _| configured.contains(p));
+        self.pending_push.lock().unwrap().retain(|p| configured.contains(p));
+        self.last_sync.lock().unwrap().retain(|p, _| configured.contains(p));
+    }
+
+    /// Publish an event to all current subscribers
+    pub fn emit(&self, kind: EventKind) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(DaemonEvent::now(kind));
+    }
+
+    /// Wait until no other sync runs; others wait in turn until the guard is dropped
+    pub async fn sync_guard(&self) -> MutexGuard<'_, ()> {
+        self.syncing.lock().await
+    }
+
+    /// Receive all events emitted from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.events.subscribe()
+    }
+
+    /// Suspend syncing, indefinitely or until a point in time
+    pub fn suspend(&self, suspended: &AtomicBool, until: Option<DateTime<Utc>>) -> bool {
+        *self.suspended_until.lock().unwrap() = until;
+        let was_suspended = suspended.swap(true, Ordering::Relaxed);
+        self.emit(EventKind::Suspended { until });
+        was_suspended
+    }
+
+    /// Resume syncing, cancelling any timed suspension
+    pub fn resume(&self, suspended: &AtomicBool) -> bool {
+        *self.suspended_until.lock().unwrap() = None;
+        let was_suspended = suspended.swap(false, Ordering::Relaxed);
+        if was_suspended {
+            self.emit(EventKind::Resumed);
+        }
+        was_suspended
+    }
+
+    /// When the current timed suspension ends
+    pub fn suspended_until(&self) -> Option<DateTime<Utc>> {
+        *self.suspended_until.lock().unwrap()
+    }
+
+    /// Resume if a timed suspension has run out; returns true if it did
+    pub fn expire_suspension(&self, suspended: &AtomicBool) -> bool {
+        let mut until = self.suspended_until.lock().unwrap();
+        match *until {
+            Some(deadline) if deadline <= Utc::now() => {
+                *until = None;
+                suspended.store(false, Ordering::Relaxed);
+                self.emit(EventKind::Resumed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pause a repository, indefinitely or until a point in time
+    pub fn pause(&self, path: &Path, until: Option<DateTime<Utc>>) {
+        self.paused.lock().unwrap().insert(path.to_path_buf(), until);
+    }
+
+    /// Unpause a repository; returns false if it wasn't paused
+    pub fn unpause(&self, path: &Path) -> bool {
+        let mut paused = self.paused.lock().unwrap();
+        Self::expire_pauses(&mut paused);
+        paused.remove(path).is_some()
+    }
+
+    /// Whether a repository is currently paused
+    pub fn is_paused(&self, path: &Path) -> bool {
+        let mut paused = self.paused.lock().unwrap();
+        Self::expire_pauses(&mut paused);
+        paused.contains_key(path)
+    }
+
+    /// All currently paused repositories
+    pub fn paused_repos(&self) -> Vec<PausedRepo> {
+        let mut paused = self.paused.lock().unwrap();
+        Self::expire_pauses(&mut paused);
+
+        let mut repos: Vec<PausedRepo> = paused.iter()
+            .map(|(path, until)| PausedRepo { path: path.clone(), until: *until })
+            .collect();
+        repos.sort_by(|a, b| a.path.cmp(&b.path));
+        repos
+    }
+
+    /// Store the latest scheduling decision
+    pub fn set_schedule(&self, schedule: ScheduleState) {
+        *self.schedule.lock().unwrap() = Some(schedule);
+    }
+
+    /// Latest scheduling decision (None before the first evaluation)
+    pub fn schedule(&self) -> Option<ScheduleState> {
+        self.schedule.lock().unwrap().clone()
+    }
+
+    /// Remember whether a repository still has a deferred push to catch up on
+    pub fn set_push_pending(&self, path: &Path, pending: bool) {
+        let mut pending_push = self.pending_push.lock().unwrap();
+        if pending {
+            pending_push.insert(path.to_path_buf());
+        } else {
+            pending_push.remove(path);
+        }
+    }
+
+    /// Whether a repository has a deferred push to catch up on
+    pub fn is_push_pending(&self, path: &Path) -> bool {
+        self.pending_push.lock().unwrap().contains(path)
+    }
+
+    /// Remember the outcome of the latest sync of a repository
+    pub fn set_last_sync(&self, entry: JournalEntry) {
+        self.last_sync.lock().unwrap().insert(entry.repo.clone(), entry);
+    }
+
+    /// Outcome of the latest sync of a repository since the daemon started
+    pub fn last_sync(&self, path: &Path) -> Option<JournalEntry> {
+        self.last_sync.lock().unwrap().get(path).cloned()
+    }
+
+    /// Record when the next scheduled check runs
+    pub fn set_next_check(&self, at: DateTime<Utc>) {
+        *self.next_check.lock().unwrap() = Some(at);
+    }
+
+    /// When the next scheduled check runs (None before the loop started)
+    pub fn next_check(&self) -> Option<DateTime<Utc>> {
+        *self.next_check.lock().unwrap()
+    }
+
+    fn expire_pauses(paused: &mut HashMap<PathBuf, Option<DateTime<Utc>>>) {
+        let now = Utc::now();
+        paused.retain(|_, until| until.is_none_or(|deadline| deadline > now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn create_test_state() -> DaemonState {
+        DaemonState::new(Journal::new("/nonexistent/history.jsonl"))
+    }
+
+    fn policy(quarantine_after: u32) -> DaemonConfig {
+        DaemonConfig {
+            check_interval_seconds: 60,
+            quarantine_after_failures: quarantine_after,
+            max_backoff_seconds: 600,
+            ..Default::default()
+        }
+    }
+
+    fn entry(repo: &str) -> JournalEntry {
+        JournalEntry {
+            timestamp: Utc::now(),
+            repo: PathBuf::from(repo),
+            commit: None,
+            files_changed: 0,
+            push: Default::default(),
+            pull: Default::default(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+        let policy = policy(0);
+
+        assert_eq!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(0));
+        assert_eq!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(1));
+        assert_eq!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(3));
+        assert_eq!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(7));
+        // Capped at max_backoff_seconds / check_interval_seconds
+        assert_eq!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(10));
+        for _ in 0..100 {
+            state.record_failure(path, "e".into(), &policy);
+        }
+        assert_eq!(state.health(path).skip_ticks, 10);
+        assert!(!state.health(path).quarantined); // 0 disables quarantine
+    }
+
+    #[test]
+    fn test_scheduled_gate_consumes_backoff_ticks() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+        let policy = policy(0);
+
+        assert_eq!(state.scheduled_gate(path), SyncGate::Ready);
+
+        state.record_failure(path, "e".into(), &policy);
+        state.record_failure(path, "e".into(), &policy);
+        state.record_failure(path, "e".into(), &policy); // Skip 3 ticks
+
+        assert_eq!(state.scheduled_gate(path), SyncGate::BackingOff);
+        assert_eq!(state.scheduled_gate(path), SyncGate::BackingOff);
+        assert_eq!(state.scheduled_gate(path), SyncGate::BackingOff);
+        assert_eq!(state.scheduled_gate(path), SyncGate::Ready);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+
+        state.record_failure(path, "e".into(), &policy(5));
+        state.record_failure(path, "e".into(), &policy(5));
+        assert_eq!(state.health(path).consecutive_failures, 2);
+
+        state.record_success(path);
+        assert_eq!(state.health(path).consecutive_failures, 0);
+        assert_eq!(state.scheduled_gate(path), SyncGate::Ready);
+    }
+
+    #[test]
+    fn test_quarantine_after_limit() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+        let policy = policy(3);
+
+        assert!(matches!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(_)));
+        assert!(matches!(state.record_failure(path, "e".into(), &policy), FailureAction::Backoff(_)));
+        assert_eq!(state.record_failure(path, "gone".into(), &policy), FailureAction::Quarantined);
+
+        assert!(state.is_quarantined(path));
+        assert_eq!(state.scheduled_gate(path), SyncGate::Quarantined);
+        assert_eq!(state.quarantined_repos(), vec![PathBuf::from("/repo")]);
+        assert_eq!(state.health(path).last_error.as_deref(), Some("gone"));
+    }
+
+    #[test]
+    fn test_clear_quarantine() {
+        let state = create_test_state();
+        let policy = policy(1);
+
+        state.record_failure(Path::new("/a"), "e".into(), &policy);
+        state.record_failure(Path::new("/b"), "e".into(), &policy);
+        state.record_failure(Path::new("/c"), "e".into(), &DaemonConfig::default()); // Only backing off
+
+        assert_eq!(state.clear_quarantine(Some(Path::new("/a"))), vec![PathBuf::from("/a")]);
+        assert!(!state.is_quarantined(Path::new("/a")));
+        assert!(state.is_quarantined(Path::new("/b")));
+
+        // Clearing a repository that isn't quarantined does nothing
+        assert!(state.clear_quarantine(Some(Path::new("/c"))).is_empty());
+
+        assert_eq!(state.clear_quarantine(None), vec![PathBuf::from("/b")]);
+        assert!(state.quarantined_repos().is_empty());
+        assert_eq!(state.health(Path::new("/c")).consecutive_failures, 1);
+    }
+
+    #[test]
+    fn test_retain_repos() {
+        let state = create_test_state();
+        let policy = policy(1);
+
+        state.record_failure(Path::new("/kept"), "e".into(), &policy);
+        state.record_failure(Path::new("/removed"), "e".into(), &policy);
+        state.pause(Path::new("/kept"), None);
+        state.pause(Path::new("/removed"), None);
+        state.set_push_pending(Path::new("/removed"), true);
+        state.set_last_sync(entry("/kept"));
+        state.set_last_sync(entry("/removed"));
+
+        state.retain_repos(&[PathBuf::from("/kept")]);
+        assert_eq!(state.quarantined_repos(), vec![PathBuf::from("/kept")]);
+        assert!(state.is_paused(Path::new("/kept")));
+        assert!(!state.is_paused(Path::new("/removed")));
+        assert!(!state.is_push_pending(Path::new("/removed")));
+        assert!(state.last_sync(Path::new("/kept")).is_some());
+        assert!(state.last_sync(Path::new("/removed")).is_none());
+    }
+
+    #[test]
+    fn test_last_sync_keeps_latest() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+        assert!(state.last_sync(path).is_none());
+
+        state.set_last_sync(entry("/repo"));
+        state.set_last_sync(JournalEntry { files_changed: 3, ..entry("/repo") });
+        assert_eq!(state.last_sync(path).unwrap().files_changed, 3);
+    }
+
+    #[test]
+    fn test_next_check() {
+        let state = create_test_state();
+        assert!(state.next_check().is_none());
+
+        let at = Utc::now() + Duration::minutes(5);
+        state.set_next_check(at);
+        assert_eq!(state.next_check(), Some(at));
+    }
+
+    #[test]
+    fn test_pending_push() {
+        let state = create_test_state();
+        let path = Path::new("/repo");
+
+        assert!(!state.is_push_pending(path));
+        state.set_push_pending(path, true);
+        assert!(state.is_push_pending(path));
+        state.set_push_pending(path, false);
+        assert!(!state.is_push_pending(path));
+    }
+
+    #[test]
+    fn test_schedule() {
+        let state = create_test_state();
+        assert!(state.schedule().is_none());
+
+        let schedule = ScheduleState {
+            remote_deferred: Some("metered connection".into()),
+            ..Default::default()
+        };
+        state.set_schedule(schedule.clone());
+        assert_eq!(state.schedule(), Some(schedule));
+    }
+
+    #[test]
+    fn test_suspend_and_resume() {
+        let state = create_test_state();
+        let suspended = AtomicBool::new(false);
+        let until = Utc::now() + Duration::hours(2);
+
+        assert!(!state.suspend(&suspended, Some(until)));
+        assert!(suspended.load(Ordering::Relaxed));
+        assert_eq!(state.suspended_until(), Some(until));
+
+        // Not expired yet
+        assert!(!state.expire_suspension(&suspended));
+        assert!(suspended.load(Ordering::Relaxed));
+
+        assert!(state.resume(&suspended));
+        assert!(!suspended.load(Ordering::Relaxed));
+        assert!(state.suspended_until().is_none());
+    }
+
+    #[test]
+    fn test_timed_suspension_expires() {
+        let state = create_test_state();
+        let suspended = AtomicBool::new(false);
+
+        state.suspend(&suspended, Some(Utc::now() - Duration::seconds(1)));
+        assert!(state.expire_suspension(&suspended));
+        assert!(!suspended.load(Ordering::Relaxed));
+        assert!(state.suspended_until().is_none());
+
+        // An indefinite suspension never expires
+        state.suspend(&suspended, None);
+        assert!(!state.expire_suspension(&suspended));
+        assert!(suspended.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_pause_and_unpause() {
+        let state = create_test_state();
+        let until = Utc::now() + Duration::minutes(30);
+
+        state.pause(Path::new("/a"), Some(until));
+        state.pause(Path::new("/b"), None);
+
+        assert!(state.is_paused(Path::new("/a")));
+        assert_eq!(state.paused_repos(), vec![
+            PausedRepo { path: PathBuf::from("/a"), until: Some(until) },
+            PausedRepo { path: PathBuf::from("/b"), until: None },
+        ]);
+
+        assert!(state.unpause(Path::new("/a")));
+        assert!(!state.unpause(Path::new("/a")));
+        assert!(!state.is_paused(Path::new("/a")));
+        assert!(state.is_paused(Path::new("/b")));
+    }
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let state = create_test_state();
+
+        state.pause(Path::new("/a"), Some(Utc::now() - Duration::seconds(1)));
+        assert!(!state.is_paused(Path::new("/a")));
+        assert!(state.paused_repos().is_empty());
+    }
+
+    #[test]
+    fn test_suspend_and_resume_emit_events() {
+        let state = create_test_state();
+        let suspended = AtomicBool::new(false);
+        let mut events = state.subscribe();
+
+        let until = Utc::now() - Duration::seconds(1);
+        state.suspend(&suspended, Some(until));
+        assert!(state.expire_suspension(&suspended));
+        state.suspend(&suspended, None);
+        state.resume(&suspended);
+        state.resume(&suspended); // Not suspended, nothing to report
+
+        assert_eq!(events.try_recv().unwrap().kind, EventKind::Suspended { until: Some(until) });
+        assert_eq!(events.try_recv().unwrap().kind, EventKind::Resumed);
+        assert_eq!(events.try_recv().unwrap().kind, EventKind::Suspended { until: None });
+        assert_eq!(events.try_recv().unwrap().kind, EventKind::Resumed);
+        assert!(events.try_recv().is_err());
+    }
+}
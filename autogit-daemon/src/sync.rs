@@ -0,0 +1,772 @@
+use anyhow::Result;
+use autogit_shared::{Config, DaemonConfig, EventKind, JournalEntry, RemoteResult, RepoDetail, Repository, SyncPreview};
+use chrono::Utc;
+use notify_rust::Notification;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::git::{self, SyncOptions, SyncOutcome};
+use crate::state::{DaemonState, FailureAction, SyncGate};
+
+/// What started a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncTrigger {
+    /// Periodic tick; honours backoff and the battery, network and idle policy
+    Scheduled,
+    /// Explicit request from the tray or CLI; bypasses backoff and the scheduling policy but not quarantine
+    Manual,
+}
+
+/// Which remote operations a sync should run (the scheduling policy may still defer them)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub push: bool,
+    pub pull: bool,
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Self { push: true, pull: true }
+    }
+}
+
+/// Check and commit a single repository, recording the outcome in the journal
+pub async fn sync_repository(
+    repo: &Repository,
+    policy: &DaemonConfig,
+    state: &DaemonState,
+    trigger: SyncTrigger,
+    transfer: Transfer,
+) -> RepoDetail {
+    if state.is_paused(&repo.path) {
+        debug!("Skipping {} (paused)", repo.path.display());
+        return skipped_detail(repo, state);
+    }
+
+    let gate = match trigger {
+        SyncTrigger::Scheduled => state.scheduled_gate(&repo.path),
+        SyncTrigger::Manual if state.is_quarantined(&repo.path) => SyncGate::Quarantined,
+        SyncTrigger::Manual => SyncGate::Ready,
+    };
+
+    if gate != SyncGate::Ready {
+        debug!("Skipping {} ({:?})", repo.path.display(), gate);
+        return skipped_detail(repo, state);
+    }
+
+    let schedule = match trigger {
+        SyncTrigger::Scheduled => state.schedule().unwrap_or_default(),
+        SyncTrigger::Manual => Default::default(),
+    };
+
+    // Pulling needs a clean tree, so a deferred commit postpones the whole sync
+    if let Some(reason) = &schedule.commit_deferred {
+        debug!("Skipping {} ({})", repo.path.display(), reason);
+        return skipped_detail(repo, state);
+    }
+
+    let options = SyncOptions {
+        remote: schedule.remote_deferred.is_none(),
+        push_pending: state.is_push_pending(&repo.path),
+        push: transfer.push,
+        pull: transfer.pull,
+    };
+
+    let result = git::check_and_commit(repo, options).await;
+
+    match &result {
+        Ok(outcome) if outcome.committed() => {
+            info!("Committed changes in: {}", repo.path.display());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            error!("Error processing repository {}: {:#}", repo.path.display(), e);
+        }
+    }
+
+    record_outcome(state, policy, repo, result)
+}
+
+/// Initialize a repository on startup (or when newly added), recording the outcome
+pub async fn initialize_repository(repo: &Repository, policy: &DaemonConfig, state: &DaemonState) -> RepoDetail {
+    if state.is_quarantined(&repo.path) || state.is_paused(&repo.path) {
+        return skipped_detail(repo, state);
+    }
+
+    let options = SyncOptions {
+        remote: state.schedule().is_none_or(|s| s.remote_deferred.is_none()),
+        push_pending: state.is_push_pending(&repo.path),
+        ..Default::default()
+    };
+
+    let result = {
+        let _syncing = state.sync_guard().await;
+        git::initialize_repository(repo, options).await
+    };
+
+    match &result {
+        Ok(_) => info!("Initialized repository: {}", repo.path.display()),
+        Err(e) => error!("Error initializing repository {}: {:#}", repo.path.display(), e),
+    }
+
+    record_outcome(state, policy, repo, result)
+}
+
+/// Sync every enabled repository
+pub async fn sync_all(config: &Config, state: &DaemonState, trigger: SyncTrigger) -> Vec<RepoDetail> {
+    let repos: Vec<&Repository> = config.all_repositories().filter(|r| r.auto_commit).collect();
+    sync_repos(&repos, &config.daemon, state, trigger, Transfer::default()).await
+}
+
+/// Sync the given repositories as one cycle, announcing it to event subscribers
+///
+/// Cycles from the timer, triggers and final syncs run one after another, never interleaved.
+pub async fn sync_repos(
+    repos: &[&Repository],
+    policy: &DaemonConfig,
+    state: &DaemonState,
+    trigger: SyncTrigger,
+    transfer: Transfer,
+) -> Vec<RepoDetail> {
+    let _syncing = state.sync_guard().await;
+    state.emit(EventKind::SyncStarted {
+        manual: trigger == SyncTrigger::Manual,
+        repositories: repos.len(),
+        paths: repos.iter().map(|repo| repo.path.clone()).collect(),
+    });
+
+    let mut details = Vec::with_capacity(repos.len());
+    for repo in repos {
+        details.push(sync_repository(repo, policy, state, trigger, transfer).await);
+    }
+
+    state.emit(EventKind::SyncFinished {
+        repos_checked: details.len(),
+        repos_committed: details.iter().filter(|d| d.committed).count(),
+        repos_failed: details.iter().filter(|d| d.error.is_some()).count(),
+    });
+
+    details
+}
+
+/// Report what a manual sync of the given repositories would do, without changing anything
+///
+/// Nothing is journaled or announced to event subscribers.
+pub async fn preview_repos(repos: &[&Repository], state: &DaemonState, transfer: Transfer) -> Vec<SyncPreview> {
+    let mut previews = Vec::with_capacity(repos.len());
+    for repo in repos {
+        previews.push(preview_repository(repo, state, transfer).await);
+    }
+    previews
+}
+
+/// Preview a manual sync, which bypasses backoff and the scheduling policy but not pauses or quarantine
+async fn preview_repository(repo: &Repository, state: &DaemonState, transfer: Transfer) -> SyncPreview {
+    let skipped = if state.is_paused(&repo.path) {
+        Some("paused")
+    } else if state.is_quarantined(&repo.path) {
+        Some("quarantined")
+    } else {
+        None
+    };
+
+    if let Some(reason) = skipped {
+        return SyncPreview {
+            path: repo.path.clone(),
+            skipped: Some(reason.to_owned()),
+            ..Default::default()
+        };
+    }
+
+    let options = SyncOptions {
+        remote: true,
+        push_pending: state.is_push_pending(&repo.path),
+        push: transfer.push,
+        pull: transfer.pull,
+    };
+
+    match git::preview(repo, options).await {
+        Ok(preview) => preview,
+        Err(e) => SyncPreview {
+            path: repo.path.clone(),
+            error: Some(format!("{:#}", e)),
+            ..Default::default()
+        },
+    }
+}
+
+/// Commit and push every repository one last time before shutdown or sleep
+///
+/// Runs as a manual sync so backoff and the scheduling policy don't leave work behind.
+/// Bounded by `final_sync_timeout_seconds`, which includes waiting for a sync already running;
+/// returns false if skipped or timed out.
+pub async fn final_sync(config: &Config, state: &DaemonState, suspended: &AtomicBool) -> bool {
+    let timeout = config.daemon.final_sync_timeout_seconds;
+    if timeout == 0 {
+        return false;
+    }
+    if suspended.load(Ordering::Relaxed) {
+        info!("Daemon is suspended, skipping final sync");
+        return false;
+    }
+
+    match tokio::time::timeout(Duration::from_secs(timeout), sync_all(config, state, SyncTrigger::Manual)).await {
+        Ok(_) => {
+            info!("Final sync complete");
+            true
+        }
+        Err(_) => {
+            warn!("Final sync did not finish within {} seconds", timeout);
+            false
+        }
+    }
+}
+
+/// Detail for a repository that was not synced because it is paused, backing off or quarantined
+fn skipped_detail(repo: &Repository, state: &DaemonState) -> RepoDetail {
+    let health = state.health(&repo.path);
+    RepoDetail {
+        path: repo.path.clone(),
+        committed: false,
+        files_changed: None,
+        error: health.last_error,
+        quarantined: health.quarantined,
+        paused: state.is_paused(&repo.path),
+    }
+}
+
+/// Update failure tracking, quarantining the repository once it keeps failing
+fn record_health(state: &DaemonState, policy: &DaemonConfig, repo: &Repository, error: Option<&str>) -> bool {
+    let Some(error) = error else {
+        state.record_success(&repo.path);
+        return false;
+    };
+
+    match state.record_failure(&repo.path, error.to_owned(), policy) {
+        FailureAction::Backoff(0) => false,
+        FailureAction::Backoff(ticks) => {
+            debug!("Backing off {} for {} tick(s)", repo.path.display(), ticks);
+            false
+        }
+        FailureAction::Quarantined => {
+            warn!(
+                "Quarantined {} after {} consecutive failures; run `autogit unquarantine` to retry",
+                repo.path.display(),
+                policy.quarantine_after_failures
+            );
+
+            // Showing a notification blocks on D-Bus, so keep it off the async workers
+            let body = format!(
+                "Repository: {}\n\nStopped syncing after {} consecutive failures:\n{}",
+                repo.path.display(),
+                policy.quarantine_after_failures,
+                error
+            );
+            tokio::task::spawn_blocking(move || {
+                let _ = Notification::new()
+                    .summary("Repository Quarantined")
+                    .body(&body)
+                    .appname(env!("CARGO_PKG_NAME"))
+                    .show();
+            });
+
+            true
+        }
+    }
+}
+
+/// Append the outcome to the journal (unless nothing happened) and build the repo detail
+fn record_outcome(
+    state: &DaemonState,
+    policy: &DaemonConfig,
+    repo: &Repository,
+    result: Result<SyncOutcome>,
+) -> RepoDetail {
+    if let Ok(outcome) = &result {
+        match outcome.push {
+            RemoteResult::Deferred => state.set_push_pending(&repo.path, true),
+            RemoteResult::Done | RemoteResult::UpToDate => state.set_push_pending(&repo.path, false),
+            _ => {}
+        }
+    }
+
+    let entry = match result {
+        Ok(outcome) => JournalEntry {
+            timestamp: Utc::now(),
+            repo: repo.path.clone(),
+            commit: outcome.commit.map(|oid| oid.to_string()),
+            files_changed: outcome.files_changed,
+            push: outcome.push,
+            pull: outcome.pull,
+            error: None,
+        },
+        Err(e) => JournalEntry {
+            timestamp: Utc::now(),
+            repo: repo.path.clone(),
+            commit: None,
+            files_changed: 0,
+            push: Default::default(),
+            pull: Default::default(),
+            error: Some(format!("{:#}", e)),
+        },
+    };
+
+    if !entry.is_noop() {
+        if let Err(e) = state.journal().append(&entry) {
+            warn!("Failed to write sync history: {:#}", e);
+        }
+    }
+
+    if let Some(commit) = &entry.commit {
+        state.emit(EventKind::CommitCreated {
+            repo: entry.repo.clone(),
+            commit: commit.clone(),
+            files_changed: entry.files_changed,
+        });
+    }
+    if let RemoteResult::Failed(error) = &entry.push {
+        state.emit(EventKind::PushFailed { repo: entry.repo.clone(), error: error.clone() });
+    }
+    if let Some(error) = &entry.error {
+        state.emit(EventKind::SyncFailed { repo: entry.repo.clone(), error: error.clone() });
+    }
+
+    let quarantined = record_health(state, policy, repo, entry.error.as_deref());
+    state.set_last_sync(entry.clone());
+
+    let committed = entry.commit.is_some();
+    RepoDetail {
+        path: entry.repo,
+        committed,
+        files_changed: committed.then_some(entry.files_changed),
+        error: entry.error,
+        quarantined,
+        paused: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::init_repo;
+    use autogit_shared::{Journal, JournalFilter, ScheduleState};
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn create_test_state(dir: &Path) -> DaemonState {
+        DaemonState::new(Journal::new(dir.join("history.jsonl")))
+    }
+
+    fn create_test_repo() -> TempDir {
+        let temp_dir = tempfile::tempdir().unwrap();
+        init_repo(temp_dir.path());
+        temp_dir
+    }
+
+    fn repo_config(path: &Path) -> Repository {
+        Repository {
+            path: path.to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Auto: {timestamp}".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_repository_records_commit() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let detail = sync_repository(&repo_config(repo_dir.path()), &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(detail.committed);
+        assert_eq!(detail.files_changed, Some(1));
+        assert!(detail.error.is_none());
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].repo, repo_dir.path());
+        assert_eq!(entries[0].files_changed, 1);
+
+        let head = git2::Repository::open(repo_dir.path()).unwrap()
+            .head().unwrap().target().unwrap();
+        assert_eq!(entries[0].commit, Some(head.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_sync_repository_skips_noop_in_journal() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+
+        let detail = sync_repository(&repo_config(repo_dir.path()), &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!detail.committed);
+        assert!(detail.files_changed.is_none());
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert!(entries.is_empty());
+
+        // Status still reports when the repository was last checked
+        let last_sync = state.last_sync(repo_dir.path()).unwrap();
+        assert!(last_sync.is_noop());
+    }
+
+    #[tokio::test]
+    async fn test_preview_repos_changes_nothing() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let mut events = state.subscribe();
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+        let paused_dir = create_test_repo();
+        state.pause(paused_dir.path(), None);
+        let missing = PathBuf::from("/nonexistent/autogit/repo");
+
+        let (repo, paused, missing) = (repo_config(repo_dir.path()), repo_config(paused_dir.path()), repo_config(&missing));
+        let previews = preview_repos(&[&repo, &paused, &missing], &state, Transfer::default()).await;
+
+        assert_eq!(previews[0].files.len(), 1);
+        assert!(previews[0].commit_message.as_deref().unwrap().starts_with("Auto: "));
+        assert_eq!(previews[1].skipped.as_deref(), Some("paused"));
+        assert!(previews[1].files.is_empty());
+        assert!(previews[2].error.is_some());
+
+        // Nothing was committed, journaled, announced or counted as a failure
+        assert!(git2::Repository::open(repo_dir.path()).unwrap().head().is_err());
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+        assert!(events.try_recv().is_err());
+        assert!(state.health(&missing.path).last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_repository_records_error() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let missing = PathBuf::from("/nonexistent/autogit/repo");
+
+        let detail = sync_repository(&repo_config(&missing), &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!detail.committed);
+        assert!(detail.error.is_some());
+
+        let entries = state.journal().query(&JournalFilter {
+            errors_only: true,
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].repo, missing);
+    }
+
+    fn failure_policy(quarantine_after: u32) -> DaemonConfig {
+        DaemonConfig {
+            check_interval_seconds: 60,
+            quarantine_after_failures: quarantine_after,
+            max_backoff_seconds: 3600,
+            ..Default::default()
+        }
+    }
+
+    fn journaled_errors(state: &DaemonState) -> usize {
+        state.journal().query(&JournalFilter {
+            errors_only: true,
+            ..Default::default()
+        }).unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_sync_backs_off_after_failures() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo = repo_config(Path::new("/nonexistent/autogit/repo"));
+        let policy = failure_policy(0);
+
+        // First failure retries on the next tick, the second skips one tick
+        sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert_eq!(journaled_errors(&state), 2);
+
+        let detail = sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(detail.error.is_some());
+        assert!(!detail.quarantined);
+        assert_eq!(journaled_errors(&state), 2); // Skipped attempts are not journaled
+
+        sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert_eq!(journaled_errors(&state), 3);
+        assert_eq!(state.health(&repo.path).consecutive_failures, 3);
+    }
+
+    #[tokio::test]
+    async fn test_manual_sync_bypasses_backoff() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo = repo_config(Path::new("/nonexistent/autogit/repo"));
+        let policy = failure_policy(0);
+
+        for _ in 0..3 {
+            sync_repository(&repo, &policy, &state, SyncTrigger::Manual, Transfer::default()).await;
+        }
+        assert_eq!(journaled_errors(&state), 3);
+    }
+
+    #[tokio::test]
+    async fn test_repository_quarantined_after_limit() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo = repo_config(Path::new("/nonexistent/autogit/repo"));
+        let policy = failure_policy(2);
+
+        let first = sync_repository(&repo, &policy, &state, SyncTrigger::Manual, Transfer::default()).await;
+        assert!(!first.quarantined);
+        let second = sync_repository(&repo, &policy, &state, SyncTrigger::Manual, Transfer::default()).await;
+        assert!(second.quarantined);
+
+        // Quarantined repositories are skipped even for manual syncs
+        let skipped = sync_repository(&repo, &policy, &state, SyncTrigger::Manual, Transfer::default()).await;
+        assert!(skipped.quarantined);
+        assert!(skipped.error.is_some());
+        assert_eq!(journaled_errors(&state), 2);
+
+        state.clear_quarantine(Some(&repo.path));
+        let retried = sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!retried.quarantined);
+        assert_eq!(journaled_errors(&state), 3);
+    }
+
+    #[tokio::test]
+    async fn test_success_clears_failure_count() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        let repo = repo_config(repo_dir.path());
+        let policy = failure_policy(5);
+
+        state.record_failure(&repo.path, "transient".to_owned(), &policy);
+
+        let detail = sync_repository(&repo, &policy, &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(detail.error.is_none());
+        assert_eq!(state.health(&repo.path).consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_paused_repository_is_skipped() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        let repo = repo_config(repo_dir.path());
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        state.pause(&repo.path, None);
+
+        for trigger in [SyncTrigger::Scheduled, SyncTrigger::Manual] {
+            let detail = sync_repository(&repo, &DaemonConfig::default(), &state, trigger, Transfer::default()).await;
+            assert!(detail.paused);
+            assert!(!detail.committed);
+        }
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+
+        state.unpause(&repo.path);
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!detail.paused);
+        assert!(detail.committed);
+    }
+
+    /// Publish the repository to a bare origin with upstream tracking
+    fn add_origin(repo_dir: &Path) -> PathBuf {
+        let origin = repo_dir.join(".origin.git");
+        git2::Repository::init_bare(&origin).unwrap();
+        std::fs::write(repo_dir.join(".gitignore"), ".origin.git\n").unwrap();
+
+        for args in [
+            vec!["remote", "add", "origin", origin.to_str().unwrap()],
+            vec!["add", "-A"],
+            vec!["commit", "-m", "Initial commit"],
+            vec!["push", "-u", "origin", "HEAD"],
+        ] {
+            let output = std::process::Command::new("git").args(&args).current_dir(repo_dir).output().unwrap();
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        }
+        origin
+    }
+
+    fn deferring_state(dir: &Path, remote_deferred: Option<&str>, commit_deferred: Option<&str>) -> DaemonState {
+        let state = create_test_state(dir);
+        state.set_schedule(ScheduleState {
+            remote_deferred: remote_deferred.map(str::to_owned),
+            commit_deferred: commit_deferred.map(str::to_owned),
+            ..Default::default()
+        });
+        state
+    }
+
+    #[tokio::test]
+    async fn test_deferred_commit_skips_scheduled_sync() {
+        let state_dir = TempDir::new().unwrap();
+        let state = deferring_state(state_dir.path(), None, Some("user active"));
+        let repo_dir = create_test_repo();
+        let repo = repo_config(repo_dir.path());
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!detail.committed);
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+
+        // Manual syncs ignore the scheduling policy
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Manual, Transfer::default()).await;
+        assert!(detail.committed);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_push_is_caught_up() {
+        let state_dir = TempDir::new().unwrap();
+        let state = deferring_state(state_dir.path(), Some("metered connection"), None);
+        let repo_dir = create_test_repo();
+        let repo = repo_config(repo_dir.path());
+        let origin = add_origin(repo_dir.path());
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(detail.committed);
+        assert!(detail.error.is_none());
+        assert!(state.is_push_pending(&repo.path));
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries[0].push, RemoteResult::Deferred);
+        assert_eq!(entries[0].pull, RemoteResult::Deferred);
+
+        // Once the policy allows it, the next sync pushes without a new commit
+        state.set_schedule(ScheduleState::default());
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Scheduled, Transfer::default()).await;
+        assert!(!detail.committed);
+        assert!(detail.error.is_none(), "{:?}", detail.error);
+        assert!(!state.is_push_pending(&repo.path));
+
+        let pushed = git2::Repository::open_bare(&origin).unwrap().head().unwrap().target();
+        let local = git2::Repository::open(repo_dir.path()).unwrap().head().unwrap().target();
+        assert_eq!(pushed, local);
+    }
+
+    #[tokio::test]
+    async fn test_sync_without_push_is_caught_up() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        let repo = repo_config(repo_dir.path());
+        let origin = add_origin(repo_dir.path());
+        let pushed_before = git2::Repository::open_bare(&origin).unwrap().head().unwrap().target();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let commit_only = Transfer { push: false, pull: false };
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Manual, commit_only).await;
+        assert!(detail.committed);
+        assert!(state.is_push_pending(&repo.path));
+        assert_eq!(git2::Repository::open_bare(&origin).unwrap().head().unwrap().target(), pushed_before);
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries[0].push, RemoteResult::Deferred);
+        assert_eq!(entries[0].pull, RemoteResult::Skipped);
+
+        let detail = sync_repository(&repo, &DaemonConfig::default(), &state, SyncTrigger::Manual, Transfer::default()).await;
+        assert!(detail.error.is_none(), "{:?}", detail.error);
+        assert!(!state.is_push_pending(&repo.path));
+    }
+
+    fn final_sync_config(repo_dir: &Path, timeout: u64) -> Config {
+        Config {
+            daemon: DaemonConfig { final_sync_timeout_seconds: timeout, ..Default::default() },
+            repositories: vec![repo_config(repo_dir)],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_final_sync_commits_pending_work() {
+        let state_dir = TempDir::new().unwrap();
+        // Neither the scheduling policy nor backoff hold back the final sync
+        let state = deferring_state(state_dir.path(), Some("metered connection"), Some("user active"));
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let config = final_sync_config(repo_dir.path(), 30);
+        state.record_failure(repo_dir.path(), "transient".to_owned(), &DaemonConfig::default());
+        state.record_failure(repo_dir.path(), "transient".to_owned(), &DaemonConfig::default());
+
+        assert!(final_sync(&config, &state, &AtomicBool::new(false)).await);
+
+        let entries = state.journal().query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].commit.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_final_sync_skipped() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        // Disabled by a zero timeout
+        assert!(!final_sync(&final_sync_config(repo_dir.path(), 0), &state, &AtomicBool::new(false)).await);
+        // Suspension is respected
+        assert!(!final_sync(&final_sync_config(repo_dir.path(), 30), &state, &AtomicBool::new(true)).await);
+
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_final_sync_waits_for_running_sync() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+        let config = final_sync_config(repo_dir.path(), 1);
+
+        // A sync that outlasts the timeout keeps the final one from starting at all
+        let running = state.sync_guard().await;
+        assert!(!final_sync(&config, &state, &AtomicBool::new(false)).await);
+        assert!(state.journal().query(&JournalFilter::default()).unwrap().is_empty());
+
+        drop(running);
+        assert!(final_sync(&config, &state, &AtomicBool::new(false)).await);
+        assert_eq!(state.journal().query(&JournalFilter::default()).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_emits_events() {
+        let state_dir = TempDir::new().unwrap();
+        let state = create_test_state(state_dir.path());
+        let repo_dir = create_test_repo();
+        std::fs::write(repo_dir.path().join("note.md"), "hello").unwrap();
+
+        let missing = state_dir.path().join("missing");
+        let mut disabled = repo_config(&missing);
+        disabled.auto_commit = false;
+        let config = Config {
+            daemon: DaemonConfig::default(),
+            repositories: vec![repo_config(repo_dir.path()), repo_config(&missing), disabled],
+            ..Default::default()
+        };
+
+        let mut events = state.subscribe();
+        let details = sync_all(&config, &state, SyncTrigger::Scheduled).await;
+        assert_eq!(details.len(), 2);
+
+        assert_eq!(events.try_recv().unwrap().kind, EventKind::SyncStarted {
+            manual: false,
+            repositories: 2,
+            paths: vec![repo_dir.path().to_path_buf(), missing.clone()],
+        });
+        match events.try_recv().unwrap().kind {
+            EventKind::CommitCreated { repo, files_changed, .. } => {
+                assert_eq!(repo, repo_dir.path());
+                assert_eq!(files_changed, 1);
+            }
+            other => panic!("Expected CommitCreated, got {:?}", other),
+        }
+        assert!(matches!(events.try_recv().unwrap().kind, EventKind::SyncFailed { repo, .. } if repo == missing));
+        assert_eq!(
+            events.try_recv().unwrap().kind,
+            EventKind::SyncFinished { repos_checked: 2, repos_committed: 1, repos_failed: 1 }
+        );
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Private D-Bus bus for tests
+
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Command};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// A private dbus-daemon, killed on drop
+pub struct TestBus {
+    daemon: Child,
+    pub address: String,
+    _dir: TempDir,
+}
+
+impl TestBus {
+    /// Start a bus, or None if dbus-daemon isn't installed
+    pub fn start() -> Option<Self> {
+        let dir = TempDir::new().unwrap();
+        let socket = dir.path().join("bus");
+        let config = dir.path().join("bus.conf");
+        std::fs::write(&config, format!(
+            r#"<busconfig>
+              <type>session</type>
+              <listen>unix:path={}</listen>
+              <auth>EXTERNAL</auth>
+              <policy context="default">
+                <allow send_destination="*"/>
+                <allow receive_sender="*"/>
+                <allow own="*"/>
+              </policy>
+            </busconfig>"#,
+            socket.display()
+        )).unwrap();
+
+        let daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .arg("--nofork")
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .ok()?;
+
+        // The socket file appears before the bus listens on it
+        for _ in 0..100 {
+            if UnixStream::connect(&socket).is_ok() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        Some(Self { daemon, address: format!("unix:path={}", socket.display()), _dir: dir })
+    }
+
+    pub async fn connect(&self) -> zbus::Connection {
+        zbus::connection::Builder::address(self.address.as_str()).unwrap()
+            .build().await.unwrap()
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
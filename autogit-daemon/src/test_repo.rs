@@ -0,0 +1,12 @@
+//! Git repositories for tests
+
+use std::path::Path;
+
+/// Initialize a repository with a committer identity, independent of the user's git config
+pub fn init_repo(dir: &Path) -> git2::Repository {
+    let repo = git2::Repository::init(dir).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    repo
+}
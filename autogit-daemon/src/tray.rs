@@ -1,8 +1,9 @@
 use anyhow::Result;
-use autogit_shared::protocol::RepoDetail;
+use autogit_shared::protocol::{PausedRepo, RepoDetail, format_remaining};
+use chrono::{DateTime, Utc};
 use ksni::{Icon, MenuItem, Tray};
 use ksni::menu::*;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 use tokio::sync::mpsc;
@@ -32,6 +33,8 @@ struct TrayState {
     pub error_count: usize,
     pub repo_details: Vec<RepoDetail>,
     pub check_interval: u64,
+    pub suspended_until: Option<DateTime<Utc>>,
+    pub paused: Vec<PausedRepo>,
 }
 
 /// Actions that can be triggered from the tray menu
@@ -39,6 +42,7 @@ struct TrayState {
 pub enum TrayAction {
     TriggerSync,
     ToggleSuspend,
+    ClearQuarantine,
     Quit,
 }
 
@@ -56,6 +60,8 @@ impl AutogitTray {
             error_count: 0,
             repo_details: Vec::new(),
             check_interval: 0,
+            suspended_until: None,
+            paused: Vec::new(),
         };
 
         Self {
@@ -83,11 +89,11 @@ impl AutogitTray {
         state.status = TrayStatus::Idle;
     }
 
-    /// Increment error count
-    pub fn increment_errors(&self) {
+    /// Set the number of repositories that are currently failing
+    pub fn set_error_count(&self, count: usize) {
         let mut state = self.status.write().unwrap();
-        state.error_count += 1;
-        state.status = TrayStatus::Error;
+        state.error_count = count;
+        state.status = if count > 0 { TrayStatus::Error } else { TrayStatus::Idle };
     }
 
     /// Update repository count
@@ -103,6 +109,21 @@ impl AutogitTray {
         state.repo_count = state.repo_details.len();
     }
 
+    /// Drop the quarantined marker from all repositories after they were released
+    pub fn clear_quarantine_flags(&self) {
+        let mut state = self.status.write().unwrap();
+        for detail in &mut state.repo_details {
+            detail.quarantined = false;
+        }
+    }
+
+    /// Set when a timed suspension ends and which repositories are paused
+    pub fn set_pause_info(&self, suspended_until: Option<DateTime<Utc>>, paused: Vec<PausedRepo>) {
+        let mut state = self.status.write().unwrap();
+        state.suspended_until = suspended_until;
+        state.paused = paused;
+    }
+
     /// Set the check interval in seconds
     pub fn set_check_interval(&self, interval: u64) {
         let mut state = self.status.write().unwrap();
@@ -144,7 +165,10 @@ impl Tray for AutogitTray {
         let suspended = self.is_suspended();
 
         if suspended {
-            "Autogit - Suspended".to_owned()
+            match state.suspended_until {
+                Some(until) => format!("Autogit - Suspended ({} left)", format_remaining(until)),
+                None => "Autogit - Suspended".to_owned(),
+            }
         } else {
             match state.status {
                 TrayStatus::Idle => "Autogit".to_owned(),
@@ -212,7 +236,11 @@ impl Tray for AutogitTray {
         let mut menu = Vec::new();
 
         // Daemon status
-        let status_text = if suspended { "Suspended" } else { "Active" };
+        let status_text = match (suspended, state.suspended_until) {
+            (true, Some(until)) => format!("Suspended ({} left)", format_remaining(until)),
+            (true, None) => "Suspended".to_owned(),
+            (false, _) => "Active".to_owned(),
+        };
         menu.push(StandardItem {
             label: format!("Daemon status: {}", status_text),
             enabled: false,
@@ -262,23 +290,48 @@ impl Tray for AutogitTray {
             }.into());
         } else {
             for repo in &state.repo_details {
-                let icon = if repo.error.is_some() {
+                let pause = state.paused.iter().find(|p| p.path == repo.path);
+
+                let icon = if repo.quarantined {
+                    "⛔"  // Quarantined after repeated failures
+                } else if pause.is_some() {
+                    "⏸"  // Temporarily paused
+                } else if repo.error.is_some() {
                     "✗"  // Error
-                } else if repo.committed {
-                    "✓"  // Success with commit
                 } else {
-                    "✓"  // Success without commit
+                    "✓"  // Success, with or without commit
                 };
 
                 let path = abbreviate_path(&repo.path);
+                let suffix = match pause {
+                    _ if repo.quarantined => " (quarantined)".to_owned(),
+                    Some(PausedRepo { until: Some(until), .. }) => format!(" (paused, {} left)", format_remaining(*until)),
+                    Some(_) => " (paused)".to_owned(),
+                    None => String::new(),
+                };
                 menu.push(StandardItem {
-                    label: format!("  {} {}", icon, path),
+                    label: format!("  {} {}{}", icon, path, suffix),
                     enabled: false,
                     ..Default::default()
                 }.into());
             }
         }
 
+        if state.repo_details.iter().any(|r| r.quarantined) {
+            menu.push(StandardItem {
+                label: "Retry Quarantined Repositories".into(),
+                activate: Box::new(|this: &mut Self| {
+                    let tx = this.trigger_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = tx.send(TrayAction::ClearQuarantine).await {
+                            error!("Failed to send clear quarantine action: {}", e);
+                        }
+                    });
+                }),
+                ..Default::default()
+            }.into());
+        }
+
         menu.push(MenuItem::Separator);
 
         // Actions
@@ -332,7 +385,7 @@ impl Tray for AutogitTray {
 // Helper functions for formatting display text
 
 /// Abbreviate a path by replacing home directory with ~
-fn abbreviate_path(path: &PathBuf) -> String {
+fn abbreviate_path(path: &Path) -> String {
     if let Some(home) = dirs::home_dir() {
         if let Ok(stripped) = path.strip_prefix(&home) {
             return format!("~/{}", stripped.display());
@@ -466,18 +519,24 @@ mod tests {
     }
 
     #[test]
-    fn test_increment_errors() {
+    fn test_set_error_count() {
         let (tray, _rx) = create_test_tray();
 
         assert_eq!(tray.get_state().error_count, 0);
 
-        tray.increment_errors();
+        tray.set_error_count(1);
         assert_eq!(tray.get_state().error_count, 1);
         assert_eq!(tray.get_state().status, TrayStatus::Error);
 
-        tray.increment_errors();
+        // Repeated failing cycles don't accumulate
+        tray.set_error_count(2);
+        tray.set_error_count(2);
         assert_eq!(tray.get_state().error_count, 2);
         assert_eq!(tray.get_state().status, TrayStatus::Error);
+
+        tray.set_error_count(0);
+        assert_eq!(tray.get_state().error_count, 0);
+        assert_eq!(tray.get_state().status, TrayStatus::Idle);
     }
 
     #[test]
@@ -527,11 +586,11 @@ mod tests {
     #[test]
     fn test_tray_title_error() {
         let (tray, _rx) = create_test_tray();
-        tray.increment_errors();
+        tray.set_error_count(1);
 
         assert_eq!(tray.title(), "Autogit - 1 errors");
 
-        tray.increment_errors();
+        tray.set_error_count(2);
         assert_eq!(tray.title(), "Autogit - 2 errors");
     }
 
@@ -544,6 +603,47 @@ mod tests {
         assert_eq!(tray.title(), "Autogit - Suspended");
     }
 
+    #[test]
+    fn test_tray_title_timed_suspension() {
+        let (tx, _rx) = mpsc::channel(10);
+        let suspended = Arc::new(AtomicBool::new(true));
+        let tray = AutogitTray::new(1, tx, suspended);
+
+        let until = Utc::now() + chrono::Duration::minutes(90) + chrono::Duration::seconds(30);
+        tray.set_pause_info(Some(until), vec![]);
+        assert_eq!(tray.title(), "Autogit - Suspended (1h 30m left)");
+    }
+
+    #[test]
+    fn test_menu_displays_paused_repos() {
+        use std::path::PathBuf;
+        let (tray, _rx) = create_test_tray();
+
+        let detail = |path: &str| RepoDetail {
+            path: PathBuf::from(path),
+            committed: false,
+            files_changed: None,
+            error: None,
+            quarantined: false,
+            paused: true,
+        };
+        tray.update_repo_details(vec![detail("/srv/timed"), detail("/srv/manual")]);
+        tray.set_pause_info(None, vec![
+            PausedRepo { path: PathBuf::from("/srv/timed"), until: Some(Utc::now() + chrono::Duration::minutes(30) + chrono::Duration::seconds(30)) },
+            PausedRepo { path: PathBuf::from("/srv/manual"), until: None },
+        ]);
+
+        let labels: Vec<String> = tray.menu().iter().filter_map(|item| match item {
+            MenuItem::Standard(std_item) if std_item.label.contains("/srv/") => Some(std_item.label.clone()),
+            _ => None,
+        }).collect();
+
+        assert_eq!(labels, vec![
+            "  ⏸ /srv/timed (paused, 30m left)".to_owned(),
+            "  ⏸ /srv/manual (paused)".to_owned(),
+        ]);
+    }
+
     #[test]
     fn test_tray_icon_name_always_empty() {
         let (tray, _rx) = create_test_tray();
@@ -554,7 +654,7 @@ mod tests {
         tray.set_status(TrayStatus::Syncing);
         assert_eq!(tray.icon_name(), "");
 
-        tray.increment_errors();
+        tray.set_error_count(1);
         assert_eq!(tray.icon_name(), "");
     }
 
@@ -577,7 +677,7 @@ mod tests {
         assert!(icons[0].width > 0);
 
         // Test error icon
-        tray.increment_errors();
+        tray.set_error_count(1);
         let icons = tray.icon_pixmap();
         assert_eq!(icons.len(), 1);
         assert!(icons[0].width > 0);
@@ -592,7 +692,7 @@ mod tests {
         assert_eq!(icons.len(), 0);
 
         // With errors - should return error icon
-        tray.increment_errors();
+        tray.set_error_count(1);
         let icons = tray.attention_icon_pixmap();
         assert_eq!(icons.len(), 1);
         assert!(icons[0].width > 0);
@@ -741,12 +841,16 @@ mod tests {
                 committed: true,
                 files_changed: None,
                 error: None,
+                quarantined: false,
+                paused: false,
             },
             RepoDetail {
                 path: PathBuf::from("/home/user/repo2"),
                 committed: false,
                 files_changed: None,
                 error: Some("Test error".to_owned()),
+                quarantined: false,
+                paused: false,
             },
         ];
 
@@ -810,12 +914,16 @@ mod tests {
                 committed: true,
                 files_changed: None,
                 error: None,
+                quarantined: false,
+                paused: false,
             },
             RepoDetail {
                 path: PathBuf::from("/home/user/repo2"),
                 committed: false,
                 files_changed: None,
                 error: Some("Test error".to_owned()),
+                quarantined: false,
+                paused: false,
             },
         ];
 
@@ -835,7 +943,7 @@ mod tests {
         assert_eq!(repo_items.len(), 2);
 
         // Check for success/error indicators
-        if let Some(MenuItem::Standard(ref item)) = repo_items.get(0) {
+        if let Some(MenuItem::Standard(ref item)) = repo_items.first() {
             assert!(item.label.contains("✓"));
         }
         if let Some(MenuItem::Standard(ref item)) = repo_items.get(1) {
@@ -843,6 +951,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_menu_displays_quarantined_repos() {
+        use std::path::PathBuf;
+        let (tray, _rx) = create_test_tray();
+
+        let retry_label = |menu: &[MenuItem<AutogitTray>]| menu.iter().any(|item| {
+            matches!(item, MenuItem::Standard(std_item) if std_item.label == "Retry Quarantined Repositories")
+        });
+
+        assert!(!retry_label(&tray.menu()));
+
+        tray.update_repo_details(vec![RepoDetail {
+            path: PathBuf::from("/home/user/gone"),
+            committed: false,
+            files_changed: None,
+            error: Some("Not a git repository".to_owned()),
+            quarantined: true,
+            paused: false,
+        }]);
+
+        let menu = tray.menu();
+        let repo_item = menu.iter().find_map(|item| match item {
+            MenuItem::Standard(std_item) if std_item.label.contains("/home/user/gone") => Some(std_item.label.clone()),
+            _ => None,
+        }).unwrap();
+
+        assert!(repo_item.contains("⛔"));
+        assert!(repo_item.ends_with("(quarantined)"));
+        assert!(retry_label(&menu));
+
+        tray.clear_quarantine_flags();
+        assert!(!retry_label(&tray.menu()));
+    }
+
     #[test]
     fn test_abbreviate_path() {
         use std::path::PathBuf;
@@ -1,19 +1,28 @@
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use crate::discovery::{ScanFilter, find_repositories};
 
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub daemon: DaemonConfig,
 
     #[serde(default)]
     pub repositories: Vec<Repository>,
+
+    /// Directories whose repositories are synced without listing each one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch_roots: Vec<WatchRoot>,
+
+    /// Repositories found under the watch roots by `discover`, never saved
+    #[serde(skip)]
+    pub discovered: Vec<Repository>,
 }
 
 /// Daemon-specific configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DaemonConfig {
     /// How often to check for changes (in seconds)
     #[serde(default = "default_check_interval")]
@@ -22,6 +31,60 @@ pub struct DaemonConfig {
     /// Whether to show system tray icon
     #[serde(default = "default_enable_tray")]
     pub enable_tray: bool,
+
+    /// Quarantine a repository after this many consecutive failed syncs (0 = never)
+    #[serde(default = "default_quarantine_after_failures")]
+    pub quarantine_after_failures: u32,
+
+    /// Upper bound for the retry backoff of a failing repository (in seconds)
+    #[serde(default = "default_max_backoff")]
+    pub max_backoff_seconds: u64,
+
+    /// Defer pulls and pushes while on battery below this charge (in percent, 0 = never)
+    #[serde(default)]
+    pub defer_remote_below_battery_percent: u8,
+
+    /// Defer pulls and pushes while the network connection is metered
+    #[serde(default)]
+    pub defer_remote_on_metered: bool,
+
+    /// Only commit once the user has been idle this long (in seconds, 0 = always)
+    #[serde(default)]
+    pub commit_after_idle_seconds: u64,
+
+    /// Time limit for the last commit and push on shutdown or sleep (in seconds, 0 = skip it)
+    #[serde(default = "default_final_sync_timeout")]
+    pub final_sync_timeout_seconds: u64,
+
+    /// Delay system sleep through logind until a final sync has run
+    #[serde(default)]
+    pub sync_before_sleep: bool,
+
+    /// Port of the loopback-only HTTP API (0 = disabled)
+    #[serde(default)]
+    pub http_api_port: u16,
+
+    /// File holding the bearer token for the HTTP API (created if missing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_api_token_file: Option<PathBuf>,
+
+    /// Publish the org.autogit.Daemon interface on the session bus
+    #[serde(default = "default_dbus_interface")]
+    pub dbus_interface: bool,
+}
+
+impl DaemonConfig {
+    /// Token file of the HTTP API (~/.config/autogit/http-token unless configured)
+    pub fn http_api_token_path(&self) -> Result<PathBuf> {
+        match &self.http_api_token_file {
+            Some(path) => Ok(path.clone()),
+            None => {
+                let config_dir = dirs::config_dir()
+                    .context("Could not determine config directory")?;
+                Ok(config_dir.join("autogit").join("http-token"))
+            }
+        }
+    }
 }
 
 impl Default for DaemonConfig {
@@ -29,6 +92,16 @@ impl Default for DaemonConfig {
         Self {
             check_interval_seconds: default_check_interval(),
             enable_tray: default_enable_tray(),
+            quarantine_after_failures: default_quarantine_after_failures(),
+            max_backoff_seconds: default_max_backoff(),
+            defer_remote_below_battery_percent: 0,
+            defer_remote_on_metered: false,
+            commit_after_idle_seconds: 0,
+            final_sync_timeout_seconds: default_final_sync_timeout(),
+            sync_before_sleep: false,
+            http_api_port: 0,
+            http_api_token_file: None,
+            dbus_interface: default_dbus_interface(),
         }
     }
 }
@@ -37,12 +110,28 @@ fn default_check_interval() -> u64 {
     300 // 5 minutes
 }
 
+fn default_final_sync_timeout() -> u64 {
+    30
+}
+
 fn default_enable_tray() -> bool {
     true
 }
 
+fn default_dbus_interface() -> bool {
+    true
+}
+
+fn default_quarantine_after_failures() -> u32 {
+    5
+}
+
+fn default_max_backoff() -> u64 {
+    3600 // 1 hour
+}
+
 /// Repository configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Repository {
     /// Path to the git repository
     pub path: PathBuf,
@@ -61,10 +150,130 @@ fn default_true() -> bool {
     true
 }
 
+/// A directory searched for git repositories, whose settings apply to each one found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchRoot {
+    /// Directory to search (a leading ~ stands for the home directory)
+    pub path: PathBuf,
+
+    /// How many directory levels below the root to search (1 = its direct subdirectories)
+    #[serde(default = "default_watch_depth")]
+    pub depth: usize,
+
+    /// Whether auto-commit is enabled for the repositories found
+    #[serde(default = "default_true")]
+    pub auto_commit: bool,
+
+    /// Template for commit messages of the repositories found
+    #[serde(default = "default_commit_message")]
+    pub commit_message_template: String,
+}
+
+fn default_watch_depth() -> usize {
+    1
+}
+
+impl WatchRoot {
+    /// The root directory with a leading ~ expanded
+    pub fn expanded_path(&self) -> PathBuf {
+        match (self.path.strip_prefix("~"), dirs::home_dir()) {
+            (Ok(rest), Some(home)) => home.join(rest),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// Repositories below the root, sorted by path (none if the root doesn't exist)
+    pub fn find_repositories(&self) -> Vec<PathBuf> {
+        match self.expanded_path().canonicalize() {
+            Ok(root) => find_repositories(&root, self.depth, &ScanFilter::default()),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Settings of a repository found below the root
+    pub fn repository(&self, path: PathBuf) -> Repository {
+        Repository {
+            path,
+            auto_commit: self.auto_commit,
+            commit_message_template: self.commit_message_template.clone(),
+        }
+    }
+
+    /// Repositories below any of the roots
+    ///
+    /// A repository below several roots takes the settings of the first one.
+    pub fn discover_all(roots: &[WatchRoot]) -> Vec<Repository> {
+        let mut found: Vec<Repository> = Vec::new();
+        for root in roots {
+            for path in root.find_repositories() {
+                if !found.iter().any(|repo| repo.path == path) {
+                    found.push(root.repository(path));
+                }
+            }
+        }
+        found
+    }
+}
+
 fn default_commit_message() -> String {
     "Auto-commit: {timestamp}".to_owned()
 }
 
+/// A per-repository setting that can be changed without editing the file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoOption {
+    AutoCommit(bool),
+    CommitMessageTemplate(String),
+}
+
+/// A change named a repository that isn't in the configuration
+#[derive(Debug, thiserror::Error)]
+#[error("Repository not found in configuration: {}", .0.display())]
+pub struct RepoNotFound(pub PathBuf);
+
+/// A validated edit of the configuration, shared by the daemon and the CLI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChange {
+    AddRepo {
+        path: PathBuf,
+        commit_message_template: Option<String>,
+    },
+    RemoveRepo {
+        path: PathBuf,
+    },
+    SetRepoOption {
+        path: PathBuf,
+        option: RepoOption,
+    },
+    SetInterval {
+        seconds: u64,
+    },
+    AddWatchRoot {
+        root: WatchRoot,
+    },
+}
+
+impl ConfigChange {
+    /// One-line description of the change once applied
+    pub fn summary(&self) -> String {
+        match self {
+            ConfigChange::AddRepo { path, .. } => format!("Added repository: {}", path.display()),
+            ConfigChange::RemoveRepo { path } => format!("Removed repository: {}", path.display()),
+            ConfigChange::SetRepoOption { path, option: RepoOption::AutoCommit(enabled) } => format!(
+                "Auto-commit {} for: {}",
+                if *enabled { "enabled" } else { "disabled" },
+                path.display()
+            ),
+            ConfigChange::SetRepoOption { path, option: RepoOption::CommitMessageTemplate(_) } => {
+                format!("Commit message template changed for: {}", path.display())
+            }
+            ConfigChange::SetInterval { seconds } => format!("Check interval set to {} seconds", seconds),
+            ConfigChange::AddWatchRoot { root } => format!("Added watch root: {}", root.path.display()),
+        }
+    }
+}
+
 impl Config {
     /// Load configuration from a TOML file
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -78,22 +287,151 @@ impl Config {
     }
 
     /// Save configuration to a TOML file
+    ///
+    /// Written to a temporary file and renamed over the old one, so readers
+    /// (and the daemon's file watcher) never see a half-written config.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
         let content = toml::to_string_pretty(self)
             .with_context(|| "Failed to serialize config")?;
 
         // Ensure parent directory exists
-        if let Some(parent) = path.as_ref().parent() {
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
         }
 
-        std::fs::write(path.as_ref(), content)
-            .with_context(|| format!("Failed to write config file: {}", path.as_ref().display()))?;
+        let file_name = path.file_name()
+            .with_context(|| format!("Invalid config file path: {}", path.display()))?;
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+
+        let write = || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&temp_path)?;
+            std::io::Write::write_all(&mut file, content.as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&temp_path, path)
+        };
+
+        if let Err(e) = write() {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e).with_context(|| format!("Failed to write config file: {}", path.display()));
+        }
 
         Ok(())
     }
 
+    /// Apply a change, refusing ones that don't make sense for this configuration
+    pub fn apply(&mut self, change: &ConfigChange) -> Result<()> {
+        match change {
+            ConfigChange::AddRepo { path, commit_message_template } => {
+                if !path.is_absolute() {
+                    bail!("Repository path must be absolute: {}", path.display());
+                }
+                if !path.join(".git").exists() {
+                    bail!("Not a git repository: {}", path.display());
+                }
+                if self.repositories.iter().any(|r| &r.path == path) {
+                    bail!("Repository already configured: {}", path.display());
+                }
+
+                self.repositories.push(Repository {
+                    path: path.clone(),
+                    auto_commit: true,
+                    commit_message_template: commit_message_template.clone().unwrap_or_else(default_commit_message),
+                });
+            }
+            ConfigChange::RemoveRepo { path } => {
+                let original_len = self.repositories.len();
+                self.repositories.retain(|r| &r.path != path);
+
+                if self.repositories.len() == original_len {
+                    if self.discovered.iter().any(|r| &r.path == path) {
+                        bail!("Repository is found under a watch root; disable it instead of removing it");
+                    }
+                    return Err(RepoNotFound(path.clone()).into());
+                }
+            }
+            ConfigChange::SetRepoOption { path, option } => {
+                // Changing a discovered repository lists it individually, overriding its root
+                if !self.repositories.iter().any(|r| &r.path == path) {
+                    if let Some(index) = self.discovered.iter().position(|r| &r.path == path) {
+                        let repo = self.discovered.remove(index);
+                        self.repositories.push(repo);
+                    }
+                }
+
+                let repo = self.repositories.iter_mut()
+                    .find(|r| &r.path == path)
+                    .ok_or_else(|| RepoNotFound(path.clone()))?;
+
+                match option {
+                    RepoOption::AutoCommit(enabled) => repo.auto_commit = *enabled,
+                    RepoOption::CommitMessageTemplate(template) => {
+                        if template.trim().is_empty() {
+                            bail!("Commit message template must not be empty");
+                        }
+                        repo.commit_message_template = template.clone();
+                    }
+                }
+            }
+            ConfigChange::SetInterval { seconds } => {
+                if *seconds == 0 {
+                    bail!("Check interval must be at least 1 second");
+                }
+                self.daemon.check_interval_seconds = *seconds;
+            }
+            ConfigChange::AddWatchRoot { root } => {
+                if !root.path.is_absolute() && !root.path.starts_with("~") {
+                    bail!("Watch root must be absolute or start with ~: {}", root.path.display());
+                }
+                if root.depth == 0 {
+                    bail!("Watch root depth must be at least 1");
+                }
+                if self.watch_roots.iter().any(|r| r.expanded_path() == root.expanded_path()) {
+                    bail!("Watch root already configured: {}", root.path.display());
+                }
+
+                self.watch_roots.push(root.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Repositories below the watch roots that aren't configured individually
+    ///
+    /// A repository below several roots takes the settings of the first one.
+    pub fn discover_repositories(&self) -> Vec<Repository> {
+        let mut found = WatchRoot::discover_all(&self.watch_roots);
+        found.retain(|repo| !self.repositories.iter().any(|r| r.path == repo.path));
+        found
+    }
+
+    /// Search the watch roots again, replacing what was discovered before
+    pub fn discover(&mut self) {
+        self.discovered = self.discover_repositories();
+    }
+
+    /// Replace the discovered repositories with those `WatchRoot::discover_all` found, leaving out
+    /// the ones configured individually. Returns whether the set changed.
+    pub fn set_discovered(&mut self, mut found: Vec<Repository>) -> bool {
+        found.retain(|repo| !self.repositories.iter().any(|r| r.path == repo.path));
+        if found == self.discovered {
+            return false;
+        }
+        self.discovered = found;
+        true
+    }
+
+    /// Configured repositories followed by the discovered ones
+    pub fn all_repositories(&self) -> impl Iterator<Item = &Repository> {
+        self.repositories.iter().chain(&self.discovered)
+    }
+
     /// Get the default config file path (~/.config/autogit/config.toml)
     pub fn default_config_path() -> Result<PathBuf> {
         let config_dir = dirs::config_dir()
@@ -116,18 +454,221 @@ impl Config {
     }
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            daemon: DaemonConfig::default(),
-            repositories: Vec::new(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
+
+    fn git_dir() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_is_atomic_and_leaves_no_temp_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("autogit").join("config.toml");
+
+        let mut config = Config::default();
+        config.save(&path).unwrap();
+        config.daemon.check_interval_seconds = 60;
+        config.save(&path).unwrap();
+
+        assert_eq!(Config::load(&path).unwrap(), config);
+        let files: Vec<_> = std::fs::read_dir(path.parent().unwrap()).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec![std::ffi::OsString::from("config.toml")]);
+    }
+
+    #[test]
+    fn test_apply_add_and_remove_repo() {
+        let repo = git_dir();
+        let mut config = Config::default();
+
+        let add = ConfigChange::AddRepo { path: repo.path().to_path_buf(), commit_message_template: None };
+        config.apply(&add).unwrap();
+        assert_eq!(config.repositories.len(), 1);
+        assert!(config.repositories[0].auto_commit);
+        assert_eq!(config.repositories[0].commit_message_template, "Auto-commit: {timestamp}");
+
+        // Adding twice is refused and leaves the config alone
+        assert!(config.apply(&add).unwrap_err().to_string().contains("already configured"));
+        assert_eq!(config.repositories.len(), 1);
+
+        let remove = ConfigChange::RemoveRepo { path: repo.path().to_path_buf() };
+        config.apply(&remove).unwrap();
+        assert!(config.repositories.is_empty());
+        let error = config.apply(&remove).unwrap_err();
+        assert!(error.to_string().contains("not found"));
+        assert!(error.is::<RepoNotFound>());
+    }
+
+    #[test]
+    fn test_apply_add_repo_validation() {
+        let mut config = Config::default();
+
+        let relative = ConfigChange::AddRepo { path: PathBuf::from("notes"), commit_message_template: None };
+        assert!(config.apply(&relative).unwrap_err().to_string().contains("absolute"));
+
+        let plain_dir = TempDir::new().unwrap();
+        let not_git = ConfigChange::AddRepo { path: plain_dir.path().to_path_buf(), commit_message_template: None };
+        assert!(config.apply(&not_git).unwrap_err().to_string().contains("Not a git repository"));
+
+        assert!(config.repositories.is_empty());
+    }
+
+    #[test]
+    fn test_apply_set_repo_option() {
+        let repo = git_dir();
+        let path = repo.path().to_path_buf();
+        let mut config = Config::default();
+        config.apply(&ConfigChange::AddRepo { path: path.clone(), commit_message_template: Some("Notes".to_owned()) }).unwrap();
+
+        config.apply(&ConfigChange::SetRepoOption { path: path.clone(), option: RepoOption::AutoCommit(false) }).unwrap();
+        assert!(!config.repositories[0].auto_commit);
+
+        let template = RepoOption::CommitMessageTemplate("Notes: {date}".to_owned());
+        config.apply(&ConfigChange::SetRepoOption { path: path.clone(), option: template }).unwrap();
+        assert_eq!(config.repositories[0].commit_message_template, "Notes: {date}");
+
+        let empty = RepoOption::CommitMessageTemplate("  ".to_owned());
+        assert!(config.apply(&ConfigChange::SetRepoOption { path, option: empty }).is_err());
+
+        let unknown = ConfigChange::SetRepoOption { path: PathBuf::from("/elsewhere"), option: RepoOption::AutoCommit(true) };
+        assert!(config.apply(&unknown).unwrap_err().is::<RepoNotFound>());
+    }
+
+    #[test]
+    fn test_apply_set_interval() {
+        let mut config = Config::default();
+
+        config.apply(&ConfigChange::SetInterval { seconds: 60 }).unwrap();
+        assert_eq!(config.daemon.check_interval_seconds, 60);
+
+        assert!(config.apply(&ConfigChange::SetInterval { seconds: 0 }).is_err());
+        assert_eq!(config.daemon.check_interval_seconds, 60);
+    }
+
+    /// A watch root over a directory holding `.git` directories at the given relative paths
+    fn watch_root(repositories: &[&str]) -> (TempDir, WatchRoot) {
+        let dir = TempDir::new().unwrap();
+        for repository in repositories {
+            std::fs::create_dir_all(dir.path().join(repository).join(".git")).unwrap();
+        }
+        let root = WatchRoot {
+            path: dir.path().canonicalize().unwrap(),
+            depth: 1,
+            auto_commit: false,
+            commit_message_template: "Notes: {date}".to_owned(),
+        };
+        (dir, root)
+    }
+
+    #[test]
+    fn test_watch_root_defaults() {
+        let config: Config = toml::from_str("[[watch_roots]]\npath = \"~/notes\"\n").unwrap();
+        let root = &config.watch_roots[0];
+
+        assert_eq!(root.depth, 1);
+        assert!(root.auto_commit);
+        assert_eq!(root.commit_message_template, "Auto-commit: {timestamp}");
+        assert_eq!(root.expanded_path(), dirs::home_dir().unwrap().join("notes"));
+
+        // Missing roots find nothing, and configs without roots don't mention them
+        assert!(WatchRoot { path: PathBuf::from("/nonexistent/notes"), ..root.clone() }.find_repositories().is_empty());
+        assert!(!toml::to_string(&Config::default()).unwrap().contains("watch_roots"));
+    }
+
+    #[test]
+    fn test_discover_repositories() {
+        let (_dir, root) = watch_root(&["journal", "work", "archive/old"]);
+        let mut config = Config { watch_roots: vec![root.clone()], ..Default::default() };
+
+        // Listing a repository individually overrides the root's settings
+        config.repositories.push(Repository {
+            path: root.path.join("work"),
+            auto_commit: true,
+            commit_message_template: "Work".to_owned(),
+        });
+        config.discover();
+
+        assert_eq!(config.discovered, [root.repository(root.path.join("journal"))]);
+        let all: Vec<_> = config.all_repositories().map(|repo| repo.path.clone()).collect();
+        assert_eq!(all, [root.path.join("work"), root.path.join("journal")]);
+
+        // Results of a search without the lock leave out configured repositories the same way
+        let found = WatchRoot::discover_all(std::slice::from_ref(&root));
+        assert_eq!(found.len(), 2);
+        assert!(!config.set_discovered(found.clone()));
+        config.repositories.clear();
+        assert!(config.set_discovered(found));
+        assert_eq!(config.discovered.len(), 2);
+        config.repositories.push(root.repository(root.path.join("work")));
+        config.discover();
+
+        // Discovered repositories are never saved
+        let file = TempDir::new().unwrap();
+        let path = file.path().join("config.toml");
+        config.save(&path).unwrap();
+        let loaded = Config::load(&path).unwrap();
+        assert!(loaded.discovered.is_empty());
+        assert_eq!(loaded.repositories.len(), 1);
+        assert_eq!(loaded.watch_roots, [root]);
+    }
+
+    #[test]
+    fn test_apply_to_discovered_repository() {
+        let (_dir, root) = watch_root(&["journal"]);
+        let path = root.path.join("journal");
+        let mut config = Config { watch_roots: vec![root], ..Default::default() };
+        config.discover();
+
+        let remove = ConfigChange::RemoveRepo { path: path.clone() };
+        assert!(config.apply(&remove).unwrap_err().to_string().contains("disable it instead"));
+
+        // Changing an option lists the repository with the root's other settings
+        let template = RepoOption::CommitMessageTemplate("Journal".to_owned());
+        config.apply(&ConfigChange::SetRepoOption { path: path.clone(), option: template }).unwrap();
+        assert_eq!(config.repositories, [Repository {
+            path,
+            auto_commit: false,
+            commit_message_template: "Journal".to_owned(),
+        }]);
+        assert!(config.discovered.is_empty());
+        assert!(config.discover_repositories().is_empty());
+    }
+
+    #[test]
+    fn test_apply_add_watch_root() {
+        let (_dir, root) = watch_root(&["journal"]);
+        let mut config = Config::default();
+
+        config.apply(&ConfigChange::AddWatchRoot { root: root.clone() }).unwrap();
+        assert_eq!(config.watch_roots.len(), 1);
+        assert_eq!(config.discover_repositories().len(), 1);
+
+        let duplicate = ConfigChange::AddWatchRoot { root: WatchRoot { depth: 2, ..root.clone() } };
+        assert!(config.apply(&duplicate).unwrap_err().to_string().contains("already configured"));
+
+        let relative = WatchRoot { path: PathBuf::from("projects"), ..root.clone() };
+        assert!(config.apply(&ConfigChange::AddWatchRoot { root: relative }).is_err());
+        let shallow = WatchRoot { path: PathBuf::from("~/projects"), depth: 0, ..root };
+        assert!(config.apply(&ConfigChange::AddWatchRoot { root: shallow }).is_err());
+        assert_eq!(config.watch_roots.len(), 1);
+    }
+
+    #[test]
+    fn test_config_change_summary() {
+        let path = PathBuf::from("/notes");
+        assert_eq!(ConfigChange::RemoveRepo { path: path.clone() }.summary(), "Removed repository: /notes");
+        assert_eq!(
+            ConfigChange::SetRepoOption { path, option: RepoOption::AutoCommit(false) }.summary(),
+            "Auto-commit disabled for: /notes"
+        );
+        assert_eq!(ConfigChange::SetInterval { seconds: 90 }.summary(), "Check interval set to 90 seconds");
+    }
 
     #[test]
     fn test_config_serialization() {
@@ -135,6 +676,7 @@ mod tests {
             daemon: DaemonConfig {
                 check_interval_seconds: 60,
                 enable_tray: true,
+                ..Default::default()
             },
             repositories: vec![
                 Repository {
@@ -143,6 +685,7 @@ mod tests {
                     commit_message_template: "Auto-commit: {timestamp}".to_owned(),
                 },
             ],
+            ..Default::default()
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -167,6 +710,91 @@ mod tests {
     fn test_daemon_config_defaults() {
         let daemon_config = DaemonConfig::default();
         assert_eq!(daemon_config.check_interval_seconds, 300);
+        assert_eq!(daemon_config.quarantine_after_failures, 5);
+        assert_eq!(daemon_config.max_backoff_seconds, 3600);
+        assert_eq!(daemon_config.defer_remote_below_battery_percent, 0);
+        assert!(!daemon_config.defer_remote_on_metered);
+        assert_eq!(daemon_config.commit_after_idle_seconds, 0);
+    }
+
+    #[test]
+    fn test_daemon_config_scheduling_policy() {
+        let toml_str = r#"
+            [daemon]
+            defer_remote_below_battery_percent = 30
+            defer_remote_on_metered = true
+            commit_after_idle_seconds = 120
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.daemon.defer_remote_below_battery_percent, 30);
+        assert!(config.daemon.defer_remote_on_metered);
+        assert_eq!(config.daemon.commit_after_idle_seconds, 120);
+        assert_eq!(config.daemon.quarantine_after_failures, 5); // Uses default
+    }
+
+    #[test]
+    fn test_daemon_config_final_sync() {
+        let config: Config = toml::from_str("[daemon]\n").unwrap();
+        assert_eq!(config.daemon.final_sync_timeout_seconds, 30);
+        assert!(!config.daemon.sync_before_sleep);
+
+        let toml_str = r#"
+            [daemon]
+            final_sync_timeout_seconds = 0
+            sync_before_sleep = true
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.daemon.final_sync_timeout_seconds, 0);
+        assert!(config.daemon.sync_before_sleep);
+    }
+
+    #[test]
+    fn test_daemon_config_http_api() {
+        let config: Config = toml::from_str("[daemon]\n").unwrap();
+        assert_eq!(config.daemon.http_api_port, 0);
+        assert!(config.daemon.http_api_token_file.is_none());
+        assert!(config.daemon.http_api_token_path().unwrap().ends_with("autogit/http-token"));
+        assert!(!toml::to_string(&config).unwrap().contains("http_api_token_file"));
+
+        let toml_str = r#"
+            [daemon]
+            http_api_port = 7415
+            http_api_token_file = "/run/secrets/autogit-token"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.daemon.http_api_port, 7415);
+        assert_eq!(
+            config.daemon.http_api_token_path().unwrap(),
+            PathBuf::from("/run/secrets/autogit-token")
+        );
+    }
+
+    #[test]
+    fn test_daemon_config_dbus_interface() {
+        let config: Config = toml::from_str("[daemon]\n").unwrap();
+        assert!(config.daemon.dbus_interface);
+
+        let config: Config = toml::from_str("[daemon]\ndbus_interface = false\n").unwrap();
+        assert!(!config.daemon.dbus_interface);
+    }
+
+    #[test]
+    fn test_daemon_config_failure_policy() {
+        let toml_str = r#"
+            [daemon]
+            quarantine_after_failures = 0
+            max_backoff_seconds = 600
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.daemon.quarantine_after_failures, 0);
+        assert_eq!(config.daemon.max_backoff_seconds, 600);
+        assert_eq!(config.daemon.check_interval_seconds, 300); // Uses default
     }
 
     #[test]
@@ -179,7 +807,7 @@ mod tests {
         let repo: Repository = toml::from_str(toml_str).unwrap();
 
         assert_eq!(repo.path, PathBuf::from("/home/user/repo"));
-        assert_eq!(repo.auto_commit, true); // default_true
+        assert!(repo.auto_commit); // default_true
         assert_eq!(repo.commit_message_template, "Auto-commit: {timestamp}"); // default_commit_message
     }
 
@@ -203,6 +831,7 @@ mod tests {
             daemon: DaemonConfig {
                 check_interval_seconds: 120,
                 enable_tray: true,
+                ..Default::default()
             },
             repositories: vec![
                 Repository {
@@ -221,14 +850,15 @@ mod tests {
                     commit_message_template: "Code changes".to_owned(),
                 },
             ],
+            ..Default::default()
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
         let deserialized: Config = toml::from_str(&toml_str).unwrap();
 
         assert_eq!(deserialized.repositories.len(), 3);
-        assert_eq!(deserialized.repositories[0].auto_commit, true);
-        assert_eq!(deserialized.repositories[1].auto_commit, false);
+        assert!(deserialized.repositories[0].auto_commit);
+        assert!(!deserialized.repositories[1].auto_commit);
         assert_eq!(deserialized.repositories[2].commit_message_template, "Code changes");
     }
 
@@ -238,8 +868,10 @@ mod tests {
             daemon: DaemonConfig {
                 check_interval_seconds: 60,
                 enable_tray: true,
+                ..Default::default()
             },
             repositories: vec![],
+            ..Default::default()
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -258,7 +890,7 @@ mod tests {
 
         let repo: Repository = toml::from_str(toml_str).unwrap();
 
-        assert_eq!(repo.auto_commit, false);
+        assert!(!repo.auto_commit);
         assert_eq!(repo.commit_message_template, "Custom message");
     }
 
@@ -269,8 +901,10 @@ mod tests {
                 daemon: DaemonConfig {
                     check_interval_seconds: interval,
                     enable_tray: true,
+                    ..Default::default()
                 },
                 repositories: vec![],
+                ..Default::default()
             };
 
             let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -0,0 +1,175 @@
+//! Finding git repositories below a directory, for `autogit scan` and watch roots
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::{Path, PathBuf};
+
+/// `*` and `?` stay within one path component, `**` spans several
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Which directories a scan reports (`include`) and which it doesn't enter (`exclude`)
+///
+/// Patterns without a `/` match the directory name, others the path relative to the scanned
+/// directory, like in `.gitignore`.
+#[derive(Debug, Default)]
+pub struct ScanFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl ScanFilter {
+    /// Parse include and exclude glob patterns
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let parse = |patterns: &[String]| -> Result<Vec<Pattern>> {
+            patterns.iter()
+                .map(|pattern| Pattern::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern)))
+                .collect()
+        };
+        Ok(Self { include: parse(include)?, exclude: parse(exclude)? })
+    }
+
+    fn included(&self, root: &Path, path: &Path) -> bool {
+        self.include.is_empty() || matches_any(&self.include, root, path)
+    }
+
+    fn excluded(&self, root: &Path, path: &Path) -> bool {
+        matches_any(&self.exclude, root, path)
+    }
+}
+
+fn matches_any(patterns: &[Pattern], root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let name = path.file_name().map(Path::new).unwrap_or(relative);
+
+    patterns.iter().any(|pattern| {
+        let subject = if pattern.as_str().contains('/') { relative } else { name };
+        pattern.matches_path_with(subject, MATCH_OPTIONS)
+    })
+}
+
+/// Whether a directory is a git repository (a work tree with `.git`, as `add` requires)
+fn is_repository(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+/// Git repositories under `root`, at most `depth` directory levels down, sorted by path
+///
+/// Hidden directories, symbolic links and the inside of repositories are not searched.
+pub fn find_repositories(root: &Path, depth: usize, filter: &ScanFilter) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    visit(root, root, depth, filter, &mut found);
+    found.sort();
+    found
+}
+
+fn visit(root: &Path, dir: &Path, depth: usize, filter: &ScanFilter, found: &mut Vec<PathBuf>) {
+    if is_repository(dir) {
+        if filter.included(root, dir) {
+            found.push(dir.to_path_buf());
+        }
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+
+    // Unreadable directories are skipped like empty ones
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let path = entry.path();
+        if is_dir && !hidden && !filter.excluded(root, &path) {
+            visit(root, &path, depth - 1, filter, found);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Create a directory with a `.git` directory for every relative path
+    fn create_tree(repositories: &[&str]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for repository in repositories {
+            fs::create_dir_all(dir.path().join(repository).join(".git")).unwrap();
+        }
+        dir
+    }
+
+    fn relative(root: &Path, found: Vec<PathBuf>) -> Vec<String> {
+        found.iter().map(|path| path.strip_prefix(root).unwrap().display().to_string()).collect()
+    }
+
+    fn filter(include: &[&str], exclude: &[&str]) -> ScanFilter {
+        let owned = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        ScanFilter::new(&owned(include), &owned(exclude)).unwrap()
+    }
+
+    #[test]
+    fn test_find_repositories() {
+        let dir = create_tree(&["notes", "work/api", "work/web", "work/api/vendor/lib", ".config/nvim"]);
+        fs::create_dir_all(dir.path().join("empty")).unwrap();
+
+        let found = find_repositories(dir.path(), 3, &ScanFilter::default());
+        // Nested and hidden repositories are not reported
+        assert_eq!(relative(dir.path(), found), ["notes", "work/api", "work/web"]);
+    }
+
+    #[test]
+    fn test_find_repositories_depth() {
+        let dir = create_tree(&["notes", "a/b/deep"]);
+
+        assert_eq!(relative(dir.path(), find_repositories(dir.path(), 1, &ScanFilter::default())), ["notes"]);
+        assert_eq!(relative(dir.path(), find_repositories(dir.path(), 3, &ScanFilter::default())), ["a/b/deep", "notes"]);
+        assert!(find_repositories(dir.path(), 0, &ScanFilter::default()).is_empty());
+    }
+
+    #[test]
+    fn test_find_repositories_root_is_repository() {
+        let dir = create_tree(&["", "inner"]);
+        assert_eq!(find_repositories(dir.path(), 3, &ScanFilter::default()), [dir.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn test_find_repositories_skips_symlinks() {
+        let dir = create_tree(&["notes"]);
+        std::os::unix::fs::symlink(dir.path().join("notes"), dir.path().join("link")).unwrap();
+
+        assert_eq!(relative(dir.path(), find_repositories(dir.path(), 3, &ScanFilter::default())), ["notes"]);
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let dir = create_tree(&["notes", "work/api", "work/web", "archive/old-notes", "work/node_modules/dep"]);
+        let root = dir.path();
+
+        let by_name = filter(&["*notes"], &[]);
+        assert_eq!(relative(root, find_repositories(root, 3, &by_name)), ["archive/old-notes", "notes"]);
+
+        let by_path = filter(&["work/*"], &[]);
+        assert_eq!(relative(root, find_repositories(root, 3, &by_path)), ["work/api", "work/web"]);
+
+        // Excluded directories are not entered
+        let excluded = filter(&[], &["archive", "node_modules", "work/web"]);
+        assert_eq!(relative(root, find_repositories(root, 3, &excluded)), ["notes", "work/api"]);
+
+        let recursive = filter(&["**/dep"], &[]);
+        assert_eq!(relative(root, find_repositories(root, 3, &recursive)), ["work/node_modules/dep"]);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let error = ScanFilter::new(&["[".to_owned()], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid glob pattern: [");
+    }
+}
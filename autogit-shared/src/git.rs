@@ -0,0 +1,111 @@
+//! Git helpers shared by the daemon and the CLI (feature `git`)
+
+use anyhow::{Context, Result, bail};
+use git2::{Repository as GitRepository, Signature};
+use std::path::Path;
+
+/// Open a git repository with user-friendly error messages
+pub fn open_repository(path: &Path) -> Result<GitRepository> {
+    match GitRepository::open(path) {
+        Ok(repo) => Ok(repo),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            bail!(
+                "Not a git repository: {}\n\
+                 The .git directory may have been deleted or the path is incorrect.\n\
+                 Use 'autogit remove {}' to remove it from configuration.",
+                path.display(),
+                path.display()
+            );
+        }
+        Err(e) => {
+            bail!("Failed to open repository {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Get git signature from repository config (respects .gitconfig)
+pub fn get_signature(repo: &GitRepository) -> Result<Signature<'static>> {
+    let config = repo.config()
+        .context("Failed to get repository config")?;
+
+    let name = config.get_string("user.name")
+        .context("user.name not set in git config")?;
+
+    let email = config.get_string("user.email")
+        .context("user.email not set in git config")?;
+
+    Signature::now(&name, &email)
+        .context("Failed to create signature")
+}
+
+/// Whether the repository has an 'origin' remote
+pub fn has_origin(repo: &GitRepository) -> Result<bool> {
+    match repo.find_remote("origin") {
+        Ok(_) => Ok(true),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper to create a test git repository
+    fn create_test_repo() -> (tempfile::TempDir, GitRepository) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(temp_dir.path()).unwrap();
+
+        // Configure git user for commits
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        (temp_dir, repo)
+    }
+
+    #[test]
+    fn test_open_repository_success() {
+        let (_temp, repo) = create_test_repo();
+        let result = open_repository(repo.path().parent().unwrap());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_open_repository_not_found() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = open_repository(temp_dir.path());
+        assert!(result.is_err());
+        let err = result.err().unwrap();
+        assert!(err.to_string().contains("Not a git repository"));
+    }
+
+    #[test]
+    fn test_get_signature_success() {
+        let (_temp, repo) = create_test_repo();
+        let signature = get_signature(&repo).unwrap();
+        assert_eq!(signature.name().unwrap(), "Test User");
+        assert_eq!(signature.email().unwrap(), "test@example.com");
+    }
+
+    #[test]
+    fn test_get_signature_uses_repo_config() {
+        let (_temp, repo) = create_test_repo();
+
+        // We configured the repo with specific values
+        let signature = get_signature(&repo).unwrap();
+
+        // Should use the test config (not global config)
+        assert_eq!(signature.name().unwrap(), "Test User");
+        assert_eq!(signature.email().unwrap(), "test@example.com");
+    }
+
+    #[test]
+    fn test_has_origin() {
+        let (_temp, repo) = create_test_repo();
+        assert!(!has_origin(&repo).unwrap());
+
+        repo.remote("origin", "https://example.com/notes.git").unwrap();
+        assert!(has_origin(&repo).unwrap());
+    }
+}
@@ -0,0 +1,391 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Rotate the journal once the active file grows beyond this size (1 MiB)
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Number of rotated files to keep next to the active journal
+const DEFAULT_KEEP_FILES: usize = 3;
+
+/// Result of a push or pull against the remote during a sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteResult {
+    /// Not attempted (no remote configured, or nothing to do)
+    #[default]
+    Skipped,
+    /// Remote was checked but already in sync
+    UpToDate,
+    /// Commits were actually transferred
+    Done,
+    /// Postponed to a later sync (scheduling policy, or a sync requested without push)
+    Deferred,
+    /// The git command failed (stderr is kept for diagnosis)
+    Failed(String),
+}
+
+impl RemoteResult {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, RemoteResult::Failed(_))
+    }
+}
+
+/// One recorded sync outcome for a single repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub repo: PathBuf,
+    /// OID of the commit created during this sync, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub files_changed: usize,
+    #[serde(default)]
+    pub push: RemoteResult,
+    #[serde(default)]
+    pub pull: RemoteResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    /// Whether this sync failed in any way (hard error, push or pull failure)
+    pub fn is_error(&self) -> bool {
+        self.error.is_some() || self.push.is_failed() || self.pull.is_failed()
+    }
+
+    /// Whether anything happened worth recording (commit, transfer or failure)
+    pub fn is_noop(&self) -> bool {
+        self.commit.is_none()
+            && !self.is_error()
+            && self.push != RemoteResult::Done
+            && self.pull != RemoteResult::Done
+    }
+}
+
+/// Criteria for querying the journal
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalFilter {
+    /// Only entries for this repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+    /// Only entries at or after this point in time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    /// Only failed syncs
+    pub errors_only: bool,
+    /// Only the most recent N matching entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        if let Some(ref repo) = self.repo {
+            if &entry.repo != repo {
+                return false;
+            }
+        }
+
+        if let Some(since) = self.since {
+            if entry.timestamp < since {
+                return false;
+            }
+        }
+
+        !self.errors_only || entry.is_error()
+    }
+}
+
+/// Append-only, size-rotated JSON lines file of sync outcomes
+pub struct Journal {
+    path: PathBuf,
+    max_bytes: u64,
+    keep_files: usize,
+    write_lock: Mutex<()>,
+}
+
+impl Journal {
+    /// Create a journal writing to the given file
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            keep_files: DEFAULT_KEEP_FILES,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Override the rotation size and the number of rotated files kept
+    pub fn with_rotation(mut self, max_bytes: u64, keep_files: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.keep_files = keep_files;
+        self
+    }
+
+    /// Get the default journal path (~/.local/state/autogit/history.jsonl)
+    pub fn default_path() -> Result<PathBuf> {
+        let state_dir = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .context("Could not determine state directory")?;
+
+        Ok(state_dir.join("autogit").join("history.jsonl"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry, rotating the file first if it grew too large
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create journal directory: {}", parent.display()))?;
+        }
+
+        self.rotate_if_needed()?;
+
+        let mut line = serde_json::to_string(entry)
+            .context("Failed to serialize journal entry")?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open journal: {}", self.path.display()))?;
+
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write journal: {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    /// Read all matching entries, oldest first
+    pub fn query(&self, filter: &JournalFilter) -> Result<Vec<JournalEntry>> {
+        let mut entries = Vec::new();
+
+        // Oldest rotated file first, active file last
+        let mut files: Vec<PathBuf> = (1..=self.keep_files)
+            .rev()
+            .map(|n| self.rotated_path(n))
+            .collect();
+        files.push(self.path.clone());
+
+        for path in files {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to open journal: {}", path.display()));
+                }
+            };
+
+            for line in BufReader::new(file).lines() {
+                let line = line.with_context(|| format!("Failed to read journal: {}", path.display()))?;
+
+                // Skip lines that can't be parsed (e.g. truncated by a crash)
+                let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) else {
+                    continue;
+                };
+
+                if filter.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        if let Some(limit) = filter.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+
+        Ok(entries)
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", n));
+        self.path.with_file_name(name)
+    }
+
+    fn rotate_if_needed(&self) -> Result<()> {
+        let size = match std::fs::metadata(&self.path) {
+            Ok(meta) => meta.len(),
+            Err(_) => return Ok(()),
+        };
+
+        if size < self.max_bytes {
+            return Ok(());
+        }
+
+        if self.keep_files == 0 {
+            std::fs::remove_file(&self.path)
+                .with_context(|| format!("Failed to truncate journal: {}", self.path.display()))?;
+            return Ok(());
+        }
+
+        // history.jsonl.2 -> history.jsonl.3, history.jsonl.1 -> history.jsonl.2, ...
+        let _ = std::fs::remove_file(self.rotated_path(self.keep_files));
+        for n in (1..self.keep_files).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(n + 1))
+                    .with_context(|| format!("Failed to rotate journal: {}", from.display()))?;
+            }
+        }
+
+        std::fs::rename(&self.path, self.rotated_path(1))
+            .with_context(|| format!("Failed to rotate journal: {}", self.path.display()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(repo: &str, minutes_ago: i64) -> JournalEntry {
+        JournalEntry {
+            timestamp: Utc::now() - Duration::minutes(minutes_ago),
+            repo: PathBuf::from(repo),
+            commit: Some("0123456789abcdef".to_owned()),
+            files_changed: 2,
+            push: RemoteResult::Done,
+            pull: RemoteResult::UpToDate,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_query_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(temp_dir.path().join("autogit").join("history.jsonl"));
+
+        journal.append(&entry("/repo1", 10)).unwrap();
+        journal.append(&entry("/repo2", 5)).unwrap();
+
+        let entries = journal.query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].repo, PathBuf::from("/repo1"));
+        assert_eq!(entries[1].repo, PathBuf::from("/repo2"));
+        assert_eq!(entries[1].push, RemoteResult::Done);
+    }
+
+    #[test]
+    fn test_query_missing_file_is_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(temp_dir.path().join("history.jsonl"));
+
+        let entries = journal.query(&JournalFilter::default()).unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(temp_dir.path().join("history.jsonl"));
+
+        journal.append(&entry("/repo1", 120)).unwrap();
+        journal.append(&entry("/repo2", 60)).unwrap();
+        let mut failed = entry("/repo1", 1);
+        failed.push = RemoteResult::Failed("rejected".to_owned());
+        journal.append(&failed).unwrap();
+
+        let by_repo = journal.query(&JournalFilter {
+            repo: Some(PathBuf::from("/repo1")),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(by_repo.len(), 2);
+
+        let recent = journal.query(&JournalFilter {
+            since: Some(Utc::now() - Duration::minutes(90)),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(recent.len(), 2);
+
+        let errors = journal.query(&JournalFilter {
+            errors_only: true,
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].push.is_failed());
+
+        let limited = journal.query(&JournalFilter {
+            limit: Some(1),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(limited.len(), 1);
+        assert!(limited[0].is_error()); // Keeps the most recent entries
+    }
+
+    #[test]
+    fn test_rotation_keeps_history_readable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(temp_dir.path().join("history.jsonl"))
+            .with_rotation(200, 2);
+
+        for i in 0..10 {
+            journal.append(&entry(&format!("/repo{}", i), 10 - i)).unwrap();
+        }
+
+        assert!(temp_dir.path().join("history.jsonl.1").exists());
+        assert!(temp_dir.path().join("history.jsonl.2").exists());
+        assert!(!temp_dir.path().join("history.jsonl.3").exists());
+
+        // Older entries were dropped, remaining ones are in chronological order
+        let entries = journal.query(&JournalFilter::default()).unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.len() < 10);
+        assert_eq!(entries.last().unwrap().repo, PathBuf::from("/repo9"));
+        assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    fn test_query_skips_corrupt_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+        let journal = Journal::new(&path);
+
+        journal.append(&entry("/repo1", 1)).unwrap();
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap()
+            .write_all(b"{\"timestamp\":\"trunc").unwrap();
+
+        let entries = journal.query(&JournalFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_entry_noop_and_error() {
+        let mut e = entry("/repo", 0);
+        assert!(!e.is_noop());
+        assert!(!e.is_error());
+
+        e.commit = None;
+        e.push = RemoteResult::Skipped;
+        assert!(e.is_noop());
+
+        e.error = Some("Not a git repository".to_owned());
+        assert!(e.is_error());
+        assert!(!e.is_noop());
+    }
+
+    #[test]
+    fn test_remote_result_serialization() {
+        assert_eq!(serde_json::to_string(&RemoteResult::UpToDate).unwrap(), "\"up_to_date\"");
+        assert_eq!(
+            serde_json::to_string(&RemoteResult::Failed("boom".to_owned())).unwrap(),
+            "{\"failed\":\"boom\"}"
+        );
+    }
+}
@@ -1,5 +1,10 @@
 pub mod config;
+pub mod discovery;
+#[cfg(feature = "git")]
+pub mod git;
+pub mod journal;
 pub mod protocol;
 
-pub use config::{Config, DaemonConfig, Repository};
-pub use protocol::{Command, Response, ResponseStatus, ResponseData, RepoDetail, socket_path};
+pub use config::{Config, ConfigChange, DaemonConfig, RepoNotFound, RepoOption, Repository, WatchRoot};
+pub use journal::{Journal, JournalEntry, JournalFilter, RemoteResult};
+pub use protocol::{ChangeKind, Command, CommandError, DRY_RUN_PROTOCOL_VERSION, DaemonEvent, ErrorKind, EventKind, FileChange, PROTOCOL_VERSION, PausedRepo, TRIGGER_OPTIONS_PROTOCOL_VERSION, Response, ResponseStatus, ResponseData, RepoDetail, RepoStatus, ScheduleState, SyncPreview, format_elapsed, format_remaining, legacy_socket_path, pid_path, socket_path};
@@ -1,29 +1,164 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::config::{ConfigChange, RepoNotFound, RepoOption, WatchRoot};
+use crate::journal::{JournalEntry, JournalFilter};
 
 /// Get the path to the daemon Unix domain socket
+///
+/// The socket lives in a private `autogit` directory under `$XDG_RUNTIME_DIR`, or under the
+/// config directory on systems without a runtime directory.
 pub fn socket_path() -> Result<PathBuf> {
+    let base_dir = match dirs::runtime_dir() {
+        Some(runtime_dir) => runtime_dir,
+        None => dirs::config_dir().context("Could not determine config directory")?,
+    };
+
+    Ok(base_dir.join("autogit").join("daemon.sock"))
+}
+
+/// Socket path used by daemons from before the runtime directory, under the config directory
+pub fn legacy_socket_path() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .context("Could not determine config directory")?;
 
     Ok(config_dir.join("autogit").join("daemon.sock"))
 }
 
+/// Path of the daemon's pid file, next to the socket in the private runtime directory
+pub fn pid_path(socket_path: &Path) -> Result<PathBuf> {
+    let dir = socket_path.parent()
+        .context("Socket path has no parent directory")?;
+    Ok(dir.join("daemon.pid"))
+}
+
+/// Version of the socket protocol, bumped whenever commands or responses change incompatibly
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// First protocol version whose daemons honour `repos`, `push` and `pull` of a trigger (older ones
+/// ignore them and sync and push every repository)
+pub const TRIGGER_OPTIONS_PROTOCOL_VERSION: u32 = 2;
+
+/// First protocol version whose daemons honour `dry_run` (older ones ignore it and really sync)
+pub const DRY_RUN_PROTOCOL_VERSION: u32 = 2;
+
 /// Commands that can be sent to the daemon via socket
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "command", rename_all = "snake_case")]
 pub enum Command {
-    /// Request the daemon to immediately check and commit all repositories
-    Trigger,
+    /// Handshake: exchange versions and learn which commands the daemon supports
+    Hello {
+        /// Protocol version spoken by the client
+        protocol_version: u32,
+        /// Client package version, for the daemon log
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_version: Option<String>,
+    },
+    /// Request the daemon to immediately check and commit repositories
+    Trigger {
+        /// Only sync these repositories (all enabled ones if not set)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        repos: Option<Vec<PathBuf>>,
+        /// Push new commits (otherwise they are pushed by a later sync)
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        push: bool,
+        /// Pull from the remote
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        pull: bool,
+        /// Only report what the sync would do, without touching the index or the remote
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        dry_run: bool,
+    },
     /// Request daemon status information
     Status,
     /// Ping the daemon to check if it's alive
     Ping,
-    /// Suspend the daemon (stop automatic syncing)
-    Suspend,
+    /// Suspend the daemon (stop automatic syncing), indefinitely or until a point in time
+    Suspend {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<DateTime<Utc>>,
+    },
     /// Resume the daemon (restart automatic syncing)
     Resume,
+    /// Query the sync history journal
+    History(JournalFilter),
+    /// Add a commit made by a client (such as a restore) to the sync history journal
+    RecordHistory(JournalEntry),
+    /// Clear the quarantine of one repository (or of all, if no path is given)
+    Unquarantine {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+    },
+    /// Stop syncing one repository without touching the config, indefinitely or until a point in time
+    Pause {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<DateTime<Utc>>,
+    },
+    /// Resume syncing a paused repository
+    Unpause {
+        path: PathBuf,
+    },
+    /// Keep the connection open and stream daemon events, one JSON line each
+    Subscribe,
+    /// Add a repository to the configuration
+    AddRepo {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        commit_message_template: Option<String>,
+    },
+    /// Remove a repository from the configuration
+    RemoveRepo {
+        path: PathBuf,
+    },
+    /// Change one setting of a configured repository
+    SetRepoOption {
+        path: PathBuf,
+        option: RepoOption,
+    },
+    /// Change the global check interval
+    SetInterval {
+        seconds: u64,
+    },
+    /// Add a directory whose repositories are synced without listing them individually
+    AddWatchRoot {
+        root: WatchRoot,
+    },
+    /// Re-read the configuration file now
+    Reload,
+}
+
+impl From<ConfigChange> for Command {
+    fn from(change: ConfigChange) -> Self {
+        match change {
+            ConfigChange::AddRepo { path, commit_message_template } => Command::AddRepo { path, commit_message_template },
+            ConfigChange::RemoveRepo { path } => Command::RemoveRepo { path },
+            ConfigChange::SetRepoOption { path, option } => Command::SetRepoOption { path, option },
+            ConfigChange::SetInterval { seconds } => Command::SetInterval { seconds },
+            ConfigChange::AddWatchRoot { root } => Command::AddWatchRoot { root },
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Why a command line from a client was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CommandError {
+    /// Not valid JSON, or missing or invalid fields
+    #[error("Malformed command: {0}")]
+    Malformed(String),
+    /// A command this daemon doesn't know, typically from a newer client
+    #[error("Unknown command '{0}'")]
+    Unknown(String),
 }
 
 /// Response from the daemon
@@ -36,6 +171,20 @@ pub struct Response {
     /// Optional detailed data (for trigger command)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<ResponseData>,
+    /// For errors that clients tell apart: why the command failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+}
+
+/// Machine-readable reason of an error response, so that clients needn't parse the message
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A repository named by the command isn't configured
+    RepoNotFound,
+    /// A kind this build doesn't know, sent by a newer daemon
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -60,7 +209,121 @@ pub enum ResponseData {
         uptime_seconds: u64,
         check_interval_seconds: u64,
         repositories_count: usize,
+        /// Repositories no longer synced after repeated failures
+        #[serde(default)]
+        quarantined: Vec<PathBuf>,
+        /// Automatic syncing is suspended
+        #[serde(default)]
+        suspended: bool,
+        /// When a timed suspension ends
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        suspended_until: Option<DateTime<Utc>>,
+        /// Temporarily paused repositories
+        #[serde(default)]
+        paused: Vec<PausedRepo>,
+        /// Conditions seen by the scheduling policy at the last check
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        schedule: Option<ScheduleState>,
+        /// When the next scheduled check runs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        next_check: Option<DateTime<Utc>>,
+        /// Live state of every configured repository
+        #[serde(default)]
+        repositories: Vec<RepoStatus>,
+    },
+    /// Data from a history command
+    History {
+        entries: Vec<JournalEntry>,
+    },
+    /// Data from a dry-run trigger command
+    Preview {
+        previews: Vec<SyncPreview>,
+    },
+    /// Data from a hello command (also attached to unknown-command errors)
+    Hello {
+        protocol_version: u32,
+        daemon_version: String,
+        /// Names of the commands the daemon understands
+        commands: Vec<String>,
+    },
+}
+
+/// Something that happened in the daemon, streamed to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DaemonEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// What happened, tagged by the `event` field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    /// A sync cycle started, over all enabled repositories or a selection of them
+    SyncStarted {
+        /// Requested from the CLI or tray rather than by the timer
+        manual: bool,
+        /// How many repositories are being synced
+        repositories: usize,
+        /// Paths of the repositories being synced
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        paths: Vec<PathBuf>,
+    },
+    /// A sync cycle finished
+    SyncFinished {
+        repos_checked: usize,
+        repos_committed: usize,
+        repos_failed: usize,
+    },
+    /// Changes were committed in a repository
+    CommitCreated {
+        repo: PathBuf,
+        commit: String,
+        files_changed: usize,
+    },
+    /// Committed changes could not be pushed
+    PushFailed {
+        repo: PathBuf,
+        error: String,
     },
+    /// Syncing a repository failed
+    SyncFailed {
+        repo: PathBuf,
+        error: String,
+    },
+    /// The configuration file was reloaded
+    ConfigReloaded {
+        repositories: usize,
+    },
+    /// Automatic syncing was suspended, indefinitely or until a point in time
+    Suspended {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<DateTime<Utc>>,
+    },
+    /// Automatic syncing was resumed
+    Resumed,
+}
+
+impl DaemonEvent {
+    /// An event that happened just now
+    pub fn now(kind: EventKind) -> Self {
+        Self { timestamp: Utc::now(), kind }
+    }
+
+    /// Convert event to JSON string (with newline for line-delimited protocol)
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = serde_json::to_string(self)
+            .context("Failed to serialize event")?;
+        json.push('\n');
+        Ok(json)
+    }
+
+    /// Parse event from JSON line
+    pub fn from_json(line: &str) -> Result<Self> {
+        serde_json::from_str(line.trim())
+            .context("Failed to parse event")
+    }
 }
 
 /// Details about a single repository check
@@ -72,6 +335,143 @@ pub struct RepoDetail {
     pub files_changed: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Repository is quarantined after repeated failures and was not synced
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quarantined: bool,
+    /// Repository is paused and was not synced
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+}
+
+/// What syncing one repository would do, as reported by a dry-run trigger
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncPreview {
+    pub path: PathBuf,
+    /// Files the commit would include, after .gitignore is applied
+    #[serde(default)]
+    pub files: Vec<FileChange>,
+    /// Message of the commit that would be created (absent without changes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+    /// The repository would pull from its remote
+    #[serde(default)]
+    pub pull: bool,
+    /// The repository would push to its remote
+    #[serde(default)]
+    pub push: bool,
+    /// Why the repository would not be synced at all (e.g. paused or quarantined)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    /// The repository could not be inspected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A file that would be committed, and how it changed since the last commit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub change: ChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    TypeChanged,
+}
+
+impl ChangeKind {
+    /// One-letter marker, as in `git status --short`
+    pub fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Modified => 'M',
+            ChangeKind::Deleted => 'D',
+            ChangeKind::TypeChanged => 'T',
+        }
+    }
+}
+
+/// Live state of one configured repository, as reported by `status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoStatus {
+    pub path: PathBuf,
+    /// Auto-commit is enabled in the config
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quarantined: bool,
+    /// Outcome of the most recent sync since the daemon started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<JournalEntry>,
+    /// Commits not yet pushed to the upstream branch (absent without an upstream)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<usize>,
+    /// Commits on the upstream branch not yet pulled, as of the last fetch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behind: Option<usize>,
+    /// A push was deferred and is still waiting
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub push_pending: bool,
+    /// Error of the last failed sync, until a sync succeeds again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A temporarily paused repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PausedRepo {
+    pub path: PathBuf,
+    /// When the pause ends (paused until unpaused if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Battery, network and idle conditions and what the scheduling policy made of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduleState {
+    /// Battery charge in percent (absent without a battery)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_percent: Option<u8>,
+    #[serde(default)]
+    pub on_battery: bool,
+    #[serde(default)]
+    pub metered: bool,
+    /// How long the user has been idle (absent if unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_seconds: Option<u64>,
+    /// Why pulls and pushes are deferred, if they are
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_deferred: Option<String>,
+    /// Why commits are deferred, if they are
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_deferred: Option<String>,
+}
+
+/// Describe the time left until a deadline, e.g. "1h 20m"
+pub fn format_remaining(until: DateTime<Utc>) -> String {
+    format_seconds((until - Utc::now()).num_seconds())
+}
+
+/// Describe the time passed since a moment, e.g. "3d 2h"
+pub fn format_elapsed(since: DateTime<Utc>) -> String {
+    format_seconds((Utc::now() - since).num_seconds())
+}
+
+fn format_seconds(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
 }
 
 impl Response {
@@ -81,6 +481,7 @@ impl Response {
             status: ResponseStatus::Ok,
             message: message.into(),
             data: None,
+            error_kind: None,
         }
     }
 
@@ -90,6 +491,7 @@ impl Response {
             status: ResponseStatus::Ok,
             message: message.into(),
             data: Some(data),
+            error_kind: None,
         }
     }
 
@@ -99,6 +501,16 @@ impl Response {
             status: ResponseStatus::Error,
             message: message.into(),
             data: None,
+            error_kind: None,
+        }
+    }
+
+    /// Create an error response for a failure, with its kind if clients tell it apart
+    pub fn failure(error: &anyhow::Error) -> Self {
+        let repo_not_found = error.is::<RepoNotFound>() || error.chain().any(|cause| cause.is::<RepoNotFound>());
+        Self {
+            error_kind: repo_not_found.then_some(ErrorKind::RepoNotFound),
+            ..Self::error(format!("{:#}", error))
         }
     }
 
@@ -118,6 +530,57 @@ impl Response {
 }
 
 impl Command {
+    /// Wire names of all commands understood by this build
+    pub const NAMES: &'static [&'static str] = &[
+        "hello",
+        "trigger",
+        "status",
+        "ping",
+        "suspend",
+        "resume",
+        "history",
+        "record_history",
+        "unquarantine",
+        "pause",
+        "unpause",
+        "subscribe",
+        "add_repo",
+        "remove_repo",
+        "set_repo_option",
+        "set_interval",
+        "add_watch_root",
+        "reload",
+    ];
+
+    /// Full sync of all enabled repositories
+    pub fn trigger_all() -> Self {
+        Command::Trigger { repos: None, push: true, pull: true, dry_run: false }
+    }
+
+    /// Handshake for this client build
+    pub fn hello() -> Self {
+        Command::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+        }
+    }
+
+    /// Parse a command line, telling unknown commands apart from malformed ones
+    pub fn parse(line: &str) -> std::result::Result<Self, CommandError> {
+        let value: serde_json::Value = serde_json::from_str(line.trim())
+            .map_err(|e| CommandError::Malformed(e.to_string()))?;
+
+        let name = value.get("command")
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| CommandError::Malformed("missing \"command\" field".to_owned()))?;
+
+        if !Self::NAMES.contains(&name) {
+            return Err(CommandError::Unknown(name.to_owned()));
+        }
+
+        serde_json::from_value(value).map_err(|e| CommandError::Malformed(e.to_string()))
+    }
+
     /// Convert command to JSON string (with newline for line-delimited protocol)
     pub fn to_json(&self) -> Result<String> {
         let mut json = serde_json::to_string(self)
@@ -137,28 +600,34 @@ impl Command {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_pid_path() {
+        let pid_path = pid_path(Path::new("/run/user/1000/autogit/daemon.sock")).unwrap();
+        assert_eq!(pid_path, PathBuf::from("/run/user/1000/autogit/daemon.pid"));
+    }
+
     #[test]
     fn test_command_serialization() {
-        let cmd = Command::Trigger;
+        let cmd = Command::trigger_all();
         let json = cmd.to_json().unwrap();
         assert_eq!(json, "{\"command\":\"trigger\"}\n");
 
         let parsed = Command::from_json(&json).unwrap();
         match parsed {
-            Command::Trigger => {},
+            Command::Trigger { repos: None, push: true, pull: true, dry_run: false } => {},
             _ => panic!("Expected Trigger command"),
         }
     }
 
     #[test]
     fn test_command_trigger() {
-        let cmd = Command::Trigger;
+        let cmd = Command::trigger_all();
         let json = cmd.to_json().unwrap();
         assert!(json.contains("\"command\":\"trigger\""));
         assert!(json.ends_with('\n'));
 
         let parsed = Command::from_json(&json).unwrap();
-        matches!(parsed, Command::Trigger);
+        matches!(parsed, Command::Trigger { .. });
     }
 
     #[test]
@@ -185,7 +654,7 @@ mod tests {
     fn test_command_with_whitespace() {
         let json = "  {\"command\":\"trigger\"}  \n";
         let parsed = Command::from_json(json).unwrap();
-        matches!(parsed, Command::Trigger);
+        matches!(parsed, Command::Trigger { .. });
     }
 
     #[test]
@@ -226,6 +695,25 @@ mod tests {
         assert_eq!(resp.status, ResponseStatus::Error);
         assert_eq!(resp.message, "Something failed");
         assert!(resp.data.is_none());
+        assert!(resp.error_kind.is_none());
+        assert!(!resp.to_json().unwrap().contains("error_kind"));
+    }
+
+    #[test]
+    fn test_response_error_kind() {
+        let missing = anyhow::Error::new(RepoNotFound(PathBuf::from("/repo"))).context("Failed to apply change");
+        let resp = Response::failure(&missing);
+        assert_eq!(resp.message, "Failed to apply change: Repository not found in configuration: /repo");
+
+        let json = resp.to_json().unwrap();
+        assert!(json.contains(r#""error_kind":"repo_not_found""#));
+        assert_eq!(Response::from_json(&json).unwrap().error_kind, Some(ErrorKind::RepoNotFound));
+
+        assert!(Response::failure(&anyhow::anyhow!("Invalid interval")).error_kind.is_none());
+
+        // Kinds from newer daemons still parse
+        let newer = Response::from_json(r#"{"status":"error","message":"x","error_kind":"disk_full"}"#).unwrap();
+        assert_eq!(newer.error_kind, Some(ErrorKind::Other));
     }
 
     #[test]
@@ -252,12 +740,16 @@ mod tests {
                         committed: true,
                         files_changed: Some(5),
                         error: None,
+                        quarantined: false,
+                        paused: false,
                     },
                     RepoDetail {
                         path: PathBuf::from("/test/repo2"),
                         committed: false,
                         files_changed: None,
                         error: None,
+                        quarantined: false,
+                        paused: false,
                     },
                 ],
             },
@@ -281,18 +773,24 @@ mod tests {
                     committed: true,
                     files_changed: Some(10),
                     error: None,
+                    quarantined: false,
+                    paused: false,
                 },
                 RepoDetail {
                     path: PathBuf::from("/repo2"),
                     committed: true,
                     files_changed: Some(5),
                     error: None,
+                    quarantined: false,
+                    paused: false,
                 },
                 RepoDetail {
                     path: PathBuf::from("/repo3"),
                     committed: false,
                     files_changed: None,
                     error: None,
+                    quarantined: false,
+                    paused: false,
                 },
             ],
         };
@@ -306,9 +804,9 @@ mod tests {
             assert_eq!(repos_checked, 3);
             assert_eq!(repos_committed, 2);
             assert_eq!(details.len(), 3);
-            assert_eq!(details[0].committed, true);
+            assert!(details[0].committed);
             assert_eq!(details[0].files_changed, Some(10));
-            assert_eq!(details[2].committed, false);
+            assert!(!details[2].committed);
         } else {
             panic!("Expected Trigger data");
         }
@@ -320,16 +818,24 @@ mod tests {
             uptime_seconds: 3600,
             check_interval_seconds: 300,
             repositories_count: 5,
+            quarantined: vec![PathBuf::from("/gone")],
+            suspended: false,
+            suspended_until: None,
+            paused: vec![],
+            schedule: None,
+            next_check: None,
+            repositories: vec![],
         };
 
         let resp = Response::ok_with_data("Status", data);
         let json = resp.to_json().unwrap();
         let parsed = Response::from_json(&json).unwrap();
 
-        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count }) = parsed.data {
+        if let Some(ResponseData::Status { uptime_seconds, check_interval_seconds, repositories_count, quarantined, .. }) = parsed.data {
             assert_eq!(uptime_seconds, 3600);
             assert_eq!(check_interval_seconds, 300);
             assert_eq!(repositories_count, 5);
+            assert_eq!(quarantined, vec![PathBuf::from("/gone")]);
         } else {
             panic!("Expected Status data");
         }
@@ -342,12 +848,14 @@ mod tests {
             committed: false,
             files_changed: None,
             error: Some("Authentication failed".to_owned()),
+            quarantined: false,
+            paused: false,
         };
 
         let json = serde_json::to_string(&detail).unwrap();
         let parsed: RepoDetail = serde_json::from_str(&json).unwrap();
 
-        assert_eq!(parsed.committed, false);
+        assert!(!parsed.committed);
         assert!(parsed.error.is_some());
         assert_eq!(parsed.error.unwrap(), "Authentication failed");
     }
@@ -359,12 +867,14 @@ mod tests {
             committed: true,
             files_changed: Some(3),
             error: None,
+            quarantined: false,
+            paused: false,
         };
 
         let json = serde_json::to_string(&detail).unwrap();
         let parsed: RepoDetail = serde_json::from_str(&json).unwrap();
 
-        assert_eq!(parsed.committed, true);
+        assert!(parsed.committed);
         assert_eq!(parsed.files_changed, Some(3));
         assert!(parsed.error.is_none());
     }
@@ -376,12 +886,14 @@ mod tests {
             committed: false,
             files_changed: None,
             error: None,
+            quarantined: false,
+            paused: false,
         };
 
         let json = serde_json::to_string(&detail).unwrap();
         let parsed: RepoDetail = serde_json::from_str(&json).unwrap();
 
-        assert_eq!(parsed.committed, false);
+        assert!(!parsed.committed);
         assert!(parsed.files_changed.is_none());
         assert!(parsed.error.is_none());
     }
@@ -405,6 +917,13 @@ mod tests {
                     uptime_seconds: 0,
                     check_interval_seconds: 60,
                     repositories_count: 0,
+                    quarantined: vec![],
+                    suspended: false,
+                    suspended_until: None,
+                    paused: vec![],
+                    schedule: None,
+                    next_check: None,
+                    repositories: vec![],
                 },
             ),
         ];
@@ -454,12 +973,129 @@ mod tests {
         assert_eq!(parsed.message, "Test");
     }
 
+    #[test]
+    fn test_command_history() {
+        let cmd = Command::History(JournalFilter {
+            repo: Some(PathBuf::from("/home/user/notes")),
+            errors_only: true,
+            ..Default::default()
+        });
+        let json = cmd.to_json().unwrap();
+        assert!(json.contains("\"command\":\"history\""));
+        assert!(json.contains("\"errors_only\":true"));
+
+        match Command::from_json(&json).unwrap() {
+            Command::History(filter) => {
+                assert_eq!(filter.repo, Some(PathBuf::from("/home/user/notes")));
+                assert!(filter.errors_only);
+                assert!(filter.since.is_none());
+            }
+            _ => panic!("Expected History command"),
+        }
+
+        // All filter fields are optional
+        let parsed = Command::from_json("{\"command\":\"history\"}").unwrap();
+        assert!(matches!(parsed, Command::History(_)));
+    }
+
+    #[test]
+    fn test_response_history_data() {
+        use crate::journal::RemoteResult;
+
+        let entry = JournalEntry {
+            timestamp: chrono::Utc::now(),
+            repo: PathBuf::from("/repo"),
+            commit: Some("abc123".to_owned()),
+            files_changed: 1,
+            push: RemoteResult::Done,
+            pull: RemoteResult::UpToDate,
+            error: None,
+        };
+
+        let resp = Response::ok_with_data("History", ResponseData::History { entries: vec![entry] });
+        let json = resp.to_json().unwrap();
+        let parsed = Response::from_json(&json).unwrap();
+
+        if let Some(ResponseData::History { entries }) = parsed.data {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].commit.as_deref(), Some("abc123"));
+            assert_eq!(entries[0].push, RemoteResult::Done);
+        } else {
+            panic!("Expected History data");
+        }
+    }
+
+    #[test]
+    fn test_command_unquarantine() {
+        let cmd = Command::Unquarantine { path: Some(PathBuf::from("/repo")) };
+        let json = cmd.to_json().unwrap();
+        assert_eq!(json, "{\"command\":\"unquarantine\",\"path\":\"/repo\"}\n");
+
+        // Without a path all repositories are released
+        match Command::from_json("{\"command\":\"unquarantine\"}").unwrap() {
+            Command::Unquarantine { path } => assert!(path.is_none()),
+            _ => panic!("Expected Unquarantine command"),
+        }
+    }
+
+    #[test]
+    fn test_repo_detail_quarantined_flag() {
+        let detail = RepoDetail {
+            path: PathBuf::from("/gone"),
+            committed: false,
+            files_changed: None,
+            error: Some("Not a git repository".to_owned()),
+            quarantined: true,
+            paused: false,
+        };
+
+        let json = serde_json::to_string(&detail).unwrap();
+        assert!(json.contains("\"quarantined\":true"));
+        assert!(serde_json::from_str::<RepoDetail>(&json).unwrap().quarantined);
+
+        // Omitted when false, and defaults to false for older daemons
+        let parsed: RepoDetail = serde_json::from_str("{\"path\":\"/repo\",\"committed\":true}").unwrap();
+        assert!(!parsed.quarantined);
+        assert!(!serde_json::to_string(&parsed).unwrap().contains("quarantined"));
+    }
+
     #[test]
     fn test_command_all_variants() {
         let commands = vec![
-            Command::Trigger,
+            Command::hello(),
+            Command::trigger_all(),
+            Command::Trigger { repos: Some(vec![PathBuf::from("/repo")]), push: false, pull: true, dry_run: false },
+            Command::Trigger { repos: None, push: true, pull: true, dry_run: true },
             Command::Status,
             Command::Ping,
+            Command::History(JournalFilter::default()),
+            Command::Unquarantine { path: None },
+            Command::Suspend { until: None },
+            Command::Pause { path: PathBuf::from("/repo"), until: Some(Utc::now()) },
+            Command::Unpause { path: PathBuf::from("/repo") },
+            Command::Subscribe,
+            Command::AddRepo { path: PathBuf::from("/repo"), commit_message_template: Some("Notes".to_owned()) },
+            Command::RemoveRepo { path: PathBuf::from("/repo") },
+            Command::SetRepoOption { path: PathBuf::from("/repo"), option: RepoOption::AutoCommit(false) },
+            Command::SetInterval { seconds: 60 },
+            Command::AddWatchRoot {
+                root: WatchRoot {
+                    path: PathBuf::from("~/projects"),
+                    depth: 2,
+                    auto_commit: false,
+                    commit_message_template: "Projects".to_owned(),
+                },
+            },
+            Command::Reload,
+            Command::RecordHistory(JournalEntry {
+                timestamp: Utc::now(),
+                repo: PathBuf::from("/repo"),
+                commit: Some("abc123".to_owned()),
+                files_changed: 1,
+                push: crate::journal::RemoteResult::Done,
+                pull: crate::journal::RemoteResult::Skipped,
+                error: None,
+            }),
         ];
 
         for cmd in commands {
@@ -469,6 +1105,321 @@ mod tests {
             // Verify round-trip works
             let json2 = parsed.to_json().unwrap();
             assert_eq!(json, json2);
+
+            // Every command is advertised in the handshake
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert!(Command::NAMES.contains(&value["command"].as_str().unwrap()), "{}", json);
+
+            // Fails to compile when a variant is added without extending the list above
+            match parsed {
+                Command::Hello { .. } | Command::Trigger { .. } | Command::Status | Command::Ping
+                | Command::Suspend { .. } | Command::Resume | Command::History(_) | Command::RecordHistory(_)
+                | Command::Unquarantine { .. } | Command::Pause { .. } | Command::Unpause { .. }
+                | Command::Subscribe | Command::AddRepo { .. } | Command::RemoveRepo { .. }
+                | Command::SetRepoOption { .. } | Command::SetInterval { .. } | Command::AddWatchRoot { .. }
+                | Command::Reload => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_command_config_changes() {
+        let change = ConfigChange::SetRepoOption {
+            path: PathBuf::from("/notes"),
+            option: RepoOption::CommitMessageTemplate("Notes: {date}".to_owned()),
+        };
+        assert_eq!(
+            Command::from(change).to_json().unwrap(),
+            "{\"command\":\"set_repo_option\",\"path\":\"/notes\",\"option\":{\"commit_message_template\":\"Notes: {date}\"}}\n"
+        );
+
+        let add = Command::from(ConfigChange::AddRepo { path: PathBuf::from("/notes"), commit_message_template: None });
+        assert_eq!(add.to_json().unwrap(), "{\"command\":\"add_repo\",\"path\":\"/notes\"}\n");
+    }
+
+    #[test]
+    fn test_command_trigger_targeted() {
+        let cmd = Command::Trigger { repos: Some(vec![PathBuf::from("/notes")]), push: false, pull: true, dry_run: false };
+        let json = cmd.to_json().unwrap();
+        assert_eq!(json, "{\"command\":\"trigger\",\"repos\":[\"/notes\"],\"push\":false}\n");
+
+        match Command::from_json(&json).unwrap() {
+            Command::Trigger { repos, push, pull, dry_run } => {
+                assert_eq!(repos, Some(vec![PathBuf::from("/notes")]));
+                assert!(!push);
+                assert!(pull);
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Trigger command"),
+        }
+    }
+
+    #[test]
+    fn test_command_trigger_dry_run() {
+        let cmd = Command::Trigger { repos: None, push: true, pull: true, dry_run: true };
+        assert_eq!(cmd.to_json().unwrap(), "{\"command\":\"trigger\",\"dry_run\":true}\n");
+
+        let preview = SyncPreview {
+            path: PathBuf::from("/notes"),
+            files: vec![FileChange { path: PathBuf::from("todo.md"), change: ChangeKind::Modified }],
+            commit_message: Some("Auto-commit".to_owned()),
+            pull: true,
+            ..Default::default()
+        };
+        let response = Response::ok_with_data("Would commit changes in 1 of 1 repositories", ResponseData::Preview {
+            previews: vec![preview.clone()],
+        });
+        let json = response.to_json().unwrap();
+        assert!(json.contains("\"change\":\"modified\""));
+
+        match Response::from_json(&json).unwrap().data {
+            Some(ResponseData::Preview { previews }) => assert_eq!(previews, vec![preview]),
+            other => panic!("Expected Preview data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_command_hello() {
+        let json = Command::hello().to_json().unwrap();
+        assert!(json.contains(&format!("\"protocol_version\":{}", PROTOCOL_VERSION)));
+
+        match Command::parse("{\"command\":\"hello\",\"protocol_version\":7}").unwrap() {
+            Command::Hello { protocol_version, client_version } => {
+                assert_eq!(protocol_version, 7);
+                assert!(client_version.is_none());
+            }
+            _ => panic!("Expected Hello command"),
+        }
+    }
+
+    #[test]
+    fn test_command_parse_errors() {
+        assert_eq!(
+            Command::parse("{\"command\":\"teleport\",\"to\":\"mars\"}").unwrap_err(),
+            CommandError::Unknown("teleport".to_owned())
+        );
+        assert!(matches!(Command::parse("not json"), Err(CommandError::Malformed(_))));
+        assert!(matches!(Command::parse("{\"path\":\"/repo\"}"), Err(CommandError::Malformed(_))));
+        // Known command with missing fields
+        assert!(matches!(Command::parse("{\"command\":\"pause\"}"), Err(CommandError::Malformed(_))));
+
+        // Commands from clients predating the handshake still parse
+        assert!(matches!(Command::parse("{\"command\":\"status\"}"), Ok(Command::Status)));
+    }
+
+    #[test]
+    fn test_response_hello_data() {
+        let response = Response::ok_with_data("hello", ResponseData::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            daemon_version: "0.9.0".to_owned(),
+            commands: vec!["ping".to_owned()],
+        });
+
+        let json = response.to_json().unwrap();
+        assert!(json.contains("\"type\":\"hello\""));
+
+        match Response::from_json(&json).unwrap().data {
+            Some(ResponseData::Hello { protocol_version, daemon_version, commands }) => {
+                assert_eq!(protocol_version, PROTOCOL_VERSION);
+                assert_eq!(daemon_version, "0.9.0");
+                assert_eq!(commands, vec!["ping".to_owned()]);
+            }
+            _ => panic!("Expected Hello data"),
+        }
+    }
+
+    #[test]
+    fn test_command_suspend_until() {
+        // Plain suspend from older clients is still accepted
+        match Command::from_json("{\"command\":\"suspend\"}").unwrap() {
+            Command::Suspend { until } => assert!(until.is_none()),
+            _ => panic!("Expected Suspend command"),
         }
+
+        let until = DateTime::parse_from_rfc3339("2025-11-15T18:00:00Z").unwrap().with_timezone(&Utc);
+        let json = Command::Suspend { until: Some(until) }.to_json().unwrap();
+        assert_eq!(json, "{\"command\":\"suspend\",\"until\":\"2025-11-15T18:00:00Z\"}\n");
+
+        match Command::from_json(&json).unwrap() {
+            Command::Suspend { until: parsed } => assert_eq!(parsed, Some(until)),
+            _ => panic!("Expected Suspend command"),
+        }
+    }
+
+    #[test]
+    fn test_command_pause() {
+        let json = Command::Pause { path: PathBuf::from("/repo"), until: None }.to_json().unwrap();
+        assert_eq!(json, "{\"command\":\"pause\",\"path\":\"/repo\"}\n");
+
+        let json = Command::Unpause { path: PathBuf::from("/repo") }.to_json().unwrap();
+        assert_eq!(json, "{\"command\":\"unpause\",\"path\":\"/repo\"}\n");
+    }
+
+    #[test]
+    fn test_status_pause_fields_default() {
+        let json = "{\"type\":\"status\",\"uptime_seconds\":1,\"check_interval_seconds\":60,\"repositories_count\":0}";
+        match serde_json::from_str::<ResponseData>(json).unwrap() {
+            ResponseData::Status { suspended, suspended_until, paused, .. } => {
+                assert!(!suspended);
+                assert!(suspended_until.is_none());
+                assert!(paused.is_empty());
+            }
+            _ => panic!("Expected Status data"),
+        }
+    }
+
+    #[test]
+    fn test_format_remaining() {
+        let now = Utc::now();
+        assert_eq!(format_remaining(now - chrono::Duration::minutes(5)), "0s");
+        assert_eq!(format_remaining(now + chrono::Duration::seconds(30) + chrono::Duration::milliseconds(500)), "30s");
+        assert_eq!(format_remaining(now + chrono::Duration::minutes(45) + chrono::Duration::seconds(30)), "45m");
+        assert_eq!(format_remaining(now + chrono::Duration::minutes(80) + chrono::Duration::seconds(30)), "1h 20m");
+        assert_eq!(format_remaining(now + chrono::Duration::hours(50) + chrono::Duration::seconds(30)), "2d 2h");
+    }
+
+    #[test]
+    fn test_status_schedule_state() {
+        let schedule = ScheduleState {
+            battery_percent: Some(15),
+            on_battery: true,
+            remote_deferred: Some("on battery at 15% (below 30%)".to_owned()),
+            ..Default::default()
+        };
+        let data = ResponseData::Status {
+            uptime_seconds: 1,
+            check_interval_seconds: 60,
+            repositories_count: 0,
+            quarantined: vec![],
+            suspended: false,
+            suspended_until: None,
+            paused: vec![],
+            schedule: Some(schedule.clone()),
+            next_check: None,
+            repositories: vec![],
+        };
+
+        let json = serde_json::to_string(&data).unwrap();
+        assert!(json.contains("\"battery_percent\":15"));
+        assert!(!json.contains("commit_deferred"));
+
+        match serde_json::from_str::<ResponseData>(&json).unwrap() {
+            ResponseData::Status { schedule: parsed, .. } => assert_eq!(parsed, Some(schedule)),
+            _ => panic!("Expected Status data"),
+        }
+    }
+
+    #[test]
+    fn test_status_with_repositories_roundtrip() {
+        use crate::journal::RemoteResult;
+
+        let next_check = DateTime::parse_from_rfc3339("2025-11-15T18:05:00Z").unwrap().with_timezone(&Utc);
+        let repo = RepoStatus {
+            path: PathBuf::from("/repo"),
+            enabled: true,
+            paused: false,
+            quarantined: false,
+            last_sync: Some(JournalEntry {
+                timestamp: next_check - chrono::Duration::minutes(5),
+                repo: PathBuf::from("/repo"),
+                commit: Some("abc123".to_owned()),
+                files_changed: 1,
+                push: RemoteResult::Done,
+                pull: RemoteResult::UpToDate,
+                error: None,
+            }),
+            ahead: Some(0),
+            behind: Some(2),
+            push_pending: false,
+            error: None,
+        };
+        let data = ResponseData::Status {
+            uptime_seconds: 60,
+            check_interval_seconds: 300,
+            repositories_count: 1,
+            quarantined: vec![],
+            suspended: false,
+            suspended_until: None,
+            paused: vec![],
+            schedule: None,
+            next_check: Some(next_check),
+            repositories: vec![repo],
+        };
+
+        let json = serde_json::to_string(&data).unwrap();
+        assert!(json.contains("\"next_check\":\"2025-11-15T18:05:00Z\""));
+        assert!(json.contains("\"behind\":2"));
+        assert!(!json.contains("push_pending"));
+
+        match serde_json::from_str::<ResponseData>(&json).unwrap() {
+            ResponseData::Status { next_check: parsed, repositories, .. } => {
+                assert_eq!(parsed, Some(next_check));
+                assert_eq!(repositories.len(), 1);
+                assert_eq!(repositories[0].behind, Some(2));
+                assert_eq!(repositories[0].last_sync.as_ref().unwrap().files_changed, 1);
+            }
+            _ => panic!("Expected Status data"),
+        }
+    }
+
+    #[test]
+    fn test_status_from_older_daemon() {
+        let json = r#"{"type":"status","uptime_seconds":5,"check_interval_seconds":300,"repositories_count":2}"#;
+        match serde_json::from_str::<ResponseData>(json) {
+            Ok(ResponseData::Status { next_check, repositories, .. }) => {
+                assert!(next_check.is_none());
+                assert!(repositories.is_empty());
+            }
+            other => panic!("Expected Status data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        let now = Utc::now();
+        assert_eq!(format_elapsed(now + chrono::Duration::minutes(5)), "0s");
+        assert_eq!(format_elapsed(now - chrono::Duration::minutes(5) - chrono::Duration::seconds(10)), "5m");
+        assert_eq!(format_elapsed(now - chrono::Duration::hours(50)), "2d 2h");
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let timestamp = DateTime::parse_from_rfc3339("2025-11-15T18:00:00Z").unwrap().with_timezone(&Utc);
+        let event = DaemonEvent {
+            timestamp,
+            kind: EventKind::CommitCreated {
+                repo: PathBuf::from("/repo"),
+                commit: "abc123".to_owned(),
+                files_changed: 2,
+            },
+        };
+
+        let json = event.to_json().unwrap();
+        assert_eq!(
+            json,
+            "{\"timestamp\":\"2025-11-15T18:00:00Z\",\"event\":\"commit_created\",\"repo\":\"/repo\",\"commit\":\"abc123\",\"files_changed\":2}\n"
+        );
+        assert_eq!(DaemonEvent::from_json(&json).unwrap(), event);
+    }
+
+    #[test]
+    fn test_event_all_kinds_roundtrip() {
+        let kinds = vec![
+            EventKind::SyncStarted { manual: true, repositories: 1, paths: vec![PathBuf::from("/repo")] },
+            EventKind::SyncFinished { repos_checked: 3, repos_committed: 1, repos_failed: 1 },
+            EventKind::PushFailed { repo: PathBuf::from("/repo"), error: "rejected".to_owned() },
+            EventKind::SyncFailed { repo: PathBuf::from("/repo"), error: "gone".to_owned() },
+            EventKind::ConfigReloaded { repositories: 2 },
+            EventKind::Suspended { until: Some(Utc::now()) },
+            EventKind::Suspended { until: None },
+            EventKind::Resumed,
+        ];
+
+        for kind in kinds {
+            let event = DaemonEvent::now(kind);
+            assert_eq!(DaemonEvent::from_json(&event.to_json().unwrap()).unwrap(), event);
+        }
+
+        assert!(DaemonEvent::from_json("{\"event\":\"resumed\"}").is_err()); // Timestamp is required
     }
 }
@@ -1,14 +1,40 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "autogit")]
 #[command(about = "Configuration tool for autogit-daemon", long_about = None)]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 pub struct Cli {
+    /// Output format: coloured text, or a stable structure for scripts
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Plain)]
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// How command results are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, coloured text
+    Plain,
+    /// One JSON document (one JSON line per event for `watch`)
+    Json,
+    /// One YAML document (one per event for `watch`)
+    Yaml,
+}
+
+/// Built-in .gitignore templates for `init`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GitignoreTemplate {
+    /// Editor swap files and operating system leftovers
+    Notes,
+    /// Notes, plus Obsidian's per-device workspace files and trash
+    Obsidian,
+    /// Notes, plus Logseq's backups and recycle bin
+    Logseq,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Add a repository to auto-commit
@@ -25,6 +51,54 @@ pub enum Commands {
         interval: Option<u64>,
     },
 
+    /// Create a new repository with an initial commit, push it and add it
+    Init {
+        /// Directory for the repository (created if missing; existing files are committed)
+        path: String,
+
+        /// URL of the remote to push to, added as 'origin'
+        #[arg(short, long)]
+        remote: Option<String>,
+
+        /// Name of the initial branch
+        #[arg(short, long, default_value = "main")]
+        branch: String,
+
+        /// Generate a .gitignore from a built-in template
+        #[arg(short, long, value_enum)]
+        template: Option<GitignoreTemplate>,
+
+        /// Commit message template for automatic commits (default: "Auto-commit: {timestamp}")
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
+    /// Find git repositories under a directory and add the chosen ones
+    Scan {
+        /// Directory to search
+        dir: String,
+
+        /// How many directory levels below DIR to search
+        #[arg(short, long, default_value_t = 3)]
+        depth: usize,
+
+        /// Only offer repositories matching this glob (repeatable; a pattern with '/' matches the path relative to DIR, otherwise the directory name)
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// Don't search directories matching this glob (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Commit message template for the added repositories (default: "Auto-commit: {timestamp}")
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Add every repository found without asking
+        #[arg(short, long)]
+        all: bool,
+    },
+
     /// Remove a repository from auto-commit
     Remove {
         /// Path to the repository to remove
@@ -52,18 +126,164 @@ pub enum Commands {
         seconds: Option<u64>,
     },
 
-    /// Show current configuration
+    /// Show the daemon, schedule and per-repository sync state
     Status,
 
     /// Edit configuration file in $EDITOR
     Edit,
 
+    /// Make the daemon re-read the configuration file
+    Reload,
+
     /// Trigger an immediate check and commit cycle
-    Now,
+    Now {
+        /// Only sync these repositories (default: all enabled repositories)
+        repos: Vec<String>,
+
+        /// Commit without pushing
+        #[arg(long)]
+        no_push: bool,
+
+        /// Skip pulling from the remote
+        #[arg(long)]
+        no_pull: bool,
+
+        /// Only show what would be committed, pulled and pushed, without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// Suspend the daemon (stop automatic syncing)
-    Suspend,
+    Suspend {
+        /// Resume automatically after this long (e.g. "2h", "30m")
+        #[arg(long = "for", value_name = "DURATION", conflicts_with = "until")]
+        duration: Option<String>,
+
+        /// Resume automatically at this time (e.g. "18:00", "2025-11-15 09:00")
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+    },
 
     /// Resume the daemon (restart automatic syncing)
     Resume,
+
+    /// Temporarily stop syncing a repository (without changing the config)
+    Pause {
+        /// Path to the repository
+        path: String,
+
+        /// Resume automatically after this long (e.g. "2h", "30m")
+        #[arg(long = "for", value_name = "DURATION", conflicts_with = "until")]
+        duration: Option<String>,
+
+        /// Resume automatically at this time (e.g. "18:00", "2025-11-15 09:00")
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+    },
+
+    /// Resume syncing a paused repository
+    Unpause {
+        /// Path to the repository
+        path: String,
+    },
+
+    /// Retry a repository the daemon stopped syncing after repeated failures
+    Unquarantine {
+        /// Path to the quarantined repository
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        path: Option<String>,
+
+        /// Release all quarantined repositories
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show the sync history (commits, pushes, pulls and errors)
+    Log {
+        /// Only show history for this repository
+        repo: Option<String>,
+
+        /// Only show entries since a time (e.g. "2h", "3 days ago", "2025-11-15")
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only show failed syncs
+        #[arg(long)]
+        errors: bool,
+
+        /// Maximum number of entries to show (0 for all)
+        #[arg(short = 'n', long, default_value_t = 25)]
+        limit: usize,
+    },
+
+    /// Undo the last automatic commit of a repository
+    Undo {
+        /// Path to the repository (default: the repository containing the current directory)
+        repo: Option<String>,
+
+        /// Keep the commit's changes in the working tree (syncing stays paused until unpaused)
+        #[arg(long, conflicts_with = "force_revert")]
+        keep_changes: bool,
+
+        /// Undo an already pushed commit by committing its revert
+        #[arg(long)]
+        force_revert: bool,
+    },
+
+    /// List the committed versions of a file, newest first
+    Versions {
+        /// Path to the file
+        file: String,
+
+        /// Maximum number of versions to show (0 for all)
+        #[arg(short = 'n', long, default_value_t = 25)]
+        limit: usize,
+    },
+
+    /// Restore a file as it was at a point in time or in a commit, and commit the restore
+    Restore {
+        /// Path to the file
+        file: String,
+
+        /// Restore the version current at this time (e.g. "2 hours ago", "2025-11-15 14:30")
+        #[arg(long, value_name = "TIME", required_unless_present = "commit", conflicts_with = "commit")]
+        at: Option<String>,
+
+        /// Restore the version in this commit (as listed by 'autogit versions')
+        #[arg(long, value_name = "OID")]
+        commit: Option<String>,
+    },
+
+    /// Write the repositories (with their remotes) and watch roots as a bundle for another machine
+    Export {
+        /// File to write the bundle to (default: stdout)
+        file: Option<String>,
+
+        /// Write paths under the home directory relative to ~, for machines with a different home
+        #[arg(long)]
+        portable: bool,
+    },
+
+    /// Add the repositories and watch roots of a bundle, cloning missing repositories
+    Import {
+        /// Bundle written by 'autogit export' ("-" reads stdin)
+        file: String,
+
+        /// Only show what would be cloned and added
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Full-screen dashboard of repositories and daemon events, with keys to sync, pause and suspend
+    Top,
+
+    /// Follow daemon events (syncs, commits, failures) as they happen
+    Watch {
+        /// Print each event as a JSON line (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the daemon, the session and every repository for common problems
+    Doctor,
 }
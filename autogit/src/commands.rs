@@ -1,79 +1,80 @@
 use anyhow::{Context, Result, bail};
-use autogit_shared::{Config, Repository, Command as DaemonCommand, Response, ResponseStatus, ResponseData, socket_path};
+use autogit_client::{Client, ClientError, EventStream};
+use crate::cli::OutputFormat;
+use crate::output::{self, Applied, DaemonReport, HistoryReport, IntervalReport, ListedRepository, Outcome, DaemonRefused, PartialFailure, PathNotFound};
+use crate::output::{PreviewReport, RepoState, RepositoryList, StatusReport, TriggerReport};
+use autogit_shared::{Config, ConfigChange, DaemonConfig, PROTOCOL_VERSION, RepoOption};
+use autogit_shared::{DaemonEvent, EventKind, Journal, JournalEntry, JournalFilter, PausedRepo, RemoteResult, RepoStatus, ScheduleState, format_elapsed, format_remaining};
+use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tabled::{Table, Tabled, settings::{Style, Width, Modify, object::Columns}};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
 
 /// Add a repository to the configuration
-pub fn add_repository(path: &str, message: Option<String>, interval: Option<u64>) -> Result<()> {
-    let mut config = Config::load_or_create_default()?;
-    let config_path = Config::default_config_path()?;
-
+pub async fn add_repository(path: &str, message: Option<String>, interval: Option<u64>, format: OutputFormat) -> Result<()> {
     // Expand and canonicalize the path
     let repo_path = expand_path(path)?;
 
-    // Verify it's a git repository
-    if !repo_path.join(".git").exists() {
-        bail!("Not a git repository: {}", repo_path.display());
-    }
-
-    // Check if already exists
-    if config.repositories.iter().any(|r| r.path == repo_path) {
-        bail!("Repository already configured: {}", repo_path.display());
-    }
-
-    // Create repository config
-    let repo = Repository {
+    let applied = change_config(ConfigChange::AddRepo {
         path: repo_path.clone(),
-        auto_commit: true,
-        commit_message_template: message.unwrap_or_else(|| "Auto-commit: {timestamp}".to_owned()),
-    };
-
-    config.repositories.push(repo);
+        commit_message_template: message,
+    }).await?;
 
     // Update interval if specified
     if let Some(seconds) = interval {
-        config.daemon.check_interval_seconds = seconds;
+        change_config(ConfigChange::SetInterval { seconds }).await?;
     }
 
-    config.save(&config_path)?;
-
-    println!("{} Added repository: {}", "✓".green().bold(), repo_path.display());
-    println!("{} Configuration saved to: {}", "→".blue(), config_path.display());
-    println!("{} Changes will be applied automatically (daemon auto-reloads config)", "→".green());
-
-    Ok(())
+    report_outcome(format, Outcome {
+        message: format!("Added repository: {}", repo_path.display()),
+        applied_by: Some(applied),
+    })
 }
 
 /// Remove a repository from the configuration
-pub fn remove_repository(path: &str) -> Result<()> {
-    let mut config = Config::load_or_create_default()?;
-    let config_path = Config::default_config_path()?;
-
+pub async fn remove_repository(path: &str, format: OutputFormat) -> Result<()> {
     let repo_path = expand_path(path)?;
 
-    let original_len = config.repositories.len();
-    config.repositories.retain(|r| r.path != repo_path);
-
-    if config.repositories.len() == original_len {
-        bail!("Repository not found in configuration: {}", repo_path.display());
-    }
+    let applied = change_config(ConfigChange::RemoveRepo { path: repo_path.clone() }).await?;
 
-    config.save(&config_path)?;
-
-    println!("{} Removed repository: {}", "✓".green().bold(), repo_path.display());
-    println!("{} Changes will be applied automatically (daemon auto-reloads config)", "→".green());
-
-    Ok(())
+    report_outcome(format, Outcome {
+        message: format!("Removed repository: {}", repo_path.display()),
+        applied_by: Some(applied),
+    })
 }
 
-/// List all configured repositories
-pub fn list_repositories() -> Result<()> {
-    let config = Config::load_or_create_default()?;
+/// List all configured repositories and the ones found under watch roots
+pub async fn list_repositories(format: OutputFormat) -> Result<()> {
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
+    let runtime = daemon_runtime().await.unwrap_or_default();
+
+    let configured = config.repositories.iter().map(|r| (r, false));
+    let discovered = config.discovered.iter().map(|r| (r, true));
+    let listed: Vec<ListedRepository> = configured.chain(discovered).map(|(r, discovered)| {
+        ListedRepository {
+            repository: r.clone(),
+            discovered,
+            state: if runtime.quarantined.contains(&r.path) {
+                RepoState::Quarantined
+            } else if runtime.pause(&r.path).is_some() {
+                RepoState::Paused
+            } else if r.auto_commit {
+                RepoState::Enabled
+            } else {
+                RepoState::Disabled
+            },
+        }
+    }).collect();
+
+    if !format.is_plain() {
+        return output::emit(format, &RepositoryList {
+            check_interval_seconds: config.daemon.check_interval_seconds,
+            repositories: listed,
+        });
+    }
 
-    if config.repositories.is_empty() {
+    if listed.is_empty() {
         println!("{}", "No repositories configured.".yellow());
         println!("\nUse {} to add a repository", "autogit add <path>".cyan());
         return Ok(());
@@ -89,79 +90,86 @@ pub fn list_repositories() -> Result<()> {
         message: String,
     }
 
-    let rows: Vec<RepoRow> = config.repositories.iter().map(|r| {
+    let rows: Vec<RepoRow> = listed.iter().map(|listed| {
         RepoRow {
-            status: if r.auto_commit {
-                "✓ Enabled".to_owned()
+            status: match listed.state {
+                RepoState::Quarantined => "⛔ Quarantined",
+                RepoState::Paused => "⏸ Paused",
+                RepoState::Enabled => "✓ Enabled",
+                RepoState::Disabled => "✗ Disabled",
+            }.to_owned(),
+            path: if listed.discovered {
+                format!("{} (watch root)", listed.repository.path.display())
             } else {
-                "✗ Disabled".to_owned()
+                listed.repository.path.display().to_string()
             },
-            path: r.path.display().to_string(),
-            message: r.commit_message_template.clone(),
+            message: listed.repository.commit_message_template.clone(),
         }
     }).collect();
 
     let mut table = Table::new(rows);
     table
         .with(Style::rounded())
-        .with(Modify::new(Columns::new(0..1)).with(Width::truncate(11).suffix("")))
+        .with(Modify::new(Columns::new(0..1)).with(Width::truncate(14).suffix("")))
         .with(Modify::new(Columns::new(1..)).with(Width::wrap(60).keep_words(true)));
 
     println!("{}", table);
 
     println!("\n{} Check interval: {} seconds", "→".blue(), config.daemon.check_interval_seconds);
 
+    if !runtime.quarantined.is_empty() {
+        println!("{} Retry quarantined repositories with: {}", "→".blue(), "autogit unquarantine --all".cyan());
+    }
+
     Ok(())
 }
 
 /// Enable auto-commit for a repository
-pub fn enable_repository(path: &str) -> Result<()> {
-    update_repository_status(path, true)
+pub async fn enable_repository(path: &str, format: OutputFormat) -> Result<()> {
+    update_repository_status(path, true, format).await
 }
 
 /// Disable auto-commit for a repository
-pub fn disable_repository(path: &str) -> Result<()> {
-    update_repository_status(path, false)
+pub async fn disable_repository(path: &str, format: OutputFormat) -> Result<()> {
+    update_repository_status(path, false, format).await
 }
 
-fn update_repository_status(path: &str, enabled: bool) -> Result<()> {
-    let mut config = Config::load_or_create_default()?;
-    let config_path = Config::default_config_path()?;
-
+async fn update_repository_status(path: &str, enabled: bool, format: OutputFormat) -> Result<()> {
     let repo_path = expand_path(path)?;
 
-    let repo = config.repositories.iter_mut()
-        .find(|r| r.path == repo_path)
-        .with_context(|| format!("Repository not found: {}", repo_path.display()))?;
-
-    repo.auto_commit = enabled;
-    config.save(&config_path)?;
+    let applied = change_config(ConfigChange::SetRepoOption {
+        path: repo_path.clone(),
+        option: RepoOption::AutoCommit(enabled),
+    }).await?;
 
     let status = if enabled { "enabled" } else { "disabled" };
-    println!("{} Auto-commit {} for: {}", "✓".green().bold(), status, repo_path.display());
-    println!("{} Changes will be applied automatically (daemon auto-reloads config)", "→".green());
-
-    Ok(())
+    report_outcome(format, Outcome {
+        message: format!("Auto-commit {} for: {}", status, repo_path.display()),
+        applied_by: Some(applied),
+    })
 }
 
 /// Set or show the global check interval
-pub fn set_interval(seconds: Option<u64>) -> Result<()> {
-    let config = Config::load_or_create_default()?;
-    let config_path = Config::default_config_path()?;
-
+pub async fn set_interval(seconds: Option<u64>, format: OutputFormat) -> Result<()> {
     match seconds {
-        Some(secs) => {
-            // Set new interval
-            let mut config = config;
-            config.daemon.check_interval_seconds = secs;
-            config.save(&config_path)?;
+        Some(seconds) => {
+            let applied = change_config(ConfigChange::SetInterval { seconds }).await?;
+
+            if !format.is_plain() {
+                return output::emit(format, &IntervalReport { check_interval_seconds: seconds, applied_by: Some(applied) });
+            }
 
-            println!("{} Check interval set to {} seconds", "✓".green().bold(), secs);
-            println!("{} Changes will be applied automatically (daemon auto-reloads config)", "→".green());
+            println!("{} Check interval set to {} seconds", "✓".green().bold(), seconds);
+            report_applied(applied)?;
         }
         None => {
             // Show current interval
-            let current = config.daemon.check_interval_seconds;
+            let current = Config::load_or_create_default()?.daemon.check_interval_seconds;
+
+            if !format.is_plain() {
+                return output::emit(format, &IntervalReport { check_interval_seconds: current, applied_by: None });
+            }
+
             println!("{} Current check interval: {} seconds", "→".blue(), current);
 
             // Convert to human-readable format
@@ -182,32 +190,108 @@ pub fn set_interval(seconds: Option<u64>) -> Result<()> {
     Ok(())
 }
 
-/// Show current configuration status
-pub async fn show_status() -> Result<()> {
+/// Show the daemon, schedule and per-repository sync state
+pub async fn show_status(format: OutputFormat) -> Result<()> {
     let config_path = Config::default_config_path()?;
-    let config = Config::load_or_create_default()?;
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
 
     // Check daemon status via socket
-    let daemon_running = is_daemon_running().await;
+    let runtime = daemon_runtime().await;
+    let hello = match runtime {
+        Some(_) => daemon_hello().await,
+        None => None,
+    };
+
+    if !format.is_plain() {
+        return output::emit(format, &status_report(config_path, &config, runtime, hello));
+    }
 
-    println!("{}", "autogit Configuration".bold().underline());
+    println!("{}", "autogit Status".bold().underline());
 
     // Show daemon status
     print!("\n{} Daemon status: ", "→".blue());
-    if daemon_running {
-        println!("{}", "running".green());
-    } else {
-        println!("{}", "not running".red());
-        println!("   Start with: systemctl --user start autogit-daemon");
+    match &runtime {
+        Some(DaemonRuntime { suspended: true, suspended_until: Some(until), .. }) => {
+            println!("{} ({} left)", "suspended".yellow(), format_remaining(*until));
+        }
+        Some(DaemonRuntime { suspended: true, .. }) => println!("{}", "suspended".yellow()),
+        Some(_) => println!("{}", "running".green()),
+        None => {
+            println!("{}", "not running".red());
+            println!("   Start with: systemctl --user start autogit-daemon");
+        }
+    }
+
+    if runtime.is_some() {
+        match hello {
+            Some((daemon_version, protocol_version)) => {
+                println!("{} Daemon version: {} (protocol {})", "→".blue(), daemon_version, protocol_version);
+                if protocol_version != PROTOCOL_VERSION {
+                    println!(
+                        "   {} This client speaks protocol {}; restart the daemon after upgrading: systemctl --user restart autogit-daemon",
+                        "⚠".yellow(),
+                        PROTOCOL_VERSION
+                    );
+                }
+            }
+            None => println!(
+                "{} Daemon version: {} (restart it after upgrading: systemctl --user restart autogit-daemon)",
+                "→".blue(),
+                "older than this client".yellow()
+            ),
+        }
     }
 
     println!("{} Config file: {}", "→".blue(), config_path.display());
     println!("{} Check interval: {} seconds", "→".blue(), config.daemon.check_interval_seconds);
-    println!("{} Repositories: {}", "→".blue(), config.repositories.len());
+    println!("{} Repositories: {}", "→".blue(), config.all_repositories().count());
 
-    if !config.repositories.is_empty() {
+    let runtime = runtime.unwrap_or_default();
+
+    if let Some(next_check) = runtime.next_check.filter(|_| !runtime.suspended) {
+        println!("{} Next check: in {}", "→".blue(), format_remaining(next_check));
+    }
+
+    let policy = describe_scheduling_policy(&config.daemon);
+    if !policy.is_empty() {
+        println!("\n{}", "Scheduling:".bold());
+        for rule in policy {
+            println!("  • {}", rule);
+        }
+
+        if let Some(schedule) = &runtime.schedule {
+            println!("  {} {}", "Conditions:".dimmed(), describe_conditions(schedule));
+            if let Some(reason) = &schedule.remote_deferred {
+                println!("  {} Pulls and pushes deferred: {}", "⏸".yellow(), reason);
+            }
+            if let Some(reason) = &schedule.commit_deferred {
+                println!("  {} Commits deferred: {}", "⏸".yellow(), reason);
+            }
+        }
+    }
+
+    if !runtime.repositories.is_empty() {
+        println!("\n{}", "Repositories:".bold());
+        println!("{}", repository_status_table(&runtime.repositories));
+    } else if config.all_repositories().next().is_some() {
+        // Without a (recent enough) daemon only the config file is known
         println!("\n{}", "Repositories:".bold());
-        for (i, repo) in config.repositories.iter().enumerate() {
+        for (i, repo) in config.all_repositories().enumerate() {
+            if runtime.quarantined.contains(&repo.path) {
+                println!("  {}. {} {} {}", i + 1, "⛔".red(), repo.path.display(), "(quarantined)".red());
+                continue;
+            }
+
+            if let Some(pause) = runtime.pause(&repo.path) {
+                let label = match pause.until {
+                    Some(until) => format!("(paused, {} left)", format_remaining(until)),
+                    None => "(paused)".to_owned(),
+                };
+                println!("  {}. {} {} {}", i + 1, "⏸".yellow(), repo.path.display(), label.yellow());
+                continue;
+            }
+
             let status = if repo.auto_commit {
                 "✓".green()
             } else {
@@ -217,11 +301,155 @@ pub async fn show_status() -> Result<()> {
         }
     }
 
+    if !runtime.quarantined.is_empty() {
+        println!(
+            "\n{} {} repositories stopped syncing after repeated failures; retry with: {}",
+            "⛔".red(),
+            runtime.quarantined.len(),
+            "autogit unquarantine --all".cyan()
+        );
+    }
+
     Ok(())
 }
 
+/// Everything `status` shows, for machine-readable output
+fn status_report(config_file: PathBuf, config: &Config, runtime: Option<DaemonRuntime>, hello: Option<(String, u32)>) -> StatusReport {
+    let runtime_repositories = runtime.as_ref().map(|r| r.repositories.clone()).unwrap_or_default();
+
+    // Without a (recent enough) daemon only the config file is known
+    let repositories = if runtime_repositories.is_empty() {
+        let runtime = runtime.as_ref();
+        config.all_repositories().map(|repo| RepoStatus {
+            path: repo.path.clone(),
+            enabled: repo.auto_commit,
+            paused: runtime.is_some_and(|r| r.pause(&repo.path).is_some()),
+            quarantined: runtime.is_some_and(|r| r.quarantined.contains(&repo.path)),
+            last_sync: None,
+            ahead: None,
+            behind: None,
+            push_pending: false,
+            error: None,
+        }).collect()
+    } else {
+        runtime_repositories
+    };
+
+    let (version, protocol_version) = hello.unzip();
+    StatusReport {
+        config_file,
+        check_interval_seconds: config.daemon.check_interval_seconds,
+        daemon: runtime.map(|runtime| DaemonReport {
+            version,
+            protocol_version,
+            suspended: runtime.suspended,
+            suspended_until: runtime.suspended_until,
+            next_check: runtime.next_check,
+            schedule: runtime.schedule,
+            quarantined: runtime.quarantined,
+            paused: runtime.paused,
+        }),
+        repositories,
+    }
+}
+
+/// Table of the live repository state reported by the daemon
+fn repository_status_table(repositories: &[RepoStatus]) -> Table {
+    #[derive(Tabled)]
+    struct StatusRow {
+        #[tabled(rename = "Status")]
+        status: String,
+        #[tabled(rename = "Repository")]
+        path: String,
+        #[tabled(rename = "Last Sync")]
+        last_sync: String,
+        #[tabled(rename = "Outcome")]
+        outcome: String,
+        #[tabled(rename = "Upstream")]
+        upstream: String,
+        #[tabled(rename = "Error")]
+        error: String,
+    }
+
+    let rows: Vec<StatusRow> = repositories.iter().map(|r| {
+        StatusRow {
+            status: describe_repo_state(r).to_owned(),
+            path: r.path.display().to_string(),
+            last_sync: r.last_sync.as_ref()
+                .map(|entry| format!("{} ago", format_elapsed(entry.timestamp)))
+                .unwrap_or_else(|| "-".to_owned()),
+            outcome: r.last_sync.as_ref().map(describe_sync_outcome).unwrap_or_else(|| "-".to_owned()),
+            upstream: describe_upstream(r),
+            error: r.error.clone().unwrap_or_default(),
+        }
+    }).collect();
+
+    let mut table = Table::new(rows);
+    table
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(1..2)).with(Width::wrap(40)))
+        .with(Modify::new(Columns::new(5..)).with(Width::wrap(40).keep_words(true)));
+    table
+}
+
+/// Short label for whether and how a repository is being synced
+fn describe_repo_state(repo: &RepoStatus) -> &'static str {
+    if repo.quarantined {
+        "⛔ Quarantined"
+    } else if repo.paused {
+        "⏸ Paused"
+    } else if !repo.enabled {
+        "✗ Disabled"
+    } else if repo.error.is_some() {
+        "⚠ Failing"
+    } else {
+        "✓ Enabled"
+    }
+}
+
+/// Summary of a single sync, e.g. "committed 2 files, push deferred"
+fn describe_sync_outcome(entry: &JournalEntry) -> String {
+    if entry.error.is_some() {
+        return "✗ failed".to_owned();
+    }
+
+    let mut parts = Vec::new();
+    if entry.commit.is_some() {
+        parts.push(format!("committed {} files", entry.files_changed));
+    }
+    for (name, result) in [("pull", &entry.pull), ("push", &entry.push)] {
+        match result {
+            RemoteResult::Failed(_) => parts.push(format!("{} failed", name)),
+            RemoteResult::Deferred => parts.push(format!("{} deferred", name)),
+            RemoteResult::Done if name == "pull" => parts.push("pulled".to_owned()),
+            RemoteResult::Done if entry.commit.is_none() => parts.push("pushed".to_owned()),
+            _ => {}
+        }
+    }
+
+    if parts.is_empty() {
+        "up to date".to_owned()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Commits ahead and behind the upstream branch, e.g. "↑2 ↓0"
+fn describe_upstream(repo: &RepoStatus) -> String {
+    let counts = match (repo.ahead, repo.behind) {
+        (Some(ahead), Some(behind)) => format!("↑{} ↓{}", ahead, behind),
+        _ => "-".to_owned(),
+    };
+
+    if repo.push_pending {
+        format!("{} (push pending)", counts)
+    } else {
+        counts
+    }
+}
+
 /// Edit configuration file in $EDITOR
-pub fn edit_config() -> Result<()> {
+pub async fn edit_config(format: OutputFormat) -> Result<()> {
     let config_path = Config::default_config_path()?;
 
     // Ensure config exists
@@ -230,170 +458,614 @@ pub fn edit_config() -> Result<()> {
     let editor = std::env::var("EDITOR")
         .unwrap_or_else(|_| "vi".to_owned());
 
-    println!("Opening {} in {}...", config_path.display(), editor);
+    if format.is_plain() {
+        println!("Opening {} in {}...", config_path.display(), editor);
+    }
 
     std::process::Command::new(editor)
         .arg(&config_path)
         .status()
         .context("Failed to open editor")?;
 
-    println!("{} Changes will be applied automatically (daemon auto-reloads config)", "→".green());
+    // Reload right away, so mistakes in the file show up here rather than in the daemon log
+    let (icon, message) = match daemon_client()?.reload().await {
+        Ok(message) => ("✓".green().bold(), message),
+        Err(ClientError::Daemon { message, data: None, .. }) => {
+            ("✗".red().bold(), format!("Daemon kept its previous configuration: {}", message))
+        }
+        Err(_) => ("→".green(), "Changes will be applied automatically (daemon auto-reloads config)".to_owned()),
+    };
+
+    if !format.is_plain() {
+        return output::emit(format, &Outcome::new(message));
+    }
+
+    println!("{} {}", icon, message);
 
     Ok(())
 }
 
+/// Make the daemon re-read the configuration file
+pub async fn reload_config(format: OutputFormat) -> Result<()> {
+    let message = daemon_client()?.reload().await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
+}
+
 /// Trigger an immediate check and commit cycle
-pub async fn trigger_now() -> Result<()> {
-    println!("{} Triggering immediate check and commit cycle...", "→".blue());
+pub async fn trigger_now(repos: &[String], push: bool, pull: bool, format: OutputFormat) -> Result<()> {
+    if format.is_plain() {
+        println!("{} Triggering immediate check and commit cycle...", "→".blue());
+    }
+
+    let summary = daemon_client()?.trigger(selected_repos(repos)?, push, pull).await.map_err(daemon_error)?;
+    let report = TriggerReport {
+        message: summary.message,
+        repos_checked: summary.repos_checked,
+        repos_committed: summary.repos_committed,
+        repos_failed: summary.details.iter().filter(|d| d.error.is_some()).count(),
+        details: summary.details,
+    };
+
+    if format.is_plain() {
+        print_trigger_report(&report);
+    } else {
+        output::emit(format, &report)?;
+    }
 
-    // Send trigger command to daemon
-    let response = send_daemon_command(DaemonCommand::Trigger).await?;
+    if report.repos_failed > 0 {
+        return Err(PartialFailure { failed: report.repos_failed, checked: report.repos_checked }.into());
+    }
 
-    // Check response status
-    if response.status != ResponseStatus::Ok {
-        bail!("Daemon returned error: {}", response.message);
+    Ok(())
+}
+
+/// Show what an immediate sync would commit, pull and push, without changing anything
+pub async fn preview_now(repos: &[String], push: bool, pull: bool, format: OutputFormat) -> Result<()> {
+    let previews = daemon_client()?.preview(selected_repos(repos)?, push, pull).await.map_err(daemon_error)?;
+    let would_commit = previews.iter().filter(|p| p.commit_message.is_some()).count();
+    let report = PreviewReport {
+        message: format!("Would commit changes in {} of {} repositories", would_commit, previews.len()),
+        previews,
+    };
+
+    if format.is_plain() {
+        print_preview_report(&report);
+    } else {
+        output::emit(format, &report)?;
     }
 
-    println!("{} {}", "✓".green().bold(), response.message);
+    let failed = report.previews.iter().filter(|p| p.error.is_some()).count();
+    if failed > 0 {
+        return Err(PartialFailure { failed, checked: report.previews.len() }.into());
+    }
 
-    // Display detailed results if available
-    if let Some(ResponseData::Trigger { repos_checked, repos_committed, details }) = response.data {
-        if !details.is_empty() {
-            println!("\n{}", "Results:".bold());
-            for detail in details {
-                let icon = if detail.committed {
-                    "✓".green()
-                } else if detail.error.is_some() {
-                    "✗".red()
-                } else {
-                    "−".yellow()
-                };
+    Ok(())
+}
+
+/// Repositories named on the command line, or None for all enabled ones
+fn selected_repos(repos: &[String]) -> Result<Option<Vec<PathBuf>>> {
+    if repos.is_empty() {
+        return Ok(None);
+    }
 
-                print!("  {} {}", icon, detail.path.display());
+    // The daemon matches configured paths, so resolve them the same way `add` did
+    repos.iter().map(|path| expand_path(path)).collect::<Result<Vec<_>>>().map(Some)
+}
 
-                if let Some(files) = detail.files_changed {
-                    if files > 0 {
-                        print!(" ({} files)", files);
-                    }
+fn print_preview_report(report: &PreviewReport) {
+    println!("{} Dry run: nothing was staged, committed, pulled or pushed", "→".blue());
+    println!("{} {}", "✓".green().bold(), report.message);
+
+    for preview in &report.previews {
+        println!();
+
+        if let Some(ref reason) = preview.skipped {
+            println!("  {} {} {}", "⏸".yellow(), preview.path.display(), format!("({}, would be skipped)", reason).yellow());
+            continue;
+        }
+        if let Some(ref error) = preview.error {
+            println!("  {} {} - {}", "✗".red(), preview.path.display(), error.red());
+            continue;
+        }
+
+        match preview.commit_message {
+            Some(ref message) => {
+                println!("  {} {} ({} files)", "✓".green(), preview.path.display(), preview.files.len());
+                println!("      Message: {}", message);
+                for file in &preview.files {
+                    println!("      {} {}", file.change.marker(), file.path.display());
                 }
+            }
+            None => println!("  {} {} (no changes to commit)", "−".yellow(), preview.path.display()),
+        }
+
+        let remote = match (preview.pull, preview.push) {
+            (true, true) => "pull, then push",
+            (true, false) => "pull",
+            (false, true) => "push",
+            (false, false) => "none",
+        };
+        println!("      Remote: {}", remote);
+    }
+}
+
+fn print_trigger_report(report: &TriggerReport) {
+    println!("{} {}", "✓".green().bold(), report.message);
+
+    // Display detailed results
+    if !report.details.is_empty() {
+        println!("\n{}", "Results:".bold());
+        for detail in &report.details {
+            let icon = if detail.quarantined {
+                "⛔".red()
+            } else if detail.paused {
+                "⏸".yellow()
+            } else if detail.committed {
+                "✓".green()
+            } else if detail.error.is_some() {
+                "✗".red()
+            } else {
+                "−".yellow()
+            };
+
+            print!("  {} {}", icon, detail.path.display());
 
-                if let Some(ref error) = detail.error {
-                    print!(" - {}", error.red());
+            if let Some(files) = detail.files_changed {
+                if files > 0 {
+                    print!(" ({} files)", files);
                 }
+            }
+
+            if let Some(ref error) = detail.error {
+                print!(" - {}", error.red());
+            }
+
+            if detail.quarantined {
+                print!(" {}", "(quarantined)".red());
+            }
 
-                println!();
+            if detail.paused {
+                print!(" {}", "(paused)".yellow());
             }
+
+            println!();
         }
+    }
 
-        if repos_committed == 0 && repos_checked > 0 {
-            println!("\n{} No changes to commit in any repository", "→".blue());
+    if report.repos_committed == 0 && report.repos_checked > 0 {
+        println!("\n{} No changes to commit in any repository", "→".blue());
+    }
+}
+
+/// Suspend the daemon (stop automatic syncing), optionally resuming after a duration or at a time
+pub async fn suspend_daemon(duration: Option<&str>, until: Option<&str>, format: OutputFormat) -> Result<()> {
+    let until = resolve_deadline(duration, until)?;
+
+    if format.is_plain() {
+        println!("{} Suspending daemon...", "→".blue());
+    }
+
+    let message = daemon_client()?.suspend(until).await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
+}
+
+/// Resume the daemon (restart automatic syncing)
+pub async fn resume_daemon(format: OutputFormat) -> Result<()> {
+    if format.is_plain() {
+        println!("{} Resuming daemon...", "→".blue());
+    }
+
+    let message = daemon_client()?.resume().await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
+}
+
+/// Temporarily stop syncing a repository, optionally resuming after a duration or at a time
+pub async fn pause_repository(path: &str, duration: Option<&str>, until: Option<&str>, format: OutputFormat) -> Result<()> {
+    let until = resolve_deadline(duration, until)?;
+    let path = expand_path(path)?;
+
+    let message = daemon_client()?.pause(path, until).await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
+}
+
+/// Resume syncing a paused repository
+pub async fn unpause_repository(path: &str, format: OutputFormat) -> Result<()> {
+    let path = expand_path(path).unwrap_or_else(|_| PathBuf::from(path));
+
+    let message = daemon_client()?.unpause(path).await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
+}
+
+/// Turn `--for` / `--until` arguments into a deadline (None = indefinitely)
+fn resolve_deadline(duration: Option<&str>, until: Option<&str>) -> Result<Option<DateTime<Utc>>> {
+    match (duration, until) {
+        (Some(duration_spec), None) => {
+            let duration = crate::timespec::parse_duration(duration_spec)?;
+            if duration <= chrono::Duration::zero() {
+                bail!("Duration must be positive");
+            }
+            match Utc::now().checked_add_signed(duration) {
+                Some(deadline) => Ok(Some(deadline)),
+                None => bail!("Duration too long: {}", duration_spec),
+            }
         }
+        (None, Some(until)) => crate::timespec::parse_until(until).map(Some),
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => bail!("Use either --for or --until, not both"),
     }
+}
 
-    Ok(())
+/// Clear the quarantine of a repository (or all of them) so the daemon retries it
+pub async fn unquarantine(path: Option<&str>, all: bool, format: OutputFormat) -> Result<()> {
+    let path = match (path, all) {
+        // Quarantined repositories are often missing, so don't insist on the path existing
+        (Some(path), false) => Some(expand_path(path).unwrap_or_else(|_| PathBuf::from(path))),
+        (None, true) => None,
+        _ => bail!("Specify a repository path or --all"),
+    };
+
+    let message = daemon_client()?.unquarantine(path).await.map_err(daemon_error)?;
+
+    report_outcome(format, Outcome::new(message))
 }
 
-/// Suspend the daemon (stop automatic syncing)
-pub async fn suspend_daemon() -> Result<()> {
-    println!("{} Suspending daemon...", "→".blue());
+/// Show the sync history journal
+pub async fn show_log(repo: Option<&str>, since: Option<&str>, errors_only: bool, limit: usize, format: OutputFormat) -> Result<()> {
+    let filter = JournalFilter {
+        // The repository may have been deleted since, so don't insist on it existing
+        repo: repo.map(|path| expand_path(path).unwrap_or_else(|_| PathBuf::from(path))),
+        since: since.map(crate::timespec::parse_since).transpose()?,
+        errors_only,
+        limit: (limit > 0).then_some(limit),
+    };
 
-    // Send suspend command to daemon
-    let response = send_daemon_command(DaemonCommand::Suspend).await?;
+    let entries = query_history(filter).await?;
 
-    // Check response status
-    if response.status != ResponseStatus::Ok {
-        bail!("Daemon returned error: {}", response.message);
+    if !format.is_plain() {
+        return output::emit(format, &HistoryReport { entries });
     }
 
-    println!("{} {}", "✓".green().bold(), response.message);
+    if entries.is_empty() {
+        println!("{}", "No sync history found.".yellow());
+        return Ok(());
+    }
+
+    #[derive(Tabled)]
+    struct HistoryRow {
+        #[tabled(rename = "Time")]
+        time: String,
+        #[tabled(rename = "Repository")]
+        repo: String,
+        #[tabled(rename = "Commit")]
+        commit: String,
+        #[tabled(rename = "Files")]
+        files: String,
+        #[tabled(rename = "Push")]
+        push: String,
+        #[tabled(rename = "Pull")]
+        pull: String,
+        #[tabled(rename = "Error")]
+        error: String,
+    }
+
+    let rows: Vec<HistoryRow> = entries.iter().map(|e| {
+        HistoryRow {
+            time: e.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+            repo: e.repo.display().to_string(),
+            commit: e.commit.as_deref().map(|oid| oid.chars().take(8).collect()).unwrap_or_default(),
+            files: if e.commit.is_some() { e.files_changed.to_string() } else { String::new() },
+            push: describe_remote_result(&e.push),
+            pull: describe_remote_result(&e.pull),
+            error: e.error.clone().unwrap_or_default(),
+        }
+    }).collect();
+
+    let mut table = Table::new(rows);
+    table
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(1..2)).with(Width::wrap(40)))
+        .with(Modify::new(Columns::new(4..)).with(Width::wrap(40).keep_words(true)));
+
+    println!("{}", table);
 
     Ok(())
 }
 
-/// Resume the daemon (restart automatic syncing)
-pub async fn resume_daemon() -> Result<()> {
-    println!("{} Resuming daemon...", "→".blue());
-
-    // Send resume command to daemon
-    let response = send_daemon_command(DaemonCommand::Resume).await?;
+/// Print daemon events as they happen, until interrupted or the daemon stops
+pub async fn watch_events(format: OutputFormat) -> Result<()> {
+    let mut events = subscribe_events().await?;
 
-    // Check response status
-    if response.status != ResponseStatus::Ok {
-        bail!("Daemon returned error: {}", response.message);
+    if format.is_plain() {
+        println!("{} Watching daemon events (Ctrl+C to stop)", "→".blue());
     }
 
-    println!("{} {}", "✓".green().bold(), response.message);
+    while let Some(line) = events.next_line().await.context("Failed to read event from daemon")? {
+        match format {
+            OutputFormat::Json => {
+                println!("{}", line.trim());
+                continue;
+            }
+            OutputFormat::Yaml => {
+                // Passed through untyped, so events this client doesn't know are kept
+                let event: serde_json::Value = serde_json::from_str(&line)
+                    .context("Failed to parse event from daemon")?;
+                print!("---\n{}", serde_norway::to_string(&event)?);
+                continue;
+            }
+            OutputFormat::Plain => {}
+        }
+
+        match DaemonEvent::from_json(&line) {
+            Ok(event) => println!(
+                "{} {}",
+                event.timestamp.with_timezone(&Local).format("%H:%M:%S").to_string().dimmed(),
+                describe_event(&event.kind)
+            ),
+            // Newer daemons may send events this client doesn't know yet
+            Err(_) => continue,
+        }
+    }
 
+    if format.is_plain() {
+        println!("{} Daemon closed the connection", "→".yellow());
+    }
     Ok(())
 }
 
+/// Open an event subscription
+async fn subscribe_events() -> Result<EventStream> {
+    Ok(daemon_client()?.subscribe().await?)
+}
+
+/// One-line, human-readable description of a daemon event
+pub(crate) fn describe_event(kind: &EventKind) -> String {
+    match kind {
+        EventKind::SyncStarted { manual, repositories, .. } => format!(
+            "{} {} sync of {} repositories started",
+            "⟳".blue(),
+            if *manual { "Manual" } else { "Scheduled" },
+            repositories
+        ),
+        EventKind::SyncFinished { repos_checked, repos_committed, repos_failed } => {
+            let summary = format!("Sync finished: checked {}, committed {}", repos_checked, repos_committed);
+            if *repos_failed > 0 {
+                format!("{} {}, {} failed", "⚠".yellow(), summary, repos_failed)
+            } else {
+                format!("{} {}", "✓".green(), summary)
+            }
+        }
+        EventKind::CommitCreated { repo, commit, files_changed } => format!(
+            "{} Committed {} files in {} ({})",
+            "✓".green(),
+            files_changed,
+            repo.display(),
+            commit.chars().take(8).collect::<String>()
+        ),
+        EventKind::PushFailed { repo, error } => format!("{} Push failed for {}: {}", "✗".red(), repo.display(), error),
+        EventKind::SyncFailed { repo, error } => format!("{} Sync failed for {}: {}", "✗".red(), repo.display(), error),
+        EventKind::ConfigReloaded { repositories } => {
+            format!("{} Configuration reloaded ({} repositories)", "→".blue(), repositories)
+        }
+        EventKind::Suspended { until: Some(until) } => format!(
+            "{} Suspended until {}",
+            "⏸".yellow(),
+            until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        ),
+        EventKind::Suspended { until: None } => format!("{} Suspended", "⏸".yellow()),
+        EventKind::Resumed => format!("{} Resumed", "▶".green()),
+    }
+}
+
+/// Query the history through the daemon, or read the journal file directly if it isn't running
+pub(crate) async fn query_history(filter: JournalFilter) -> Result<Vec<JournalEntry>> {
+    match daemon_client()?.history(filter.clone()).await {
+        Ok(entries) => Ok(entries),
+        Err(e @ (ClientError::Daemon { .. } | ClientError::MissingData(_))) => Err(daemon_error(e)),
+        Err(_) => {
+            let journal = Journal::new(Journal::default_path()?);
+            journal.query(&filter)
+        }
+    }
+}
+
+/// Add an entry to the sync history through the daemon, or to the journal file if it isn't running
+pub(crate) async fn record_history(entry: JournalEntry) -> Result<()> {
+    match daemon_client()?.record_history(entry.clone()).await {
+        Ok(_) => Ok(()),
+        Err(e @ (ClientError::Daemon { .. } | ClientError::MissingData(_))) => Err(daemon_error(e)),
+        Err(_) => Journal::new(Journal::default_path()?).append(&entry),
+    }
+}
+
+fn describe_remote_result(result: &RemoteResult) -> String {
+    match result {
+        RemoteResult::Skipped => "-".to_owned(),
+        RemoteResult::UpToDate => "up to date".to_owned(),
+        RemoteResult::Done => "✓".to_owned(),
+        RemoteResult::Deferred => "deferred".to_owned(),
+        RemoteResult::Failed(message) => format!("✗ {}", message),
+    }
+}
+
+/// Human-readable rules of the battery, network and idle policy (empty if disabled)
+fn describe_scheduling_policy(daemon: &DaemonConfig) -> Vec<String> {
+    let mut rules = Vec::new();
+    if daemon.defer_remote_below_battery_percent > 0 {
+        rules.push(format!(
+            "Defer pulls and pushes on battery below {}%",
+            daemon.defer_remote_below_battery_percent
+        ));
+    }
+    if daemon.defer_remote_on_metered {
+        rules.push("Defer pulls and pushes on metered connections".to_owned());
+    }
+    if daemon.commit_after_idle_seconds > 0 {
+        rules.push(format!("Commit only after {}s of user inactivity", daemon.commit_after_idle_seconds));
+    }
+    rules
+}
+
+/// One-line summary of the conditions the daemon last observed
+fn describe_conditions(schedule: &ScheduleState) -> String {
+    let power = match schedule.battery_percent {
+        Some(percent) if schedule.on_battery => format!("on battery ({}%)", percent),
+        Some(percent) => format!("charging ({}%)", percent),
+        None => "mains power".to_owned(),
+    };
+    let network = if schedule.metered { "metered network" } else { "unmetered network" };
+
+    match schedule.idle_seconds {
+        Some(idle) => format!("{}, {}, idle {}s", power, network, idle),
+        None => format!("{}, {}", power, network),
+    }
+}
+
 /// Expand ~ and canonicalize path
-fn expand_path(path: &str) -> Result<PathBuf> {
-    let expanded = if path.starts_with("~/") {
+pub(crate) fn expand_path(path: &str) -> Result<PathBuf> {
+    expand_home(path)?.canonicalize()
+        .with_context(|| PathNotFound(path.to_owned()))
+}
+
+/// Expand a leading ~/ to the home directory
+pub(crate) fn expand_home(path: &str) -> Result<PathBuf> {
+    if path.starts_with("~/") {
         let home = std::env::var("HOME")
             .context("HOME environment variable not set")?;
-        PathBuf::from(path.replacen("~/", &format!("{}/", home), 1))
+        Ok(PathBuf::from(path.replacen("~/", &format!("{}/", home), 1)))
     } else {
-        PathBuf::from(path)
-    };
+        Ok(PathBuf::from(path))
+    }
+}
+
+/// Run `git push` with the given arguments, failing with git's first error line
+pub(crate) async fn git_push(path: &Path, args: &[&str]) -> Result<()> {
+    let output = tokio::process::Command::new("git")
+        .arg("push")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .await
+        .context("Failed to run git push")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("git push failed").trim());
+    }
+    Ok(())
+}
 
-    expanded.canonicalize()
-        .with_context(|| format!("Failed to resolve path: {}", path))
+/// Let the running daemon validate, save and apply a change, or edit the file if it isn't running
+///
+/// Daemons predating config commands reject them as unknown; they still reload the edited file.
+pub(crate) async fn change_config(change: ConfigChange) -> Result<Applied> {
+    match daemon_client()?.change_config(change.clone()).await {
+        Ok(_) => Ok(Applied::ByDaemon),
+        Err(error @ ClientError::Daemon { data: None, .. }) => Err(error.into()),
+        Err(_) => {
+            let config_path = Config::default_config_path()?;
+            let mut config = Config::load_or_create_default()?;
+            config.discover();
+            config.apply(&change)?;
+            config.save(&config_path)?;
+            Ok(Applied::InFile)
+        }
+    }
 }
 
-/// Send a command to the daemon via Unix socket and get response
-async fn send_daemon_command(command: DaemonCommand) -> Result<Response> {
-    let socket_path = socket_path()
-        .context("Failed to get socket path")?;
+/// Print the result of a command that changed something
+fn report_outcome(format: OutputFormat, outcome: Outcome) -> Result<()> {
+    if !format.is_plain() {
+        return output::emit(format, &outcome);
+    }
 
-    // Try to connect to the socket
-    let stream = UnixStream::connect(&socket_path).await
-        .with_context(|| format!(
-            "Failed to connect to daemon socket: {}\nIs the daemon running? Start it with: systemctl --user start autogit-daemon",
-            socket_path.display()
-        ))?;
+    println!("{} {}", "✓".green().bold(), outcome.message);
+    match outcome.applied_by {
+        Some(applied) => report_applied(applied),
+        None => Ok(()),
+    }
+}
 
-    // Send command
-    let command_json = command.to_json()
-        .context("Failed to serialize command")?;
+pub(crate) fn report_applied(applied: Applied) -> Result<()> {
+    match applied {
+        Applied::ByDaemon => println!("{} Applied by the running daemon", "→".green()),
+        Applied::InFile => println!(
+            "{} Configuration saved to: {} (applied when the daemon loads it)",
+            "→".blue(),
+            Config::default_config_path()?.display()
+        ),
+    }
 
-    let mut stream = stream;
-    stream.write_all(command_json.as_bytes()).await
-        .context("Failed to send command to daemon")?;
+    Ok(())
+}
 
-    stream.flush().await
-        .context("Failed to flush socket")?;
+/// Client for the daemon of the current user
+pub(crate) fn daemon_client() -> Result<Client> {
+    Ok(Client::new()?)
+}
 
-    // Shutdown write side to signal we're done sending
-    stream.shutdown().await
-        .context("Failed to shutdown write side of socket")?;
+/// Report refusals by the daemon the way the CLI always has
+pub(crate) fn daemon_error(error: ClientError) -> anyhow::Error {
+    match error {
+        error @ ClientError::Daemon { .. } => DaemonRefused(error).into(),
+        error => error.into(),
+    }
+}
 
-    // Read response
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
+/// Version and protocol of the running daemon (None if it predates the handshake or isn't running)
+async fn daemon_hello() -> Option<(String, u32)> {
+    let info = daemon_client().ok()?.hello().await.ok()?;
+    Some((info.daemon_version, info.protocol_version))
+}
 
-    reader.read_line(&mut line).await
-        .context("Failed to read response from daemon")?;
+/// Runtime state reported by the daemon, beyond what is in the config file
+#[derive(Debug, Default)]
+struct DaemonRuntime {
+    suspended: bool,
+    suspended_until: Option<DateTime<Utc>>,
+    quarantined: Vec<PathBuf>,
+    paused: Vec<PausedRepo>,
+    schedule: Option<ScheduleState>,
+    next_check: Option<DateTime<Utc>>,
+    repositories: Vec<RepoStatus>,
+}
 
-    if line.is_empty() {
-        bail!("Daemon closed connection without sending response");
+impl DaemonRuntime {
+    /// The pause of a repository, if it is paused
+    fn pause(&self, path: &Path) -> Option<&PausedRepo> {
+        self.paused.iter().find(|p| p.path == path)
     }
-
-    Response::from_json(&line)
-        .context("Failed to parse daemon response")
 }
 
-/// Check if daemon is running by trying to ping it
-async fn is_daemon_running() -> bool {
-    send_daemon_command(DaemonCommand::Ping).await.is_ok()
+/// Ask the daemon for its runtime state (None if it isn't running)
+async fn daemon_runtime() -> Option<DaemonRuntime> {
+    match daemon_client().ok()?.status().await {
+        Ok(status) => Some(DaemonRuntime {
+            suspended: status.suspended,
+            suspended_until: status.suspended_until,
+            quarantined: status.quarantined,
+            paused: status.paused,
+            schedule: status.schedule,
+            next_check: status.next_check,
+            repositories: status.repositories,
+        }),
+        Err(ClientError::Daemon { .. } | ClientError::MissingData(_)) => Some(DaemonRuntime::default()),
+        Err(_) => None,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use autogit_shared::{Command as DaemonCommand, Response, ResponseData, socket_path};
     use std::fs;
     use std::env;
     use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
     use serial_test::serial;
 
     /// Helper to create a temporary git repository
@@ -411,6 +1083,7 @@ mod tests {
 
         // Set XDG_CONFIG_HOME to our temp directory
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         Ok((config_dir, repo_dir))
     }
@@ -418,38 +1091,37 @@ mod tests {
     #[test]
     #[serial]
     fn test_expand_path_absolute() {
-        let _path = "/tmp/test";
-        // expand_path is private, but we can test it indirectly
-        // Just documenting the behavior here
-        assert!(true); // Placeholder
+        let temp_dir = TempDir::new().unwrap();
+        let path = expand_path(temp_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(path, temp_dir.path().canonicalize().unwrap());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
     #[serial]
-    fn test_add_repository_creates_config() {
+    async fn test_add_repository_creates_config() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        let result = add_repository(repo_path, None, None);
+        let result = add_repository(repo_path, None, None, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         // Verify config was created
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.repositories.len(), 1);
         assert!(config.repositories[0].path.to_str().unwrap().contains("tmp"));
-        assert_eq!(config.repositories[0].auto_commit, true);
+        assert!(config.repositories[0].auto_commit);
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_with_custom_message() {
+    async fn test_add_repository_with_custom_message() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        let result = add_repository(repo_path, Some("Custom: {date}".to_owned()), None);
+        let result = add_repository(repo_path, Some("Custom: {date}".to_owned()), None, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
@@ -458,13 +1130,13 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_with_interval() {
+    async fn test_add_repository_with_interval() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        let result = add_repository(repo_path, None, Some(60));
+        let result = add_repository(repo_path, None, Some(60), OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
@@ -473,49 +1145,50 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_not_git_repo() {
+    async fn test_add_repository_not_git_repo() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         let non_git_dir = TempDir::new().unwrap();
         let path = non_git_dir.path().to_str().unwrap();
 
-        let result = add_repository(path, None, None);
+        let result = add_repository(path, None, None, OutputFormat::Plain).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Not a git repository"));
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_duplicate() {
+    async fn test_add_repository_duplicate() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Add first time
-        let result1 = add_repository(repo_path, None, None);
+        let result1 = add_repository(repo_path, None, None, OutputFormat::Plain).await;
         assert!(result1.is_ok());
 
         // Try to add again
-        let result2 = add_repository(repo_path, None, None);
+        let result2 = add_repository(repo_path, None, None, OutputFormat::Plain).await;
         assert!(result2.is_err());
         assert!(result2.unwrap_err().to_string().contains("already configured"));
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_remove_repository() {
+    async fn test_remove_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Add repository
-        add_repository(repo_path, None, None).unwrap();
+        add_repository(repo_path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Remove it
-        let result = remove_repository(repo_path);
+        let result = remove_repository(repo_path, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         // Verify it's gone
@@ -525,190 +1198,195 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_remove_repository_not_found() {
+    async fn test_remove_repository_not_found() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        let result = remove_repository(repo_path);
+        let result = remove_repository(repo_path, OutputFormat::Plain).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not found"));
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_enable_repository() {
+    async fn test_enable_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Add and then disable
-        add_repository(repo_path, None, None).unwrap();
-        disable_repository(repo_path).unwrap();
+        add_repository(repo_path, None, None, OutputFormat::Plain).await.unwrap();
+        disable_repository(repo_path, OutputFormat::Plain).await.unwrap();
 
         // Now enable
-        let result = enable_repository(repo_path);
+        let result = enable_repository(repo_path, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
-        assert_eq!(config.repositories[0].auto_commit, true);
+        assert!(config.repositories[0].auto_commit);
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_disable_repository() {
+    async fn test_disable_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Add repository
-        add_repository(repo_path, None, None).unwrap();
+        add_repository(repo_path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Disable it
-        let result = disable_repository(repo_path);
+        let result = disable_repository(repo_path, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
-        assert_eq!(config.repositories[0].auto_commit, false);
+        assert!(!config.repositories[0].auto_commit);
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_enable_repository_not_found() {
+    async fn test_enable_repository_not_found() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        let result = enable_repository(repo_path);
+        let result = enable_repository(repo_path, OutputFormat::Plain).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not found"));
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_set_interval_new_value() {
+    async fn test_set_interval_new_value() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
-        let result = set_interval(Some(120));
+        let result = set_interval(Some(120), OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.daemon.check_interval_seconds, 120);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_set_interval_show_current() {
+    async fn test_set_interval_show_current() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Set a value first
-        set_interval(Some(180)).unwrap();
+        set_interval(Some(180), OutputFormat::Plain).await.unwrap();
 
         // Show current (no arg) - should not error
-        let result = set_interval(None);
+        let result = set_interval(None, OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_list_repositories_empty() {
+    async fn test_list_repositories_empty() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
-        let result = list_repositories();
+        let result = list_repositories(OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_list_repositories_with_repos() {
+    async fn test_list_repositories_with_repos() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        add_repository(repo_path, Some("Test message".to_owned()), None).unwrap();
+        add_repository(repo_path, Some("Test message".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
-        let result = list_repositories();
+        let result = list_repositories(OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_multiple_repositories() {
+    async fn test_multiple_repositories() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         let repo1 = create_temp_git_repo().unwrap();
         let repo2 = create_temp_git_repo().unwrap();
 
-        add_repository(repo1.path().to_str().unwrap(), None, None).unwrap();
-        add_repository(repo2.path().to_str().unwrap(), Some("Custom".to_owned()), None).unwrap();
+        add_repository(repo1.path().to_str().unwrap(), None, None, OutputFormat::Plain).await.unwrap();
+        add_repository(repo2.path().to_str().unwrap(), Some("Custom".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.repositories.len(), 2);
         assert_eq!(config.repositories[1].commit_message_template, "Custom");
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_remove_one_of_multiple_repositories() {
+    async fn test_remove_one_of_multiple_repositories() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         let repo1 = create_temp_git_repo().unwrap();
         let repo2 = create_temp_git_repo().unwrap();
 
-        add_repository(repo1.path().to_str().unwrap(), None, None).unwrap();
-        add_repository(repo2.path().to_str().unwrap(), None, None).unwrap();
+        add_repository(repo1.path().to_str().unwrap(), None, None, OutputFormat::Plain).await.unwrap();
+        add_repository(repo2.path().to_str().unwrap(), None, None, OutputFormat::Plain).await.unwrap();
 
-        remove_repository(repo1.path().to_str().unwrap()).unwrap();
+        remove_repository(repo1.path().to_str().unwrap(), OutputFormat::Plain).await.unwrap();
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.repositories.len(), 1);
         assert_eq!(config.repositories[0].path, repo2.path().canonicalize().unwrap());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_disable_then_enable_repository() {
+    async fn test_disable_then_enable_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
-        add_repository(repo_path, None, None).unwrap();
+        add_repository(repo_path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Disable
-        disable_repository(repo_path).unwrap();
+        disable_repository(repo_path, OutputFormat::Plain).await.unwrap();
         let config = Config::load_or_create_default().unwrap();
-        assert_eq!(config.repositories[0].auto_commit, false);
+        assert!(!config.repositories[0].auto_commit);
 
         // Enable
-        enable_repository(repo_path).unwrap();
+        enable_repository(repo_path, OutputFormat::Plain).await.unwrap();
         let config = Config::load_or_create_default().unwrap();
-        assert_eq!(config.repositories[0].auto_commit, true);
+        assert!(config.repositories[0].auto_commit);
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_interval_persists_across_operations() {
+    async fn test_interval_persists_across_operations() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Set interval
-        set_interval(Some(240)).unwrap();
+        set_interval(Some(240), OutputFormat::Plain).await.unwrap();
 
         // Add repository (shouldn't change interval)
-        add_repository(repo_path, None, None).unwrap();
+        add_repository(repo_path, None, None, OutputFormat::Plain).await.unwrap();
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.daemon.check_interval_seconds, 240);
@@ -716,14 +1394,14 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_updates_interval() {
+    async fn test_add_repository_updates_interval() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let repo_path = repo_dir.path().to_str().unwrap();
 
         // Add repository with interval
-        add_repository(repo_path, None, Some(90)).unwrap();
+        add_repository(repo_path, None, Some(90), OutputFormat::Plain).await.unwrap();
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.daemon.check_interval_seconds, 90);
@@ -731,9 +1409,9 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_config_survives_operations() {
+    async fn test_config_survives_operations() {
         let (config_dir, repo_dir1) = setup_test_env().unwrap();
         let repo_dir2 = create_temp_git_repo().unwrap();
 
@@ -741,19 +1419,19 @@ mod tests {
         let path2 = repo_dir2.path().to_str().unwrap();
 
         // Add first repo
-        add_repository(path1, Some("Msg1".to_owned()), Some(60)).unwrap();
+        add_repository(path1, Some("Msg1".to_owned()), Some(60), OutputFormat::Plain).await.unwrap();
 
         // Add second repo
-        add_repository(path2, Some("Msg2".to_owned()), None).unwrap();
+        add_repository(path2, Some("Msg2".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         // Disable first
-        disable_repository(path1).unwrap();
+        disable_repository(path1, OutputFormat::Plain).await.unwrap();
 
         // Verify final state
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.repositories.len(), 2);
-        assert_eq!(config.repositories[0].auto_commit, false);
-        assert_eq!(config.repositories[1].auto_commit, true);
+        assert!(!config.repositories[0].auto_commit);
+        assert!(config.repositories[1].auto_commit);
         assert_eq!(config.repositories[0].commit_message_template, "Msg1");
         assert_eq!(config.repositories[1].commit_message_template, "Msg2");
 
@@ -762,57 +1440,256 @@ mod tests {
 
     #[tokio::test]
     #[serial]
-    async fn test_is_daemon_running_no_socket() {
+    async fn test_daemon_runtime_no_socket() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // Should report the daemon as not running when the socket doesn't exist
+        let runtime = daemon_runtime().await;
+        assert!(runtime.is_none());
 
-        // Should return false when socket doesn't exist
-        let running = is_daemon_running().await;
-        assert!(!running);
+        drop(config_dir);
+    }
+
+    /// Serve one connection on the daemon socket, replying with the given line (or closing if None)
+    fn spawn_fake_daemon(reply: Option<String>) -> tokio::task::JoinHandle<String> {
+        let path = socket_path().unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let _ = fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).await.unwrap();
+            if let Some(reply) = reply {
+                reader.into_inner().write_all(reply.as_bytes()).await.unwrap();
+            }
+            request
+        })
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_config_change_sent_to_running_daemon() {
+        let (config_dir, repo_dir) = setup_test_env().unwrap();
+        let daemon = spawn_fake_daemon(Some(Response::ok("Added repository").to_json().unwrap()));
+
+        add_repository(repo_dir.path().to_str().unwrap(), Some("Notes".to_owned()), None, OutputFormat::Plain).await.unwrap();
+
+        // The daemon saves the change, so the CLI leaves the file alone
+        let request = DaemonCommand::from_json(&daemon.await.unwrap()).unwrap();
+        assert!(matches!(request, DaemonCommand::AddRepo { commit_message_template: Some(ref m), .. } if m == "Notes"));
+        assert!(!Config::default_config_path().unwrap().exists());
 
         drop(config_dir);
     }
 
     #[tokio::test]
     #[serial]
-    async fn test_send_daemon_command_no_daemon() {
+    async fn test_config_change_refused_by_daemon() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+        spawn_fake_daemon(Some(Response::error("Check interval must be at least 1 second").to_json().unwrap()));
+
+        let result = set_interval(Some(0), OutputFormat::Plain).await;
+        assert_eq!(result.unwrap_err().to_string(), "Check interval must be at least 1 second");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_config_change_falls_back_for_old_daemon() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // Daemons without config commands reject them as unknown, with their handshake data
+        let unknown = Response {
+            data: Some(ResponseData::Hello { protocol_version: 1, daemon_version: "0.8.0".to_owned(), commands: vec![] }),
+            ..Response::error("Unknown command 'set_interval'")
+        };
+        spawn_fake_daemon(Some(unknown.to_json().unwrap()));
+
+        set_interval(Some(90), OutputFormat::Plain).await.unwrap();
+        assert_eq!(Config::load_or_create_default().unwrap().daemon.check_interval_seconds, 90);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_reload_config() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // Nothing to reload without a daemon
+        assert!(reload_config(OutputFormat::Plain).await.is_err());
+
+        let daemon = spawn_fake_daemon(Some(Response::error("Failed to parse config file").to_json().unwrap()));
+        let result = reload_config(OutputFormat::Plain).await;
+        assert!(result.unwrap_err().to_string().contains("Failed to parse config file"));
+        assert!(daemon.await.unwrap().contains("\"command\":\"reload\""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_daemon_hello() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let reply = Response::ok_with_data("hello", ResponseData::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            daemon_version: "1.2.3".to_owned(),
+            commands: vec![],
+        });
+        let daemon = spawn_fake_daemon(Some(reply.to_json().unwrap()));
+
+        assert_eq!(daemon_hello().await, Some(("1.2.3".to_owned(), PROTOCOL_VERSION)));
+        assert!(daemon.await.unwrap().contains("\"command\":\"hello\""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_selective_trigger_needs_protocol() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let reply = Response::ok_with_data("hello", ResponseData::Hello {
+            protocol_version: 1,
+            daemon_version: "0.8.0".to_owned(),
+            commands: vec!["trigger".to_owned()],
+        });
+        let daemon = spawn_fake_daemon(Some(reply.to_json().unwrap()));
+
+        // Only the handshake is sent, never the trigger the old daemon would misread
+        let repo = config_dir.path().to_string_lossy().into_owned();
+        let error = trigger_now(&[repo], false, true, OutputFormat::Plain).await.unwrap_err();
+        assert!(error.to_string().starts_with("The daemon (protocol 1) is too old for selective syncs"), "{}", error);
+        assert!(daemon.await.unwrap().contains("\"command\":\"hello\""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_daemon_predating_handshake() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // Older daemons drop the connection on commands they can't parse
+        spawn_fake_daemon(None);
+        assert_eq!(daemon_hello().await, None);
+
+        spawn_fake_daemon(None);
+        let error = daemon_client().unwrap().ping().await.unwrap_err();
+        assert!(format!("{:#}", error).contains("restart it"));
+    }
+
+    #[test]
+    fn test_describe_event() {
+        colored::control::set_override(false);
+
+        let commit = EventKind::CommitCreated {
+            repo: PathBuf::from("/repo"),
+            commit: "0123456789abcdef".to_owned(),
+            files_changed: 3,
+        };
+        assert_eq!(describe_event(&commit), "✓ Committed 3 files in /repo (01234567)");
+
+        let finished = EventKind::SyncFinished { repos_checked: 3, repos_committed: 1, repos_failed: 1 };
+        assert_eq!(describe_event(&finished), "⚠ Sync finished: checked 3, committed 1, 1 failed");
+
+        let started = EventKind::SyncStarted { manual: false, repositories: 2, paths: vec![] };
+        assert_eq!(describe_event(&started), "⟳ Scheduled sync of 2 repositories started");
+
+        colored::control::unset_override();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_subscribe_events() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let path = socket_path().unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let daemon = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).await.unwrap();
+
+            let mut stream = reader.into_inner();
+            stream.write_all(Response::ok("Subscribed").to_json().unwrap().as_bytes()).await.unwrap();
+            stream.write_all(DaemonEvent::now(EventKind::Resumed).to_json().unwrap().as_bytes()).await.unwrap();
+            request
+        });
+
+        let mut lines = subscribe_events().await.unwrap();
+        let event = DaemonEvent::from_json(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(event.kind, EventKind::Resumed);
+        assert_eq!(lines.next_line().await.unwrap(), None);
+
+        assert!(daemon.await.unwrap().contains("\"command\":\"subscribe\""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_watch_events_no_daemon() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        assert!(watch_events(OutputFormat::Plain).await.is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_daemon_client_no_daemon() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should fail when daemon is not running
-        let result = send_daemon_command(DaemonCommand::Ping).await;
-        assert!(result.is_err());
+        let result = daemon_client().unwrap().ping().await;
+        assert!(result.unwrap_err().is_not_running());
 
         drop(config_dir);
     }
 
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_list_repositories_formatting() {
+    async fn test_list_repositories_formatting() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let path = repo_dir.path().to_str().unwrap();
 
-        add_repository(path, Some("Test message".to_owned()), None).unwrap();
+        add_repository(path, Some("Test message".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         // List should succeed (even though we can't easily test the output format)
-        let result = list_repositories();
+        let result = list_repositories(OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         drop(config_dir);
         drop(repo_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_remove_repository_cleans_up_empty_config() {
+    async fn test_remove_repository_cleans_up_empty_config() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let path = repo_dir.path().to_str().unwrap();
 
-        add_repository(path, None, None).unwrap();
+        add_repository(path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Remove it
-        remove_repository(path).unwrap();
+        remove_repository(path, OutputFormat::Plain).await.unwrap();
 
         // Config should have no repositories
         let config = Config::load_or_create_default().unwrap();
@@ -822,16 +1699,16 @@ mod tests {
         drop(repo_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_enable_already_enabled_repository() {
+    async fn test_enable_already_enabled_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let path = repo_dir.path().to_str().unwrap();
 
-        add_repository(path, None, None).unwrap();
+        add_repository(path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Enable it (should already be enabled)
-        let result = enable_repository(path);
+        let result = enable_repository(path, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
@@ -841,19 +1718,19 @@ mod tests {
         drop(repo_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_disable_already_disabled_repository() {
+    async fn test_disable_already_disabled_repository() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let path = repo_dir.path().to_str().unwrap();
 
-        add_repository(path, None, None).unwrap();
+        add_repository(path, None, None, OutputFormat::Plain).await.unwrap();
 
         // Disable it
-        disable_repository(path).unwrap();
+        disable_repository(path, OutputFormat::Plain).await.unwrap();
 
         // Disable it again (should be idempotent)
-        let result = disable_repository(path);
+        let result = disable_repository(path, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
@@ -863,13 +1740,14 @@ mod tests {
         drop(repo_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_disable_repository_not_found() {
+    async fn test_disable_repository_not_found() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
-        let result = disable_repository("/nonexistent/path");
+        let result = disable_repository("/nonexistent/path", OutputFormat::Plain).await;
         assert!(result.is_err());
         let err = result.err().unwrap().to_string();
         assert!(err.contains("Repository not found") || err.contains("Failed to resolve path"));
@@ -877,30 +1755,32 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_set_interval_zero() {
+    async fn test_set_interval_zero() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
-        // Should allow setting interval to 0 (though not recommended)
-        let result = set_interval(Some(0));
-        assert!(result.is_ok());
+        // A zero interval would make the daemon spin, so it is refused
+        let result = set_interval(Some(0), OutputFormat::Plain).await;
+        assert!(result.unwrap_err().to_string().contains("at least 1 second"));
 
         let config = Config::load_or_create_default().unwrap();
-        assert_eq!(config.daemon.check_interval_seconds, 0);
+        assert_eq!(config.daemon.check_interval_seconds, 300);
 
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_set_interval_large_value() {
+    async fn test_set_interval_large_value() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should allow large intervals
-        let result = set_interval(Some(86400)); // 24 hours
+        let result = set_interval(Some(86400), OutputFormat::Plain).await; // 24 hours
         assert!(result.is_ok());
 
         let config = Config::load_or_create_default().unwrap();
@@ -909,11 +1789,12 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_preserves_other_repos() {
+    async fn test_add_repository_preserves_other_repos() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Create two test git repos
         let repo1 = create_temp_git_repo().unwrap();
@@ -923,10 +1804,10 @@ mod tests {
         let path2 = repo2.path().to_str().unwrap();
 
         // Add first repo
-        add_repository(path1, Some("Message 1".to_owned()), None).unwrap();
+        add_repository(path1, Some("Message 1".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         // Add second repo
-        add_repository(path2, Some("Message 2".to_owned()), None).unwrap();
+        add_repository(path2, Some("Message 2".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         // Verify both exist
         let config = Config::load_or_create_default().unwrap();
@@ -939,11 +1820,12 @@ mod tests {
         drop(repo2);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_remove_repository_preserves_others() {
+    async fn test_remove_repository_preserves_others() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Create three test git repos
         let repo1 = create_temp_git_repo().unwrap();
@@ -955,12 +1837,12 @@ mod tests {
         let path3 = repo3.path().to_str().unwrap();
 
         // Add all three
-        add_repository(path1, Some("Msg1".to_owned()), None).unwrap();
-        add_repository(path2, Some("Msg2".to_owned()), None).unwrap();
-        add_repository(path3, Some("Msg3".to_owned()), None).unwrap();
+        add_repository(path1, Some("Msg1".to_owned()), None, OutputFormat::Plain).await.unwrap();
+        add_repository(path2, Some("Msg2".to_owned()), None, OutputFormat::Plain).await.unwrap();
+        add_repository(path3, Some("Msg3".to_owned()), None, OutputFormat::Plain).await.unwrap();
 
         // Remove middle one
-        remove_repository(path2).unwrap();
+        remove_repository(path2, OutputFormat::Plain).await.unwrap();
 
         // Verify only first and third remain
         let config = Config::load_or_create_default().unwrap();
@@ -974,14 +1856,14 @@ mod tests {
         drop(repo3);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_add_repository_default_message_template() {
+    async fn test_add_repository_default_message_template() {
         let (config_dir, repo_dir) = setup_test_env().unwrap();
         let path = repo_dir.path().to_str().unwrap();
 
         // Add without specifying message template
-        add_repository(path, None, None).unwrap();
+        add_repository(path, None, None, OutputFormat::Plain).await.unwrap();
 
         let config = Config::load_or_create_default().unwrap();
         assert_eq!(config.repositories[0].commit_message_template, "Auto-commit: {timestamp}");
@@ -990,14 +1872,15 @@ mod tests {
         drop(repo_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_update_repository_status_not_found() {
+    async fn test_update_repository_status_not_found() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Try to update non-existent repository
-        let result = enable_repository("/nonexistent");
+        let result = enable_repository("/nonexistent", OutputFormat::Plain).await;
         assert!(result.is_err());
         let err = result.err().unwrap().to_string();
         assert!(err.contains("Repository not found") || err.contains("Failed to resolve path"));
@@ -1005,15 +1888,16 @@ mod tests {
         drop(config_dir);
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_edit_config_creates_config() {
+    async fn test_edit_config_creates_config() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
         env::set_var("EDITOR", "true"); // Use 'true' command which just exits successfully
 
         // Should create config if it doesn't exist
-        let result = edit_config();
+        let result = edit_config(OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         // Verify config was created
@@ -1023,14 +1907,52 @@ mod tests {
         drop(config_dir);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_show_log_reads_journal_without_daemon() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+        env::set_var("XDG_STATE_HOME", config_dir.path());
+
+        // Empty history
+        assert!(show_log(None, None, false, 25, OutputFormat::Plain).await.is_ok());
+
+        let journal = Journal::new(Journal::default_path().unwrap());
+        journal.append(&JournalEntry {
+            timestamp: chrono::Utc::now(),
+            repo: PathBuf::from("/home/user/notes"),
+            commit: Some("0123456789abcdef".to_owned()),
+            files_changed: 3,
+            push: RemoteResult::Done,
+            pull: RemoteResult::UpToDate,
+            error: None,
+        }).unwrap();
+
+        let entries = query_history(JournalFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].files_changed, 3);
+
+        assert!(show_log(Some("/home/user/notes"), Some("1h"), false, 25, OutputFormat::Plain).await.is_ok());
+        assert!(show_log(None, Some("not a time"), false, 25, OutputFormat::Plain).await.is_err());
+    }
+
+    #[test]
+    fn test_describe_remote_result() {
+        assert_eq!(describe_remote_result(&RemoteResult::Skipped), "-");
+        assert_eq!(describe_remote_result(&RemoteResult::Done), "✓");
+        assert_eq!(describe_remote_result(&RemoteResult::Failed("rejected".to_owned())), "✗ rejected");
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_show_status_no_daemon() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should handle daemon not running gracefully
-        let result = show_status().await;
+        let result = show_status(OutputFormat::Plain).await;
         // It's okay if this fails since daemon is not running
         // Just testing that it doesn't panic
         let _ = result;
@@ -1043,9 +1965,10 @@ mod tests {
     async fn test_trigger_now_no_daemon() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should fail when daemon is not running
-        let result = trigger_now().await;
+        let result = trigger_now(&[], true, true, OutputFormat::Plain).await;
         assert!(result.is_err());
 
         drop(config_dir);
@@ -1056,9 +1979,10 @@ mod tests {
     async fn test_suspend_daemon_no_daemon() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should fail when daemon is not running
-        let result = suspend_daemon().await;
+        let result = suspend_daemon(None, None, OutputFormat::Plain).await;
         assert!(result.is_err());
 
         drop(config_dir);
@@ -1069,11 +1993,290 @@ mod tests {
     async fn test_resume_daemon_no_daemon() {
         let config_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
 
         // Should fail when daemon is not running
-        let result = resume_daemon().await;
+        let result = resume_daemon(OutputFormat::Plain).await;
         assert!(result.is_err());
 
         drop(config_dir);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_unquarantine_requires_path_or_all() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let err = unquarantine(None, false, OutputFormat::Plain).await.unwrap_err();
+        assert!(err.to_string().contains("--all"));
+
+        let err = unquarantine(Some("/tmp/repo"), true, OutputFormat::Plain).await.unwrap_err();
+        assert!(err.to_string().contains("--all"));
+
+        // Valid arguments still need a running daemon
+        assert!(unquarantine(Some("/nonexistent/repo"), false, OutputFormat::Plain).await.is_err());
+        assert!(unquarantine(None, true, OutputFormat::Plain).await.is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_trigger_partial_failure() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let detail = |path: &str, error: Option<&str>| autogit_shared::RepoDetail {
+            path: PathBuf::from(path),
+            committed: error.is_none(),
+            files_changed: None,
+            error: error.map(str::to_owned),
+            quarantined: false,
+            paused: false,
+        };
+        let reply = Response::ok_with_data("Checked 2 repositories, committed changes in 1", ResponseData::Trigger {
+            repos_checked: 2,
+            repos_committed: 1,
+            details: vec![detail("/notes", None), detail("/wiki", Some("Push rejected"))],
+        });
+        spawn_fake_daemon(Some(reply.to_json().unwrap()));
+
+        let error = trigger_now(&[], true, true, OutputFormat::Json).await.unwrap_err();
+        let failure = error.downcast_ref::<PartialFailure>().unwrap();
+        assert_eq!((failure.failed, failure.checked), (1, 2));
+        assert_eq!(output::exit_code(&error), output::EXIT_PARTIAL_FAILURE);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_preview_refused_by_outdated_daemon() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let hello = Response::ok_with_data("Hello", ResponseData::Hello {
+            protocol_version: 1,
+            daemon_version: "0.8.0".to_owned(),
+            commands: vec![],
+        });
+        let daemon = spawn_fake_daemon(Some(hello.to_json().unwrap()));
+
+        let error = preview_now(&[], true, true, OutputFormat::Plain).await.unwrap_err();
+        assert!(error.to_string().contains("too old for dry runs"), "{}", error);
+
+        // An old daemon would ignore the flag, so the trigger is never sent
+        let request = DaemonCommand::from_json(&daemon.await.unwrap()).unwrap();
+        assert!(matches!(request, DaemonCommand::Hello { .. }));
+    }
+
+    #[test]
+    fn test_status_report_without_daemon() {
+        let config = Config {
+            repositories: vec![autogit_shared::Repository {
+                path: PathBuf::from("/notes"),
+                auto_commit: false,
+                commit_message_template: "Auto: {timestamp}".to_owned(),
+            }],
+            ..Default::default()
+        };
+
+        let report = status_report(PathBuf::from("/config.toml"), &config, None, None);
+        assert!(report.daemon.is_none());
+        assert_eq!(report.repositories.len(), 1);
+        assert!(!report.repositories[0].enabled);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["daemon"], serde_json::Value::Null);
+        assert_eq!(json["repositories"][0]["path"], "/notes");
+        assert_eq!(json["check_interval_seconds"], config.daemon.check_interval_seconds);
+    }
+
+    #[test]
+    fn test_status_report_with_daemon() {
+        let config = Config::default();
+        let runtime = DaemonRuntime {
+            suspended: true,
+            quarantined: vec![PathBuf::from("/wiki")],
+            ..Default::default()
+        };
+
+        let report = status_report(PathBuf::from("/config.toml"), &config, Some(runtime), Some(("1.2.3".to_owned(), 1)));
+        let daemon = report.daemon.unwrap();
+        assert_eq!(daemon.version.as_deref(), Some("1.2.3"));
+        assert_eq!(daemon.protocol_version, Some(1));
+        assert!(daemon.suspended);
+        assert_eq!(daemon.quarantined, vec![PathBuf::from("/wiki")]);
+    }
+
+    #[test]
+    fn test_daemon_runtime_pause_lookup() {
+        let runtime = DaemonRuntime {
+            paused: vec![PausedRepo { path: PathBuf::from("/repo"), until: None }],
+            ..Default::default()
+        };
+
+        assert!(runtime.pause(Path::new("/repo")).is_some());
+        assert!(runtime.pause(Path::new("/other")).is_none());
+    }
+
+    fn repo_status(path: &str) -> RepoStatus {
+        RepoStatus {
+            path: PathBuf::from(path),
+            enabled: true,
+            paused: false,
+            quarantined: false,
+            last_sync: None,
+            ahead: None,
+            behind: None,
+            push_pending: false,
+            error: None,
+        }
+    }
+
+    fn sync_entry(commit: Option<&str>, push: RemoteResult, pull: RemoteResult) -> JournalEntry {
+        JournalEntry {
+            timestamp: Utc::now(),
+            repo: PathBuf::from("/repo"),
+            commit: commit.map(str::to_owned),
+            files_changed: 2,
+            push,
+            pull,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_describe_sync_outcome() {
+        let noop = sync_entry(None, RemoteResult::UpToDate, RemoteResult::UpToDate);
+        assert_eq!(describe_sync_outcome(&noop), "up to date");
+
+        let committed = sync_entry(Some("abc"), RemoteResult::Done, RemoteResult::UpToDate);
+        assert_eq!(describe_sync_outcome(&committed), "committed 2 files");
+
+        let deferred = sync_entry(Some("abc"), RemoteResult::Deferred, RemoteResult::Done);
+        assert_eq!(describe_sync_outcome(&deferred), "committed 2 files, pulled, push deferred");
+
+        let caught_up = sync_entry(None, RemoteResult::Done, RemoteResult::UpToDate);
+        assert_eq!(describe_sync_outcome(&caught_up), "pushed");
+
+        let push_failed = sync_entry(None, RemoteResult::Failed("rejected".into()), RemoteResult::UpToDate);
+        assert_eq!(describe_sync_outcome(&push_failed), "push failed");
+
+        let failed = JournalEntry { error: Some("boom".into()), ..noop };
+        assert_eq!(describe_sync_outcome(&failed), "✗ failed");
+    }
+
+    #[test]
+    fn test_describe_upstream() {
+        let mut repo = repo_status("/repo");
+        assert_eq!(describe_upstream(&repo), "-");
+
+        repo.ahead = Some(2);
+        repo.behind = Some(0);
+        assert_eq!(describe_upstream(&repo), "↑2 ↓0");
+
+        repo.push_pending = true;
+        assert_eq!(describe_upstream(&repo), "↑2 ↓0 (push pending)");
+    }
+
+    #[test]
+    fn test_describe_repo_state() {
+        let repo = repo_status("/repo");
+        assert_eq!(describe_repo_state(&repo), "✓ Enabled");
+        assert_eq!(describe_repo_state(&RepoStatus { error: Some("e".into()), ..repo.clone() }), "⚠ Failing");
+        assert_eq!(describe_repo_state(&RepoStatus { enabled: false, ..repo.clone() }), "✗ Disabled");
+        assert_eq!(describe_repo_state(&RepoStatus { paused: true, ..repo.clone() }), "⏸ Paused");
+        assert_eq!(
+            describe_repo_state(&RepoStatus { quarantined: true, paused: true, ..repo }),
+            "⛔ Quarantined"
+        );
+    }
+
+    #[test]
+    fn test_repository_status_table() {
+        let repo = RepoStatus {
+            last_sync: Some(sync_entry(Some("abc"), RemoteResult::Done, RemoteResult::UpToDate)),
+            ahead: Some(0),
+            behind: Some(1),
+            ..repo_status("/home/me/notes")
+        };
+
+        let table = repository_status_table(&[repo, repo_status("/home/me/journal")]).to_string();
+        assert!(table.contains("/home/me/notes"));
+        assert!(table.contains("0s ago"));
+        assert!(table.contains("committed 2 files"));
+        assert!(table.contains("↑0 ↓1"));
+        assert!(table.contains("/home/me/journal"));
+    }
+
+    #[test]
+    fn test_describe_scheduling_policy() {
+        assert!(describe_scheduling_policy(&DaemonConfig::default()).is_empty());
+
+        let daemon = DaemonConfig {
+            defer_remote_below_battery_percent: 20,
+            defer_remote_on_metered: true,
+            commit_after_idle_seconds: 120,
+            ..Default::default()
+        };
+        assert_eq!(describe_scheduling_policy(&daemon), vec![
+            "Defer pulls and pushes on battery below 20%".to_owned(),
+            "Defer pulls and pushes on metered connections".to_owned(),
+            "Commit only after 120s of user inactivity".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn test_describe_conditions() {
+        assert_eq!(describe_conditions(&ScheduleState::default()), "mains power, unmetered network");
+
+        let schedule = ScheduleState {
+            battery_percent: Some(35),
+            on_battery: true,
+            metered: true,
+            idle_seconds: Some(42),
+            ..Default::default()
+        };
+        assert_eq!(describe_conditions(&schedule), "on battery (35%), metered network, idle 42s");
+
+        let charging = ScheduleState { battery_percent: Some(80), ..Default::default() };
+        assert_eq!(describe_conditions(&charging), "charging (80%), unmetered network");
+    }
+
+    #[test]
+    fn test_resolve_deadline() {
+        assert_eq!(resolve_deadline(None, None).unwrap(), None);
+
+        let until = resolve_deadline(Some("2h"), None).unwrap().unwrap();
+        let expected = Utc::now() + chrono::Duration::hours(2);
+        assert!((expected - until).num_seconds().abs() < 5);
+
+        let until = resolve_deadline(None, Some("2999-01-01 10:00")).unwrap().unwrap();
+        assert!(until > Utc::now());
+
+        assert!(resolve_deadline(Some("0m"), None).is_err());
+        assert!(resolve_deadline(Some("soon"), None).is_err());
+        assert!(resolve_deadline(None, Some("2020-01-01 10:00")).is_err());
+        assert!(resolve_deadline(Some("2h"), Some("18:00")).is_err());
+
+        let error = resolve_deadline(Some("9999999999w"), None).unwrap_err();
+        assert!(error.to_string().starts_with("Duration too long"));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_pause_repository_no_daemon() {
+        let (config_dir, repo_dir) = setup_test_env().unwrap();
+        let path = repo_dir.path().to_str().unwrap();
+
+        assert!(pause_repository(path, Some("1h"), None, OutputFormat::Plain).await.is_err());
+        assert!(unpause_repository(path, OutputFormat::Plain).await.is_err());
+
+        // Invalid durations are rejected before contacting the daemon
+        let err = pause_repository(path, Some("forever"), None, OutputFormat::Plain).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid duration"));
+
+        drop(config_dir);
+    }
 }
@@ -0,0 +1,648 @@
+//! `autogit doctor`: check the setup for the usual reasons syncing doesn't work
+
+use crate::cli::OutputFormat;
+use crate::output::{self, Check, CheckStatus, ChecksFailed, DoctorReport};
+use anyhow::Result;
+use autogit_client::{Client, ClientError, DaemonInfo};
+use autogit_shared::git::{get_signature, has_origin, open_repository};
+use autogit_shared::{Config, PROTOCOL_VERSION, Repository, pid_path, socket_path};
+use colored::Colorize;
+use git2::RepositoryState;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// How long the daemon may take to answer the handshake
+const DAEMON_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a remote may take to list its refs
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(15);
+
+const RESTART_HINT: &str = "systemctl --user restart autogit-daemon";
+
+/// Run every check and print the results
+pub async fn run_doctor(format: OutputFormat) -> Result<()> {
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
+    let mut checks = Vec::new();
+
+    let daemon = daemon_check(Client::new()?.timeout(DAEMON_TIMEOUT).hello().await);
+    // The pid file of a daemon that isn't running may name an unrelated process
+    let environment = match daemon.status {
+        CheckStatus::Fail => None,
+        _ => daemon_environment(),
+    };
+    checks.push(daemon);
+    checks.push(systemd_check(systemd_unit_state().await.as_deref()));
+
+    let ssh_remotes = config.all_repositories()
+        .any(|repo| origin_url(&repo.path).is_some_and(|url| is_ssh_url(&url)));
+    checks.extend(ssh_agent_check(environment.as_ref(), ssh_remotes));
+
+    for repo in config.all_repositories() {
+        checks.extend(repository_checks(repo, environment.as_ref()).await);
+    }
+
+    let report = DoctorReport::new(checks);
+    if format.is_plain() {
+        print_report(&report);
+    } else {
+        output::emit(format, &report)?;
+    }
+
+    if report.failed > 0 {
+        return Err(ChecksFailed(report.failed).into());
+    }
+    Ok(())
+}
+
+fn print_report(report: &DoctorReport) {
+    println!("{}", "autogit doctor".bold().underline());
+
+    let mut scope = None;
+    for check in &report.checks {
+        if scope != Some(&check.repository) {
+            scope = Some(&check.repository);
+            let title = match &check.repository {
+                Some(path) => path.display().to_string(),
+                None => "Daemon".to_owned(),
+            };
+            println!("\n{}", title.bold());
+        }
+
+        let symbol = match check.status {
+            CheckStatus::Pass => "✓".green(),
+            CheckStatus::Warn => "⚠".yellow(),
+            CheckStatus::Fail => "✗".red(),
+        };
+        println!("  {} {}", symbol, check.message);
+        if let Some(hint) = &check.hint {
+            println!("    {} {}", "→".blue(), hint.cyan());
+        }
+    }
+
+    println!(
+        "\n{} passed, {} warnings, {} failed",
+        report.passed.to_string().green(),
+        report.warnings.to_string().yellow(),
+        report.failed.to_string().red()
+    );
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self { repository: None, name, status: CheckStatus::Pass, message: message.into(), hint: None }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Warn, hint: Some(hint.into()), ..Self::pass(name, message) }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Fail, hint: Some(hint.into()), ..Self::pass(name, message) }
+    }
+
+    fn for_repository(self, path: &Path) -> Self {
+        Self { repository: Some(path.to_path_buf()), ..self }
+    }
+}
+
+/// Is the daemon reachable, and does it speak our protocol?
+fn daemon_check(hello: Result<DaemonInfo, ClientError>) -> Check {
+    match hello {
+        Ok(info) if info.protocol_version == PROTOCOL_VERSION => {
+            Check::pass("daemon", format!("Daemon is running (version {})", info.daemon_version))
+        }
+        Ok(info) => Check::warn(
+            "daemon",
+            format!(
+                "Daemon {} speaks protocol {}, this client protocol {}",
+                info.daemon_version, info.protocol_version, PROTOCOL_VERSION
+            ),
+            RESTART_HINT,
+        ),
+        Err(ClientError::Daemon { .. }) => Check::warn("daemon", "Daemon is older than this client", RESTART_HINT),
+        Err(e) if e.is_not_running() => Check::fail(
+            "daemon",
+            "Daemon is not running",
+            "systemctl --user start autogit-daemon",
+        ),
+        Err(e) => Check::fail(
+            "daemon",
+            format!("Daemon is not responding: {}", e),
+            "journalctl --user -u autogit-daemon -n 50",
+        ),
+    }
+}
+
+/// Output of `systemctl --user is-enabled`, or None if systemctl couldn't be run
+async fn systemd_unit_state() -> Option<String> {
+    let output = tokio::process::Command::new("systemctl")
+        .args(["--user", "is-enabled", "autogit-daemon"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Will the daemon start with the session?
+fn systemd_check(state: Option<&str>) -> Check {
+    match state {
+        Some(state @ ("enabled" | "enabled-runtime" | "linked" | "linked-runtime" | "static" | "alias")) => {
+            Check::pass("systemd_unit", format!("systemd unit is {}", state))
+        }
+        Some("disabled") => Check::warn(
+            "systemd_unit",
+            "systemd unit is disabled, the daemon won't start with your session",
+            "systemctl --user enable --now autogit-daemon",
+        ),
+        Some("masked") => Check::fail(
+            "systemd_unit",
+            "systemd unit is masked",
+            "systemctl --user unmask autogit-daemon",
+        ),
+        Some("" | "not-found") => Check::warn(
+            "systemd_unit",
+            "systemd unit is not installed",
+            "Install autogit-daemon.service into ~/.config/systemd/user/ (see the README)",
+        ),
+        Some(state) => Check::warn(
+            "systemd_unit",
+            format!("systemd unit is {}", state),
+            "systemctl --user enable --now autogit-daemon",
+        ),
+        None => Check::warn(
+            "systemd_unit",
+            "Could not ask systemd about the unit",
+            "Without systemd, start autogit-daemon with your session some other way",
+        ),
+    }
+}
+
+/// Environment of the running daemon, read from /proc via its pid file
+fn daemon_environment() -> Option<HashMap<String, String>> {
+    let pid_file = pid_path(&socket_path().ok()?).ok()?;
+    let pid: u32 = std::fs::read_to_string(pid_file).ok()?.trim().parse().ok()?;
+    let environ = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    Some(parse_environ(&environ))
+}
+
+/// Parse the NUL-separated `KEY=value` pairs of /proc/<pid>/environ
+fn parse_environ(environ: &[u8]) -> HashMap<String, String> {
+    environ.split(|&byte| byte == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (key, value) = entry.split_once('=')?;
+            Some((key.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// Can the daemon reach an SSH agent? (skipped if its environment is unknown)
+fn ssh_agent_check(environment: Option<&HashMap<String, String>>, ssh_remotes: bool) -> Option<Check> {
+    let socket = environment?.get("SSH_AUTH_SOCK");
+    let hint = "Run 'systemctl --user import-environment SSH_AUTH_SOCK' when your session starts, then restart the daemon";
+
+    let check = match socket {
+        Some(socket) if Path::new(socket).exists() => {
+            Check::pass("ssh_agent", format!("Daemon uses the SSH agent at {}", socket))
+        }
+        _ if !ssh_remotes => Check::pass("ssh_agent", "No repository uses an SSH remote"),
+        Some(socket) => Check::warn(
+            "ssh_agent",
+            format!("SSH_AUTH_SOCK of the daemon points to a missing socket: {}", socket),
+            hint,
+        ),
+        None => Check::warn(
+            "ssh_agent",
+            "SSH_AUTH_SOCK is not set for the daemon, pushes over SSH only work with keys without a passphrase",
+            hint,
+        ),
+    };
+    Some(check)
+}
+
+/// URL of the 'origin' remote, if the repository has one
+fn origin_url(path: &Path) -> Option<String> {
+    let repo = git2::Repository::open(path).ok()?;
+    let remote = repo.find_remote("origin").ok()?;
+    remote.url().map(str::to_owned)
+}
+
+/// Whether git reaches the remote over SSH (`ssh://` or scp-like `host:path`)
+fn is_ssh_url(url: &str) -> bool {
+    if let Some((scheme, _)) = url.split_once("://") {
+        return matches!(scheme, "ssh" | "git+ssh" | "ssh+git");
+    }
+    url.split_once(':').is_some_and(|(host, _)| !host.is_empty() && !host.contains('/'))
+}
+
+/// Check one repository, stopping at the first problem that makes further checks pointless
+async fn repository_checks(repo: &Repository, environment: Option<&HashMap<String, String>>) -> Vec<Check> {
+    let path = repo.path.as_path();
+    let display = path.display();
+    let mut checks = Vec::new();
+    let mut push = |check: Check| checks.push(check.for_repository(path));
+
+    if !path.is_dir() {
+        push(Check::fail("path", "Path does not exist", format!("autogit remove {}", display)));
+        return checks;
+    }
+    push(Check::pass("path", "Path exists"));
+
+    let git_repo = match open_repository(path) {
+        Ok(git_repo) => git_repo,
+        Err(_) => {
+            push(Check::fail(
+                "git_repository",
+                "Not a git repository",
+                format!("git init {}, or autogit remove {}", display, display),
+            ));
+            return checks;
+        }
+    };
+    push(Check::pass("git_repository", "Is a git repository"));
+
+    push(match get_signature(&git_repo) {
+        Ok(signature) => Check::pass(
+            "identity",
+            format!("Commits as {} <{}>", signature.name().unwrap_or_default(), signature.email().unwrap_or_default()),
+        ),
+        Err(e) => Check::fail(
+            "identity",
+            format!("No commit identity: {}", e),
+            "git config --global user.name \"Your Name\" && git config --global user.email you@example.com",
+        ),
+    });
+
+    push(state_check(&git_repo, path));
+
+    match has_origin(&git_repo) {
+        Ok(true) => {}
+        _ => {
+            push(Check::warn(
+                "upstream",
+                "No 'origin' remote, changes are committed but never pushed",
+                format!("git -C {} remote add origin <url>", display),
+            ));
+            return checks;
+        }
+    }
+    push(upstream_check(&git_repo, path));
+    push(remote_check(path, environment).await);
+
+    checks
+}
+
+/// Is a merge, rebase or similar waiting to be finished?
+fn state_check(git_repo: &git2::Repository, path: &Path) -> Check {
+    if let Some((operation, abort)) = unfinished_operation(git_repo.state()) {
+        return Check::fail(
+            "repository_state",
+            format!("A {} is in progress", operation),
+            format!("Finish or abort it: git -C {} {}", path.display(), abort),
+        );
+    }
+
+    if git_repo.index().is_ok_and(|index| index.has_conflicts()) {
+        return Check::fail(
+            "repository_state",
+            "The index has unresolved conflicts",
+            format!("Resolve them and commit: git -C {} status", path.display()),
+        );
+    }
+
+    Check::pass("repository_state", "No merge or rebase in progress")
+}
+
+/// The operation left unfinished in a repository, and the git command that aborts it
+fn unfinished_operation(state: RepositoryState) -> Option<(&'static str, &'static str)> {
+    match state {
+        RepositoryState::Clean => None,
+        RepositoryState::Merge => Some(("merge", "merge --abort")),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(("revert", "revert --abort")),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some(("cherry-pick", "cherry-pick --abort")),
+        RepositoryState::Bisect => Some(("bisect", "bisect reset")),
+        RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => {
+            Some(("rebase", "rebase --abort"))
+        }
+        RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => Some(("git am", "am --abort")),
+    }
+}
+
+/// Does the checked-out branch track a remote branch? (pulls fail without one)
+fn upstream_check(git_repo: &git2::Repository, path: &Path) -> Check {
+    let head = match git_repo.head() {
+        Ok(head) if head.is_branch() => head,
+        Ok(_) => {
+            return Check::fail(
+                "upstream",
+                "HEAD is detached, there is no branch to sync",
+                format!("git -C {} switch <branch>", path.display()),
+            );
+        }
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            return Check::warn(
+                "upstream",
+                "No commits yet, the upstream is set by the first push",
+                format!("git -C {} push -u origin HEAD", path.display()),
+            );
+        }
+        Err(e) => return Check::fail("upstream", format!("Failed to read HEAD: {}", e), "git status"),
+    };
+
+    let branch_name = head.shorthand().unwrap_or("HEAD");
+    let upstream = git_repo.find_branch(branch_name, git2::BranchType::Local)
+        .and_then(|branch| branch.upstream());
+    match upstream {
+        Ok(upstream) => Check::pass(
+            "upstream",
+            format!("{} tracks {}", branch_name, upstream.name().ok().flatten().unwrap_or("its upstream")),
+        ),
+        Err(_) => Check::fail(
+            "upstream",
+            format!("{} has no upstream branch, pulls and pushes fail", branch_name),
+            format!("git -C {} push -u origin {}", path.display(), branch_name),
+        ),
+    }
+}
+
+/// Can git list the refs of 'origin', with the daemon's SSH agent if known?
+async fn remote_check(path: &Path, environment: Option<&HashMap<String, String>>) -> Check {
+    let mut command = tokio::process::Command::new("git");
+    command.args(["ls-remote", "origin", "HEAD"])
+        .current_dir(path)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    // ssh asks for passphrases and host keys on the terminal, which would stall the check
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+    if let Some(environment) = environment {
+        match environment.get("SSH_AUTH_SOCK") {
+            Some(socket) => command.env("SSH_AUTH_SOCK", socket),
+            None => command.env_remove("SSH_AUTH_SOCK"),
+        };
+    }
+
+    let hint = match origin_url(path) {
+        Some(url) if is_ssh_url(&url) => "Check that the SSH agent holds your key (ssh-add -l) and the host is known".to_owned(),
+        _ => format!("Check the URL and your credentials: git -C {} remote -v", path.display()),
+    };
+
+    match tokio::time::timeout(REMOTE_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => Check::pass("remote", "Remote 'origin' is reachable"),
+        Ok(Ok(output)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("git ls-remote failed");
+            Check::fail("remote", format!("Remote 'origin' is not reachable: {}", reason.trim()), hint)
+        }
+        Ok(Err(e)) => Check::fail("remote", format!("Failed to run git: {}", e), "Install git and make sure it is on PATH"),
+        Err(_) => Check::fail(
+            "remote",
+            format!("Remote 'origin' did not answer within {} seconds", REMOTE_TIMEOUT.as_secs()),
+            hint,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// Name and repository of every check that didn't pass
+    fn failing(checks: &[Check]) -> Vec<(&'static str, Option<PathBuf>)> {
+        checks.iter()
+            .filter(|check| check.status != CheckStatus::Pass)
+            .map(|check| (check.name, check.repository.clone()))
+            .collect()
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&status.stderr));
+    }
+
+    fn repository(path: &Path) -> Repository {
+        Repository {
+            path: path.to_path_buf(),
+            auto_commit: true,
+            commit_message_template: "Auto-commit: {timestamp}".to_owned(),
+        }
+    }
+
+    /// A repository with an identity and one commit
+    fn create_repo(dir: &Path) -> Repository {
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+        std::fs::write(dir.join("notes.md"), "notes").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "Initial"]);
+        repository(dir)
+    }
+
+    #[test]
+    fn test_daemon_check() {
+        let info = |protocol_version| DaemonInfo {
+            protocol_version,
+            daemon_version: "0.9.0".to_owned(),
+            commands: vec![],
+        };
+        assert_eq!(daemon_check(Ok(info(PROTOCOL_VERSION))).status, CheckStatus::Pass);
+        assert_eq!(daemon_check(Ok(info(PROTOCOL_VERSION + 1))).status, CheckStatus::Warn);
+
+        let old = ClientError::Daemon { message: "Unknown command".to_owned(), data: None, kind: None };
+        assert_eq!(daemon_check(Err(old)).status, CheckStatus::Warn);
+
+        let not_running = ClientError::NotRunning {
+            path: PathBuf::from("/run/user/1000/autogit/daemon.sock"),
+            source: std::io::ErrorKind::NotFound.into(),
+        };
+        let check = daemon_check(Err(not_running));
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(check.hint.as_deref(), Some("systemctl --user start autogit-daemon"));
+
+        assert_eq!(daemon_check(Err(ClientError::Timeout(DAEMON_TIMEOUT))).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_systemd_check() {
+        assert_eq!(systemd_check(Some("enabled")).status, CheckStatus::Pass);
+        assert_eq!(systemd_check(Some("static")).status, CheckStatus::Pass);
+        assert_eq!(systemd_check(Some("disabled")).status, CheckStatus::Warn);
+        assert_eq!(systemd_check(Some("masked")).status, CheckStatus::Fail);
+
+        let missing = systemd_check(Some(""));
+        assert_eq!(missing.message, "systemd unit is not installed");
+        assert_eq!(systemd_check(None).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_parse_environ() {
+        let environment = parse_environ(b"HOME=/home/me\0SSH_AUTH_SOCK=/run/user/1000/ssh-agent\0EMPTY=\0A=b=c\0");
+        assert_eq!(environment["SSH_AUTH_SOCK"], "/run/user/1000/ssh-agent");
+        assert_eq!(environment["EMPTY"], "");
+        assert_eq!(environment["A"], "b=c");
+        assert_eq!(environment.len(), 4);
+    }
+
+    #[test]
+    fn test_ssh_agent_check() {
+        let dir = TempDir::new().unwrap();
+        let agent = dir.path().join("agent.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&agent).unwrap();
+
+        // Unknown environment, e.g. the daemon isn't running
+        assert!(ssh_agent_check(None, true).is_none());
+
+        let with_agent = HashMap::from([("SSH_AUTH_SOCK".to_owned(), agent.display().to_string())]);
+        assert_eq!(ssh_agent_check(Some(&with_agent), true).unwrap().status, CheckStatus::Pass);
+
+        let stale = HashMap::from([("SSH_AUTH_SOCK".to_owned(), "/nonexistent/agent".to_owned())]);
+        assert_eq!(ssh_agent_check(Some(&stale), true).unwrap().status, CheckStatus::Warn);
+
+        let without = HashMap::new();
+        assert_eq!(ssh_agent_check(Some(&without), true).unwrap().status, CheckStatus::Warn);
+        assert_eq!(ssh_agent_check(Some(&without), false).unwrap().status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_is_ssh_url() {
+        assert!(is_ssh_url("git@github.com:awalland/autogit.git"));
+        assert!(is_ssh_url("ssh://git@example.com/notes.git"));
+        assert!(is_ssh_url("example.com:notes.git"));
+        assert!(!is_ssh_url("https://github.com/awalland/autogit.git"));
+        assert!(!is_ssh_url("/srv/git/notes.git"));
+        assert!(!is_ssh_url("./back:up"));
+        assert!(!is_ssh_url("file:///srv/git/notes.git"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_path() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("gone");
+        let repo = repository(&path);
+
+        let checks = repository_checks(&repo, None).await;
+        assert_eq!(failing(&checks), vec![("path", Some(path.clone()))]);
+        assert_eq!(checks[0].hint.as_deref(), Some(format!("autogit remove {}", path.display()).as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_not_a_repository() {
+        let dir = TempDir::new().unwrap();
+        let repo = repository(dir.path());
+
+        let checks = repository_checks(&repo, None).await;
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[1].name, "git_repository");
+        assert_eq!(checks[1].status, CheckStatus::Fail);
+    }
+
+    #[tokio::test]
+    async fn test_healthy_repository() {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(&remote).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+        git(&remote, &["init", "-q", "--bare"]);
+        let repo = create_repo(&work);
+        git(&work, &["remote", "add", "origin", remote.to_str().unwrap()]);
+        git(&work, &["push", "-q", "-u", "origin", "HEAD"]);
+
+        let checks = repository_checks(&repo, None).await;
+        assert!(failing(&checks).is_empty(), "{:?}", checks);
+        let names: Vec<_> = checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, ["path", "git_repository", "identity", "repository_state", "upstream", "remote"]);
+        assert_eq!(checks[2].message, "Commits as Test User <test@example.com>");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_remote_without_upstream() {
+        let dir = TempDir::new().unwrap();
+        let repo = create_repo(dir.path());
+        let missing = dir.path().join("missing.git");
+        git(dir.path(), &["remote", "add", "origin", missing.to_str().unwrap()]);
+
+        let checks = repository_checks(&repo, None).await;
+        let path = Some(dir.path().to_path_buf());
+        assert_eq!(failing(&checks), vec![("upstream", path.clone()), ("remote", path)]);
+        assert!(checks[4].hint.as_deref().unwrap().contains("push -u origin"));
+        assert!(checks[5].message.contains("does not appear to be a git repository"), "{}", checks[5].message);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_remote_check_without_prompts() {
+        let dir = TempDir::new().unwrap();
+        create_repo(dir.path());
+        git(dir.path(), &["remote", "add", "origin", "ssh://git@example.invalid/notes.git"]);
+
+        // A stand-in for ssh that reports how it was called
+        let bin = TempDir::new().unwrap();
+        let ssh = bin.path().join("ssh");
+        std::fs::write(&ssh, "#!/bin/sh\necho \"ssh $*\" >&2\nexit 255\n").unwrap();
+        std::fs::set_permissions(&ssh, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+        let path = env::var_os("PATH").unwrap_or_default();
+        let user_command = env::var_os("GIT_SSH_COMMAND");
+        env::set_var("PATH", env::join_paths(std::iter::once(bin.path().to_path_buf()).chain(env::split_paths(&path))).unwrap());
+        env::remove_var("GIT_SSH_COMMAND");
+        let batch = remote_check(dir.path(), None).await;
+
+        // A command the user set is kept as it is
+        env::set_var("GIT_SSH_COMMAND", format!("{} -o ConnectTimeout=5", ssh.display()));
+        let custom = remote_check(dir.path(), None).await;
+
+        env::set_var("PATH", path);
+        match user_command {
+            Some(command) => env::set_var("GIT_SSH_COMMAND", command),
+            None => env::remove_var("GIT_SSH_COMMAND"),
+        }
+
+        assert_eq!(batch.status, CheckStatus::Fail);
+        assert!(batch.message.contains("ssh -o BatchMode=yes"), "{}", batch.message);
+        assert!(custom.message.contains("ssh -o ConnectTimeout=5"), "{}", custom.message);
+        assert!(!custom.message.contains("BatchMode"), "{}", custom.message);
+    }
+
+    #[tokio::test]
+    async fn test_rebase_in_progress_without_origin() {
+        let dir = TempDir::new().unwrap();
+        let repo = create_repo(dir.path());
+        std::fs::create_dir(dir.path().join(".git").join("rebase-merge")).unwrap();
+
+        let checks = repository_checks(&repo, None).await;
+        let path = Some(dir.path().to_path_buf());
+        assert_eq!(failing(&checks), vec![("repository_state", path.clone()), ("upstream", path)]);
+
+        let state = &checks[3];
+        assert_eq!(state.status, CheckStatus::Fail);
+        assert_eq!(state.message, "A rebase is in progress");
+        // No origin is only a warning: committing locally still works
+        assert_eq!(checks[4].status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_unfinished_operation() {
+        assert_eq!(unfinished_operation(RepositoryState::Clean), None);
+        assert_eq!(unfinished_operation(RepositoryState::Merge), Some(("merge", "merge --abort")));
+        assert_eq!(unfinished_operation(RepositoryState::Bisect), Some(("bisect", "bisect reset")));
+    }
+}
@@ -0,0 +1,353 @@
+//! `autogit init`: create a repository, connect it to a remote and register it in one go
+
+use crate::cli::{GitignoreTemplate, OutputFormat};
+use crate::commands::{change_config, expand_home, git_push, report_applied};
+use crate::output::{self, InitReport};
+use anyhow::{Context, Result, bail};
+use autogit_shared::ConfigChange;
+use autogit_shared::git::get_signature;
+use colored::Colorize;
+use git2::{IndexAddOption, Repository as GitRepository, RepositoryInitOptions};
+use std::path::Path;
+
+/// Message of the commit created by `init`
+const INITIAL_COMMIT_MESSAGE: &str = "Initial commit";
+
+/// Editor and operating system leftovers, ignored by every template
+const COMMON_IGNORES: &str = "\
+# Editor swap and backup files
+*.swp
+*.swo
+*~
+.#*
+
+# Operating system files
+.DS_Store
+Thumbs.db
+";
+
+impl GitignoreTemplate {
+    pub fn name(self) -> &'static str {
+        match self {
+            GitignoreTemplate::Notes => "notes",
+            GitignoreTemplate::Obsidian => "obsidian",
+            GitignoreTemplate::Logseq => "logseq",
+        }
+    }
+
+    /// Contents of the generated .gitignore
+    pub fn contents(self) -> String {
+        let specific = match self {
+            GitignoreTemplate::Notes => "",
+            GitignoreTemplate::Obsidian => "\
+# Obsidian: per-device layout and caches
+.obsidian/workspace.json
+.obsidian/workspace-mobile.json
+.obsidian/cache
+.trash/
+",
+            GitignoreTemplate::Logseq => "\
+# Logseq: backups, deleted pages and caches
+logseq/bak/
+logseq/.recycle/
+.logseq/
+",
+        };
+
+        match specific {
+            "" => COMMON_IGNORES.to_owned(),
+            specific => format!("{}\n{}", COMMON_IGNORES, specific),
+        }
+    }
+}
+
+/// What `init` should set up besides the repository itself
+#[derive(Debug, Clone)]
+pub struct InitOptions {
+    pub branch: String,
+    pub remote: Option<String>,
+    pub template: Option<GitignoreTemplate>,
+    /// Commit message template for automatic commits
+    pub message: Option<String>,
+}
+
+/// Create a repository with an initial commit, push it and add it to the configuration
+pub async fn init_repository(path: &str, options: InitOptions, format: OutputFormat) -> Result<()> {
+    let requested = expand_home(path)?;
+    std::fs::create_dir_all(&requested)
+        .with_context(|| format!("Failed to create directory: {}", requested.display()))?;
+    let repo_path = requested.canonicalize()
+        .with_context(|| format!("Failed to resolve path: {}", path))?;
+
+    let mut report = create_repository(&repo_path, &options)?;
+    if format.is_plain() {
+        print_created(&report);
+    }
+
+    if let Some(remote) = &options.remote {
+        let pushed = git_push(&repo_path, &["--set-upstream", "origin", &options.branch]).await;
+        if format.is_plain() {
+            match &pushed {
+                Ok(()) => println!("{} Pushed to {} (tracking origin/{})", "✓".green().bold(), remote, options.branch),
+                Err(e) => {
+                    println!("{} Push to {} failed: {}", "⚠".yellow(), remote, e);
+                    println!("   The daemon pushes with the next sync; check the remote with: autogit doctor");
+                }
+            }
+        }
+        report.pushed = pushed.is_ok();
+        report.push_error = pushed.err().map(|e| e.to_string());
+    }
+
+    let applied = change_config(ConfigChange::AddRepo {
+        path: repo_path.clone(),
+        commit_message_template: options.message.clone(),
+    }).await?;
+    report.applied_by = Some(applied);
+
+    if !format.is_plain() {
+        return output::emit(format, &report);
+    }
+    println!("{} Added repository: {}", "✓".green().bold(), repo_path.display());
+    report_applied(applied)
+}
+
+fn print_created(report: &InitReport) {
+    println!(
+        "{} Initialized repository: {} (branch {})",
+        "✓".green().bold(),
+        report.path.display(),
+        report.branch
+    );
+    if let Some(template) = report.gitignore_template {
+        println!("{} Wrote .gitignore ({} template)", "✓".green().bold(), template);
+    }
+    println!(
+        "{} Created initial commit {} ({} files)",
+        "✓".green().bold(),
+        &report.initial_commit[..7],
+        report.files
+    );
+}
+
+/// Everything up to the push: init, .gitignore, initial commit and remote
+fn create_repository(path: &Path, options: &InitOptions) -> Result<InitReport> {
+    if path.join(".git").exists() {
+        bail!("Already a git repository: {}\nAdd it with: autogit add {}", path.display(), path.display());
+    }
+    if !git2::Branch::name_is_valid(&options.branch)? {
+        bail!("Invalid branch name: {}", options.branch);
+    }
+
+    let mut init_options = RepositoryInitOptions::new();
+    init_options.initial_head(&options.branch).no_reinit(true);
+    let repo = GitRepository::init_opts(path, &init_options)
+        .with_context(|| format!("Failed to initialize repository: {}", path.display()))?;
+
+    // Without an identity nothing can be committed: leave the directory as it was
+    let signature = match get_signature(&repo) {
+        Ok(signature) => signature,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(path.join(".git"));
+            return Err(e.context(
+                "Set your identity with: git config --global user.name \"Your Name\" && git config --global user.email you@example.com",
+            ));
+        }
+    };
+
+    let gitignore_path = path.join(".gitignore");
+    let gitignore_template = match options.template {
+        // An existing .gitignore is the user's choice
+        Some(template) if !gitignore_path.exists() => {
+            std::fs::write(&gitignore_path, template.contents())
+                .with_context(|| format!("Failed to write {}", gitignore_path.display()))?;
+            Some(template.name())
+        }
+        _ => None,
+    };
+
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)
+        .context("Failed to stage files")?;
+    index.write()?;
+    let files = index.len();
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let commit = repo.commit(Some("HEAD"), &signature, &signature, INITIAL_COMMIT_MESSAGE, &tree, &[])
+        .context("Failed to create the initial commit")?;
+
+    if let Some(url) = &options.remote {
+        repo.remote("origin", url)
+            .with_context(|| format!("Failed to add remote: {}", url))?;
+
+        // Track the remote branch right away, so the daemon's pulls and pushes work even if the
+        // first push below fails
+        let mut config = repo.config()?;
+        config.set_str(&format!("branch.{}.remote", options.branch), "origin")?;
+        config.set_str(&format!("branch.{}.merge", options.branch), &format!("refs/heads/{}", options.branch))?;
+    }
+
+    Ok(InitReport {
+        path: path.to_path_buf(),
+        branch: options.branch.clone(),
+        remote: options.remote.clone(),
+        gitignore_template,
+        initial_commit: commit.to_string(),
+        files,
+        pushed: false,
+        push_error: None,
+        applied_by: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::Config;
+    use serial_test::serial;
+    use std::env;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// Give libgit2 an identity through a global config in a temporary directory
+    fn set_identity(home: &Path) {
+        fs::write(home.join(".gitconfig"), "[user]\n\tname = Test User\n\temail = test@example.com\n").unwrap();
+        // SAFETY: tests that touch the global config search path are serialized
+        unsafe { git2::opts::set_search_path(git2::ConfigLevel::Global, home).unwrap() };
+    }
+
+    fn options(remote: Option<String>) -> InitOptions {
+        InitOptions { branch: "main".to_owned(), remote, template: Some(GitignoreTemplate::Notes), message: None }
+    }
+
+    #[test]
+    fn test_gitignore_templates() {
+        assert_eq!(GitignoreTemplate::Notes.contents(), COMMON_IGNORES);
+
+        let obsidian = GitignoreTemplate::Obsidian.contents();
+        assert!(obsidian.starts_with(COMMON_IGNORES));
+        assert!(obsidian.contains(".obsidian/workspace.json"));
+        assert!(GitignoreTemplate::Logseq.contents().contains("logseq/bak/"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_repository() {
+        let home = TempDir::new().unwrap();
+        set_identity(home.path());
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("todo.md"), "- [ ] write notes").unwrap();
+        fs::write(dir.path().join("scratch.swp"), "").unwrap();
+
+        let report = create_repository(dir.path(), &options(Some("git@example.com:notes.git".to_owned()))).unwrap();
+        assert_eq!(report.gitignore_template, Some("notes"));
+        // .gitignore and the note; the swap file is ignored
+        assert_eq!(report.files, 2);
+
+        let repo = GitRepository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap();
+        assert_eq!(head.shorthand(), Some("main"));
+        assert_eq!(head.target().unwrap().to_string(), report.initial_commit);
+        assert_eq!(repo.find_remote("origin").unwrap().url(), Some("git@example.com:notes.git"));
+
+        let config = repo.config().unwrap().snapshot().unwrap();
+        assert_eq!(config.get_str("branch.main.remote").unwrap(), "origin");
+        assert_eq!(config.get_str("branch.main.merge").unwrap(), "refs/heads/main");
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_repository_keeps_gitignore() {
+        let home = TempDir::new().unwrap();
+        set_identity(home.path());
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".gitignore"), "private/\n").unwrap();
+
+        let report = create_repository(dir.path(), &options(None)).unwrap();
+        assert_eq!(report.gitignore_template, None);
+        assert_eq!(fs::read_to_string(dir.path().join(".gitignore")).unwrap(), "private/\n");
+        assert!(GitRepository::open(dir.path()).unwrap().find_remote("origin").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_repository_refuses_existing() {
+        let home = TempDir::new().unwrap();
+        set_identity(home.path());
+        let dir = TempDir::new().unwrap();
+        GitRepository::init(dir.path()).unwrap();
+
+        let error = create_repository(dir.path(), &options(None)).unwrap_err();
+        assert!(error.to_string().contains("Already a git repository"));
+
+        let bad_branch = InitOptions { branch: "no..dots".to_owned(), ..options(None) };
+        let other = TempDir::new().unwrap();
+        let error = create_repository(other.path(), &bad_branch).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid branch name: no..dots");
+        assert!(!other.path().join(".git").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_repository_without_identity() {
+        let empty = TempDir::new().unwrap();
+        for level in [git2::ConfigLevel::Global, git2::ConfigLevel::XDG, git2::ConfigLevel::System] {
+            // SAFETY: see set_identity
+            unsafe { git2::opts::set_search_path(level, empty.path()).unwrap() };
+        }
+        let dir = TempDir::new().unwrap();
+
+        let error = create_repository(dir.path(), &options(None)).unwrap_err();
+        assert!(error.to_string().starts_with("Set your identity"));
+        // Nothing is left behind, so init can simply be run again
+        assert!(!dir.path().join(".git").exists());
+        assert!(!dir.path().join(".gitignore").exists());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_init_repository_pushes_and_registers() {
+        let home = TempDir::new().unwrap();
+        set_identity(home.path());
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let remote = TempDir::new().unwrap();
+        GitRepository::init_bare(remote.path()).unwrap();
+        let parent = TempDir::new().unwrap();
+        let vault = parent.path().join("vault");
+
+        let mut init_options = options(Some(remote.path().display().to_string()));
+        init_options.message = Some("Vault: {timestamp}".to_owned());
+        init_repository(vault.to_str().unwrap(), init_options, OutputFormat::Plain).await.unwrap();
+
+        let branches = Command::new("git")
+            .args(["ls-remote", "--heads", remote.path().to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert!(String::from_utf8_lossy(&branches.stdout).contains("refs/heads/main"));
+
+        let config = Config::load_or_create_default().unwrap();
+        assert_eq!(config.repositories.len(), 1);
+        assert_eq!(config.repositories[0].path, vault.canonicalize().unwrap());
+        assert_eq!(config.repositories[0].commit_message_template, "Vault: {timestamp}");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_init_repository_failed_push_still_registers() {
+        let home = TempDir::new().unwrap();
+        set_identity(home.path());
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.git").display().to_string();
+        let vault = dir.path().join("vault");
+        init_repository(vault.to_str().unwrap(), options(Some(missing)), OutputFormat::Json).await.unwrap();
+
+        assert_eq!(Config::load_or_create_default().unwrap().repositories.len(), 1);
+    }
+}
@@ -1,47 +1,116 @@
+mod bundle;
 mod commands;
 mod cli;
+mod doctor;
+mod init;
+mod output;
+mod restore;
+mod scan;
+mod timespec;
+mod top;
+mod undo;
 
 use anyhow::Result;
 use clap::Parser;
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, OutputFormat};
+use std::process::ExitCode;
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    let format = cli.output;
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => ExitCode::from(output::report_error(format, &e)),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let format = cli.output;
 
     match cli.command {
         Commands::Add { path, message, interval } => {
-            commands::add_repository(&path, message, interval)?;
+            commands::add_repository(&path, message, interval, format).await?;
+        }
+        Commands::Init { path, remote, branch, template, message } => {
+            init::init_repository(&path, init::InitOptions { branch, remote, template, message }, format).await?;
+        }
+        Commands::Scan { dir, depth, include, exclude, message, all } => {
+            let filter = autogit_shared::discovery::ScanFilter::new(&include, &exclude)?;
+            scan::scan_directory(&dir, depth, filter, message, all, format).await?;
         }
         Commands::Remove { path } => {
-            commands::remove_repository(&path)?;
+            commands::remove_repository(&path, format).await?;
         }
         Commands::List => {
-            commands::list_repositories()?;
+            commands::list_repositories(format).await?;
         }
         Commands::Enable { path } => {
-            commands::enable_repository(&path)?;
+            commands::enable_repository(&path, format).await?;
         }
         Commands::Disable { path } => {
-            commands::disable_repository(&path)?;
+            commands::disable_repository(&path, format).await?;
         }
         Commands::Interval { seconds } => {
-            commands::set_interval(seconds)?;
+            commands::set_interval(seconds, format).await?;
         }
         Commands::Status => {
-            commands::show_status().await?;
+            commands::show_status(format).await?;
         }
         Commands::Edit => {
-            commands::edit_config()?;
+            commands::edit_config(format).await?;
+        }
+        Commands::Reload => {
+            commands::reload_config(format).await?;
         }
-        Commands::Now => {
-            commands::trigger_now().await?;
+        Commands::Now { repos, no_push, no_pull, dry_run: true } => {
+            commands::preview_now(&repos, !no_push, !no_pull, format).await?;
         }
-        Commands::Suspend => {
-            commands::suspend_daemon().await?;
+        Commands::Now { repos, no_push, no_pull, dry_run: false } => {
+            commands::trigger_now(&repos, !no_push, !no_pull, format).await?;
+        }
+        Commands::Suspend { duration, until } => {
+            commands::suspend_daemon(duration.as_deref(), until.as_deref(), format).await?;
         }
         Commands::Resume => {
-            commands::resume_daemon().await?;
+            commands::resume_daemon(format).await?;
+        }
+        Commands::Log { repo, since, errors, limit } => {
+            commands::show_log(repo.as_deref(), since.as_deref(), errors, limit, format).await?;
+        }
+        Commands::Pause { path, duration, until } => {
+            commands::pause_repository(&path, duration.as_deref(), until.as_deref(), format).await?;
+        }
+        Commands::Unpause { path } => {
+            commands::unpause_repository(&path, format).await?;
+        }
+        Commands::Unquarantine { path, all } => {
+            commands::unquarantine(path.as_deref(), all, format).await?;
+        }
+        Commands::Undo { repo, keep_changes, force_revert } => {
+            undo::undo_last_commit(repo.as_deref(), keep_changes, force_revert, format).await?;
+        }
+        Commands::Versions { file, limit } => {
+            restore::show_versions(&file, limit, format).await?;
+        }
+        Commands::Restore { file, at, commit } => {
+            restore::restore_file(&file, at.as_deref(), commit.as_deref(), format).await?;
+        }
+        Commands::Export { file, portable } => {
+            bundle::export_config(file.as_deref(), portable, format).await?;
+        }
+        Commands::Import { file, dry_run } => {
+            bundle::import_config(&file, dry_run, format).await?;
+        }
+        Commands::Top => {
+            top::run_top(format).await?;
+        }
+        Commands::Watch { json } => {
+            commands::watch_events(if json { OutputFormat::Json } else { format }).await?;
+        }
+        Commands::Doctor => {
+            doctor::run_doctor(format).await?;
         }
     }
 
@@ -195,16 +264,78 @@ mod tests {
         matches!(cli.command, Commands::Edit);
     }
 
+    #[test]
+    fn test_cli_parse_reload() {
+        let cli = Cli::parse_from(["autogit", "reload"]);
+        assert!(matches!(cli.command, Commands::Reload));
+    }
+
     #[test]
     fn test_cli_parse_now() {
         let cli = Cli::parse_from(["autogit", "now"]);
-        matches!(cli.command, Commands::Now);
+        assert!(matches!(cli.command, Commands::Now { ref repos, no_push: false, no_pull: false, dry_run: false } if repos.is_empty()));
+    }
+
+    #[test]
+    fn test_cli_parse_now_targeted() {
+        let cli = Cli::parse_from(["autogit", "now", "~/notes", "~/wiki", "--no-push", "--no-pull"]);
+        match cli.command {
+            Commands::Now { repos, no_push, no_pull, dry_run } => {
+                assert_eq!(repos, vec!["~/notes", "~/wiki"]);
+                assert!(no_push);
+                assert!(no_pull);
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Now command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_now_dry_run() {
+        let cli = Cli::parse_from(["autogit", "now", "--dry-run", "~/notes"]);
+        assert!(matches!(cli.command, Commands::Now { ref repos, dry_run: true, .. } if repos == &["~/notes"]));
     }
 
     #[test]
     fn test_cli_parse_suspend() {
         let cli = Cli::parse_from(["autogit", "suspend"]);
-        matches!(cli.command, Commands::Suspend);
+        assert!(matches!(cli.command, Commands::Suspend { duration: None, until: None }));
+    }
+
+    #[test]
+    fn test_cli_parse_suspend_timed() {
+        let cli = Cli::parse_from(["autogit", "suspend", "--for", "2h"]);
+        match cli.command {
+            Commands::Suspend { duration, until } => {
+                assert_eq!(duration, Some("2h".to_owned()));
+                assert_eq!(until, None);
+            }
+            _ => panic!("Expected Suspend command"),
+        }
+
+        let cli = Cli::parse_from(["autogit", "suspend", "--until", "18:00"]);
+        assert!(matches!(cli.command, Commands::Suspend { duration: None, until: Some(_) }));
+
+        // --for and --until are mutually exclusive
+        assert!(Cli::try_parse_from(["autogit", "suspend", "--for", "2h", "--until", "18:00"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_pause() {
+        let cli = Cli::parse_from(["autogit", "pause", "~/notes", "--for", "30m"]);
+        match cli.command {
+            Commands::Pause { path, duration, until } => {
+                assert_eq!(path, "~/notes");
+                assert_eq!(duration, Some("30m".to_owned()));
+                assert_eq!(until, None);
+            }
+            _ => panic!("Expected Pause command"),
+        }
+
+        let cli = Cli::parse_from(["autogit", "unpause", "~/notes"]);
+        assert!(matches!(cli.command, Commands::Unpause { path } if path == "~/notes"));
+
+        assert!(Cli::try_parse_from(["autogit", "pause"]).is_err());
     }
 
     #[test]
@@ -213,10 +344,217 @@ mod tests {
         matches!(cli.command, Commands::Resume);
     }
 
-    // Integration tests that execute main logic
     #[test]
+    fn test_cli_parse_log_defaults() {
+        let cli = Cli::parse_from(["autogit", "log"]);
+        match cli.command {
+            Commands::Log { repo, since, errors, limit } => {
+                assert_eq!(repo, None);
+                assert_eq!(since, None);
+                assert!(!errors);
+                assert_eq!(limit, 25);
+            }
+            _ => panic!("Expected Log command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_log_with_options() {
+        let cli = Cli::parse_from([
+            "autogit", "log", "~/notes", "--since", "2h", "--errors", "-n", "5",
+        ]);
+        match cli.command {
+            Commands::Log { repo, since, errors, limit } => {
+                assert_eq!(repo, Some("~/notes".to_owned()));
+                assert_eq!(since, Some("2h".to_owned()));
+                assert!(errors);
+                assert_eq!(limit, 5);
+            }
+            _ => panic!("Expected Log command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_unquarantine() {
+        let cli = Cli::parse_from(["autogit", "unquarantine", "/tmp/repo"]);
+        match cli.command {
+            Commands::Unquarantine { path, all } => {
+                assert_eq!(path, Some("/tmp/repo".to_owned()));
+                assert!(!all);
+            }
+            _ => panic!("Expected Unquarantine command"),
+        }
+
+        let cli = Cli::parse_from(["autogit", "unquarantine", "--all"]);
+        assert!(matches!(cli.command, Commands::Unquarantine { path: None, all: true }));
+    }
+
+    #[test]
+    fn test_cli_parse_unquarantine_requires_target() {
+        assert!(Cli::try_parse_from(["autogit", "unquarantine"]).is_err());
+        assert!(Cli::try_parse_from(["autogit", "unquarantine", "/tmp/repo", "--all"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_watch() {
+        let cli = Cli::parse_from(["autogit", "watch"]);
+        assert!(matches!(cli.command, Commands::Watch { json: false }));
+
+        let cli = Cli::parse_from(["autogit", "watch", "--json"]);
+        assert!(matches!(cli.command, Commands::Watch { json: true }));
+    }
+
+    #[test]
+    fn test_cli_parse_top() {
+        let cli = Cli::parse_from(["autogit", "top"]);
+        assert!(matches!(cli.command, Commands::Top));
+    }
+
+    #[test]
+    fn test_cli_parse_export_import() {
+        let cli = Cli::parse_from(["autogit", "export"]);
+        assert!(matches!(cli.command, Commands::Export { file: None, portable: false }));
+
+        let cli = Cli::parse_from(["autogit", "export", "--portable", "autogit.toml"]);
+        assert!(matches!(cli.command, Commands::Export { file: Some(ref file), portable: true } if file == "autogit.toml"));
+
+        let cli = Cli::parse_from(["autogit", "import", "-", "--dry-run"]);
+        assert!(matches!(cli.command, Commands::Import { ref file, dry_run: true } if file == "-"));
+        assert!(Cli::try_parse_from(["autogit", "import"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_init() {
+        let cli = Cli::parse_from(["autogit", "init", "~/vault"]);
+        match cli.command {
+            Commands::Init { path, remote, branch, template, message } => {
+                assert_eq!(path, "~/vault");
+                assert_eq!((remote, template, message), (None, None, None));
+                assert_eq!(branch, "main");
+            }
+            _ => panic!("Expected Init command"),
+        }
+
+        let cli = Cli::parse_from([
+            "autogit", "init", "~/vault", "--remote", "git@example.com:vault.git", "-b", "trunk", "--template", "obsidian",
+        ]);
+        match cli.command {
+            Commands::Init { remote, branch, template, .. } => {
+                assert_eq!(remote.as_deref(), Some("git@example.com:vault.git"));
+                assert_eq!(branch, "trunk");
+                assert_eq!(template, Some(cli::GitignoreTemplate::Obsidian));
+            }
+            _ => panic!("Expected Init command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_scan() {
+        let cli = Cli::parse_from(["autogit", "scan", "~/code"]);
+        match cli.command {
+            Commands::Scan { dir, depth, include, exclude, message, all } => {
+                assert_eq!(dir, "~/code");
+                assert_eq!(depth, 3);
+                assert!(include.is_empty() && exclude.is_empty());
+                assert_eq!(message, None);
+                assert!(!all);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+
+        let cli = Cli::parse_from([
+            "autogit", "scan", "~/code", "-d", "1", "--include", "work/*", "--exclude", "archive", "--exclude", "tmp", "--all",
+        ]);
+        match cli.command {
+            Commands::Scan { depth, include, exclude, all, .. } => {
+                assert_eq!(depth, 1);
+                assert_eq!(include, ["work/*"]);
+                assert_eq!(exclude, ["archive", "tmp"]);
+                assert!(all);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_undo() {
+        let cli = Cli::parse_from(["autogit", "undo"]);
+        match cli.command {
+            Commands::Undo { repo, keep_changes, force_revert } => {
+                assert_eq!(repo, None);
+                assert!(!keep_changes && !force_revert);
+            }
+            _ => panic!("Expected Undo command"),
+        }
+
+        let cli = Cli::parse_from(["autogit", "undo", "~/notes", "--keep-changes"]);
+        match cli.command {
+            Commands::Undo { repo, keep_changes, .. } => {
+                assert_eq!(repo.as_deref(), Some("~/notes"));
+                assert!(keep_changes);
+            }
+            _ => panic!("Expected Undo command"),
+        }
+
+        // Reverting commits the changes away, so they can't also be kept
+        assert!(Cli::try_parse_from(["autogit", "undo", "--keep-changes", "--force-revert"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_restore() {
+        let cli = Cli::parse_from(["autogit", "restore", "todo.md", "--at", "2 hours ago"]);
+        match cli.command {
+            Commands::Restore { file, at, commit } => {
+                assert_eq!(file, "todo.md");
+                assert_eq!(at.as_deref(), Some("2 hours ago"));
+                assert_eq!(commit, None);
+            }
+            _ => panic!("Expected Restore command"),
+        }
+
+        // Exactly one of --at and --commit
+        assert!(Cli::try_parse_from(["autogit", "restore", "todo.md"]).is_err());
+        assert!(Cli::try_parse_from(["autogit", "restore", "todo.md", "--at", "1h", "--commit", "abc"]).is_err());
+
+        let cli = Cli::parse_from(["autogit", "versions", "todo.md", "-n", "5"]);
+        assert!(matches!(cli.command, Commands::Versions { limit: 5, .. }));
+    }
+
+    #[test]
+    fn test_cli_parse_output_format() {
+        let cli = Cli::parse_from(["autogit", "list"]);
+        assert_eq!(cli.output, OutputFormat::Plain);
+
+        // Global, so it may follow the subcommand
+        let cli = Cli::parse_from(["autogit", "status", "--output", "json"]);
+        assert_eq!(cli.output, OutputFormat::Json);
+
+        let cli = Cli::parse_from(["autogit", "-o", "yaml", "now"]);
+        assert_eq!(cli.output, OutputFormat::Yaml);
+
+        assert!(Cli::try_parse_from(["autogit", "--output", "xml", "list"]).is_err());
+    }
+
+    #[tokio::test]
     #[serial]
-    fn test_main_add_command() {
+    async fn test_run_reports_missing_repository() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let cli = Cli::parse_from(["autogit", "--output", "json", "remove", "/nonexistent/repo"]);
+        let error = run(cli).await.unwrap_err();
+        assert_eq!(output::exit_code(&error), output::EXIT_REPO_NOT_FOUND);
+
+        // Needs the daemon, which isn't running
+        let error = run(Cli::parse_from(["autogit", "resume"])).await.unwrap_err();
+        assert_eq!(output::exit_code(&error), output::EXIT_DAEMON_NOT_RUNNING);
+    }
+
+    // Integration tests that execute main logic
+    #[tokio::test]
+    #[serial]
+    async fn test_main_add_command() {
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
 
@@ -230,39 +568,39 @@ mod tests {
             .unwrap();
 
         // Test add command through CLI parsing
-        let result = commands::add_repository(&repo_dir.to_string_lossy(), None, None);
+        let result = commands::add_repository(&repo_dir.to_string_lossy(), None, None, OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_main_list_command() {
+    async fn test_main_list_command() {
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
 
         // Should work even with no config
-        let result = commands::list_repositories();
+        let result = commands::list_repositories(OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_main_interval_command() {
+    async fn test_main_interval_command() {
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
 
         // Test showing interval
-        let result = commands::set_interval(None);
+        let result = commands::set_interval(None, OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         // Test setting interval
-        let result = commands::set_interval(Some(600));
+        let result = commands::set_interval(Some(600), OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_main_enable_disable_commands() {
+    async fn test_main_enable_disable_commands() {
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
 
@@ -276,20 +614,20 @@ mod tests {
             .unwrap();
 
         // Add it first
-        commands::add_repository(&repo_dir.to_string_lossy(), None, None).unwrap();
+        commands::add_repository(&repo_dir.to_string_lossy(), None, None, OutputFormat::Plain).await.unwrap();
 
         // Test disable
-        let result = commands::disable_repository(&repo_dir.to_string_lossy());
+        let result = commands::disable_repository(&repo_dir.to_string_lossy(), OutputFormat::Plain).await;
         assert!(result.is_ok());
 
         // Test enable
-        let result = commands::enable_repository(&repo_dir.to_string_lossy());
+        let result = commands::enable_repository(&repo_dir.to_string_lossy(), OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 
-    #[test]
+    #[tokio::test]
     #[serial]
-    fn test_main_remove_command() {
+    async fn test_main_remove_command() {
         let temp_dir = TempDir::new().unwrap();
         env::set_var("XDG_CONFIG_HOME", temp_dir.path());
 
@@ -303,10 +641,10 @@ mod tests {
             .unwrap();
 
         // Add it first
-        commands::add_repository(&repo_dir.to_string_lossy(), None, None).unwrap();
+        commands::add_repository(&repo_dir.to_string_lossy(), None, None, OutputFormat::Plain).await.unwrap();
 
         // Test remove
-        let result = commands::remove_repository(&repo_dir.to_string_lossy());
+        let result = commands::remove_repository(&repo_dir.to_string_lossy(), OutputFormat::Plain).await;
         assert!(result.is_ok());
     }
 }
@@ -0,0 +1,544 @@
+//! Machine-readable output (`--output json|yaml`) and exit codes
+//!
+//! The structures here are the documented output format: fields may be added, but existing ones keep
+//! their names and meaning.
+
+use crate::bundle::Bundle;
+use crate::cli::OutputFormat;
+use anyhow::Result;
+use autogit_client::ClientError;
+use autogit_shared::{JournalEntry, PausedRepo, RepoDetail, RepoNotFound, RepoStatus, Repository, ScheduleState, SyncPreview};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Something went wrong that has no more specific exit code
+pub const EXIT_ERROR: u8 = 1;
+/// The command needs the daemon, and it isn't running
+pub const EXIT_DAEMON_NOT_RUNNING: u8 = 3;
+/// A repository path doesn't exist or isn't configured
+pub const EXIT_REPO_NOT_FOUND: u8 = 4;
+/// A sync ran, but some repositories failed
+pub const EXIT_PARTIAL_FAILURE: u8 = 5;
+
+impl OutputFormat {
+    pub fn is_plain(self) -> bool {
+        self == OutputFormat::Plain
+    }
+}
+
+/// Print a report in the selected machine-readable format (plain output is up to each command)
+pub fn emit<T: Serialize>(format: OutputFormat, report: &T) -> Result<()> {
+    match format {
+        OutputFormat::Plain => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Yaml => print!("{}", serde_norway::to_string(report)?),
+    }
+    Ok(())
+}
+
+/// Where a configuration change was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Applied {
+    /// Saved and applied by the running daemon
+    #[serde(rename = "daemon")]
+    ByDaemon,
+    /// Written to the config file, because no daemon (that knows the command) is running
+    #[serde(rename = "file")]
+    InFile,
+}
+
+/// Result of a command that changes something
+#[derive(Debug, Serialize)]
+pub struct Outcome {
+    pub message: String,
+    /// For configuration changes: whether the daemon applied it or only the file was changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<Applied>,
+}
+
+impl Outcome {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), applied_by: None }
+    }
+}
+
+/// Output of `list`
+#[derive(Debug, Serialize)]
+pub struct RepositoryList {
+    pub check_interval_seconds: u64,
+    pub repositories: Vec<ListedRepository>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedRepository {
+    #[serde(flatten)]
+    pub repository: Repository,
+    /// Found under a watch root rather than configured individually
+    pub discovered: bool,
+    pub state: RepoState,
+}
+
+/// Whether a repository is synced, as far as the config and the daemon know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoState {
+    Enabled,
+    Disabled,
+    Paused,
+    Quarantined,
+}
+
+/// Output of `interval`
+#[derive(Debug, Serialize)]
+pub struct IntervalReport {
+    pub check_interval_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<Applied>,
+}
+
+/// Output of `status`
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub config_file: PathBuf,
+    pub check_interval_seconds: u64,
+    /// Runtime state, absent if the daemon isn't running
+    pub daemon: Option<DaemonReport>,
+    pub repositories: Vec<RepoStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DaemonReport {
+    /// Absent for daemons predating the handshake
+    pub version: Option<String>,
+    pub protocol_version: Option<u32>,
+    pub suspended: bool,
+    pub suspended_until: Option<DateTime<Utc>>,
+    pub next_check: Option<DateTime<Utc>>,
+    pub schedule: Option<ScheduleState>,
+    pub quarantined: Vec<PathBuf>,
+    pub paused: Vec<PausedRepo>,
+}
+
+/// Output of `now`
+#[derive(Debug, Serialize)]
+pub struct TriggerReport {
+    pub message: String,
+    pub repos_checked: usize,
+    pub repos_committed: usize,
+    pub repos_failed: usize,
+    pub details: Vec<RepoDetail>,
+}
+
+/// Output of `now --dry-run`
+#[derive(Debug, Serialize)]
+pub struct PreviewReport {
+    pub message: String,
+    pub previews: Vec<SyncPreview>,
+}
+
+/// Output of `log`
+#[derive(Debug, Serialize)]
+pub struct HistoryReport {
+    pub entries: Vec<JournalEntry>,
+}
+
+/// Output of `init`
+#[derive(Debug, Serialize)]
+pub struct InitReport {
+    pub path: PathBuf,
+    pub branch: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// Template the .gitignore was generated from (absent if none was written)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitignore_template: Option<&'static str>,
+    pub initial_commit: String,
+    /// Number of files in the initial commit
+    pub files: usize,
+    pub pushed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<Applied>,
+}
+
+/// Output of `versions`
+#[derive(Debug, Serialize)]
+pub struct VersionsReport {
+    pub path: PathBuf,
+    pub repository: PathBuf,
+    pub versions: Vec<FileVersion>,
+}
+
+/// A commit that changed a file
+#[derive(Debug, Serialize)]
+pub struct FileVersion {
+    pub commit: String,
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    /// The commit deleted the file
+    pub deleted: bool,
+    /// Made by autogit, according to the sync history
+    pub automatic: bool,
+}
+
+/// Output of `restore`
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub path: PathBuf,
+    pub repository: PathBuf,
+    /// Commit the file was restored from
+    pub source_commit: String,
+    pub source_time: DateTime<Utc>,
+    /// Commit of the restore (absent if the file already matched)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub pushed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_error: Option<String>,
+}
+
+/// Output of `undo`
+#[derive(Debug, Serialize)]
+pub struct UndoReport {
+    pub path: PathBuf,
+    /// The automatic commit that was undone
+    pub commit: String,
+    pub summary: String,
+    pub action: UndoAction,
+    /// The commit reverting a pushed commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_commit: Option<String>,
+    /// Whether the revert was pushed
+    pub pushed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_error: Option<String>,
+    /// Whether the daemon keeps the repository paused, so kept changes aren't committed again
+    pub paused: bool,
+}
+
+/// How a commit was undone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoAction {
+    /// HEAD moved back and the commit's changes were discarded
+    Reset,
+    /// HEAD moved back, the commit's changes are uncommitted in the working tree
+    KeptChanges,
+    /// A revert commit was added on top
+    Reverted,
+}
+
+/// Output of `scan`
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    pub directory: PathBuf,
+    /// Repositories found that weren't configured yet
+    pub found: Vec<PathBuf>,
+    pub already_configured: Vec<PathBuf>,
+    pub added: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<Applied>,
+}
+
+/// Output of `export`
+#[derive(Debug, Serialize)]
+pub struct ExportReport {
+    /// File the bundle was written to (absent if it's included here instead)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<Bundle>,
+    pub repositories: usize,
+    pub watch_roots: usize,
+    /// Repositories exported without a remote, which import can't clone
+    pub without_remote: Vec<PathBuf>,
+}
+
+/// Output of `import`
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub file: PathBuf,
+    /// Nothing was cloned or configured, outcomes say what would have happened
+    pub dry_run: bool,
+    pub repositories: Vec<ImportedEntry>,
+    pub watch_roots: Vec<ImportedEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<Applied>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedEntry {
+    /// Path on this machine
+    pub path: PathBuf,
+    pub outcome: ImportOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// What `import` did with one entry of the bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    /// Existing repository or watch root added to the configuration
+    Added,
+    /// Cloned from its remote, then added
+    Cloned,
+    AlreadyConfigured,
+    /// Already configured at another path, with the same remote
+    Mapped,
+    Skipped,
+    Failed,
+}
+
+/// Output of `doctor`
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub passed: usize,
+    pub warnings: usize,
+    pub failed: usize,
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    pub fn new(checks: Vec<Check>) -> Self {
+        let count = |status| checks.iter().filter(|check| check.status == status).count();
+        Self {
+            passed: count(CheckStatus::Pass),
+            warnings: count(CheckStatus::Warn),
+            failed: count(CheckStatus::Fail),
+            checks,
+        }
+    }
+}
+
+/// One diagnostic of `doctor`
+#[derive(Debug, Serialize)]
+pub struct Check {
+    /// The repository checked, absent for checks of the daemon and the session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<PathBuf>,
+    /// Stable identifier of the check, e.g. `daemon` or `upstream`
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    /// How to fix a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A sync ran, but not every repository made it
+#[derive(Debug, thiserror::Error)]
+#[error("{failed} of {checked} repositories failed to sync")]
+pub struct PartialFailure {
+    pub failed: usize,
+    pub checked: usize,
+}
+
+/// `import` couldn't clone or add some entries
+#[derive(Debug, thiserror::Error)]
+#[error("{failed} of {total} entries failed to import")]
+pub struct ImportFailed {
+    pub failed: usize,
+    pub total: usize,
+}
+
+/// `doctor` found problems that keep autogit from working
+#[derive(Debug, thiserror::Error)]
+#[error("{0} checks failed")]
+pub struct ChecksFailed(pub usize);
+
+/// A path given on the command line doesn't exist
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to resolve path: {0}")]
+pub struct PathNotFound(pub String);
+
+/// The daemon answered a command with an error
+#[derive(Debug, thiserror::Error)]
+#[error("Daemon returned error: {0}")]
+pub struct DaemonRefused(pub ClientError);
+
+/// Printed instead of a report when a command fails
+#[derive(Debug, Serialize)]
+struct ErrorReport {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorDetail {
+    kind: &'static str,
+    exit_code: u8,
+    message: String,
+}
+
+/// Exit code for a failed command
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    if client_error(error, ClientError::is_not_running) {
+        return EXIT_DAEMON_NOT_RUNNING;
+    }
+    if caused_by::<RepoNotFound>(error) || caused_by::<PathNotFound>(error) || client_error(error, ClientError::is_repo_not_found) {
+        return EXIT_REPO_NOT_FOUND;
+    }
+    if caused_by::<PartialFailure>(error) || caused_by::<ImportFailed>(error) {
+        return EXIT_PARTIAL_FAILURE;
+    }
+
+    EXIT_ERROR
+}
+
+/// Whether the error or anything it wraps is an `E`
+///
+/// `chain` yields errors attached with `with_context` wrapped, so `is` checks the outer one.
+fn caused_by<E: std::error::Error + Send + Sync + 'static>(error: &anyhow::Error) -> bool {
+    error.is::<E>() || error.chain().any(|cause| cause.is::<E>())
+}
+
+/// Whether a daemon client error in the chain matches `test`
+fn client_error(error: &anyhow::Error, test: fn(&ClientError) -> bool) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<ClientError>().or_else(|| cause.downcast_ref::<DaemonRefused>().map(|refused| &refused.0)).is_some_and(test)
+    })
+}
+
+fn error_kind(exit_code: u8) -> &'static str {
+    match exit_code {
+        EXIT_DAEMON_NOT_RUNNING => "daemon_not_running",
+        EXIT_REPO_NOT_FOUND => "repository_not_found",
+        EXIT_PARTIAL_FAILURE => "partial_failure",
+        _ => "error",
+    }
+}
+
+/// Report a failed command and return its exit code
+pub fn report_error(format: OutputFormat, error: &anyhow::Error) -> u8 {
+    let exit_code = exit_code(error);
+
+    // The report already lists the failed repositories or checks
+    if !format.is_plain()
+        && (caused_by::<PartialFailure>(error) || caused_by::<ImportFailed>(error) || caused_by::<ChecksFailed>(error))
+    {
+        return exit_code;
+    }
+
+    let report = ErrorReport {
+        error: ErrorDetail { kind: error_kind(exit_code), exit_code, message: format!("{:#}", error) },
+    };
+    if format.is_plain() || emit(format, &report).is_err() {
+        eprintln!("Error: {:?}", error);
+    }
+
+    exit_code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, anyhow};
+    use autogit_shared::ErrorKind;
+
+    #[test]
+    fn test_exit_codes() {
+        let not_running = anyhow::Error::new(ClientError::NotRunning {
+            path: PathBuf::from("/run/user/1000/autogit/daemon.sock"),
+            source: std::io::ErrorKind::NotFound.into(),
+        });
+        assert_eq!(exit_code(&not_running), EXIT_DAEMON_NOT_RUNNING);
+        assert_eq!(exit_code(&not_running.context("Failed to trigger")), EXIT_DAEMON_NOT_RUNNING);
+
+        let not_configured = anyhow::Error::new(RepoNotFound(PathBuf::from("/repo")));
+        assert_eq!(exit_code(&not_configured), EXIT_REPO_NOT_FOUND);
+
+        let missing: Result<()> = Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .with_context(|| PathNotFound("~/nowhere".to_owned()));
+        assert_eq!(exit_code(&missing.unwrap_err()), EXIT_REPO_NOT_FOUND);
+
+        let wrapped = anyhow::Error::new(RepoNotFound(PathBuf::from("/repo"))).context("Failed to pause");
+        assert_eq!(exit_code(&wrapped), EXIT_REPO_NOT_FOUND);
+
+        let from_daemon = anyhow::Error::new(DaemonRefused(ClientError::Daemon {
+            message: "Repository not found in configuration: /repo".to_owned(),
+            data: None,
+            kind: Some(ErrorKind::RepoNotFound),
+        }));
+        assert_eq!(from_daemon.to_string(), "Daemon returned error: Repository not found in configuration: /repo");
+        assert_eq!(exit_code(&from_daemon), EXIT_REPO_NOT_FOUND);
+        assert_eq!(exit_code(&from_daemon.context("Failed to trigger")), EXIT_REPO_NOT_FOUND);
+
+        // Only the kind counts, not the wording
+        let refused = ClientError::Daemon { message: "Repository not found in configuration: /repo".to_owned(), data: None, kind: None };
+        assert_eq!(exit_code(&DaemonRefused(refused).into()), EXIT_ERROR);
+
+        assert_eq!(exit_code(&PartialFailure { failed: 1, checked: 3 }.into()), EXIT_PARTIAL_FAILURE);
+        assert_eq!(exit_code(&anyhow::Error::new(PartialFailure { failed: 1, checked: 3 }).context("Sync")), EXIT_PARTIAL_FAILURE);
+        assert_eq!(exit_code(&ImportFailed { failed: 1, total: 4 }.into()), EXIT_PARTIAL_FAILURE);
+        assert_eq!(exit_code(&anyhow!("Check interval must be at least 1 second")), EXIT_ERROR);
+    }
+
+    #[test]
+    fn test_error_report_shape() {
+        let report = ErrorReport {
+            error: ErrorDetail { kind: error_kind(EXIT_REPO_NOT_FOUND), exit_code: EXIT_REPO_NOT_FOUND, message: "gone".to_owned() },
+        };
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["error"]["kind"], "repository_not_found");
+        assert_eq!(json["error"]["exit_code"], 4);
+        assert_eq!(json["error"]["message"], "gone");
+    }
+
+    #[test]
+    fn test_outcome_shape() {
+        let outcome = Outcome { message: "Added repository: /repo".to_owned(), applied_by: Some(Applied::ByDaemon) };
+        assert_eq!(
+            serde_json::to_string(&outcome).unwrap(),
+            r#"{"message":"Added repository: /repo","applied_by":"daemon"}"#
+        );
+        assert_eq!(serde_json::to_string(&Outcome::new("Resumed")).unwrap(), r#"{"message":"Resumed"}"#);
+
+        let yaml = serde_norway::to_string(&Outcome { applied_by: Some(Applied::InFile), ..Outcome::new("Removed") }).unwrap();
+        assert_eq!(yaml, "message: Removed\napplied_by: file\n");
+    }
+
+    #[test]
+    fn test_doctor_report_counts() {
+        let check = |status| Check { repository: None, name: "daemon", status, message: String::new(), hint: None };
+        let report = DoctorReport::new(vec![check(CheckStatus::Pass), check(CheckStatus::Fail), check(CheckStatus::Pass)]);
+        assert_eq!((report.passed, report.warnings, report.failed), (2, 0, 1));
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"][1]["status"], "fail");
+        assert!(json["checks"][1].get("repository").is_none());
+        assert_eq!(exit_code(&ChecksFailed(1).into()), EXIT_ERROR);
+    }
+
+    #[test]
+    fn test_repository_list_shape() {
+        let list = RepositoryList {
+            check_interval_seconds: 300,
+            repositories: vec![ListedRepository {
+                repository: Repository {
+                    path: PathBuf::from("/repo"),
+                    auto_commit: true,
+                    commit_message_template: "Auto: {timestamp}".to_owned(),
+                },
+                discovered: true,
+                state: RepoState::Paused,
+            }],
+        };
+
+        let json: serde_json::Value = serde_json::to_value(&list).unwrap();
+        assert_eq!(json["check_interval_seconds"], 300);
+        assert_eq!(json["repositories"][0]["path"], "/repo");
+        assert_eq!(json["repositories"][0]["auto_commit"], true);
+        assert_eq!(json["repositories"][0]["discovered"], true);
+        assert_eq!(json["repositories"][0]["state"], "paused");
+    }
+}
@@ -0,0 +1,574 @@
+//! `autogit versions` and `autogit restore`: use the commit history of a file
+
+use crate::cli::OutputFormat;
+use crate::commands::{expand_home, git_push, query_history, record_history};
+use crate::output::{self, FileVersion, PathNotFound, RestoreReport, VersionsReport};
+use anyhow::{Context, Result, bail};
+use autogit_shared::git::{get_signature, open_repository};
+use autogit_shared::{Config, JournalEntry, JournalFilter, RemoteResult};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use colored::Colorize;
+use git2::build::TreeUpdateBuilder;
+use git2::{Commit, FileMode, ObjectType, Oid, Repository as GitRepository, Sort};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled, settings::Style};
+
+/// Which version of a file to restore
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreTarget {
+    /// The version committed last before this time
+    At(DateTime<Utc>),
+    /// The version in a commit (anything git accepts as a revision)
+    Commit(String),
+}
+
+/// A file inside a configured repository
+struct TrackedFile {
+    repository: PathBuf,
+    /// Path relative to the repository
+    relative: PathBuf,
+}
+
+impl TrackedFile {
+    fn path(&self) -> PathBuf {
+        self.repository.join(&self.relative)
+    }
+}
+
+/// Find the configured repository a file belongs to (the file itself may be deleted)
+fn locate(file: &str) -> Result<TrackedFile> {
+    let path = expand_home(file)?;
+    let path = if path.is_absolute() { path } else { std::env::current_dir()?.join(path) };
+
+    let name = path.file_name().with_context(|| format!("Not a file: {}", file))?;
+    let parent = path.parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .with_context(|| PathNotFound(file.to_owned()))?;
+    let path = parent.join(name);
+
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
+    let repository = config.all_repositories()
+        .map(|repo| &repo.path)
+        .filter(|repo| path.starts_with(repo))
+        .max_by_key(|repo| repo.components().count())
+        .with_context(|| format!("{} is not inside a configured repository", path.display()))?;
+
+    Ok(TrackedFile { relative: path.strip_prefix(repository)?.to_path_buf(), repository: repository.clone() })
+}
+
+/// Commit ids the sync history records for a repository
+async fn automatic_commits(repository: &Path) -> Result<Vec<String>> {
+    let filter = JournalFilter { repo: Some(repository.to_path_buf()), ..Default::default() };
+    Ok(query_history(filter).await?.into_iter().filter_map(|entry| entry.commit).collect())
+}
+
+/// List the commits that changed a file
+pub async fn show_versions(file: &str, limit: usize, format: OutputFormat) -> Result<()> {
+    let tracked = locate(file)?;
+    let repo = open_repository(&tracked.repository)?;
+    let automatic = automatic_commits(&tracked.repository).await?;
+
+    let versions = file_versions(&repo, &tracked.relative, (limit > 0).then_some(limit))?
+        .into_iter()
+        .map(|version| FileVersion { automatic: automatic.contains(&version.commit), ..version })
+        .collect();
+    let report = VersionsReport { path: tracked.path(), repository: tracked.repository, versions };
+
+    if !format.is_plain() {
+        return output::emit(format, &report);
+    }
+
+    if report.versions.is_empty() {
+        println!("{}", format!("No committed versions of {}", report.path.display()).yellow());
+        return Ok(());
+    }
+
+    #[derive(Tabled)]
+    struct VersionRow {
+        #[tabled(rename = "Commit")]
+        commit: String,
+        #[tabled(rename = "Time")]
+        time: String,
+        #[tabled(rename = "By")]
+        by: &'static str,
+        #[tabled(rename = "Summary")]
+        summary: String,
+    }
+
+    let rows: Vec<VersionRow> = report.versions.iter().map(|version| VersionRow {
+        commit: version.commit.chars().take(8).collect(),
+        time: version.time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+        by: if version.automatic { "autogit" } else { "manual" },
+        summary: if version.deleted {
+            format!("{} (deleted the file)", version.summary)
+        } else {
+            version.summary.clone()
+        },
+    }).collect();
+
+    println!("{}", Table::new(rows).with(Style::rounded()));
+    println!("Restore one with: {}", format!("autogit restore {} --commit <commit>", file).cyan());
+    Ok(())
+}
+
+/// The commits reachable from HEAD that changed a file, newest first
+fn file_versions(repo: &GitRepository, relative: &Path, limit: Option<usize>) -> Result<Vec<FileVersion>> {
+    let mut walk = repo.revwalk()?;
+    walk.push_head().context("The repository has no commits")?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut versions = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let blob = blob_id(&commit, relative)?;
+
+        // A merge only counts if the file differs from every side
+        let changed = if commit.parent_count() == 0 {
+            blob.is_some()
+        } else {
+            commit.parents().try_fold(true, |changed, parent| -> Result<bool> {
+                Ok(changed && blob_id(&parent, relative)? != blob)
+            })?
+        };
+
+        if changed {
+            versions.push(FileVersion {
+                commit: commit.id().to_string(),
+                time: commit_time(&commit),
+                summary: commit.summary().unwrap_or_default().to_owned(),
+                deleted: blob.is_none(),
+                automatic: false,
+            });
+            if limit.is_some_and(|limit| versions.len() >= limit) {
+                break;
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+/// The file's blob in a commit (None if it doesn't exist there)
+fn blob_id(commit: &Commit, relative: &Path) -> Result<Option<Oid>> {
+    match commit.tree()?.get_path(relative) {
+        Ok(entry) if entry.kind() == Some(ObjectType::Blob) => Ok(Some(entry.id())),
+        Ok(_) => Ok(None),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn commit_time(commit: &Commit) -> DateTime<Utc> {
+    Utc.timestamp_opt(commit.time().seconds(), 0).single().unwrap_or_default()
+}
+
+/// Restore a file from its history, commit the restore and push it
+pub async fn restore_file(file: &str, at: Option<&str>, commit: Option<&str>, format: OutputFormat) -> Result<()> {
+    let target = match (at, commit) {
+        (Some(at), None) => RestoreTarget::At(crate::timespec::parse_since(at)?),
+        (None, Some(commit)) => RestoreTarget::Commit(commit.to_owned()),
+        _ => bail!("Specify either --at or --commit"),
+    };
+    let tracked = locate(file)?;
+
+    let mut report = restore_version(&tracked, &target)?;
+
+    if let Some(commit) = &report.commit {
+        // The daemon only pushes what it committed itself
+        let repo = open_repository(&tracked.repository)?;
+        let push = if has_upstream(&repo) {
+            match git_push(&tracked.repository, &[]).await {
+                Ok(()) => RemoteResult::Done,
+                Err(e) => RemoteResult::Failed(e.to_string()),
+            }
+        } else {
+            RemoteResult::Skipped
+        };
+        report.pushed = push == RemoteResult::Done;
+        if let RemoteResult::Failed(error) = &push {
+            report.push_error = Some(error.clone());
+        }
+
+        // Recorded like the daemon's commits, so it shows up in the log and can be undone
+        record_history(JournalEntry {
+            timestamp: Utc::now(),
+            repo: tracked.repository.clone(),
+            commit: Some(commit.clone()),
+            files_changed: 1,
+            push,
+            pull: RemoteResult::Skipped,
+            error: None,
+        }).await?;
+    }
+
+    if !format.is_plain() {
+        return output::emit(format, &report);
+    }
+    print_restored(&report);
+    Ok(())
+}
+
+fn print_restored(report: &RestoreReport) {
+    let source = format!(
+        "{} ({})",
+        &report.source_commit[..7],
+        report.source_time.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    );
+    let Some(commit) = &report.commit else {
+        println!("{} {} already matches {}; nothing to restore", "→".blue(), report.path.display(), source);
+        return;
+    };
+
+    println!("{} Restored {} from {}", "✓".green().bold(), report.path.display(), source);
+    println!("{} Committed the restore as {}", "✓".green().bold(), &commit[..7]);
+    match &report.push_error {
+        None if report.pushed => println!("{} Pushed the restore", "✓".green().bold()),
+        None => {}
+        Some(error) => {
+            println!("{} Push failed: {}", "⚠".yellow(), error);
+            println!("   Push it with: {}", format!("git -C {} push", report.repository.display()).cyan());
+        }
+    }
+}
+
+/// Write the chosen version into the working tree and commit it
+fn restore_version(tracked: &TrackedFile, target: &RestoreTarget) -> Result<RestoreReport> {
+    let repo = open_repository(&tracked.repository)?;
+    let relative = &tracked.relative;
+    let path = tracked.path();
+
+    let source = match target {
+        RestoreTarget::Commit(spec) => repo.revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Unknown commit: {}", spec))?,
+        RestoreTarget::At(time) => commit_at(&repo, *time)?,
+    };
+    let source_commit = source.id().to_string();
+    let source_time = commit_time(&source);
+
+    let entry = match source.tree()?.get_path(relative) {
+        Ok(entry) if entry.kind() == Some(ObjectType::Blob) => entry,
+        _ => bail!("{} didn't exist in commit {} ({})", relative.display(), &source_commit[..7], source.summary().unwrap_or_default()),
+    };
+
+    // Restoring must not overwrite work that isn't committed yet
+    match repo.status_file(relative) {
+        Ok(status) if !status.is_empty() => bail!(
+            "{} has uncommitted changes; commit them first, e.g. with: autogit now {}",
+            path.display(),
+            tracked.repository.display()
+        ),
+        Ok(_) => {}
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+        Err(e) => return Err(e).context("Failed to read the file's status"),
+    }
+
+    let blob = repo.find_blob(entry.id())?;
+    let mut report = RestoreReport {
+        path: path.clone(),
+        repository: tracked.repository.clone(),
+        source_commit,
+        source_time,
+        commit: None,
+        pushed: false,
+        push_error: None,
+    };
+    // A symlink's blob holds its target
+    let is_link = entry.filemode() == 0o120000;
+    let current = std::fs::symlink_metadata(&path).ok();
+    let unchanged = match current.as_ref().map(|metadata| metadata.file_type().is_symlink()) {
+        Some(true) => is_link && std::fs::read_link(&path).is_ok_and(|target| target.as_os_str().as_bytes() == blob.content()),
+        Some(false) => !is_link && std::fs::read(&path).is_ok_and(|current| current == blob.content()),
+        None => false,
+    };
+    if unchanged {
+        return Ok(report);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    // Never write through a symlink, and make room for one
+    if current.is_some_and(|metadata| metadata.file_type().is_symlink() || is_link) {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    if is_link {
+        std::os::unix::fs::symlink(OsStr::from_bytes(blob.content()), &path)
+            .with_context(|| format!("Failed to create symlink {}", path.display()))?;
+    } else {
+        std::fs::write(&path, blob.content())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        if entry.filemode() == 0o100755 {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+    }
+
+    // Commit only the restored file: other staged changes stay staged, but out of the commit
+    let head = repo.head()?.peel_to_commit()?;
+    let mode = match entry.filemode() {
+        0o100755 => FileMode::BlobExecutable,
+        0o120000 => FileMode::Link,
+        _ => FileMode::Blob,
+    };
+    let tree = TreeUpdateBuilder::new()
+        .upsert(relative, entry.id(), mode)
+        .create_updated(&repo, &head.tree()?)
+        .context("Failed to build the restore commit")?;
+    let tree = repo.find_tree(tree)?;
+    let mut index = repo.index()?;
+    index.add_path(relative).context("Failed to stage the restored file")?;
+    index.write()?;
+    let signature = get_signature(&repo)?;
+    let message = format!("Restore {} from {}", relative.display(), &report.source_commit[..7]);
+    let commit = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&head])
+        .context("Failed to commit the restore")?;
+
+    report.commit = Some(commit.to_string());
+    Ok(report)
+}
+
+/// The newest commit reachable from HEAD made at or before a time
+fn commit_at(repo: &GitRepository, time: DateTime<Utc>) -> Result<Commit<'_>> {
+    let mut walk = repo.revwalk()?;
+    walk.push_head().context("The repository has no commits")?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        if commit_time(&commit) <= time {
+            return Ok(commit);
+        }
+    }
+    bail!("No commit is as old as {}", time.with_timezone(&Local).format("%Y-%m-%d %H:%M"))
+}
+
+/// Whether the current branch tracks a remote branch
+fn has_upstream(repo: &GitRepository) -> bool {
+    repo.head().is_ok_and(|head| head.is_branch() && git2::Branch::wrap(head).upstream().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::ConfigChange;
+    use git2::{IndexAddOption, Signature, Status, Time};
+    use serial_test::serial;
+    use std::env;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository with an identity, committed to at the given Unix times
+    struct TestRepository {
+        dir: TempDir,
+        repo: GitRepository,
+    }
+
+    impl TestRepository {
+        fn new() -> Self {
+            let dir = TempDir::new().unwrap();
+            let repo = GitRepository::init(dir.path()).unwrap();
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test User").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+            Self { dir, repo }
+        }
+
+        fn tracked(&self, relative: &str) -> TrackedFile {
+            TrackedFile { repository: self.dir.path().to_path_buf(), relative: PathBuf::from(relative) }
+        }
+
+        /// Write (or with None delete) a file and commit everything
+        fn commit(&self, file: &str, contents: Option<&str>, message: &str, time: i64) -> Oid {
+            let path = self.dir.path().join(file);
+            match contents {
+                Some(contents) => {
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    fs::write(&path, contents).unwrap();
+                }
+                None => fs::remove_file(&path).unwrap(),
+            }
+
+            let mut index = self.repo.index().unwrap();
+            index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+            index.update_all(["*"], None).unwrap();
+            index.write().unwrap();
+            let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::new("Test User", "test@example.com", &Time::new(time, 0)).unwrap();
+            let parent = self.repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+            let parents: Vec<_> = parent.iter().collect();
+            self.repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+        }
+
+        fn read(&self, file: &str) -> String {
+            fs::read_to_string(self.dir.path().join(file)).unwrap()
+        }
+    }
+
+    fn at(time: i64) -> RestoreTarget {
+        RestoreTarget::At(Utc.timestamp_opt(time, 0).unwrap())
+    }
+
+    #[test]
+    fn test_file_versions() {
+        let test = TestRepository::new();
+        let first = test.commit("todo.md", Some("one"), "First", 1000);
+        test.commit("other.md", Some("other"), "Other file", 2000);
+        let second = test.commit("todo.md", Some("two"), "Second", 3000);
+        let deleted = test.commit("todo.md", None, "Delete", 4000);
+
+        let versions = file_versions(&test.repo, Path::new("todo.md"), None).unwrap();
+        let commits: Vec<_> = versions.iter().map(|version| version.commit.clone()).collect();
+        assert_eq!(commits, [deleted.to_string(), second.to_string(), first.to_string()]);
+        assert!(versions[0].deleted);
+        assert!(!versions[1].deleted);
+        assert_eq!(versions[1].summary, "Second");
+        assert_eq!(versions[1].time.timestamp(), 3000);
+
+        assert_eq!(file_versions(&test.repo, Path::new("todo.md"), Some(1)).unwrap().len(), 1);
+        assert!(file_versions(&test.repo, Path::new("missing.md"), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_version_by_commit() {
+        let test = TestRepository::new();
+        let first = test.commit("todo.md", Some("one"), "First", 1000);
+        test.commit("todo.md", Some("two"), "Second", 2000);
+
+        let report = restore_version(&test.tracked("todo.md"), &RestoreTarget::Commit(first.to_string()[..8].to_owned())).unwrap();
+        assert_eq!(report.source_commit, first.to_string());
+        assert_eq!(test.read("todo.md"), "one");
+
+        // The restore is a commit of its own on top of the history
+        let head = test.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(Some(head.id().to_string()), report.commit);
+        assert_eq!(head.summary(), Some(format!("Restore todo.md from {}", &first.to_string()[..7]).as_str()));
+        assert_eq!(head.parent(0).unwrap().summary(), Some("Second"));
+        assert!(test.repo.statuses(None).unwrap().is_empty());
+
+        // Restoring the same version again changes nothing
+        let again = restore_version(&test.tracked("todo.md"), &RestoreTarget::Commit(first.to_string())).unwrap();
+        assert_eq!(again.commit, None);
+    }
+
+    #[test]
+    fn test_restore_version_symlink() {
+        let test = TestRepository::new();
+        let link = test.dir.path().join("current.md");
+        std::os::unix::fs::symlink("todo.md", &link).unwrap();
+        let first = test.commit("todo.md", Some("one"), "Link", 1000);
+        fs::remove_file(&link).unwrap();
+        test.commit("current.md", Some("plain"), "Replace link", 2000);
+
+        let report = restore_version(&test.tracked("current.md"), &RestoreTarget::Commit(first.to_string())).unwrap();
+        assert!(report.commit.is_some());
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("todo.md"));
+        assert_eq!(test.read("current.md"), "one");
+
+        let head = test.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.tree().unwrap().get_path(Path::new("current.md")).unwrap().filemode(), 0o120000);
+        assert!(test.repo.statuses(None).unwrap().is_empty());
+
+        // Already the same link
+        let again = restore_version(&test.tracked("current.md"), &RestoreTarget::Commit(first.to_string())).unwrap();
+        assert_eq!(again.commit, None);
+    }
+
+    #[test]
+    fn test_restore_version_leaves_staged_files() {
+        let test = TestRepository::new();
+        let first = test.commit("todo.md", Some("one"), "First", 1000);
+        test.commit("todo.md", Some("two"), "Second", 2000);
+
+        fs::write(test.dir.path().join("draft.md"), "draft").unwrap();
+        let mut index = test.repo.index().unwrap();
+        index.add_path(Path::new("draft.md")).unwrap();
+        index.write().unwrap();
+
+        restore_version(&test.tracked("todo.md"), &RestoreTarget::Commit(first.to_string())).unwrap();
+
+        // The restore commit only changes the restored file
+        let head = test.repo.head().unwrap().peel_to_commit().unwrap();
+        assert!(head.tree().unwrap().get_path(Path::new("draft.md")).is_err());
+        let restored = head.tree().unwrap().get_path(Path::new("todo.md")).unwrap();
+        assert_eq!(test.repo.find_blob(restored.id()).unwrap().content(), b"one");
+
+        // The unrelated file is still staged, and the restored one clean
+        assert_eq!(test.repo.status_file(Path::new("draft.md")).unwrap(), Status::INDEX_NEW);
+        assert!(test.repo.status_file(Path::new("todo.md")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_version_at_time() {
+        let test = TestRepository::new();
+        test.commit("todo.md", Some("one"), "First", 1000);
+        test.commit("todo.md", Some("two"), "Second", 2000);
+        test.commit("todo.md", None, "Delete", 3000);
+
+        // A deleted file comes back
+        restore_version(&test.tracked("todo.md"), &at(2500)).unwrap();
+        assert_eq!(test.read("todo.md"), "two");
+
+        restore_version(&test.tracked("todo.md"), &at(1999)).unwrap();
+        assert_eq!(test.read("todo.md"), "one");
+
+        let error = restore_version(&test.tracked("todo.md"), &at(500)).err().unwrap();
+        assert!(error.to_string().starts_with("No commit is as old as"));
+    }
+
+    #[test]
+    fn test_restore_version_refusals() {
+        let test = TestRepository::new();
+        let first = test.commit("todo.md", Some("one"), "First", 1000);
+        test.commit("todo.md", Some("two"), "Second", 2000);
+
+        let error = restore_version(&test.tracked("later.md"), &RestoreTarget::Commit(first.to_string())).err().unwrap();
+        assert!(error.to_string().starts_with("later.md didn't exist in commit"));
+
+        let error = restore_version(&test.tracked("todo.md"), &RestoreTarget::Commit("nope".to_owned())).err().unwrap();
+        assert_eq!(error.to_string(), "Unknown commit: nope");
+
+        fs::write(test.dir.path().join("todo.md"), "unsaved").unwrap();
+        let error = restore_version(&test.tracked("todo.md"), &RestoreTarget::Commit(first.to_string())).err().unwrap();
+        assert!(error.to_string().contains("uncommitted changes"));
+        assert_eq!(test.read("todo.md"), "unsaved");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_restore_file_records_commit() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+        env::set_var("XDG_STATE_HOME", config_dir.path());
+
+        let test = TestRepository::new();
+        let first = test.commit("notes/todo.md", Some("one"), "First", 1000);
+        test.commit("notes/todo.md", Some("two"), "Second", 2000);
+        let repository = test.dir.path().canonicalize().unwrap();
+        let file = repository.join("notes/todo.md");
+
+        let error = restore_file(file.to_str().unwrap(), None, Some("HEAD~1"), OutputFormat::Json).await.unwrap_err();
+        assert!(error.to_string().contains("not inside a configured repository"));
+
+        let mut config = Config::load_or_create_default().unwrap();
+        config.apply(&ConfigChange::AddRepo { path: repository.clone(), commit_message_template: None }).unwrap();
+        config.save(Config::default_config_path().unwrap()).unwrap();
+
+        let tracked = locate(file.to_str().unwrap()).unwrap();
+        assert_eq!(tracked.relative, PathBuf::from("notes/todo.md"));
+
+        restore_file(file.to_str().unwrap(), None, Some(&first.to_string()), OutputFormat::Json).await.unwrap();
+        assert_eq!(test.read("notes/todo.md"), "one");
+
+        // The restore is in the sync history, so it counts as an automatic commit
+        let head = test.repo.head().unwrap().target().unwrap().to_string();
+        assert!(automatic_commits(&repository).await.unwrap().contains(&head));
+    }
+}
@@ -0,0 +1,155 @@
+//! `autogit scan`: find git repositories under a directory and add many at once
+
+use crate::cli::OutputFormat;
+use crate::commands::{change_config, expand_path, report_applied};
+use crate::output::{self, ScanReport};
+use anyhow::{Context, Result, bail};
+use autogit_shared::discovery::{ScanFilter, find_repositories};
+use autogit_shared::{Config, ConfigChange};
+use colored::Colorize;
+use dialoguer::MultiSelect;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// Find repositories under a directory and add the chosen ones
+pub async fn scan_directory(
+    dir: &str,
+    depth: usize,
+    filter: ScanFilter,
+    message: Option<String>,
+    all: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let root = expand_path(dir)?;
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
+
+    // Repositories under a watch root are followed already
+    let (already_configured, found): (Vec<PathBuf>, Vec<PathBuf>) = find_repositories(&root, depth, &filter)
+        .into_iter()
+        .partition(|path| config.all_repositories().any(|repo| &repo.path == path));
+
+    if format.is_plain() {
+        println!("Found {} new repositories under {}", found.len(), root.display());
+        if !already_configured.is_empty() {
+            println!("{}", format!("Skipping {} already configured", already_configured.len()).dimmed());
+        }
+    }
+
+    let chosen = if all || found.is_empty() {
+        found.clone()
+    } else {
+        choose(&root, &found)?
+    };
+
+    let mut applied_by = None;
+    for path in &chosen {
+        let applied = change_config(ConfigChange::AddRepo {
+            path: path.clone(),
+            commit_message_template: message.clone(),
+        })
+        .await
+        .with_context(|| format!("Failed to add {}", path.display()))?;
+        applied_by = Some(applied);
+
+        if format.is_plain() {
+            println!("{} Added repository: {}", "✓".green().bold(), path.display());
+        }
+    }
+
+    if !format.is_plain() {
+        return output::emit(format, &ScanReport { directory: root, found, already_configured, added: chosen, applied_by });
+    }
+    match applied_by {
+        Some(applied) => report_applied(applied),
+        None => Ok(()),
+    }
+}
+
+/// Let the user pick repositories, all selected to start with
+fn choose(root: &Path, found: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        bail!("Not running in a terminal; pass --all to add every repository found");
+    }
+
+    let items: Vec<String> = found.iter()
+        .map(|path| match path.strip_prefix(root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+            _ => path.display().to_string(),
+        })
+        .collect();
+
+    let selection = MultiSelect::new()
+        .with_prompt("Repositories to add (space toggles, enter confirms)")
+        .items(&items)
+        .defaults(&vec![true; items.len()])
+        .interact_opt()
+        .context("Failed to read the selection")?;
+
+    Ok(selection.unwrap_or_default().into_iter().map(|index| found[index].clone()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::WatchRoot;
+    use serial_test::serial;
+    use std::env;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scan_adds_new_repositories() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // notes is configured already, inbox found under a watch root and drafts is excluded,
+        // leaving journal to add
+        let tree = TempDir::new().unwrap();
+        let root = tree.path().canonicalize().unwrap();
+        for name in ["notes", "journal", "drafts", "watched/inbox"] {
+            fs::create_dir_all(root.join(name).join(".git")).unwrap();
+        }
+        let mut config = Config::load_or_create_default().unwrap();
+        config.apply(&ConfigChange::AddRepo { path: root.join("notes"), commit_message_template: None }).unwrap();
+        config.apply(&ConfigChange::AddWatchRoot { root: WatchRoot {
+            path: root.join("watched"),
+            depth: 1,
+            auto_commit: true,
+            commit_message_template: "Watched: {timestamp}".to_owned(),
+        } }).unwrap();
+        config.save(Config::default_config_path().unwrap()).unwrap();
+
+        let message = Some("Scanned: {timestamp}".to_owned());
+        let filter = ScanFilter::new(&[], &["drafts".to_owned()]).unwrap();
+        scan_directory(root.to_str().unwrap(), 2, filter, message, true, OutputFormat::Plain)
+            .await
+            .unwrap();
+
+        let config = Config::load_or_create_default().unwrap();
+        let paths: Vec<_> = config.repositories.iter().map(|repo| repo.path.clone()).collect();
+        assert_eq!(paths, [root.join("notes"), root.join("journal")]);
+        assert_eq!(config.repositories[1].commit_message_template, "Scanned: {timestamp}");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scan_requires_terminal_to_choose() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        // Test runs have no terminal on stdin
+        if std::io::stdin().is_terminal() {
+            return;
+        }
+
+        let tree = TempDir::new().unwrap();
+        fs::create_dir_all(tree.path().join("notes").join(".git")).unwrap();
+        let result = scan_directory(tree.path().to_str().unwrap(), 2, ScanFilter::default(), None, false, OutputFormat::Json).await;
+        assert!(result.unwrap_err().to_string().contains("pass --all"));
+        assert!(Config::load_or_create_default().unwrap().repositories.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+
+/// Parse a duration like "90s", "30m", "2h", "1d", "1w", "1h30m" or "2 hours"
+pub fn parse_duration(spec: &str) -> Result<Duration> {
+    let mut total = Duration::zero();
+    let mut rest = spec.trim();
+
+    if rest.is_empty() {
+        bail!("Empty duration");
+    }
+
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            bail!("Invalid duration '{}': expected a number", spec);
+        }
+
+        let value: i64 = rest[..digits].parse()
+            .with_context(|| format!("Invalid duration '{}'", spec))?;
+        rest = rest[digits..].trim_start();
+
+        let unit_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = rest[unit_len..].trim_start();
+
+        let part = match unit {
+            "s" | "sec" | "secs" | "second" | "seconds" => TimeDelta::try_seconds(value),
+            "" | "m" | "min" | "mins" | "minute" | "minutes" => TimeDelta::try_minutes(value),
+            "h" | "hr" | "hrs" | "hour" | "hours" => TimeDelta::try_hours(value),
+            "d" | "day" | "days" => TimeDelta::try_days(value),
+            "w" | "week" | "weeks" => TimeDelta::try_weeks(value),
+            _ => bail!("Invalid duration '{}': unknown unit '{}' (use s, m, h, d or w)", spec, unit),
+        };
+
+        total = part.and_then(|part| total.checked_add(&part))
+            .with_context(|| format!("Invalid duration '{}': too long", spec))?;
+    }
+
+    Ok(total)
+}
+
+/// Parse a point in the past: a relative duration ("2h", "2 hours ago"),
+/// a date ("2025-11-15"), a local date and time ("2025-11-15 14:30") or RFC 3339
+pub fn parse_since(spec: &str) -> Result<DateTime<Utc>> {
+    let spec = spec.trim();
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(spec, format) {
+            return local_to_utc(naive);
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        return local_to_utc(date.and_hms_opt(0, 0, 0).context("Invalid date")?);
+    }
+
+    let relative = spec.strip_suffix("ago").unwrap_or(spec);
+    let duration = parse_duration(relative)
+        .with_context(|| format!(
+            "Invalid time '{}': use a duration (2h, 3 days ago), a date (2025-11-15) or a date and time (2025-11-15 14:30)",
+            spec
+        ))?;
+
+    Utc::now().checked_sub_signed(duration)
+        .with_context(|| format!("Invalid time '{}': too far in the past", spec))
+}
+
+/// Parse a point in the future: a time of day ("18:00", tomorrow if already past),
+/// a local date and time ("2025-11-15 18:00") or RFC 3339
+pub fn parse_until(spec: &str) -> Result<DateTime<Utc>> {
+    let spec = spec.trim();
+
+    let until = if let Ok(timestamp) = DateTime::parse_from_rfc3339(spec) {
+        timestamp.with_timezone(&Utc)
+    } else if let Some(naive) = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(spec, format).ok())
+    {
+        local_to_utc(naive)?
+    } else if let Some(time) = ["%H:%M", "%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(spec, format).ok())
+    {
+        let today = Local::now().date_naive();
+        let candidate = local_to_utc(today.and_time(time))?;
+        if candidate > Utc::now() {
+            candidate
+        } else {
+            local_to_utc((today + Duration::days(1)).and_time(time))?
+        }
+    } else {
+        bail!("Invalid time '{}': use a time of day (18:00) or a date and time (2025-11-15 18:00)", spec);
+    };
+
+    if until <= Utc::now() {
+        bail!("Time '{}' is in the past", spec);
+    }
+
+    Ok(until)
+}
+
+fn local_to_utc(naive: NaiveDateTime) -> Result<DateTime<Utc>> {
+    Local.from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .with_context(|| format!("Invalid local time: {}", naive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::days(1));
+        assert_eq!(parse_duration("1w").unwrap(), Duration::weeks(1));
+    }
+
+    #[test]
+    fn test_parse_duration_words_and_combinations() {
+        assert_eq!(parse_duration("2 hours").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1 day 2 hours").unwrap(), Duration::hours(26));
+        // Bare numbers are minutes
+        assert_eq!(parse_duration("45").unwrap(), Duration::minutes(45));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("2 fortnights").is_err());
+        assert!(parse_duration("-5m").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_overflow() {
+        let err = parse_duration("99999999999w").unwrap_err();
+        assert!(err.to_string().contains("too long"));
+        assert!(parse_duration("100000000000000w 1s").is_err());
+
+        // Fits a duration, but not a date
+        let err = parse_since("100000000w").unwrap_err();
+        assert!(err.to_string().contains("Invalid time"));
+        let err = parse_since("99999999999w").unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid time"));
+    }
+
+    #[test]
+    fn test_parse_since_relative() {
+        let before = Utc::now() - Duration::hours(2);
+        let since = parse_since("2h").unwrap();
+        let after = Utc::now() - Duration::hours(2);
+        assert!(since >= before && since <= after);
+
+        let since = parse_since("3 days ago").unwrap();
+        let expected = Utc::now() - Duration::days(3);
+        assert!((expected - since).num_seconds().abs() < 5);
+    }
+
+    #[test]
+    fn test_parse_since_absolute() {
+        let since = parse_since("2025-11-15T10:00:00Z").unwrap();
+        assert_eq!(since.to_rfc3339(), "2025-11-15T10:00:00+00:00");
+
+        let date = parse_since("2025-11-15").unwrap();
+        let local = date.with_timezone(&Local);
+        assert_eq!(local.format("%Y-%m-%d %H:%M").to_string(), "2025-11-15 00:00");
+
+        let datetime = parse_since("2025-11-15 14:30").unwrap();
+        let local = datetime.with_timezone(&Local);
+        assert_eq!(local.format("%Y-%m-%d %H:%M").to_string(), "2025-11-15 14:30");
+    }
+
+    #[test]
+    fn test_parse_until_time_of_day() {
+        let until = parse_until("18:00").unwrap();
+        let local = until.with_timezone(&Local);
+        assert_eq!(local.format("%H:%M").to_string(), "18:00");
+        assert!(until > Utc::now());
+        assert!(until - Utc::now() <= Duration::days(1));
+    }
+
+    #[test]
+    fn test_parse_until_absolute() {
+        let until = parse_until("2999-01-01 09:30").unwrap();
+        let local = until.with_timezone(&Local);
+        assert_eq!(local.format("%Y-%m-%d %H:%M").to_string(), "2999-01-01 09:30");
+
+        let until = parse_until("2999-01-01T09:30:00Z").unwrap();
+        assert_eq!(until.to_rfc3339(), "2999-01-01T09:30:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_until_invalid() {
+        assert!(parse_until("teatime").is_err());
+        assert!(parse_until("25:00").is_err());
+
+        let err = parse_until("2020-01-01 10:00").unwrap_err();
+        assert!(err.to_string().contains("in the past"));
+    }
+
+    #[test]
+    fn test_parse_since_invalid() {
+        let err = parse_since("last tuesday").unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid time"));
+    }
+}
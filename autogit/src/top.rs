@@ -0,0 +1,829 @@
+//! `autogit top`: a full-screen dashboard of the daemon, for sessions without a tray
+
+use crate::cli::OutputFormat;
+use crate::commands::{daemon_client, daemon_error, describe_event};
+use anyhow::{bail, Context, Result};
+use autogit_client::{Client, Status};
+use autogit_shared::{format_elapsed, format_remaining, DaemonEvent, EventKind, RepoStatus};
+use chrono::{DateTime, Local};
+use git2::{Repository as GitRepository, StatusOptions};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{enable_raw_mode, EnterAlternateScreen};
+use ratatui::crossterm::execute;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+
+/// How often the repository table is refreshed without being asked to
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait for a key press before looking for updates again
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Event log lines kept for scrolling back
+const LOG_CAPACITY: usize = 500;
+
+const KEY_HINTS: &str = "↑↓ select  t sync  T sync all  p pause  s suspend  e editor  enter error  ? help  q quit";
+
+/// Show the dashboard until the user quits
+pub async fn run_top(format: OutputFormat) -> Result<()> {
+    if !format.is_plain() || !std::io::stdout().is_terminal() {
+        bail!("autogit top needs a terminal; use `autogit status` or `autogit watch` in scripts");
+    }
+
+    // Fail with the usual hint right away if the daemon isn't running
+    let client = daemon_client()?;
+    let status = client.status().await.map_err(daemon_error)?;
+
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || run(client, handle, status))
+        .await
+        .context("Dashboard stopped unexpectedly")?
+}
+
+/// Something the background tasks report to the dashboard
+#[derive(Debug)]
+enum Update {
+    /// Fresh daemon status, with the number of uncommitted files per repository
+    Status(Box<Status>, HashMap<PathBuf, usize>),
+    /// The daemon could not be reached
+    Disconnected(String),
+    Event(DaemonEvent),
+    /// An action requested from the keyboard finished
+    Done(String),
+    Failed(String),
+}
+
+/// Something the user asked for that needs the daemon or the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    /// Sync one repository now (all enabled ones if None)
+    Trigger(Option<PathBuf>),
+    Pause(PathBuf),
+    Unpause(PathBuf),
+    Suspend,
+    Resume,
+    OpenEditor(PathBuf),
+}
+
+/// What a repository is doing, as shown in the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepoState {
+    Syncing,
+    Quarantined,
+    Paused,
+    Disabled,
+    Error,
+    Idle,
+}
+
+impl RepoState {
+    fn of(repo: &RepoStatus, syncing: bool) -> Self {
+        if syncing {
+            RepoState::Syncing
+        } else if repo.quarantined {
+            RepoState::Quarantined
+        } else if repo.paused {
+            RepoState::Paused
+        } else if !repo.enabled {
+            RepoState::Disabled
+        } else if repo.error.is_some() {
+            RepoState::Error
+        } else {
+            RepoState::Idle
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            RepoState::Syncing => "⟳ syncing",
+            RepoState::Quarantined => "⛔ quarantined",
+            RepoState::Paused => "⏸ paused",
+            RepoState::Disabled => "− disabled",
+            RepoState::Error => "✗ error",
+            RepoState::Idle => "✓ idle",
+        }
+    }
+
+    fn style(self) -> Style {
+        match self {
+            RepoState::Syncing => Style::new().fg(Color::Cyan),
+            RepoState::Quarantined | RepoState::Error => Style::new().fg(Color::Red),
+            RepoState::Paused => Style::new().fg(Color::Yellow),
+            RepoState::Disabled => Style::new().add_modifier(Modifier::DIM),
+            RepoState::Idle => Style::new().fg(Color::Green),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct LogLine {
+    time: DateTime<Local>,
+    level: Level,
+    text: String,
+}
+
+/// A box shown over the dashboard until dismissed
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Popup {
+    Help,
+    Error { title: String, message: String },
+}
+
+/// Everything the dashboard shows, updated from key presses and daemon updates
+#[derive(Debug, Default)]
+struct App {
+    status: Option<Status>,
+    /// Uncommitted files per repository, as of the last refresh
+    pending: HashMap<PathBuf, usize>,
+    /// Why the daemon can't be reached, until it answers again
+    disconnected: Option<String>,
+    /// Repositories in the sync cycle that is running
+    syncing: HashSet<PathBuf>,
+    log: VecDeque<LogLine>,
+    /// Lines scrolled back from the newest log line
+    log_scroll: usize,
+    selected: usize,
+    popup: Option<Popup>,
+    quit: bool,
+}
+
+impl App {
+    fn new(status: Status) -> Self {
+        let mut app = App::default();
+        app.push_log(Level::Info, format!("Connected to daemon ({} repositories)", status.repositories.len()));
+        app.status = Some(status);
+        app
+    }
+
+    fn repositories(&self) -> &[RepoStatus] {
+        self.status.as_ref().map_or(&[], |status| &status.repositories)
+    }
+
+    fn selected_repo(&self) -> Option<&RepoStatus> {
+        self.repositories().get(self.selected)
+    }
+
+    fn push_log(&mut self, level: Level, text: String) {
+        self.push_log_at(Local::now(), level, text);
+    }
+
+    fn push_log_at(&mut self, time: DateTime<Local>, level: Level, text: String) {
+        if self.log.len() == LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(LogLine { time, level, text });
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Status(status, pending) => {
+                if self.disconnected.take().is_some() {
+                    self.push_log(Level::Success, "Reconnected to daemon".to_owned());
+                }
+                self.selected = self.selected.min(status.repositories.len().saturating_sub(1));
+                self.status = Some(*status);
+                self.pending = pending;
+            }
+            Update::Disconnected(error) => {
+                if self.disconnected.is_none() {
+                    self.push_log(Level::Error, format!("Lost connection to daemon: {}", error));
+                }
+                self.disconnected = Some(error);
+                self.syncing.clear();
+            }
+            Update::Event(event) => self.apply_event(event),
+            Update::Done(message) => self.push_log(Level::Info, message),
+            Update::Failed(error) => self.push_log(Level::Error, error),
+        }
+    }
+
+    fn apply_event(&mut self, event: DaemonEvent) {
+        let level = match &event.kind {
+            EventKind::SyncStarted { paths, .. } => {
+                self.syncing = paths.iter().cloned().collect();
+                Level::Info
+            }
+            EventKind::SyncFinished { repos_failed, .. } => {
+                self.syncing.clear();
+                if *repos_failed > 0 { Level::Warning } else { Level::Success }
+            }
+            EventKind::CommitCreated { .. } | EventKind::Resumed => Level::Success,
+            EventKind::PushFailed { .. } | EventKind::SyncFailed { .. } => Level::Error,
+            EventKind::Suspended { .. } => Level::Warning,
+            EventKind::ConfigReloaded { .. } => Level::Info,
+        };
+
+        self.push_log_at(event.timestamp.with_timezone(&Local), level, describe_event(&event.kind));
+    }
+
+    /// React to a key press, returning what needs the daemon or the terminal
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        if self.popup.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('?')) {
+                self.popup = None;
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.repositories().len().saturating_sub(1));
+            }
+            KeyCode::PageUp => self.log_scroll = (self.log_scroll + 5).min(self.log.len().saturating_sub(1)),
+            KeyCode::PageDown => self.log_scroll = self.log_scroll.saturating_sub(5),
+            KeyCode::Char('?') => self.popup = Some(Popup::Help),
+            KeyCode::Enter => self.popup = self.selected_repo().map(error_popup),
+            KeyCode::Char('T') => return Some(Action::Trigger(None)),
+            KeyCode::Char('t') => return self.selected_repo().map(|repo| Action::Trigger(Some(repo.path.clone()))),
+            KeyCode::Char('e') => return self.selected_repo().map(|repo| Action::OpenEditor(repo.path.clone())),
+            KeyCode::Char('p') => {
+                return self.selected_repo().map(|repo| match repo.paused {
+                    true => Action::Unpause(repo.path.clone()),
+                    false => Action::Pause(repo.path.clone()),
+                });
+            }
+            KeyCode::Char('s') => {
+                let suspended = self.status.as_ref().is_some_and(|status| status.suspended);
+                return Some(if suspended { Action::Resume } else { Action::Suspend });
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// The last error of a repository, or a note that there is none
+fn error_popup(repo: &RepoStatus) -> Popup {
+    let failed_sync = repo.last_sync.as_ref().and_then(|entry| entry.error.clone());
+    let message = match repo.error.clone().or(failed_sync) {
+        Some(error) if repo.quarantined => format!("{}\n\nQuarantined; run `autogit unquarantine` to retry.", error),
+        Some(error) => error,
+        None => "No error since the daemon started.".to_owned(),
+    };
+
+    Popup::Error { title: repo.path.display().to_string(), message }
+}
+
+/// Run the dashboard on this (blocking) thread; daemon requests run as tasks on the runtime
+fn run(client: Client, handle: Handle, status: Status) -> Result<()> {
+    let (updates, received) = mpsc::channel();
+    let refresh = Arc::new(Notify::new());
+
+    handle.spawn(poll_status(client.clone(), updates.clone(), refresh.clone()));
+    handle.spawn(forward_events(client.clone(), updates.clone()));
+    // Count uncommitted files right away rather than after the first interval
+    refresh.notify_one();
+
+    let dashboard = Dashboard { client, handle, updates, refresh };
+    let mut terminal = ratatui::try_init().context("Failed to set up the terminal")?;
+    // describe_event is shared with `watch`; colours would show up as escape codes here
+    colored::control::set_override(false);
+
+    let result = dashboard.event_loop(&mut terminal, App::new(status), &received);
+
+    ratatui::restore();
+    colored::control::unset_override();
+    result
+}
+
+/// Connections to the daemon and the background tasks feeding the dashboard
+struct Dashboard {
+    client: Client,
+    handle: Handle,
+    updates: mpsc::Sender<Update>,
+    refresh: Arc<Notify>,
+}
+
+impl Dashboard {
+    fn event_loop(&self, terminal: &mut DefaultTerminal, mut app: App, received: &mpsc::Receiver<Update>) -> Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| draw(frame, &app)).context("Failed to draw the dashboard")?;
+
+            if event::poll(INPUT_POLL).context("Failed to read from the terminal")? {
+                if let Event::Key(key) = event::read().context("Failed to read from the terminal")? {
+                    if let Some(action) = app.handle_key(key) {
+                        self.perform(terminal, &mut app, action)?;
+                    }
+                }
+            }
+
+            while let Ok(update) = received.try_recv() {
+                app.apply(update);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open the editor, or send the action to the daemon in the background; its outcome shows up in the log
+    fn perform(&self, terminal: &mut DefaultTerminal, app: &mut App, action: Action) -> Result<()> {
+        let description = match &action {
+            Action::OpenEditor(path) => return open_editor(terminal, app, path),
+            Action::Trigger(Some(path)) => format!("Syncing {}...", path.display()),
+            Action::Trigger(None) => "Syncing all enabled repositories...".to_owned(),
+            Action::Pause(path) => format!("Pausing {}...", path.display()),
+            Action::Unpause(path) => format!("Resuming {}...", path.display()),
+            Action::Suspend => "Suspending the daemon...".to_owned(),
+            Action::Resume => "Resuming the daemon...".to_owned(),
+        };
+        app.push_log(Level::Info, description);
+
+        let (client, updates, refresh) = (self.client.clone(), self.updates.clone(), self.refresh.clone());
+        self.handle.spawn(async move {
+            let result = match action {
+                Action::Trigger(repos) => client.trigger(repos.map(|path| vec![path]), true, true).await
+                    .map(|summary| summary.message),
+                Action::Pause(path) => client.pause(path, None).await,
+                Action::Unpause(path) => client.unpause(path).await,
+                Action::Suspend => client.suspend(None).await,
+                Action::Resume => client.resume().await,
+                Action::OpenEditor(_) => return,
+            };
+
+            let _ = updates.send(match result {
+                Ok(message) => Update::Done(message),
+                Err(e) => Update::Failed(format!("{:#}", daemon_error(e))),
+            });
+            refresh.notify_one();
+        });
+
+        Ok(())
+    }
+}
+
+/// Leave the dashboard for `$EDITOR` in the repository, then come back
+fn open_editor(terminal: &mut DefaultTerminal, app: &mut App, path: &Path) -> Result<()> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_owned());
+
+    ratatui::restore();
+    let status = std::process::Command::new(&editor)
+        .arg(path)
+        .current_dir(path)
+        .status();
+    enable_raw_mode().context("Failed to set up the terminal")?;
+    execute!(std::io::stdout(), EnterAlternateScreen).context("Failed to set up the terminal")?;
+    terminal.clear().context("Failed to set up the terminal")?;
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => app.push_log(Level::Warning, format!("{} exited with {}", editor, status)),
+        Err(e) => app.push_log(Level::Error, format!("Failed to start {}: {}", editor, e)),
+    }
+    Ok(())
+}
+
+/// Refresh the status periodically, and right away when asked to
+async fn poll_status(client: Client, updates: mpsc::Sender<Update>, refresh: Arc<Notify>) {
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+            _ = refresh.notified() => {}
+        }
+
+        let update = match client.status().await {
+            Ok(status) => {
+                let paths: Vec<PathBuf> = status.repositories.iter().map(|repo| repo.path.clone()).collect();
+                let pending = tokio::task::spawn_blocking(move || {
+                    paths.into_iter()
+                        .filter_map(|path| uncommitted_files(&path).map(|count| (path, count)))
+                        .collect()
+                }).await.unwrap_or_default();
+                Update::Status(Box::new(status), pending)
+            }
+            Err(e) => Update::Disconnected(format!("{:#}", e)),
+        };
+
+        // The dashboard is gone once nobody receives updates
+        if updates.send(update).is_err() {
+            return;
+        }
+    }
+}
+
+/// Forward daemon events to the dashboard, subscribing again after the daemon restarts
+async fn forward_events(client: Client, updates: mpsc::Sender<Update>) {
+    let client = client.reconnect(u32::MAX, REFRESH_INTERVAL);
+
+    loop {
+        let mut events = match client.subscribe().await {
+            Ok(events) => events,
+            Err(e) => {
+                if updates.send(Update::Disconnected(format!("{:#}", e))).is_err() {
+                    return;
+                }
+                tokio::time::sleep(REFRESH_INTERVAL).await;
+                continue;
+            }
+        };
+
+        while let Ok(Some(event)) = events.next().await {
+            if updates.send(Update::Event(event)).is_err() {
+                return;
+            }
+        }
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+/// Number of files `git status` would list (None if the repository can't be read)
+fn uncommitted_files(path: &Path) -> Option<usize> {
+    let repo = GitRepository::open(path).ok()?;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true);
+    opts.include_ignored(false);
+    repo.statuses(Some(&mut opts)).ok().map(|statuses| statuses.len())
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [header, repos, log, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Percentage(55),
+        Constraint::Min(4),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(Paragraph::new(header_line(app)), header);
+    draw_repositories(frame, app, repos);
+    draw_log(frame, app, log);
+    frame.render_widget(Paragraph::new(KEY_HINTS).style(Style::new().add_modifier(Modifier::DIM)), footer);
+
+    if let Some(popup) = &app.popup {
+        draw_popup(frame, popup);
+    }
+}
+
+fn header_line(app: &App) -> Line<'static> {
+    let mut spans = vec![Span::styled("autogit top", Style::new().bold())];
+
+    if let Some(error) = &app.disconnected {
+        spans.push(Span::styled(format!("  daemon unreachable: {}", error), Style::new().fg(Color::Red)));
+        return Line::from(spans);
+    }
+
+    let Some(status) = &app.status else {
+        return Line::from(spans);
+    };
+
+    spans.push(Span::raw(format!("  {} repositories", status.repositories.len())));
+
+    if status.suspended {
+        let until = status.suspended_until
+            .map(|until| format!(" for {}", format_remaining(until)))
+            .unwrap_or_default();
+        spans.push(Span::styled(format!("  suspended{}", until), Style::new().fg(Color::Yellow)));
+    } else if !app.syncing.is_empty() {
+        spans.push(Span::styled(format!("  syncing {}", app.syncing.len()), Style::new().fg(Color::Cyan)));
+    } else if let Some(next) = status.next_check {
+        spans.push(Span::raw(format!("  next check in {}", format_remaining(next))));
+    }
+
+    if let Some(reason) = status.schedule.as_ref().and_then(|s| s.commit_deferred.as_ref().or(s.remote_deferred.as_ref())) {
+        spans.push(Span::styled(format!("  deferred: {}", reason), Style::new().fg(Color::Yellow)));
+    }
+
+    Line::from(spans)
+}
+
+fn draw_repositories(frame: &mut Frame, app: &App, area: Rect) {
+    let rows = app.repositories().iter().map(|repo| {
+        let state = RepoState::of(repo, app.syncing.contains(&repo.path));
+        let last_sync = repo.last_sync.as_ref()
+            .map(|entry| format!("{} ago", format_elapsed(entry.timestamp)))
+            .unwrap_or_else(|| "-".to_owned());
+        let remote = match (repo.ahead, repo.behind) {
+            (Some(ahead), Some(behind)) => format!("↑{} ↓{}", ahead, behind),
+            (Some(ahead), None) => format!("↑{}", ahead),
+            _ => "-".to_owned(),
+        };
+        let pending = app.pending.get(&repo.path).map_or_else(|| "-".to_owned(), |count| count.to_string());
+
+        Row::new(vec![
+            Cell::from(state.label()).style(state.style()),
+            Cell::from(repo.path.display().to_string()),
+            Cell::from(last_sync),
+            Cell::from(remote),
+            Cell::from(pending),
+        ])
+    });
+
+    let table = Table::new(rows, [
+        Constraint::Length(14),
+        Constraint::Fill(1),
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(8),
+    ])
+    .header(Row::new(["State", "Repository", "Last sync", "Remote", "Changes"]).style(Style::new().bold()))
+    .block(Block::bordered().title(" Repositories "))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_log(frame: &mut Frame, app: &App, area: Rect) {
+    // Keep the newest lines in view, unless scrolled back
+    let visible = area.height.saturating_sub(2) as usize;
+    let end = app.log.len().saturating_sub(app.log_scroll);
+    let start = end.saturating_sub(visible);
+
+    let lines: Vec<Line> = app.log.range(start..end).map(|line| {
+        let style = match line.level {
+            Level::Info => Style::new(),
+            Level::Success => Style::new().fg(Color::Green),
+            Level::Warning => Style::new().fg(Color::Yellow),
+            Level::Error => Style::new().fg(Color::Red),
+        };
+        Line::from(vec![
+            Span::styled(line.time.format("%H:%M:%S ").to_string(), Style::new().add_modifier(Modifier::DIM)),
+            Span::styled(line.text.clone(), style),
+        ])
+    }).collect();
+
+    let title = match app.log_scroll {
+        0 => " Events ".to_owned(),
+        scroll => format!(" Events ({} newer, PgDn) ", scroll),
+    };
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+}
+
+fn draw_popup(frame: &mut Frame, popup: &Popup) {
+    let (title, text) = match popup {
+        Popup::Help => (" Keys ".to_owned(), help_text()),
+        Popup::Error { title, message } => (format!(" {} ", title), message.clone()),
+    };
+
+    let area = frame.area().centered(Constraint::Percentage(70), Constraint::Percentage(50));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(title).title_bottom(" esc to close ")),
+        area,
+    );
+}
+
+fn help_text() -> String {
+    [
+        "↑/k ↓/j    select a repository",
+        "t          sync the selected repository now",
+        "T          sync all enabled repositories now",
+        "p          pause or resume the selected repository",
+        "s          suspend or resume the daemon",
+        "e          open the selected repository in $EDITOR",
+        "enter      show the last error of the selected repository",
+        "PgUp/PgDn  scroll the event log",
+        "q          quit",
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::JournalEntry;
+    use chrono::Utc;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn repo(path: &str) -> RepoStatus {
+        RepoStatus {
+            path: PathBuf::from(path),
+            enabled: true,
+            paused: false,
+            quarantined: false,
+            last_sync: None,
+            ahead: None,
+            behind: None,
+            push_pending: false,
+            error: None,
+        }
+    }
+
+    fn test_app() -> App {
+        let mut wiki = repo("/home/me/wiki");
+        wiki.error = Some("Push rejected".to_owned());
+        wiki.ahead = Some(2);
+        wiki.behind = Some(0);
+
+        App::new(Status {
+            repositories: vec![repo("/home/me/notes"), wiki],
+            ..Default::default()
+        })
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        app.handle_key(KeyEvent::from(code))
+    }
+
+    fn render(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content.chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_repo_state() {
+        let mut notes = repo("/notes");
+        assert_eq!(RepoState::of(&notes, false), RepoState::Idle);
+        notes.error = Some("gone".to_owned());
+        assert_eq!(RepoState::of(&notes, false), RepoState::Error);
+        notes.enabled = false;
+        assert_eq!(RepoState::of(&notes, false), RepoState::Disabled);
+        notes.paused = true;
+        assert_eq!(RepoState::of(&notes, false), RepoState::Paused);
+        notes.quarantined = true;
+        assert_eq!(RepoState::of(&notes, false), RepoState::Quarantined);
+        assert_eq!(RepoState::of(&notes, true), RepoState::Syncing);
+    }
+
+    #[test]
+    fn test_keys_select_and_act() {
+        let mut app = test_app();
+
+        assert_eq!(press(&mut app, KeyCode::Char('t')), Some(Action::Trigger(Some(PathBuf::from("/home/me/notes")))));
+        assert_eq!(press(&mut app, KeyCode::Char('T')), Some(Action::Trigger(None)));
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected, 1);
+        assert_eq!(press(&mut app, KeyCode::Char('p')), Some(Action::Pause(PathBuf::from("/home/me/wiki"))));
+        assert_eq!(press(&mut app, KeyCode::Char('e')), Some(Action::OpenEditor(PathBuf::from("/home/me/wiki"))));
+
+        app.status.as_mut().unwrap().repositories[1].paused = true;
+        assert_eq!(press(&mut app, KeyCode::Char('p')), Some(Action::Unpause(PathBuf::from("/home/me/wiki"))));
+
+        assert_eq!(press(&mut app, KeyCode::Char('s')), Some(Action::Suspend));
+        app.status.as_mut().unwrap().suspended = true;
+        assert_eq!(press(&mut app, KeyCode::Char('s')), Some(Action::Resume));
+
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.selected, 0);
+
+        assert!(!app.quit);
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_error_popup() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.popup, Some(Popup::Error {
+            title: "/home/me/notes".to_owned(),
+            message: "No error since the daemon started.".to_owned(),
+        }));
+
+        // Keys only close the popup while it is open
+        assert_eq!(press(&mut app, KeyCode::Char('t')), None);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.popup, None);
+        assert!(!app.quit);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.popup, Some(Popup::Error { ref message, .. }) if message == "Push rejected"));
+        assert!(render(&app).contains("Push rejected"));
+    }
+
+    #[test]
+    fn test_events_update_state_and_log() {
+        let mut app = test_app();
+        let notes = PathBuf::from("/home/me/notes");
+
+        app.apply(Update::Event(DaemonEvent::now(EventKind::SyncStarted {
+            manual: true,
+            repositories: 1,
+            paths: vec![notes.clone()],
+        })));
+        assert!(app.syncing.contains(&notes));
+        assert!(render(&app).contains("⟳ syncing"));
+
+        app.apply(Update::Event(DaemonEvent::now(EventKind::SyncFailed {
+            repo: notes.clone(),
+            error: "Repository is gone".to_owned(),
+        })));
+        app.apply(Update::Event(DaemonEvent::now(EventKind::SyncFinished {
+            repos_checked: 1,
+            repos_committed: 0,
+            repos_failed: 1,
+        })));
+        assert!(app.syncing.is_empty());
+
+        let last = app.log.back().unwrap();
+        assert_eq!(last.level, Level::Warning);
+        assert_eq!(app.log[app.log.len() - 2].level, Level::Error);
+        assert!(render(&app).contains("Sync failed for /home/me/notes: Repository is gone"));
+    }
+
+    #[test]
+    fn test_disconnect_and_reconnect() {
+        let mut app = test_app();
+        app.syncing.insert(PathBuf::from("/home/me/notes"));
+
+        app.apply(Update::Disconnected("Connection refused".to_owned()));
+        app.apply(Update::Disconnected("Connection refused".to_owned()));
+        assert!(app.syncing.is_empty());
+        assert!(render(&app).contains("daemon unreachable: Connection refused"));
+        // Repeated failures are only logged once
+        assert_eq!(app.log.iter().filter(|line| line.level == Level::Error).count(), 1);
+
+        let status = Status { repositories: vec![repo("/home/me/notes")], ..Default::default() };
+        app.selected = 1;
+        app.apply(Update::Status(Box::new(status), HashMap::from([(PathBuf::from("/home/me/notes"), 3)])));
+        assert!(app.disconnected.is_none());
+        assert_eq!(app.selected, 0);
+        assert_eq!(app.log.back().unwrap().text, "Reconnected to daemon");
+    }
+
+    #[test]
+    fn test_render_repositories() {
+        let mut app = test_app();
+        app.pending.insert(PathBuf::from("/home/me/notes"), 4);
+        app.status.as_mut().unwrap().repositories[0].last_sync = Some(JournalEntry {
+            timestamp: Utc::now() - chrono::Duration::minutes(5),
+            repo: PathBuf::from("/home/me/notes"),
+            commit: None,
+            files_changed: 0,
+            push: Default::default(),
+            pull: Default::default(),
+            error: None,
+        });
+
+        let screen = render(&app);
+        let notes = screen.lines().find(|line| line.contains("/home/me/notes")).unwrap();
+        assert!(notes.contains("✓ idle"), "{}", notes);
+        assert!(notes.contains("5m ago"), "{}", notes);
+        assert!(notes.trim_end().trim_end_matches('│').trim_end().ends_with('4'), "{}", notes);
+
+        let wiki = screen.lines().find(|line| line.contains("/home/me/wiki")).unwrap();
+        assert!(wiki.contains("✗ error") && wiki.contains("↑2 ↓0"), "{}", wiki);
+
+        assert!(screen.contains("Connected to daemon (2 repositories)"));
+        assert!(screen.contains("q quit"));
+    }
+
+    #[test]
+    fn test_log_scrolling_and_capacity() {
+        let mut app = test_app();
+        for i in 0..LOG_CAPACITY + 10 {
+            app.push_log(Level::Info, format!("line {}", i));
+        }
+        assert_eq!(app.log.len(), LOG_CAPACITY);
+
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.log_scroll, 5);
+        let screen = render(&app);
+        assert!(screen.contains("line 504") && !screen.contains("line 505"));
+        assert!(screen.contains("5 newer"));
+
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.log_scroll, 0);
+        assert!(render(&app).contains(&format!("line {}", LOG_CAPACITY + 9)));
+    }
+
+    #[test]
+    fn test_uncommitted_files() {
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(uncommitted_files(temp.path()), None);
+
+        GitRepository::init(temp.path()).unwrap();
+        std::fs::write(temp.path().join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(temp.path().join("notes.md"), "hello").unwrap();
+        std::fs::write(temp.path().join("debug.log"), "ignored").unwrap();
+        assert_eq!(uncommitted_files(temp.path()), Some(2));
+    }
+}
@@ -0,0 +1,378 @@
+//! `autogit undo`: take back the last automatic commit of a repository
+
+use crate::cli::OutputFormat;
+use crate::commands::{expand_path, git_push, query_history};
+use crate::output::{self, UndoAction, UndoReport};
+use anyhow::{Context, Result, bail};
+use autogit_client::Client;
+use autogit_shared::git::{get_signature, open_repository};
+use autogit_shared::{Config, JournalFilter, RepoNotFound};
+use colored::Colorize;
+use git2::{Oid, Repository as GitRepository, ResetType, StatusOptions};
+use std::path::{Path, PathBuf};
+
+/// Undo the last commit if the daemon made it, pausing the repository while doing so
+pub async fn undo_last_commit(repo: Option<&str>, keep_changes: bool, force_revert: bool, format: OutputFormat) -> Result<()> {
+    let path = match repo {
+        Some(repo) => expand_path(repo)?,
+        None => current_repository()?,
+    };
+
+    let mut config = Config::load_or_create_default()?;
+    config.discover();
+    if !config.all_repositories().any(|configured| configured.path == path) {
+        return Err(RepoNotFound(path).into());
+    }
+
+    // Only commits the journal recorded were made by autogit
+    let filter = JournalFilter { repo: Some(path.clone()), ..Default::default() };
+    let authored: Vec<String> = query_history(filter).await?
+        .into_iter()
+        .filter_map(|entry| entry.commit)
+        .collect();
+
+    // Keep the daemon from syncing while HEAD moves
+    let daemon = Client::new()?;
+    let paused_here = match daemon.status().await {
+        Ok(status) if !status.paused.iter().any(|paused| paused.path == path) => {
+            daemon.pause(path.clone(), None).await?;
+            Some(true)
+        }
+        Ok(_) => Some(false),
+        Err(_) => None,
+    };
+
+    let undone = undo_commit(&path, &authored, keep_changes, force_revert);
+
+    // Kept changes stay paused, or the next sync would commit them again; a revert leaves none behind
+    let stays_paused = matches!(&undone, Ok(report) if report.action == UndoAction::KeptChanges);
+    if paused_here == Some(true) && !stays_paused {
+        daemon.unpause(path.clone()).await?;
+    }
+
+    let mut report = undone?;
+    report.paused = stays_paused && paused_here.is_some();
+
+    // The daemon only pushes what it committed itself
+    if report.action == UndoAction::Reverted {
+        let pushed = git_push(&path, &[]).await;
+        report.pushed = pushed.is_ok();
+        report.push_error = pushed.err().map(|e| e.to_string());
+    }
+
+    if !format.is_plain() {
+        return output::emit(format, &report);
+    }
+    print_undone(&report, paused_here.is_some());
+    Ok(())
+}
+
+fn print_undone(report: &UndoReport, daemon_running: bool) {
+    let commit = format!("{} ({})", &report.commit[..7], report.summary);
+    match (report.action, &report.revert_commit) {
+        (UndoAction::Reverted, Some(revert)) => {
+            println!("{} Reverted pushed commit {} with {}", "✓".green().bold(), commit, &revert[..7]);
+            match &report.push_error {
+                None => println!("{} Pushed the revert", "✓".green().bold()),
+                Some(error) => {
+                    println!("{} Push failed: {}", "⚠".yellow(), error);
+                    println!("   Push it with: {}", format!("git -C {} push", report.path.display()).cyan());
+                }
+            }
+        }
+        (UndoAction::KeptChanges, _) => {
+            println!("{} Undid commit {}; its changes are back in the working tree", "✓".green().bold(), commit);
+            if report.paused {
+                println!(
+                    "   Syncing is paused so they aren't committed again; resume with: {}",
+                    format!("autogit unpause {}", report.path.display()).cyan()
+                );
+            } else if !daemon_running {
+                println!("   {} Deal with them before starting the daemon, or it commits them again", "⚠".yellow());
+            }
+        }
+        _ => println!("{} Undid commit {} and discarded its changes", "✓".green().bold(), commit),
+    }
+}
+
+/// Work tree of the repository containing the current directory
+fn current_repository() -> Result<PathBuf> {
+    let repo = GitRepository::discover(".")
+        .context("Not inside a git repository; name the repository to undo")?;
+    let workdir = repo.workdir().context("Bare repositories aren't synced by autogit")?;
+    Ok(workdir.canonicalize()?)
+}
+
+/// Move HEAD back past an autogit commit, or revert it if it was pushed
+fn undo_commit(path: &Path, authored: &[String], keep_changes: bool, force_revert: bool) -> Result<UndoReport> {
+    let repo = open_repository(path)?;
+    let head = repo.head()
+        .and_then(|head| head.peel_to_commit())
+        .context("The repository has no commits")?;
+    let commit = head.id().to_string();
+    let summary = head.summary().unwrap_or_default().to_owned();
+
+    if !authored.contains(&commit) {
+        bail!("The last commit {} ({}) wasn't made by autogit; undo it with git instead", &commit[..7], summary);
+    }
+    let parent = head.parent(0)
+        .context("The last commit is the repository's first one; there is nothing to go back to")?;
+
+    let pushed = is_pushed(&repo, head.id())?;
+    if pushed && !force_revert {
+        bail!(
+            "Commit {} ({}) has already been pushed; pass --force-revert to undo it with a revert commit",
+            &commit[..7],
+            summary
+        );
+    }
+    if !keep_changes && has_uncommitted_changes(&repo)? {
+        bail!("{} has uncommitted changes; commit them first or pass --keep-changes", path.display());
+    }
+
+    let mut report = UndoReport {
+        path: path.to_path_buf(),
+        commit,
+        summary,
+        action: UndoAction::Reset,
+        revert_commit: None,
+        paused: false,
+        pushed: false,
+        push_error: None,
+    };
+
+    if pushed {
+        let mut index = repo.revert_commit(&head, &head, 0, None)
+            .context("Failed to revert the commit")?;
+        let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+        let signature = get_signature(&repo)?;
+        repo.checkout_tree(tree.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))
+            .context("Failed to update the working tree")?;
+        let message = format!("Revert \"{}\"\n\nThis reverts commit {}.\n", report.summary, report.commit);
+        let revert = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&head])
+            .context("Failed to commit the revert")?;
+
+        report.action = UndoAction::Reverted;
+        report.revert_commit = Some(revert.to_string());
+    } else if keep_changes {
+        repo.reset(parent.as_object(), ResetType::Mixed, None)
+            .context("Failed to move back to the previous commit")?;
+        report.action = UndoAction::KeptChanges;
+    } else {
+        repo.reset(parent.as_object(), ResetType::Hard, None)
+            .context("Failed to move back to the previous commit")?;
+    }
+
+    Ok(report)
+}
+
+/// Whether the upstream branch already contains a commit
+fn is_pushed(repo: &GitRepository, commit: Oid) -> Result<bool> {
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Ok(false);
+    }
+
+    let upstream = match git2::Branch::wrap(head).upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(false),
+        Err(e) => return Err(e).context("Failed to look up the upstream branch"),
+    };
+    let Some(target) = upstream.get().target() else {
+        return Ok(false);
+    };
+
+    Ok(target == commit || repo.graph_descendant_of(target, commit)?)
+}
+
+/// Whether tracked files differ from HEAD (untracked files survive a reset)
+fn has_uncommitted_changes(repo: &GitRepository) -> Result<bool> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options))
+        .context("Failed to read the repository status")?;
+    Ok(!statuses.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autogit_shared::{ConfigChange, Journal, JournalEntry};
+    use git2::Signature;
+    use serial_test::serial;
+    use std::env;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository with a first commit by the user and a second one adding `auto.md`
+    fn repository_with_commits() -> (TempDir, GitRepository, Oid) {
+        let dir = TempDir::new().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        {
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test User").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+        }
+
+        fs::write(dir.path().join("notes.md"), "notes").unwrap();
+        commit_all(&repo, "First");
+        fs::write(dir.path().join("auto.md"), "automatic").unwrap();
+        let auto = commit_all(&repo, "Auto-commit: now");
+        (dir, repo, auto)
+    }
+
+    fn commit_all(repo: &GitRepository, message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test User", "test@example.com").unwrap();
+        let parents: Vec<_> = repo.head().ok().map(|head| head.peel_to_commit().unwrap()).into_iter().collect();
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    fn head_summary(repo: &GitRepository) -> String {
+        repo.head().unwrap().peel_to_commit().unwrap().summary().unwrap().to_owned()
+    }
+
+    /// Pretend the current HEAD was pushed to origin
+    fn mark_pushed(repo: &GitRepository) {
+        let head = repo.head().unwrap();
+        let branch = head.shorthand().unwrap().to_owned();
+        repo.remote("origin", "/nonexistent/remote.git").unwrap();
+        repo.reference(&format!("refs/remotes/origin/{}", branch), head.target().unwrap(), true, "test").unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str(&format!("branch.{}.remote", branch), "origin").unwrap();
+        config.set_str(&format!("branch.{}.merge", branch), &format!("refs/heads/{}", branch)).unwrap();
+    }
+
+    #[test]
+    fn test_undo_discards_changes() {
+        let (dir, repo, auto) = repository_with_commits();
+
+        let report = undo_commit(dir.path(), &[auto.to_string()], false, false).unwrap();
+        assert_eq!(report.action, UndoAction::Reset);
+        assert_eq!(report.summary, "Auto-commit: now");
+        assert_eq!(head_summary(&repo), "First");
+        assert!(!dir.path().join("auto.md").exists());
+    }
+
+    #[test]
+    fn test_undo_keeps_changes() {
+        let (dir, repo, auto) = repository_with_commits();
+
+        let report = undo_commit(dir.path(), &[auto.to_string()], true, false).unwrap();
+        assert_eq!(report.action, UndoAction::KeptChanges);
+        assert_eq!(head_summary(&repo), "First");
+        assert_eq!(fs::read_to_string(dir.path().join("auto.md")).unwrap(), "automatic");
+    }
+
+    #[test]
+    fn test_undo_refuses_foreign_and_first_commits() {
+        let (dir, repo, auto) = repository_with_commits();
+
+        let error = undo_commit(dir.path(), &[], false, false).unwrap_err();
+        assert!(error.to_string().contains("wasn't made by autogit"));
+        assert_eq!(repo.head().unwrap().target(), Some(auto));
+
+        let first = repo.head().unwrap().peel_to_commit().unwrap().parent_id(0).unwrap();
+        repo.reset(&repo.find_object(first, None).unwrap(), ResetType::Hard, None).unwrap();
+        let error = undo_commit(dir.path(), &[first.to_string()], false, false).unwrap_err();
+        assert!(error.to_string().contains("first one"));
+    }
+
+    #[test]
+    fn test_undo_refuses_uncommitted_changes() {
+        let (dir, repo, auto) = repository_with_commits();
+        fs::write(dir.path().join("notes.md"), "edited").unwrap();
+
+        let error = undo_commit(dir.path(), &[auto.to_string()], false, false).unwrap_err();
+        assert!(error.to_string().contains("--keep-changes"));
+        assert_eq!(repo.head().unwrap().target(), Some(auto));
+
+        // Keeping changes doesn't touch the working tree, so it's fine
+        undo_commit(dir.path(), &[auto.to_string()], true, false).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("notes.md")).unwrap(), "edited");
+    }
+
+    #[test]
+    fn test_undo_pushed_commit_needs_revert() {
+        let (dir, repo, auto) = repository_with_commits();
+        mark_pushed(&repo);
+
+        let error = undo_commit(dir.path(), &[auto.to_string()], true, false).unwrap_err();
+        assert!(error.to_string().contains("already been pushed"));
+        assert_eq!(repo.head().unwrap().target(), Some(auto));
+
+        let report = undo_commit(dir.path(), &[auto.to_string()], false, true).unwrap();
+        assert_eq!(report.action, UndoAction::Reverted);
+        let revert = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(Some(revert.id().to_string()), report.revert_commit);
+        assert_eq!(revert.parent_id(0).unwrap(), auto);
+        assert_eq!(revert.summary(), Some("Revert \"Auto-commit: now\""));
+        assert!(!dir.path().join("auto.md").exists());
+        assert!(!has_uncommitted_changes(&repo).unwrap());
+    }
+
+    /// Configure the repository and record a commit as made by autogit, with state kept in `dir`
+    fn register(dir: &Path, path: &Path, commit: Oid) {
+        env::set_var("XDG_CONFIG_HOME", dir);
+        env::set_var("XDG_RUNTIME_DIR", dir);
+        env::set_var("XDG_STATE_HOME", dir);
+
+        let mut config = Config::load_or_create_default().unwrap();
+        config.apply(&ConfigChange::AddRepo { path: path.to_path_buf(), commit_message_template: None }).unwrap();
+        config.save(Config::default_config_path().unwrap()).unwrap();
+
+        Journal::new(Journal::default_path().unwrap()).append(&JournalEntry {
+            timestamp: chrono::Utc::now(),
+            repo: path.to_path_buf(),
+            commit: Some(commit.to_string()),
+            files_changed: 1,
+            push: Default::default(),
+            pull: Default::default(),
+            error: None,
+        }).unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_undo_last_commit_uses_journal() {
+        let config_dir = TempDir::new().unwrap();
+        env::set_var("XDG_CONFIG_HOME", config_dir.path());
+        env::set_var("XDG_RUNTIME_DIR", config_dir.path());
+
+        let (dir, repo, auto) = repository_with_commits();
+        let path = dir.path().canonicalize().unwrap();
+
+        // Not configured yet
+        let error = undo_last_commit(path.to_str(), false, false, OutputFormat::Json).await.unwrap_err();
+        assert!(error.is::<RepoNotFound>());
+
+        register(config_dir.path(), &path, auto);
+        undo_last_commit(path.to_str(), false, false, OutputFormat::Json).await.unwrap();
+        assert_eq!(head_summary(&repo), "First");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_undo_last_commit_pushes_revert() {
+        let config_dir = TempDir::new().unwrap();
+        let (dir, repo, auto) = repository_with_commits();
+        let path = dir.path().canonicalize().unwrap();
+        register(config_dir.path(), &path, auto);
+
+        let remote = TempDir::new().unwrap();
+        GitRepository::init_bare(remote.path()).unwrap();
+        repo.remote("origin", remote.path().to_str().unwrap()).unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_owned();
+        git_push(&path, &["--set-upstream", "origin", &branch]).await.unwrap();
+
+        undo_last_commit(path.to_str(), false, true, OutputFormat::Json).await.unwrap();
+
+        let remote = GitRepository::open_bare(remote.path()).unwrap();
+        let pushed = remote.find_branch(&branch, git2::BranchType::Local).unwrap().get().peel_to_commit().unwrap();
+        assert_eq!(pushed.summary(), Some("Revert \"Auto-commit: now\""));
+    }
+}